@@ -0,0 +1,1164 @@
+use std::cmp::Ordering;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use derive_builder::Builder;
+use num_traits::Float;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::tt::{Bound, Entry, ReplacementPolicy, TranspositionTable};
+use crate::{Evaluator, Line, Player, State};
+
+/// The number of consecutive iterations the best move has to stay the same for before it can be
+/// played early.
+const EASY_MOVE_ITERATIONS: u32 = 3;
+
+/// The number of nodes searched between each check of the hard time limit. Must be a power of two.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// The depth stored in the transposition table for states whose every line was searched until the
+/// end of the game, so that their scores are used at any depth.
+const EXHAUSTIVE: u32 = u32::MAX;
+
+/// The number of moves that the time left on a clock is assumed to need to last for, when there's
+/// no telling how long the game will go on.
+const MOVES_TO_GO: u32 = 30;
+
+/// The result of a search by an [`Engine`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult<V, A> {
+    /// The best lines found, ordered from best to worst.
+    pub lines: Vec<Line<V, A>>,
+    /// The depth of the last completed iteration.
+    pub depth: u32,
+    /// The number of nodes searched, including those of any aborted iteration.
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub stats: SearchStats,
+}
+
+impl<V: Copy, A> SearchResult<V, A> {
+    pub fn best_move(&self) -> Option<&A> {
+        self.pv().first()
+    }
+
+    /// The score of the best line, from the perspective of the player to move at the root.
+    pub fn score(&self) -> Option<V> {
+        self.lines.first().map(|line| line.score)
+    }
+
+    /// The principal variation of the best line.
+    pub fn pv(&self) -> &[A] {
+        self.lines.first().map_or(&[], |line| &line.pv)
+    }
+}
+
+impl<V: Float, A> SearchResult<V, A> {
+    /// Samples the first move of one of the lines, with probabilities given by the softmax of
+    /// their scores divided by `temperature`, which is in the units of the evaluation.
+    ///
+    /// Higher temperatures make weaker moves more likely, and a temperature of zero always picks
+    /// the best move. Only the lines that were searched for can be sampled, so MultiPV should
+    /// cover every root move that is meant to be considered.
+    pub fn sample_move<R: Rng + ?Sized>(&self, temperature: V, rng: &mut R) -> Option<&A> {
+        let best = self.score()?;
+        let weights: Vec<f64> = self
+            .lines
+            .iter()
+            .map(|line| {
+                // Scores are compared to the best one, so that a winning best move can't overflow
+                if line.score == best {
+                    1.0
+                } else {
+                    ((line.score - best) / temperature)
+                        .to_f64()
+                        .map_or(0.0, f64::exp)
+                }
+            })
+            .collect();
+
+        let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        for (line, weight) in self.lines.iter().zip(weights) {
+            if target < weight {
+                return line.pv.first();
+            }
+            target -= weight;
+        }
+
+        self.best_move()
+    }
+}
+
+/// A report on a running search, passed to the progress callback of
+/// [`Engine::search_with_progress`].
+#[derive(Debug)]
+pub struct SearchInfo<'a, V, A> {
+    /// The depth of the iteration being searched.
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    /// The best line found so far, which comes from the current iteration once its first root
+    /// move has been searched.
+    pub best_line: Option<&'a Line<V, A>>,
+}
+
+/// A flag that stops a running search from another thread.
+///
+/// Clones of a token share the same flag, so one clone can be given to an [`Engine`] while another
+/// is kept to stop it. A stopped search returns the result of the last iteration it completed.
+#[derive(Clone, Debug, Default)]
+pub struct StopToken(Arc<AtomicBool>);
+
+impl StopToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stop(&self) {
+        self.0.store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Clears the flag, so that the token can be used for another search.
+    pub fn reset(&self) {
+        self.0.store(false, AtomicOrdering::Relaxed);
+    }
+}
+
+/// Statistics gathered during a search, for measuring the effect of changes to move ordering and
+/// pruning.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    /// The number of nodes searched in each completed iteration, indexed by the depth minus one.
+    pub nodes_per_depth: Vec<u64>,
+    /// The number of nodes where a move caused a beta cutoff.
+    pub cutoffs: u64,
+    /// The number of beta cutoffs caused by the first move searched.
+    pub first_move_cutoffs: u64,
+    /// The number of transposition table lookups.
+    pub table_probes: u64,
+    /// The number of transposition table lookups that found an entry.
+    pub table_hits: u64,
+}
+
+impl SearchStats {
+    /// The fraction of beta cutoffs caused by the first move searched, which is close to one when
+    /// moves are well ordered.
+    pub fn first_move_cutoff_rate(&self) -> Option<f64> {
+        ratio(self.first_move_cutoffs, self.cutoffs)
+    }
+
+    /// The fraction of transposition table lookups that found an entry.
+    pub fn table_hit_rate(&self) -> Option<f64> {
+        ratio(self.table_hits, self.table_probes)
+    }
+
+    /// The effective branching factor: how many times more nodes the last completed iteration
+    /// searched than the one before it.
+    pub fn effective_branching_factor(&self) -> Option<f64> {
+        match self.nodes_per_depth[..] {
+            [.., previous, last] => ratio(last, previous),
+            _ => None,
+        }
+    }
+}
+
+fn ratio(numerator: u64, denominator: u64) -> Option<f64> {
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+/// A configurable alpha-beta search engine.
+///
+/// Searches use iterative deepening, searching one ply deeper each iteration until the depth or
+/// time limits are reached.
+///
+/// ```
+/// # use djinn_minimax::{EngineBuilder, Player};
+/// let engine = EngineBuilder::<f32>::default()
+///     .depth(4)
+///     .futility_margins([200.0, 500.0])
+///     .player_depth(Player::Min, 2)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Builder, Clone, Debug)]
+pub struct Engine<V> {
+    /// The maximum number of plies to search, including the root move.
+    #[builder(default = "4")]
+    depth: u32,
+    /// Futility pruning margins, indexed by the remaining depth minus one.
+    ///
+    /// At a node with `depth` plies remaining, quiet moves are skipped if the static evaluation
+    /// plus `futility_margins[depth - 1]` can't reach alpha. Pruning is disabled at depths without
+    /// a margin, so it is off by default.
+    #[builder(default, setter(into))]
+    futility_margins: Vec<V>,
+    /// Razoring margins, indexed by the remaining depth minus one.
+    ///
+    /// At a node with `depth` plies remaining, the search is cut short if the static evaluation
+    /// plus `razoring_margins[depth - 1]` can't reach alpha.
+    #[builder(default, setter(into))]
+    razoring_margins: Vec<V>,
+    /// The maximum number of plies of non-quiet moves searched beyond the full-width search, to
+    /// avoid evaluating positions in the middle of an exchange. Quiescence search is disabled by
+    /// default.
+    #[builder(default)]
+    quiescence_depth: u32,
+    /// Per-player overrides of `depth`, indexed by the player to move at the root.
+    #[builder(default, setter(custom))]
+    player_depths: [Option<u32>; 2],
+    /// The number of lines searched for by [`Engine::search`], each starting with a different
+    /// root move.
+    #[builder(default = "1")]
+    multi_pv: usize,
+    /// Once this much time has passed, no new iterations are started.
+    #[builder(default, setter(strip_option))]
+    soft_time_limit: Option<Duration>,
+    /// Once this much time has passed, the current iteration is aborted and the result of the
+    /// previous one is used. The first iteration is always completed, even past this limit.
+    #[builder(default, setter(strip_option))]
+    hard_time_limit: Option<Duration>,
+    /// Once this many nodes have been searched, the current iteration is aborted like it is by
+    /// `hard_time_limit`.
+    #[builder(default, setter(strip_option))]
+    node_limit: Option<u64>,
+    /// A token that stops the search once it's stopped, aborting the current iteration like
+    /// `hard_time_limit` does.
+    #[builder(default, setter(strip_option))]
+    stop_token: Option<StopToken>,
+    /// If set, the search stops early once the best move has stayed the same for several
+    /// iterations and leads every other move by at least this margin.
+    #[builder(default, setter(strip_option))]
+    easy_move_margin: Option<V>,
+    /// If set, [`Engine::best_move`] searches every root move exactly and picks one of them with
+    /// [`SearchResult::sample_move`] at this temperature, instead of always playing the best.
+    #[builder(default, setter(strip_option))]
+    temperature: Option<V>,
+    /// The seed for the random choices made with `temperature`. If `None`, they are seeded from
+    /// the operating system.
+    #[builder(default, setter(strip_option))]
+    seed: Option<u64>,
+    /// The number of entries in the transposition table, which is only used by games providing
+    /// [`State::hash_key`]. The table is disabled by default.
+    #[builder(default)]
+    transposition_table_size: usize,
+    /// How entries in the transposition table are chosen to be overwritten.
+    #[builder(default)]
+    replacement_policy: ReplacementPolicy,
+    /// The minimum time between calls to the progress callback of
+    /// [`Engine::search_with_progress`].
+    #[builder(default = "Duration::from_millis(100)")]
+    progress_interval: Duration,
+}
+
+impl<V> EngineBuilder<V> {
+    /// Searches to `depth` plies when `player` is to move, instead of the depth used for both
+    /// players. This allows for handicapped matches between two players using the same engine.
+    pub fn player_depth(&mut self, player: Player, depth: u32) -> &mut Self {
+        self.player_depths.get_or_insert_with(Default::default)[player as usize] = Some(depth);
+        self
+    }
+
+    /// Sets the time limits for a player with `remaining` time left on their clock, which gains
+    /// `increment` after every move. The soft limit spreads the remaining time over the rest of
+    /// the game, and the hard limit stops a single move from using more than half of it.
+    pub fn time_control(&mut self, remaining: Duration, increment: Duration) -> &mut Self {
+        let soft = remaining / MOVES_TO_GO + increment * 3 / 4;
+        let hard = (soft * 3).min(remaining / 2);
+        self.soft_time_limit = Some(Some(soft.min(hard)));
+        self.hard_time_limit = Some(Some(hard));
+        self
+    }
+}
+
+impl<V: Float> Engine<V> {
+    /// Creates an engine searching to `depth` plies, with every other option at its default.
+    pub fn new(depth: u32) -> Self {
+        EngineBuilder::default()
+            .depth(depth)
+            .build()
+            .expect("every other option has a default")
+    }
+
+    /// A copy of the engine that's stopped by `token`, for stopping one search without building
+    /// the engine again.
+    pub fn with_stop_token(&self, token: StopToken) -> Self {
+        Self {
+            stop_token: Some(token),
+            ..self.clone()
+        }
+    }
+
+    /// The number of plies searched when `player` is to move at the root.
+    pub fn depth_for(&self, player: Player) -> u32 {
+        self.player_depths[player as usize].unwrap_or(self.depth)
+    }
+
+    /// Searches for the best line from `state`, or for the best few if MultiPV is enabled.
+    pub fn search<S, A>(&self, state: &S) -> SearchResult<V, A>
+    where
+        S: State<V, A>,
+        A: Clone,
+    {
+        self.search_with(state, &S::evaluation)
+    }
+
+    /// Like [`Engine::search`], but evaluating non-terminal states with `evaluator`.
+    pub fn search_with<S, A, E>(&self, state: &S, evaluator: &E) -> SearchResult<V, A>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        self.search_with_table(state, evaluator, &mut self.transposition_table())
+    }
+
+    /// Like [`Engine::search_with`], but using `table` as the transposition table, so that it can
+    /// be kept between searches.
+    pub fn search_with_table<S, A, E>(
+        &self,
+        state: &S,
+        evaluator: &E,
+        table: &mut TranspositionTable<V>,
+    ) -> SearchResult<V, A>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        self.search_with_callback(state, evaluator, table, |_| {})
+    }
+
+    /// Like [`Engine::search_with_table`], but calling `on_iteration` with the result so far after
+    /// every completed iteration, e.g. to show the progress of the search.
+    pub fn search_with_callback<S, A, E, F>(
+        &self,
+        state: &S,
+        evaluator: &E,
+        table: &mut TranspositionTable<V>,
+        mut on_iteration: F,
+    ) -> SearchResult<V, A>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+        F: FnMut(&SearchResult<V, A>),
+    {
+        self.iterative_deepening(state, evaluator, self.multi_pv, table, &mut on_iteration, None)
+    }
+
+    /// Like [`Engine::search_with_callback`], but also calling `on_progress` during iterations,
+    /// at most once every `progress_interval`, so that long iterations can be followed too.
+    pub fn search_with_progress<S, A, E, F, G>(
+        &self,
+        state: &S,
+        evaluator: &E,
+        table: &mut TranspositionTable<V>,
+        mut on_iteration: F,
+        mut on_progress: G,
+    ) -> SearchResult<V, A>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+        F: FnMut(&SearchResult<V, A>),
+        G: FnMut(&SearchInfo<V, A>),
+    {
+        self.iterative_deepening(
+            state,
+            evaluator,
+            self.multi_pv,
+            table,
+            &mut on_iteration,
+            Some(&mut on_progress),
+        )
+    }
+
+    pub fn best_move<S, A>(&self, state: &S) -> Option<A>
+    where
+        S: State<V, A>,
+        A: Clone,
+    {
+        self.best_move_with(state, &S::evaluation)
+    }
+
+    /// Like [`Engine::best_move`], but evaluating non-terminal states with `evaluator`.
+    pub fn best_move_with<S, A, E>(&self, state: &S, evaluator: &E) -> Option<A>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        self.best_move_with_table(state, evaluator, &mut self.transposition_table())
+    }
+
+    /// Like [`Engine::best_move_with`], but using `table` as the transposition table, so that it
+    /// can be kept between searches.
+    pub fn best_move_with_table<S, A, E>(
+        &self,
+        state: &S,
+        evaluator: &E,
+        table: &mut TranspositionTable<V>,
+    ) -> Option<A>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        match self.temperature {
+            Some(temperature) => self
+                .iterative_deepening(state, evaluator, usize::MAX, table, &mut |_| {}, None)
+                .sample_move(temperature, &mut self.rng())
+                .cloned(),
+            None => self
+                .iterative_deepening(state, evaluator, 1, table, &mut |_| {}, None)
+                .best_move()
+                .cloned(),
+        }
+    }
+
+    /// Searches for the `k` best lines that start with distinct root moves, ordered from best to
+    /// worst.
+    ///
+    /// Fewer than `k` lines are returned if there aren't enough moves available.
+    pub fn multi_pv<S, A>(&self, state: &S, k: usize) -> Vec<Line<V, A>>
+    where
+        S: State<V, A>,
+        A: Clone,
+    {
+        self.multi_pv_with(state, k, &S::evaluation)
+    }
+
+    /// Like [`Engine::multi_pv`], but evaluating non-terminal states with `evaluator`.
+    pub fn multi_pv_with<S, A, E>(&self, state: &S, k: usize, evaluator: &E) -> Vec<Line<V, A>>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        let table = &mut self.transposition_table();
+        self.iterative_deepening(state, evaluator, k, table, &mut |_| {}, None)
+            .lines
+    }
+
+    /// The value of `state` from the perspective of the player to move, from a single search to
+    /// the full depth.
+    pub fn value<S, A>(&self, state: &S) -> V
+    where
+        S: State<V, A>,
+        A: Clone,
+    {
+        self.value_with(state, &S::evaluation)
+    }
+
+    /// Like [`Engine::value`], but evaluating non-terminal states with `evaluator`.
+    pub fn value_with<S, A, E>(&self, state: &S, evaluator: &E) -> V
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        self.alpha_beta_with_table(
+            state,
+            evaluator,
+            &mut self.transposition_table(),
+            V::neg_infinity(),
+            V::infinity(),
+            self.depth_for(state.current_player()),
+        )
+    }
+
+    /// A single alpha-beta search of `state` to `depth` plies with the window `alpha..beta`,
+    /// returning its value from the perspective of the player to move.
+    ///
+    /// Values outside the window are only bounds on the true value: a value at most `alpha` is an
+    /// upper bound, and one at least `beta` is a lower bound. This lets other drivers, such as
+    /// aspiration windows or MTD(f), be built on top of the search. Unlike [`Engine::search`],
+    /// it is never aborted by the time or node limits.
+    pub fn alpha_beta<S, A>(&self, state: &S, alpha: V, beta: V, depth: u32) -> V
+    where
+        S: State<V, A>,
+        A: Clone,
+    {
+        let table = &mut self.transposition_table();
+        self.alpha_beta_with_table(state, &S::evaluation, table, alpha, beta, depth)
+    }
+
+    /// Like [`Engine::alpha_beta`], but evaluating non-terminal states with `evaluator` and using
+    /// `table` as the transposition table, so that repeated searches of the same state can reuse
+    /// each other's results.
+    pub fn alpha_beta_with_table<S, A, E>(
+        &self,
+        state: &S,
+        evaluator: &E,
+        table: &mut TranspositionTable<V>,
+        alpha: V,
+        beta: V,
+        depth: u32,
+    ) -> V
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        table.next_iteration();
+        let mut search = Search::new(self, evaluator, None, table);
+        search.can_abort = false;
+        search.alpha_beta(state, alpha, beta, depth, &mut Vec::new())
+    }
+
+    /// Creates an empty transposition table, with the size and replacement policy of this engine.
+    pub fn transposition_table(&self) -> TranspositionTable<V> {
+        TranspositionTable::new(self.transposition_table_size, self.replacement_policy)
+    }
+
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    fn iterative_deepening<'a, S, A, E>(
+        &'a self,
+        state: &S,
+        evaluator: &'a E,
+        k: usize,
+        table: &'a mut TranspositionTable<V>,
+        on_iteration: &mut dyn FnMut(&SearchResult<V, A>),
+        on_progress: Option<ProgressCallback<'a, V, A>>,
+    ) -> SearchResult<V, A>
+    where
+        S: State<V, A>,
+        A: Clone,
+        E: Evaluator<S, V>,
+    {
+        let start = Instant::now();
+        let deadline = self.hard_time_limit.map(|limit| start + limit);
+        let mut search = Search::new(self, evaluator, deadline, table);
+        search.on_progress = on_progress;
+
+        let mut result = SearchResult {
+            lines: Vec::new(),
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            stats: SearchStats::default(),
+        };
+
+        if k > 0 && !state.is_terminal() {
+            let mut root: Vec<_> = search
+                .ordered_actions(state)
+                .into_iter()
+                .map(|(index, _, action)| RootMove {
+                    index,
+                    action,
+                    score: V::neg_infinity(),
+                })
+                .collect();
+
+            let mut best_index = None;
+            let mut stable_iterations = 0;
+            // There is nothing to search if a non-terminal state has no moves
+            let max_depth = if root.is_empty() {
+                0
+            } else {
+                self.depth_for(state.current_player()).max(1)
+            };
+
+            for depth in 1..=max_depth {
+                // The first iteration is never aborted, so that there is always a move to play
+                search.can_abort = depth > 1;
+                search.depth = depth;
+                search.table.next_iteration();
+                search.reached_depth_limit = false;
+                let nodes = search.nodes;
+
+                let Some(lines) = search.root(state, &mut root, k, depth) else {
+                    break;
+                };
+                search.stats.nodes_per_depth.push(search.nodes - nodes);
+                result.lines = lines;
+                result.depth = depth;
+                result.nodes = search.nodes;
+                result.elapsed = start.elapsed();
+                result.stats = search.stats.clone();
+                on_iteration(&result);
+
+                if best_index == Some(root[0].index) {
+                    stable_iterations += 1;
+                } else {
+                    best_index = Some(root[0].index);
+                    stable_iterations = 1;
+                }
+
+                // Every line ended in a terminal state, so searching deeper won't change anything
+                if !search.reached_depth_limit {
+                    break;
+                }
+
+                if self.stop_token.as_ref().is_some_and(StopToken::is_stopped) {
+                    break;
+                }
+
+                if self
+                    .soft_time_limit
+                    .is_some_and(|limit| start.elapsed() >= limit)
+                {
+                    break;
+                }
+
+                let lead = root[0].score - root.get(1).map_or(V::neg_infinity(), |m| m.score);
+                let easy_move = self.easy_move_margin.is_some_and(|margin| {
+                    stable_iterations >= EASY_MOVE_ITERATIONS && lead >= margin
+                });
+                if easy_move {
+                    break;
+                }
+            }
+        }
+
+        result.nodes = search.nodes;
+        result.elapsed = start.elapsed();
+        result.stats = search.stats;
+        result
+    }
+}
+
+/// The window to search `child` with, given the window `alpha..beta` of its parent `state`, and
+/// the sign turning the child's value into one from the parent's perspective.
+///
+/// Players don't have to alternate, so both are only negated if the move passed the turn.
+fn child_window<S, V, A>(state: &S, child: &S, alpha: V, beta: V) -> (V, V, V)
+where
+    S: State<V, A>,
+    V: Float,
+    A: Clone,
+{
+    if child.current_player() == state.current_player() {
+        (alpha, beta, V::one())
+    } else {
+        (-beta, -alpha, -V::one())
+    }
+}
+
+/// A move from the root state, with its score from the latest iteration.
+struct RootMove<V, A> {
+    /// The position of the move in the order the root moves were first generated in.
+    index: usize,
+    action: A,
+    score: V,
+}
+
+/// A move as it is ordered for searching: its index in the order the moves were generated in,
+/// and its static exchange evaluation if it isn't quiet.
+type OrderedAction<V, A> = (usize, Option<V>, A);
+
+type ProgressCallback<'a, V, A> = &'a mut dyn FnMut(&SearchInfo<V, A>);
+
+/// A single search, borrowing the options of the engine running it.
+struct Search<'a, V, A, E> {
+    engine: &'a Engine<V>,
+    evaluator: &'a E,
+    deadline: Option<Instant>,
+    can_abort: bool,
+    aborted: bool,
+    nodes: u64,
+    stats: SearchStats,
+    table: &'a mut TranspositionTable<V>,
+    /// Whether any line was cut short by the depth limit, rather than ending in a terminal state.
+    reached_depth_limit: bool,
+    /// The depth of the current iteration.
+    depth: u32,
+    start: Instant,
+    /// The best line found so far, for reporting progress.
+    best_line: Option<Line<V, A>>,
+    on_progress: Option<ProgressCallback<'a, V, A>>,
+    last_progress: Instant,
+    /// Buffers reused between nodes, so that the search doesn't allocate at every node. Each node
+    /// takes the buffers it needs and returns them once it's done with them.
+    generated_actions: Vec<A>,
+    action_buffers: Vec<Vec<OrderedAction<V, A>>>,
+    pv_buffers: Vec<Vec<A>>,
+}
+
+impl<'a, V: Float, A: Clone, E> Search<'a, V, A, E> {
+    fn new(
+        engine: &'a Engine<V>,
+        evaluator: &'a E,
+        deadline: Option<Instant>,
+        table: &'a mut TranspositionTable<V>,
+    ) -> Self {
+        Self {
+            engine,
+            evaluator,
+            deadline,
+            can_abort: true,
+            aborted: false,
+            nodes: 0,
+            stats: SearchStats::default(),
+            table,
+            reached_depth_limit: false,
+            depth: 0,
+            start: Instant::now(),
+            best_line: None,
+            on_progress: None,
+            last_progress: Instant::now(),
+            generated_actions: Vec::new(),
+            action_buffers: Vec::new(),
+            pv_buffers: Vec::new(),
+        }
+    }
+
+    /// Counts a new node, returning whether the search has been aborted.
+    fn should_abort(&mut self) -> bool {
+        self.nodes += 1;
+
+        if self.nodes & (TIME_CHECK_INTERVAL - 1) == 0 {
+            self.report_progress();
+        }
+
+        let out_of_nodes = self
+            .engine
+            .node_limit
+            .is_some_and(|limit| self.nodes > limit);
+        let out_of_time = || {
+            self.nodes & (TIME_CHECK_INTERVAL - 1) == 0
+                && self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+        };
+
+        let stopped = self
+            .engine
+            .stop_token
+            .as_ref()
+            .is_some_and(StopToken::is_stopped);
+
+        if self.can_abort && (out_of_nodes || stopped || out_of_time()) {
+            self.aborted = true;
+        }
+
+        self.aborted
+    }
+
+    /// Calls the progress callback if enough time has passed since it was last called.
+    fn report_progress(&mut self) {
+        let Some(on_progress) = &mut self.on_progress else {
+            return;
+        };
+
+        let now = Instant::now();
+        if now - self.last_progress < self.engine.progress_interval {
+            return;
+        }
+        self.last_progress = now;
+
+        on_progress(&SearchInfo {
+            depth: self.depth,
+            nodes: self.nodes,
+            elapsed: now - self.start,
+            best_line: self.best_line.as_ref(),
+        });
+    }
+
+    /// Searches every root move to `depth` plies, returning the `k` best lines, or `None` if the
+    /// search was aborted. The root moves are then sorted by their new scores, so that the best
+    /// moves are searched first in the next iteration.
+    fn root<S>(
+        &mut self,
+        state: &S,
+        root: &mut [RootMove<V, A>],
+        k: usize,
+        depth: u32,
+    ) -> Option<Vec<Line<V, A>>>
+    where
+        S: State<V, A>,
+        E: Evaluator<S, V>,
+    {
+        let mut lines: Vec<Line<V, A>> = Vec::with_capacity(k.min(root.len()) + 1);
+        let mut child_pv = Vec::new();
+
+        for root_move in root.iter_mut() {
+            // Once `k` lines have been found, a move only needs to be searched exactly if it can
+            // beat the worst of them
+            let worst = if lines.len() == k {
+                lines[k - 1].score
+            } else {
+                V::neg_infinity()
+            };
+
+            // Lowering the window by the easy move margin tells whether the best move is clearly
+            // ahead of the others
+            let alpha = self
+                .engine
+                .easy_move_margin
+                .map_or(worst, |margin| worst - margin)
+                // Once a win has been found the window would be empty, which would make the
+                // bounds stored in the transposition table meaningless
+                .min(V::max_value());
+
+            let child = state.result(&root_move.action);
+            let (child_alpha, child_beta, sign) = child_window(state, &child, alpha, V::infinity());
+            let score =
+                sign * self.alpha_beta(&child, child_alpha, child_beta, depth - 1, &mut child_pv);
+
+            if self.aborted {
+                return None;
+            }
+
+            root_move.score = score;
+
+            if lines.len() < k || score > worst {
+                let mut pv = vec![root_move.action.clone()];
+                pv.append(&mut child_pv);
+
+                let index = lines.partition_point(|line| line.score >= score);
+                lines.insert(index, Line { score, pv });
+                lines.truncate(k);
+
+                if index == 0 {
+                    self.best_line = Some(lines[0].clone());
+                }
+            }
+        }
+
+        root.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+        Some(lines)
+    }
+
+    /// Negamax alpha-beta search, returning the value of `state` from the perspective of the
+    /// player to move, and writing the principal variation into `pv`.
+    fn alpha_beta<S>(&mut self, state: &S, alpha: V, beta: V, depth: u32, pv: &mut Vec<A>) -> V
+    where
+        S: State<V, A>,
+        E: Evaluator<S, V>,
+    {
+        pv.clear();
+
+        if self.should_abort() {
+            return V::zero();
+        }
+
+        if state.is_terminal() {
+            return self.relative_evaluation(state);
+        }
+
+        if depth == 0 {
+            self.reached_depth_limit = true;
+            return self.quiescence(state, alpha, beta, self.engine.quiescence_depth, pv);
+        }
+
+        if state.is_chance() {
+            return self.expected_value(state, depth);
+        }
+
+        let key = if self.table.is_enabled() {
+            state.hash_key()
+        } else {
+            None
+        };
+        let entry = key.and_then(|key| self.table.probe(key));
+
+        if key.is_some() {
+            self.stats.table_probes += 1;
+            self.stats.table_hits += entry.is_some() as u64;
+        }
+
+        if let Some(entry) = entry {
+            if entry.depth >= depth && entry.bound.allows_cutoff(entry.score, alpha, beta) {
+                self.reached_depth_limit |= entry.depth != EXHAUSTIVE;
+                return entry.score;
+            }
+        }
+
+        // Track whether this subtree reaches the depth limit separately from the rest of the tree
+        let outer_reached_depth_limit = mem::take(&mut self.reached_depth_limit);
+        let (value, best_move) = self.search_moves(
+            state,
+            alpha,
+            beta,
+            depth,
+            pv,
+            entry.and_then(|e| e.best_move),
+        );
+        let reached_depth_limit = self.reached_depth_limit;
+        self.reached_depth_limit |= outer_reached_depth_limit;
+
+        if let (Some(key), false) = (key, self.aborted) {
+            self.table.store(Entry {
+                key,
+                depth: if reached_depth_limit {
+                    depth
+                } else {
+                    EXHAUSTIVE
+                },
+                score: value,
+                bound: Bound::new(value, alpha, beta),
+                best_move,
+            });
+        }
+
+        value
+    }
+
+    /// The expected value of a chance `state` from the perspective of its player to move, with
+    /// every outcome searched to the same `depth`, since chance doesn't use up a ply.
+    ///
+    /// Each outcome is searched with a full window, as a bound on one of them says little about
+    /// the average. The principal variation ends at the chance state, since it depends on the
+    /// outcome.
+    fn expected_value<S>(&mut self, state: &S, depth: u32) -> V
+    where
+        S: State<V, A>,
+        E: Evaluator<S, V>,
+    {
+        let mut child_pv = self.pv_buffers.pop().unwrap_or_default();
+        let mut value = V::zero();
+
+        for (probability, outcome) in state.outcomes() {
+            let (alpha, beta, sign) =
+                child_window(state, &outcome, V::neg_infinity(), V::infinity());
+            value = value
+                + probability * sign * self.alpha_beta(&outcome, alpha, beta, depth, &mut child_pv);
+
+            if self.aborted {
+                break;
+            }
+        }
+
+        self.pv_buffers.push(child_pv);
+
+        if self.aborted {
+            return V::zero();
+        }
+
+        value
+    }
+
+    /// Searches the moves from a non-terminal `state`, trying `hash_move` first, and returns its
+    /// value along with the index of the best move in the order the moves were generated in.
+    fn search_moves<S>(
+        &mut self,
+        state: &S,
+        mut alpha: V,
+        beta: V,
+        depth: u32,
+        pv: &mut Vec<A>,
+        hash_move: Option<usize>,
+    ) -> (V, Option<usize>)
+    where
+        S: State<V, A>,
+        E: Evaluator<S, V>,
+    {
+        let mut best_value = V::neg_infinity();
+        let mut best_move = None;
+        let mut futile = false;
+
+        if !state.is_tactical() {
+            let margin = |margins: &[V]| margins.get(depth as usize - 1).copied();
+            let razoring_margin = margin(&self.engine.razoring_margins);
+            let futility_margin = margin(&self.engine.futility_margins);
+
+            if razoring_margin.is_some() || futility_margin.is_some() {
+                let evaluation = self.relative_evaluation(state);
+
+                if razoring_margin.is_some_and(|margin| evaluation + margin <= alpha) {
+                    let value =
+                        self.quiescence(state, alpha, beta, self.engine.quiescence_depth, pv);
+                    if value <= alpha {
+                        return (value, None);
+                    }
+                    pv.clear();
+                }
+
+                if let Some(margin) = futility_margin.filter(|m| evaluation + *m <= alpha) {
+                    futile = true;
+                    best_value = evaluation + margin;
+                }
+            }
+        }
+
+        let mut actions = self.ordered_actions(state);
+        let hash_move_position =
+            hash_move.and_then(|hash_move| actions.iter().position(|(i, ..)| *i == hash_move));
+        if let Some(position) = hash_move_position {
+            actions[..=position].rotate_right(1);
+        }
+
+        let mut child_pv = self.pv_buffers.pop().unwrap_or_default();
+        let mut searched = 0;
+
+        for (index, exchange, action) in actions.drain(..) {
+            if futile && exchange.is_none() {
+                continue;
+            }
+            searched += 1;
+
+            let child = state.result(&action);
+            let (child_alpha, child_beta, sign) = child_window(state, &child, alpha, beta);
+            let value =
+                sign * self.alpha_beta(&child, child_alpha, child_beta, depth - 1, &mut child_pv);
+
+            if self.aborted {
+                break;
+            }
+
+            // A futile node's value starts at its evaluation plus the margin, which isn't the value
+            // of any move, so its moves have to beat it to be the best
+            if value > best_value || (best_move.is_none() && !futile) {
+                best_move = Some(index);
+                pv.clear();
+                pv.push(action);
+                pv.append(&mut child_pv);
+            }
+
+            best_value = V::max(best_value, value);
+            alpha = V::max(alpha, value);
+
+            if alpha >= beta {
+                self.stats.cutoffs += 1;
+                self.stats.first_move_cutoffs += (searched == 1) as u64;
+                break;
+            }
+        }
+
+        self.action_buffers.push(actions);
+        self.pv_buffers.push(child_pv);
+
+        if self.aborted {
+            return (V::zero(), None);
+        }
+
+        (best_value, best_move)
+    }
+
+    /// Searches only non-quiet moves until the position is quiet, returning the value of `state`
+    /// from the perspective of the player to move.
+    ///
+    /// Non-quiet moves that lose material according to [`State::see`] are skipped, as the player
+    /// to move would rather keep the current evaluation.
+    fn quiescence<S>(&mut self, state: &S, mut alpha: V, beta: V, depth: u32, pv: &mut Vec<A>) -> V
+    where
+        S: State<V, A>,
+        E: Evaluator<S, V>,
+    {
+        pv.clear();
+
+        if self.should_abort() {
+            return V::zero();
+        }
+
+        let evaluation = self.relative_evaluation(state);
+        if state.is_terminal() || depth == 0 {
+            return evaluation;
+        }
+
+        // In tactical positions the evaluation can't be relied on, so every move is searched
+        let tactical = state.is_tactical();
+        let mut best_value = V::neg_infinity();
+
+        if !tactical {
+            if evaluation >= beta {
+                return evaluation;
+            }
+            best_value = evaluation;
+            alpha = V::max(alpha, evaluation);
+        }
+
+        let mut actions = self.ordered_actions(state);
+        let mut child_pv = self.pv_buffers.pop().unwrap_or_default();
+
+        for (_, exchange, action) in actions.drain(..) {
+            if !tactical && exchange.is_none_or(|exchange| exchange < V::zero()) {
+                continue;
+            }
+
+            let child = state.result(&action);
+            let (child_alpha, child_beta, sign) = child_window(state, &child, alpha, beta);
+            let value =
+                sign * self.quiescence(&child, child_alpha, child_beta, depth - 1, &mut child_pv);
+
+            if self.aborted {
+                break;
+            }
+
+            if value > best_value {
+                pv.clear();
+                pv.push(action);
+                pv.append(&mut child_pv);
+            }
+
+            best_value = V::max(best_value, value);
+            alpha = V::max(alpha, value);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        self.action_buffers.push(actions);
+        self.pv_buffers.push(child_pv);
+
+        if self.aborted {
+            return V::zero();
+        }
+
+        best_value
+    }
+
+    /// The available actions, with non-quiet moves first in descending order of their static
+    /// exchange evaluation. Quiet moves have no exchange value and keep the order they were
+    /// generated in. The buffer should be returned to `action_buffers` once it's no longer needed.
+    fn ordered_actions<S>(&mut self, state: &S) -> Vec<OrderedAction<V, A>>
+    where
+        S: State<V, A>,
+    {
+        let mut actions = self.action_buffers.pop().unwrap_or_default();
+
+        state.actions_into(&mut self.generated_actions);
+        actions.extend(
+            self.generated_actions
+                .drain(..)
+                .enumerate()
+                .map(|(index, action)| {
+                    let exchange = (!state.is_quiet(&action)).then(|| state.see(&action));
+                    (index, exchange, action)
+                }),
+        );
+
+        // Unlike a stable sort, this doesn't allocate, so ties are broken by the generated order.
+        // `None` sorts before `Some`, so exchanges are compared in reverse
+        actions.sort_unstable_by(|(i, a, _), (j, b, _)| {
+            b.partial_cmp(a).unwrap_or(Ordering::Equal).then(i.cmp(j))
+        });
+        actions
+    }
+
+    /// The evaluation of `state` from the perspective of the player to move.
+    fn relative_evaluation<S>(&self, state: &S) -> V
+    where
+        S: State<V, A>,
+        E: Evaluator<S, V>,
+    {
+        let evaluation = if state.is_terminal() {
+            state.evaluation()
+        } else {
+            self.evaluator.evaluate(state)
+        };
+
+        evaluation
+            * if state.current_player() == Player::Max {
+                V::one()
+            } else {
+                -V::one()
+            }
+    }
+}
@@ -1,7 +1,13 @@
 use num_traits::Float;
-use std::cmp::Ordering;
 use std::fmt::Debug;
 
+pub use engine::{Engine, EngineBuilder, SearchInfo, SearchResult, SearchStats, StopToken};
+pub use tt::{ReplacementPolicy, TranspositionTable};
+
+mod engine;
+mod tt;
+pub mod tune;
+
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum Player {
     #[default]
@@ -21,9 +27,99 @@ impl Player {
 pub trait State<V: Float, A: Clone> {
     fn is_terminal(&self) -> bool;
     fn evaluation(&self) -> V;
+
+    /// The player to move. Players don't have to alternate, so a move may leave the same player
+    /// to move again, like the extra turns in Kalah.
     fn current_player(&self) -> Player;
     fn actions(&self) -> Vec<A>;
     fn result(&self, action: &A) -> Self;
+
+    /// Appends the available actions to `actions`, which lets the search reuse the same buffer
+    /// at every node.
+    ///
+    /// Defaults to extending `actions` with [`State::actions`], so games that can generate their
+    /// moves without allocating should override this.
+    fn actions_into(&self, actions: &mut Vec<A>) {
+        actions.extend(self.actions());
+    }
+
+    /// Whether chance decides what happens next instead of the player to move, such as when the
+    /// dice are about to be rolled. Chance states are valued at the expected value of their
+    /// [`State::outcomes`] rather than by their actions, so a search has to start from a state
+    /// where a player is to move.
+    fn is_chance(&self) -> bool {
+        false
+    }
+
+    /// The states that chance can lead to from a chance state, each with its probability. The
+    /// probabilities should add up to one.
+    ///
+    /// An expected value can't mix outcomes that are won and lost by infinite scores, so games
+    /// with chance should score the end of the game with large finite values instead.
+    fn outcomes(&self) -> Vec<(V, Self)>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+
+    /// Whether `action` is a quiet move, which futility pruning is allowed to skip.
+    ///
+    /// Every move is quiet by default, so games enabling futility pruning should override this to
+    /// exclude moves that change the evaluation sharply (e.g. captures).
+    fn is_quiet(&self, action: &A) -> bool {
+        let _ = action;
+        true
+    }
+
+    /// The static exchange evaluation of `action`: the material the player to move expects to gain
+    /// from it once any exchange it starts has played out, on the same scale as the evaluation.
+    ///
+    /// It is used to order non-quiet moves and to skip losing ones during quiescence search.
+    /// Defaults to zero for every move.
+    fn see(&self, action: &A) -> V {
+        let _ = action;
+        V::zero()
+    }
+
+    /// Whether the evaluation of this state is unreliable, e.g. because the player to move is in
+    /// check. Pruning based on the evaluation is disabled in tactical states.
+    fn is_tactical(&self) -> bool {
+        false
+    }
+
+    /// A hash identifying this state, including the player to move, used to look it up in the
+    /// transposition table. States without a hash are never stored in the table.
+    fn hash_key(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Evaluates non-terminal states from the perspective of [`Player::Max`], in place of
+/// [`State::evaluation`].
+///
+/// This allows the same game to be searched with different evaluation functions. Terminal states
+/// are always scored by [`State::evaluation`], so evaluators only need to provide a heuristic.
+pub trait Evaluator<S, V> {
+    fn evaluate(&self, state: &S) -> V;
+}
+
+impl<S, V, F> Evaluator<S, V> for F
+where
+    F: Fn(&S) -> V,
+{
+    fn evaluate(&self, state: &S) -> V {
+        self(state)
+    }
+}
+
+/// A line of play from the root, with the principal variation starting at the root move.
+///
+/// The score is from the perspective of the player to move at the root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line<V, A> {
+    pub score: V,
+    pub pv: Vec<A>,
 }
 
 pub fn best_move<S, V, A>(state: &S, depth: u32) -> A
@@ -32,56 +128,677 @@ pub fn best_move<S, V, A>(state: &S, depth: u32) -> A
         V: Float,
         A: Clone,
 {
-    let cmp: fn(&V, &V) -> Option<Ordering> = match state.current_player() {
-        Player::Max => V::partial_cmp,
-        Player::Min => |a, b| V::partial_cmp(a, b).map(|o| o.reverse()),
-    };
-
-    state
-        .actions()
-        .into_iter()
-        .max_by(|x, y| {
-            let key = |action| minimax(&state.result(action), depth);
-            cmp(&key(x), &key(y)).unwrap_or(Ordering::Equal)
-        })
+    // Every available move is searched to the full depth, so the root itself isn't counted
+    Engine::new(depth.saturating_add(1))
+        .best_move(state)
         .expect("No moves available")
 }
 
-pub fn minimax<S, V, A>(state: &S, depth: u32) -> V
+/// Searches for the `k` best lines that start with distinct root moves, ordered from best to worst.
+///
+/// Fewer than `k` lines are returned if there aren't enough moves available.
+pub fn multi_pv<S, V, A>(state: &S, depth: u32, k: usize) -> Vec<Line<V, A>>
     where
         S: State<V, A>,
         V: Float,
         A: Clone,
 {
-    alpha_beta(state, V::neg_infinity(), V::infinity(), depth)
+    // Searched to the same depth as `best_move`, so that the best line starts with its move
+    Engine::new(depth.saturating_add(1)).multi_pv(state, k)
 }
 
-fn alpha_beta<S, V, A>(state: &S, mut alpha: V, beta: V, depth: u32) -> V
+pub fn minimax<S, V, A>(state: &S, depth: u32) -> V
     where
         S: State<V, A>,
         V: Float,
         A: Clone,
 {
-    if state.is_terminal() || depth == 0 {
-        return state.evaluation() * if state.current_player() == Player::Max {
-            V::one()
-        } else {
-            -V::one()
+    Engine::new(depth).value(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// A pile of stones where each player takes one or two in turn, and whoever takes the last
+    /// stone wins. Positions with a multiple of three stones are lost for the player to move.
+    #[derive(Clone, Debug)]
+    struct Pile {
+        stones: u32,
+        player: Player,
+    }
+
+    impl State<f64, u32> for Pile {
+        fn is_terminal(&self) -> bool {
+            self.stones == 0
+        }
+
+        fn evaluation(&self) -> f64 {
+            match (self.is_terminal(), self.player) {
+                (false, _) => 0.0,
+                (true, Player::Max) => f64::NEG_INFINITY,
+                (true, Player::Min) => f64::INFINITY,
+            }
+        }
+
+        fn current_player(&self) -> Player {
+            self.player
+        }
+
+        fn actions(&self) -> Vec<u32> {
+            (1..=self.stones.min(2)).collect()
+        }
+
+        fn result(&self, action: &u32) -> Self {
+            Pile {
+                stones: self.stones - action,
+                player: self.player.opposite(),
+            }
+        }
+
+        fn hash_key(&self) -> Option<u64> {
+            Some(2 * self.stones as u64 + self.player as u64)
+        }
+    }
+
+    /// Players take turns making one of the same trades, each gaining its material for the player
+    /// making it, until `plies` moves have been made.
+    #[derive(Clone, Debug)]
+    struct Trades {
+        material: f64,
+        player: Player,
+        plies: u32,
+        trades: &'static [Trade],
+    }
+
+    /// The material a trade gains, and its static exchange evaluation if it's a capture, which
+    /// can be wrong just like in real games.
+    #[derive(Debug)]
+    struct Trade {
+        gain: f64,
+        see: Option<f64>,
+    }
+
+    impl State<f64, usize> for Trades {
+        fn is_terminal(&self) -> bool {
+            self.plies == 0
+        }
+
+        fn evaluation(&self) -> f64 {
+            self.material
+        }
+
+        fn current_player(&self) -> Player {
+            self.player
+        }
+
+        fn actions(&self) -> Vec<usize> {
+            (0..self.trades.len()).collect()
+        }
+
+        fn result(&self, action: &usize) -> Self {
+            let sign = if self.player == Player::Max { 1.0 } else { -1.0 };
+            Trades {
+                material: self.material + sign * self.trades[*action].gain,
+                player: self.player.opposite(),
+                plies: self.plies - 1,
+                trades: self.trades,
+            }
+        }
+
+        fn is_quiet(&self, action: &usize) -> bool {
+            self.trades[*action].see.is_none()
+        }
+
+        fn see(&self, action: &usize) -> f64 {
+            self.trades[*action].see.unwrap_or(0.0)
         }
     }
 
-    let mut best_value = V::neg_infinity();
+    #[test]
+    fn best_move_for_both_players() {
+        for player in [Player::Max, Player::Min] {
+            let state = Pile { stones: 7, player };
+            assert_eq!(best_move(&state, u32::MAX), 1);
+        }
+    }
 
-    for action in state.actions() {
-        let value  = -alpha_beta(&state.result(&action), -beta, -alpha, depth - 1);
+    #[test]
+    fn multi_pv_orders_lines() {
+        let state = Pile {
+            stones: 5,
+            player: Player::Max,
+        };
+        let lines = multi_pv(&state, u32::MAX, 3);
 
-        best_value = V::max(best_value, value);
-        alpha = V::max(alpha, value);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].pv[0], 2);
+        assert_eq!(lines[0].score, f64::INFINITY);
+        assert_eq!(lines[1].pv[0], 1);
+        assert_eq!(lines[1].score, f64::NEG_INFINITY);
+        assert_eq!(lines[0].pv.iter().sum::<u32>(), 5);
+    }
 
-        if alpha >= beta {
-            break;
+    #[test]
+    fn multi_pv_matches_best_move() {
+        for stones in 1..=8 {
+            for depth in 0..=4 {
+                let state = Pile {
+                    stones,
+                    player: Player::Max,
+                };
+                let lines = multi_pv(&state, depth, 1);
+                let expected = best_move(&state, depth);
+                assert_eq!(lines[0].pv[0], expected, "{stones} stones, depth {depth}");
+            }
         }
     }
 
-    return best_value;
+    #[test]
+    fn evaluator_replaces_heuristic() {
+        let state = Pile {
+            stones: 4,
+            player: Player::Max,
+        };
+        let evaluator = |pile: &Pile| pile.stones as f64;
+
+        assert_eq!(Engine::new(0).value(&state), 0.0);
+        assert_eq!(Engine::new(0).value_with(&state, &evaluator), 4.0);
+        // Terminal states are still scored by the state itself
+        assert_eq!(Engine::new(4).value_with(&state, &evaluator), f64::INFINITY);
+    }
+
+    #[test]
+    fn alpha_beta_window() {
+        let engine = Engine::new(u32::MAX);
+        let lost = Pile {
+            stones: 9,
+            player: Player::Min,
+        };
+        let won = Pile {
+            stones: 10,
+            player: Player::Min,
+        };
+
+        assert_eq!(
+            engine.alpha_beta(&lost, f64::NEG_INFINITY, f64::INFINITY, u32::MAX),
+            f64::NEG_INFINITY
+        );
+        // Values outside the window are bounds in the same direction as the true value
+        assert!(engine.alpha_beta(&lost, -1.0, 1.0, u32::MAX) <= -1.0);
+        assert!(engine.alpha_beta(&won, -1.0, 1.0, u32::MAX) >= 1.0);
+    }
+
+    #[test]
+    fn extra_turns() {
+        /// Max adds one or two to a sum twice in a row, and then Min subtracts one or two.
+        #[derive(Clone, Debug, Default)]
+        struct Picks {
+            sum: i32,
+            picks: u32,
+        }
+
+        impl State<f64, i32> for Picks {
+            fn is_terminal(&self) -> bool {
+                self.picks == 3
+            }
+
+            fn evaluation(&self) -> f64 {
+                self.sum as f64
+            }
+
+            fn current_player(&self) -> Player {
+                if self.picks < 2 {
+                    Player::Max
+                } else {
+                    Player::Min
+                }
+            }
+
+            fn actions(&self) -> Vec<i32> {
+                vec![1, 2]
+            }
+
+            fn result(&self, action: &i32) -> Self {
+                let sign = if self.current_player() == Player::Max { 1 } else { -1 };
+                Picks {
+                    sum: self.sum + sign * action,
+                    picks: self.picks + 1,
+                }
+            }
+        }
+
+        let result = Engine::new(3).search(&Picks::default());
+        assert_eq!(result.score(), Some(2.0));
+        assert_eq!(result.pv(), [2, 2, 2]);
+        assert_eq!(Engine::new(3).value(&Picks::default()), 2.0);
+    }
+
+    #[test]
+    fn futility_pruning() {
+        // The quiet move gains more than the margin allows for, so skipping it changes the result
+        let state = Trades {
+            material: 0.0,
+            player: Player::Max,
+            plies: 4,
+            trades: &[
+                Trade {
+                    gain: 1.0,
+                    see: Some(1.0),
+                },
+                Trade {
+                    gain: 3.0,
+                    see: None,
+                },
+            ],
+        };
+        let search = |margin: f64| {
+            EngineBuilder::default()
+                .depth(4)
+                .futility_margins([margin])
+                .build()
+                .unwrap()
+                .search(&state)
+        };
+        let unpruned = Engine::new(4).search(&state);
+        let pruned = search(1.0);
+        let wide = search(10.0);
+
+        assert_eq!(unpruned.score(), Some(0.0));
+        assert_eq!(unpruned.pv(), [1, 1, 1, 1]);
+        // Once Min is below alpha it only tries the capture, missing its better quiet reply
+        assert_eq!(pruned.score(), Some(2.0));
+        assert_eq!(pruned.pv(), [1, 0, 1, 1]);
+        assert!(pruned.nodes < unpruned.nodes);
+        // Margins that no quiet move can make up never skip one
+        assert_eq!(wide.score(), unpruned.score());
+        assert_eq!(wide.pv(), unpruned.pv());
+        assert_eq!(wide.nodes, unpruned.nodes);
+    }
+
+    #[test]
+    fn static_exchange_evaluation() {
+        use std::cell::RefCell;
+
+        // The capture that looks like it loses material would really win the most
+        let state = Trades {
+            material: 0.0,
+            player: Player::Max,
+            plies: 4,
+            trades: &[
+                Trade {
+                    gain: 0.0,
+                    see: None,
+                },
+                Trade {
+                    gain: 5.0,
+                    see: Some(-1.0),
+                },
+                Trade {
+                    gain: 1.0,
+                    see: Some(1.0),
+                },
+                Trade {
+                    gain: 2.0,
+                    see: Some(2.0),
+                },
+            ],
+        };
+        let evaluated = RefCell::new(Vec::new());
+        let evaluator = |trades: &Trades| {
+            evaluated.borrow_mut().push(trades.material);
+            trades.material
+        };
+
+        // Without quiescence search, the root moves are evaluated in the order they're searched
+        Engine::new(1).search_with(&state, &evaluator);
+        assert_eq!(evaluated.take(), [2.0, 1.0, 5.0, 0.0]);
+
+        // Min only answers with the good captures, so Max's best move keeps three of its five
+        let result = EngineBuilder::default()
+            .depth(1)
+            .quiescence_depth(1)
+            .build()
+            .unwrap()
+            .search_with(&state, &evaluator);
+        assert_eq!(result.score(), Some(3.0));
+        assert_eq!(result.pv(), [1, 3]);
+    }
+
+    #[test]
+    fn chance() {
+        /// Max either takes a point, or flips a coin landing heads with probability `heads` to win
+        /// three points on heads and nothing on tails.
+        #[derive(Clone, Debug)]
+        struct Gamble {
+            heads: f64,
+            flipping: bool,
+            points: Option<f64>,
+        }
+
+        impl State<f64, bool> for Gamble {
+            fn is_terminal(&self) -> bool {
+                self.points.is_some()
+            }
+
+            fn evaluation(&self) -> f64 {
+                self.points.unwrap_or(0.0)
+            }
+
+            fn current_player(&self) -> Player {
+                Player::Max
+            }
+
+            fn actions(&self) -> Vec<bool> {
+                if self.flipping {
+                    Vec::new()
+                } else {
+                    vec![false, true]
+                }
+            }
+
+            fn result(&self, flip: &bool) -> Self {
+                Gamble {
+                    flipping: *flip,
+                    points: (!flip).then_some(1.0),
+                    ..*self
+                }
+            }
+
+            fn is_chance(&self) -> bool {
+                self.flipping && self.points.is_none()
+            }
+
+            fn outcomes(&self) -> Vec<(f64, Self)> {
+                [(self.heads, 3.0), (1.0 - self.heads, 0.0)]
+                    .map(|(probability, points)| {
+                        let outcome = Gamble {
+                            points: Some(points),
+                            ..*self
+                        };
+                        (probability, outcome)
+                    })
+                    .to_vec()
+            }
+        }
+
+        let gamble = |heads| Gamble {
+            heads,
+            flipping: false,
+            points: None,
+        };
+
+        let result = Engine::new(2).search(&gamble(0.5));
+        assert_eq!(result.score(), Some(1.5));
+        assert_eq!(result.pv(), [true]);
+        let result = Engine::new(2).search(&gamble(0.25));
+        assert_eq!(result.score(), Some(1.0));
+        assert_eq!(result.pv(), [false]);
+
+        let flipping = gamble(0.5).result(&true);
+        assert_eq!(Engine::new(1).value(&flipping), 1.5);
+        // Chance doesn't use up a ply, but the coin isn't flipped once the depth runs out
+        assert_eq!(Engine::new(0).value(&flipping), 0.0);
+        // There's nothing to choose until the coin has been flipped
+        assert!(Engine::new(2).search(&flipping).best_move().is_none());
+    }
+
+    #[test]
+    fn player_depths() {
+        let engine = EngineBuilder::default()
+            .depth(10)
+            .player_depth(Player::Max, 1)
+            .build()
+            .unwrap();
+
+        // Max can't see far enough to find the win, but Min can
+        let position = |player| Pile { stones: 5, player };
+        assert_eq!(engine.value(&position(Player::Max)), 0.0);
+        assert_eq!(engine.value(&position(Player::Min)), f64::INFINITY);
+    }
+
+    #[test]
+    fn transposition_table() {
+        let state = Pile {
+            stones: 20,
+            player: Player::Min,
+        };
+        let without_table = Engine::new(u32::MAX).search(&state);
+
+        for policy in [
+            ReplacementPolicy::AlwaysReplace,
+            ReplacementPolicy::DepthPreferred,
+            ReplacementPolicy::TwoBucket,
+        ] {
+            let engine = EngineBuilder::default()
+                .depth(u32::MAX)
+                .transposition_table_size(8)
+                .replacement_policy(policy)
+                .build()
+                .unwrap();
+            let result = engine.search(&state);
+
+            assert_eq!(result.score(), without_table.score());
+            assert_eq!(result.best_move(), without_table.best_move());
+            assert!(result.nodes < without_table.nodes);
+            assert!(result.stats.table_hits > 0);
+        }
+    }
+
+    #[test]
+    fn table_kept_between_searches() {
+        let engine = EngineBuilder::default()
+            .depth(u32::MAX)
+            .transposition_table_size(64)
+            .build()
+            .unwrap();
+        let state = Pile {
+            stones: 20,
+            player: Player::Max,
+        };
+
+        let mut table = engine.transposition_table();
+        let first = engine.search_with_table(&state, &Pile::evaluation, &mut table);
+        let second = engine.search_with_table(&state, &Pile::evaluation, &mut table);
+
+        assert_eq!(first.score(), second.score());
+        assert_eq!(first.best_move(), second.best_move());
+        assert!(second.nodes < first.nodes);
+    }
+
+    #[test]
+    fn iteration_callback() {
+        let state = Pile {
+            stones: 10,
+            player: Player::Max,
+        };
+        let mut depths = Vec::new();
+        let result = Engine::new(5).search_with_callback(
+            &state,
+            &Pile::evaluation,
+            &mut TranspositionTable::new(0, ReplacementPolicy::default()),
+            |result| depths.push((result.depth, result.nodes)),
+        );
+
+        assert_eq!(depths.iter().map(|(depth, _)| *depth).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+        assert_eq!(depths.last(), Some(&(result.depth, result.nodes)));
+    }
+
+    #[test]
+    fn progress_callback() {
+        let state = Pile {
+            stones: 1000,
+            player: Player::Max,
+        };
+        let engine = EngineBuilder::default()
+            .depth(16)
+            .progress_interval(Duration::ZERO)
+            .build()
+            .unwrap();
+        let mut reports = Vec::new();
+        let result = engine.search_with_progress(
+            &state,
+            &Pile::evaluation,
+            &mut engine.transposition_table(),
+            |_| {},
+            |info| reports.push((info.depth, info.nodes, info.best_line.cloned())),
+        );
+
+        assert!(!reports.is_empty());
+        assert!(reports.is_sorted_by_key(|(depth, nodes, _)| (*depth, *nodes)));
+        assert!(reports.iter().all(|(_, nodes, _)| *nodes <= result.nodes));
+        // Only the first iteration can be reported before any line has been found
+        assert!(reports
+            .iter()
+            .all(|(depth, _, line)| *depth == 1 || line.is_some()));
+    }
+
+    #[test]
+    fn temperature_sampling() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        // Taking one stone is scored a point higher than taking two
+        let evaluator = |pile: &Pile| pile.stones as f64;
+        let state = Pile {
+            stones: 10,
+            player: Player::Max,
+        };
+        let result = EngineBuilder::default()
+            .depth(1)
+            .multi_pv(2)
+            .build()
+            .unwrap()
+            .search_with(&state, &evaluator);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut samples = |temperature| {
+            (0..1000)
+                .filter(|_| result.sample_move(temperature, &mut rng) == Some(&2))
+                .count()
+        };
+
+        assert_eq!(samples(0.0), 0);
+        // The weaker move should be picked with probability 1 / (1 + e)
+        assert!((220..320).contains(&samples(1.0)));
+    }
+
+    #[test]
+    fn hard_time_limit() {
+        let engine = EngineBuilder::default()
+            .depth(1000)
+            .hard_time_limit(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        let state = Pile {
+            stones: 1000,
+            player: Player::Max,
+        };
+        let result = engine.search(&state);
+
+        assert!(result.depth >= 1 && result.depth < 1000);
+        assert!(result.best_move().is_some());
+        assert!(result.elapsed < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn time_control() {
+        // A single move never uses more than half of the time left
+        let engine = EngineBuilder::default()
+            .depth(1000)
+            .time_control(Duration::from_millis(40), Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let state = Pile {
+            stones: 1000,
+            player: Player::Max,
+        };
+        let result = engine.search(&state);
+
+        assert!(result.depth >= 1 && result.depth < 1000);
+        assert!(result.elapsed < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn node_limit() {
+        let engine = EngineBuilder::default()
+            .depth(1000)
+            .node_limit(10_000)
+            .build()
+            .unwrap();
+        let state = Pile {
+            stones: 1000,
+            player: Player::Max,
+        };
+        let result = engine.search(&state);
+
+        assert!(result.depth >= 1);
+        // Only the aborted iteration can go over the limit, and only by its last node
+        assert!(result.stats.nodes_per_depth.iter().sum::<u64>() <= 10_000);
+        assert_eq!(result.nodes, 10_001);
+    }
+
+    #[test]
+    fn stop_token() {
+        let token = StopToken::new();
+        let engine = EngineBuilder::default()
+            .depth(1000)
+            .stop_token(token.clone())
+            .build()
+            .unwrap();
+        let state = Pile {
+            stones: 1000,
+            player: Player::Max,
+        };
+
+        let stopper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            token.stop();
+        });
+        let result = engine.search(&state);
+        stopper.join().unwrap();
+
+        assert!(result.depth >= 1 && result.depth < 1000);
+        assert!(result.best_move().is_some());
+
+        // The token can be given to an engine that's already built, and stops it straight away
+        let token = StopToken::new();
+        token.stop();
+        let result = Engine::new(1000).with_stop_token(token).search(&state);
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn easy_move() {
+        // A perfect evaluation, so the best move is clear from the first iteration
+        let evaluator = |pile: &Pile| {
+            let sign = if pile.player == Player::Max { 1.0 } else { -1.0 };
+            if pile.stones.is_multiple_of(3) {
+                -sign
+            } else {
+                sign
+            }
+        };
+        let state = Pile {
+            stones: 100,
+            player: Player::Max,
+        };
+
+        let mut builder = EngineBuilder::default();
+        builder.depth(10);
+        let full = builder.build().unwrap().search_with(&state, &evaluator);
+        let easy = builder
+            .easy_move_margin(1.0)
+            .build()
+            .unwrap()
+            .search_with(&state, &evaluator);
+
+        assert_eq!(full.depth, 10);
+        assert_eq!(full.stats.nodes_per_depth.len(), 10);
+        assert_eq!(full.stats.nodes_per_depth.iter().sum::<u64>(), full.nodes);
+        assert!(full.stats.effective_branching_factor().is_some());
+        assert_eq!(easy.depth, 3);
+        assert_eq!(easy.best_move(), Some(&1));
+    }
 }
@@ -0,0 +1,221 @@
+//! A transposition table, which lets the search reuse the results of states reached through
+//! different orders of moves.
+
+use std::ops::Range;
+
+/// How the transposition table chooses which entry to overwrite when a new one is stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplacementPolicy {
+    /// New entries always overwrite the existing one, keeping the table filled with the most
+    /// recently searched states.
+    AlwaysReplace,
+    /// Entries are only overwritten by searches at least as deep, keeping the results that were
+    /// the most expensive to find.
+    #[default]
+    DepthPreferred,
+    /// Each slot holds two entries: one that is only overwritten by deeper searches or once it's
+    /// from an earlier iteration, and one that is always overwritten.
+    TwoBucket,
+}
+
+/// Whether a stored score is exact, or only a bound on the true score because of a cutoff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+impl Bound {
+    /// The bound on the true value given by `value`, the result of a search with the window
+    /// `alpha..beta`.
+    pub fn new<V: PartialOrd>(value: V, alpha: V, beta: V) -> Self {
+        if value <= alpha {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        }
+    }
+
+    /// Whether a stored `score` with this bound can be returned from a search with the window
+    /// `alpha..beta` without changing its result.
+    pub fn allows_cutoff<V: PartialOrd>(self, score: V, alpha: V, beta: V) -> bool {
+        match self {
+            Bound::Exact => true,
+            Bound::Lower => score >= beta,
+            Bound::Upper => score <= alpha,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Entry<V> {
+    pub key: u64,
+    /// The remaining depth the state was searched to.
+    pub depth: u32,
+    pub score: V,
+    pub bound: Bound,
+    /// The index of the best move, in the order the search generates moves in.
+    pub best_move: Option<usize>,
+}
+
+/// A table of previously searched states, which can be kept between searches with
+/// [`Engine::search_with_table`](crate::Engine::search_with_table).
+#[derive(Clone, Debug)]
+pub struct TranspositionTable<V> {
+    /// The entries, each stored with the iteration it was stored in.
+    entries: Vec<Option<(Entry<V>, u32)>>,
+    policy: ReplacementPolicy,
+    /// The current iteration, counted across every search using the table.
+    age: u32,
+}
+
+impl<V: Copy> TranspositionTable<V> {
+    /// Creates a table holding up to `size` entries. A table without any entries is disabled.
+    pub fn new(size: usize, policy: ReplacementPolicy) -> Self {
+        let size = size - size % Self::bucket_size(policy);
+        Self {
+            entries: vec![None; size],
+            policy,
+            age: 0,
+        }
+    }
+
+    /// The number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Removes every entry, e.g. before starting a new game.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.age = 0;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// Marks the start of a new iteration of iterative deepening.
+    pub(crate) fn next_iteration(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    pub(crate) fn probe(&self, key: u64) -> Option<Entry<V>> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        self.entries[self.bucket(key)]
+            .iter()
+            .flatten()
+            .map(|(entry, _)| entry)
+            .find(|entry| entry.key == key)
+            .copied()
+    }
+
+    pub(crate) fn store(&mut self, entry: Entry<V>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let age = self.age;
+        let range = self.bucket(entry.key);
+        let bucket = &mut self.entries[range];
+
+        let deeper = |(old, _): &(Entry<V>, u32)| old.key == entry.key || entry.depth >= old.depth;
+
+        match self.policy {
+            ReplacementPolicy::AlwaysReplace => bucket[0] = Some((entry, age)),
+            ReplacementPolicy::DepthPreferred => {
+                if bucket[0].as_ref().is_none_or(deeper) {
+                    bucket[0] = Some((entry, age));
+                }
+            }
+            ReplacementPolicy::TwoBucket => {
+                if bucket[0]
+                    .as_ref()
+                    .is_none_or(|old| deeper(old) || old.1 != age)
+                {
+                    // The displaced entry is still likely to be useful, so it gets another chance
+                    // in the other half of the bucket
+                    if let Some(old) = bucket[0].filter(|(old, _)| old.key != entry.key) {
+                        bucket[1] = Some(old);
+                    }
+                    bucket[0] = Some((entry, age));
+                } else {
+                    bucket[1] = Some((entry, age));
+                }
+            }
+        }
+    }
+
+    fn bucket_size(policy: ReplacementPolicy) -> usize {
+        match policy {
+            ReplacementPolicy::TwoBucket => 2,
+            _ => 1,
+        }
+    }
+
+    /// The range of entries that `key` can be stored in.
+    fn bucket(&self, key: u64) -> Range<usize> {
+        let size = Self::bucket_size(self.policy);
+        let buckets = (self.entries.len() / size) as u64;
+        let start = (key % buckets) as usize * size;
+        start..start + size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, depth: u32) -> Option<Entry<f64>> {
+        Some(Entry {
+            key,
+            depth,
+            score: 0.0,
+            bound: Bound::Exact,
+            best_move: None,
+        })
+    }
+
+    #[test]
+    fn replacement_policies() {
+        // Keys 0, 4, 8 and 12 all share a bucket
+        // Entries are stored in order, with `None` starting a new iteration
+        let stored = |policy, entries: &[Option<Entry<f64>>]| {
+            let mut table = TranspositionTable::new(4, policy);
+            for entry in entries {
+                match entry {
+                    Some(entry) => table.store(*entry),
+                    None => table.next_iteration(),
+                }
+            }
+            [0, 4, 8, 12].map(|key| table.probe(key).is_some())
+        };
+
+        let entries = [entry(0, 5), entry(4, 1), entry(8, 1)];
+        assert_eq!(
+            stored(ReplacementPolicy::AlwaysReplace, &entries),
+            [false, false, true, false]
+        );
+        assert_eq!(
+            stored(ReplacementPolicy::DepthPreferred, &entries),
+            [true, false, false, false]
+        );
+        assert_eq!(
+            stored(ReplacementPolicy::TwoBucket, &entries),
+            [true, false, true, false]
+        );
+
+        // Entries from earlier iterations are replaced regardless of their depth
+        let entries = [entry(0, 5), entry(4, 1), None, entry(12, 1)];
+        assert_eq!(
+            stored(ReplacementPolicy::TwoBucket, &entries),
+            [true, false, false, true]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Texel-style tuning of evaluation parameters.
+//!
+//! The tuner fits a vector of evaluation parameters against a set of positions labelled with the
+//! result of the game they were taken from. The error being minimised is the mean squared
+//! difference between each result and the win probability predicted by the evaluation, where the
+//! prediction is `sigmoid(scaling * evaluation)`.
+
+/// A position labelled with the result of the game it was taken from.
+///
+/// The result is from the perspective of [`Player::Max`](crate::Player::Max): `1.0` for a win,
+/// `0.5` for a draw and `0.0` for a loss.
+#[derive(Clone, Debug)]
+pub struct Sample<P> {
+    pub position: P,
+    pub result: f64,
+}
+
+/// Local search tuner, as originally described for the Texel chess engine.
+///
+/// Each iteration tries to move every parameter up or down by `step`, keeping any change that
+/// lowers the error. Tuning stops once an iteration makes no improvement, or after
+/// `max_iterations` iterations.
+#[derive(Clone, Debug)]
+pub struct Tuner {
+    pub step: f64,
+    pub max_iterations: usize,
+    /// The scaling constant used by the sigmoid. If `None`, the scaling that minimises the error
+    /// of the initial parameters is used.
+    pub scaling: Option<f64>,
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Self {
+            step: 1.0,
+            max_iterations: 100,
+            scaling: None,
+        }
+    }
+}
+
+impl Tuner {
+    /// Tunes `params` in place, returning the final error.
+    ///
+    /// `eval` evaluates a position from [`Player::Max`](crate::Player::Max)'s perspective using
+    /// the given parameters.
+    pub fn tune<P, F>(&self, samples: &[Sample<P>], params: &mut [f64], eval: F) -> f64
+    where
+        F: Fn(&P, &[f64]) -> f64,
+    {
+        let scaling = self
+            .scaling
+            .unwrap_or_else(|| optimal_scaling(samples, params, &eval));
+
+        let mut best_error = error(samples, params, scaling, &eval);
+
+        for _ in 0..self.max_iterations {
+            let mut improved = false;
+
+            for i in 0..params.len() {
+                let original = params[i];
+
+                for delta in [self.step, -self.step] {
+                    params[i] = original + delta;
+                    let new_error = error(samples, params, scaling, &eval);
+                    if new_error < best_error {
+                        best_error = new_error;
+                        improved = true;
+                        break;
+                    }
+                    params[i] = original;
+                }
+            }
+
+            if !improved {
+                break;
+            }
+        }
+
+        best_error
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-x / 400.0))
+}
+
+/// The mean squared error between the results of `samples` and their predicted results.
+pub fn error<P, F>(samples: &[Sample<P>], params: &[f64], scaling: f64, eval: &F) -> f64
+where
+    F: Fn(&P, &[f64]) -> f64,
+{
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let total: f64 = samples
+        .iter()
+        .map(|sample| {
+            let predicted = sigmoid(scaling * eval(&sample.position, params));
+            (sample.result - predicted).powi(2)
+        })
+        .sum();
+
+    total / samples.len() as f64
+}
+
+/// Finds the sigmoid scaling constant that minimises the error of `params`.
+pub fn optimal_scaling<P, F>(samples: &[Sample<P>], params: &[f64], eval: &F) -> f64
+where
+    F: Fn(&P, &[f64]) -> f64,
+{
+    // The error is unimodal in the scaling constant, so a ternary search is sufficient
+    let (mut low, mut high) = (0.0, 10.0);
+    for _ in 0..100 {
+        let a = low + (high - low) / 3.0;
+        let b = high - (high - low) / 3.0;
+        if error(samples, params, a, eval) < error(samples, params, b, eval) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+    (low + high) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_linear_weight() {
+        // Positions are a single feature, with results generated by a weight of 50
+        let samples: Vec<_> = (-10..=10)
+            .map(|x| Sample {
+                position: x as f64,
+                result: sigmoid(50.0 * x as f64),
+            })
+            .collect();
+
+        let mut params = [10.0];
+        let tuner = Tuner {
+            scaling: Some(1.0),
+            max_iterations: 1000,
+            ..Default::default()
+        };
+        let error = tuner.tune(&samples, &mut params, |x, params| x * params[0]);
+
+        assert_eq!(params[0], 50.0);
+        assert!(error < 1e-9);
+    }
+}
@@ -0,0 +1,207 @@
+# This file is automatically generated by pyo3_stub_gen
+# ruff: noqa: E501, F401
+
+import builtins
+import typing
+from enum import Enum
+
+class Engine:
+    r"""
+    A search engine that keeps its transposition table between searches, which makes searching
+    successive states of the same game faster.
+    
+    Takes the same arguments as [`best_move`], except that the transposition table is enabled by
+    default.
+    """
+    def __new__(cls, depth:typing.Optional[builtins.int]=None, **options) -> Engine: ...
+    def best_move(self, state:typing.Any) -> typing.Any:
+        r"""
+        Finds the best move from `state` like [`best_move`].
+        """
+    def search(self, state:typing.Any, callback:typing.Optional[typing.Any]=None, progress:typing.Optional[typing.Any]=None) -> SearchResult:
+        r"""
+        Searches `state`, calling `callback` with the depth, score and principal variation after
+        every completed iteration if it's given, and `progress` with a [`SearchInfo`] at most every
+        `progress_interval` during iterations.
+        """
+    def search_async(self, state:typing.Any, callback:typing.Optional[typing.Any]=None, progress:typing.Optional[typing.Any]=None) -> typing.Any:
+        r"""
+        Searches `state` like [`Engine::search`] in the running event loop's default executor,
+        returning an asyncio future that completes with the result. The GIL is only held while
+        calling into Python, so the event loop keeps running during the search. Cancelling the
+        future stops the search.
+        """
+    def alpha_beta(self, state:typing.Any, alpha:builtins.float, beta:builtins.float, depth:builtins.int) -> builtins.float:
+        r"""
+        A single alpha-beta search of `state` to `depth` plies with the window `alpha..beta`,
+        returning its value from the perspective of the player to move. Values outside the window
+        are only bounds on the true value, which lets drivers like MTD(f) be written in Python.
+        
+        The engine's transposition table is kept between calls, so repeated searches of the same
+        state are cheaper, while its time and node limits are ignored.
+        """
+    def stop(self) -> None:
+        r"""
+        Stops the current search, which then returns the result of the last depth it completed.
+        If no search is running, the next one is stopped as soon as it starts.
+        
+        This is meant to be called from another thread, or from a callback passed to
+        [`Engine::search`].
+        """
+    def new_game(self) -> None:
+        r"""
+        Clears the transposition table, so that nothing from the previous game is reused.
+        """
+
+class SearchInfo:
+    r"""
+    A report on a running search, passed to the `progress` callback of [`search`].
+    """
+    depth: builtins.int
+    r"""
+    The depth of the iteration being searched, not counting the root move.
+    """
+    nodes: builtins.int
+    r"""
+    The number of nodes searched so far.
+    """
+    elapsed: builtins.float
+    r"""
+    The time since the search started, in seconds.
+    """
+    score: typing.Optional[builtins.float]
+    r"""
+    The score of the best move found so far, if any.
+    """
+    best_move: typing.Optional[typing.Any]
+    r"""
+    The best move found so far, if any.
+    """
+    pv: builtins.list[typing.Any]
+    r"""
+    The principal variation of the best move found so far.
+    """
+    def __repr__(self) -> builtins.str: ...
+
+class SearchResult:
+    r"""
+    The result of [`search`].
+    """
+    score: typing.Optional[builtins.float]
+    r"""
+    The score of the best move, from the perspective of the player to move.
+    """
+    depth: builtins.int
+    r"""
+    The depth of the last completed iteration, not counting the root move.
+    """
+    nodes: builtins.int
+    r"""
+    The number of nodes searched.
+    """
+    elapsed: builtins.float
+    r"""
+    The time taken by the search, in seconds.
+    """
+    best_move: typing.Optional[typing.Any]
+    r"""
+    The best move found, or `None` if there were no moves available.
+    """
+    pv: builtins.list[typing.Any]
+    r"""
+    The principal variation, starting with the best move.
+    """
+    def __repr__(self) -> builtins.str: ...
+
+class Player(Enum):
+    r"""
+    A player in a two-player game. `MAX` is the player that `heuristic_value` is from the
+    perspective of, for whom `is_maximising_player` returns true.
+    """
+    MAX = ...
+    MIN = ...
+
+    def opposite(self) -> Player: ...
+
+def alpha_beta(state:typing.Any, alpha:builtins.float, beta:builtins.float, depth:builtins.int) -> builtins.float:    r"""
+    Searches `state` like [`Engine::alpha_beta`], without a transposition table.
+    """
+
+def best_move(state:typing.Any, depth:typing.Optional[builtins.int]=None, **options) -> typing.Any:    r"""
+    Finds the best move from `state`, searching to `depth` plies after the move if given, and
+    raises [`SearchError`] if there are no moves. The search is configured by keyword arguments:
+    
+    - `time_limit`: the time to search for, in milliseconds.
+    - `node_limit`: the number of nodes to search.
+    - `tt_size`: the number of transposition table entries, which are only used for states with a
+      `hash_key` method.
+    - `threads`: the number of threads to search with, which must be 1 for now.
+    - `temperature`: if given, moves are sampled by the softmax of their scores divided by the
+      temperature instead of always playing the best one.
+    - `seed`: the seed of the random choices made with `temperature`.
+    - `progress_interval`: the minimum time between calls to a search's `progress` callback, in
+      milliseconds.
+    
+    Searches limited by time or nodes keep deepening until they run out, unless `depth` is given.
+    """
+
+def best_moves(states:typing.Sequence[typing.Any], depth:typing.Optional[builtins.int]=None, *, workers:typing.Optional[builtins.int]=None, **options) -> builtins.list[typing.Any]:    r"""
+    Finds the best moves from each of `states` like [`best_move`], searching them in parallel on
+    `workers` threads, or on one per CPU if not given. Raises `ValueError` if `workers` is 0.
+    
+    The states only share the GIL, so the searches can only run in parallel while none of them is
+    calling into Python.
+    """
+
+def search(state:typing.Any, depth:typing.Optional[builtins.int]=None, *, callback:typing.Optional[typing.Any]=None, progress:typing.Optional[typing.Any]=None, **options) -> SearchResult:    r"""
+    Searches `state` like [`best_move`], returning the score and principal variation along with
+    the best move. See [`Engine::search`] for `callback` and `progress`.
+    """
+
+def search_async(state:typing.Any, depth:typing.Optional[builtins.int]=None, *, callback:typing.Optional[typing.Any]=None, progress:typing.Optional[typing.Any]=None, **options) -> typing.Any:    r"""
+    Searches `state` like [`search`] without blocking the event loop, returning an asyncio future. See
+    [`Engine::search_async`].
+    """
+
+class SearchError(Exception): ...
+
+
+from abc import ABC, abstractmethod
+from typing import Generic, List, TypeVar
+
+A = TypeVar("A")
+
+
+class State(ABC, Generic[A]):
+    """A base class for states searched by djinn_py.
+
+    Subclasses have to implement every abstract method, and can't be instantiated until they do.
+    They may also define `hash_key(self) -> Optional[int]`, returning a 64-bit hash identifying
+    the state (including the player to move), which lets the search store them in the
+    transposition table, and `expand(self) -> List[Tuple[A, State]]`, returning every action
+    along with the state it results in, which fetches all of them at once instead of calling
+    `result` for each.
+    """
+
+    __slots__ = ()
+
+    @abstractmethod
+    def is_terminal(self) -> bool:
+        """Returns True if the game is over in this state."""
+
+    @abstractmethod
+    def heuristic_value(self) -> float:
+        """Returns the value of the state from the perspective of the maximising player."""
+
+    @abstractmethod
+    def is_maximising_player(self) -> bool:
+        """Returns True if the maximising player is to move, and False otherwise."""
+
+    @abstractmethod
+    def actions(self) -> List[A]:
+        """Returns the actions available to the player to move."""
+
+    @abstractmethod
+    def result(self, action: A) -> "State[A]":
+        """Returns the state after playing `action`, which is one of this state's actions."""
+
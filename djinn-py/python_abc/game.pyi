@@ -1,5 +1,5 @@
 from abc import ABC, abstractmethod
-from typing import Optional, Tuple, List
+from typing import Optional, Tuple, List, Union
 
 class Game(ABC):
     @abstractmethod
@@ -28,8 +28,8 @@ class Game(ABC):
         pass
 
     @abstractmethod
-    def win_state(self) -> Optional[bool]:
-        """Returns the win state, returning True for a decisive win, False for a draw, and None if the game is in progress."""
+    def win_state(self) -> Optional[Union[int, bool]]:
+        """Returns the win state, returning the index of the winning side (0 for the side that moves first), False for a draw, and None if the game is in progress. True is also accepted for a win by whoever moved last."""
         pass
 
     @abstractmethod
@@ -0,0 +1,9 @@
+//! Writes `djinn_py.pyi`, which has to be done whenever the bindings change.
+
+use std::fs;
+
+fn main() -> pyo3_stub_gen::Result<()> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/djinn_py.pyi");
+    fs::write(path, djinn_py::stub()?)?;
+    Ok(())
+}
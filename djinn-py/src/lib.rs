@@ -1,81 +1,1323 @@
-use pyo3::prelude::*;
+//! Python bindings for the minimax engine. The module's API is described for type checkers by
+//! `djinn_py.pyi`, which maturin bundles with the module. It's generated from the bindings by
+//! `cargo run --bin stub_gen`, which has to be run again whenever they change.
+
+use std::cell::{OnceCell, RefCell};
+use std::ffi::CString;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::time::Duration;
+
 use djinn_minimax as minimax;
+use minimax::State as _;
+use pyo3::exceptions::{PyException, PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict, PyTuple};
+use pyo3_stub_gen::derive::{
+    gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pyfunction, gen_stub_pymethods,
+};
+use pyo3_stub_gen::{create_exception, define_stub_info_gatherer};
+use rayon::prelude::*;
+
+create_exception!(
+    djinn_py,
+    SearchError,
+    PyException,
+    "Raised when a search can't find a move."
+);
+
+/// The number of transposition table entries used by an [`Engine`] unless it's given a `tt_size`.
+const DEFAULT_TT_SIZE: usize = 1 << 16;
+
+/// A Python object implementing the minimax state interface.
+///
+/// The search can't be interrupted by the state, so the first exception raised by a Python method
+/// is stored in the context shared by every state reached during the search. From then on every
+/// state reports itself as terminal, so that the search finishes as quickly as possible and the
+/// exception can be raised once it returns.
+///
+/// The search asks the same state for the same information several times, so the result of every
+/// Python method is cached with the state, making each one cost a single call and GIL acquisition.
+struct State {
+    object: PyObject,
+    context: Rc<Context>,
+    cache: Cache,
+}
+
+struct Context {
+    error: RefCell<Option<PyErr>>,
+    /// Whether the states implement the optional `hash_key` method.
+    has_hash_key: bool,
+    /// Whether the states implement the optional `expand` method, returning each action along with
+    /// its resulting state.
+    has_expand: bool,
+}
+
+#[derive(Default)]
+struct Cache {
+    is_terminal: OnceCell<bool>,
+    evaluation: OnceCell<f64>,
+    is_maximising_player: OnceCell<bool>,
+    hash_key: OnceCell<Option<u64>>,
+    /// The actions and resulting states returned by `expand`.
+    children: RefCell<Vec<(Action, PyObject)>>,
+}
+
+/// The methods every state has to implement, which are checked before searching so that missing
+/// ones are reported up front.
+const REQUIRED_METHODS: [&str; 5] = [
+    "is_terminal",
+    "heuristic_value",
+    "is_maximising_player",
+    "actions",
+    "result",
+];
+
+impl State {
+    fn new(object: PyObject) -> PyResult<Self> {
+        let (has_hash_key, has_expand) = Python::with_gil(|py| {
+            let object = object.bind(py);
+            for name in REQUIRED_METHODS {
+                if !object.hasattr(name)? {
+                    return Err(PyTypeError::new_err(format!(
+                        "'{}' object is missing the '{name}' method required of states",
+                        object.get_type().qualname()?
+                    )));
+                }
+            }
+            PyResult::Ok((object.hasattr("hash_key")?, object.hasattr("expand")?))
+        })?;
+
+        Ok(Self {
+            object,
+            context: Rc::new(Context {
+                error: RefCell::default(),
+                has_hash_key,
+                has_expand,
+            }),
+            cache: Cache::default(),
+        })
+    }
+
+    /// The state reached from this one, sharing its context.
+    fn child(&self, object: PyObject) -> Self {
+        Self {
+            object,
+            context: self.context.clone(),
+            cache: Cache::default(),
+        }
+    }
+
+    fn failed(&self) -> bool {
+        self.context.error.borrow().is_some()
+    }
+
+    /// Calls the Python method `name`, returning `fallback` if it raises an exception or returns
+    /// a value of the wrong type.
+    fn call<T, A>(&self, name: &str, args: A, fallback: T) -> T
+    where
+        T: for<'py> FromPyObject<'py>,
+        A: for<'py> IntoPyObject<'py, Target = PyTuple>,
+    {
+        Python::with_gil(|py| self.call_with_gil(py, name, args, fallback))
+    }
+
+    /// Like [`State::call`], for when the GIL is already held.
+    fn call_with_gil<'py, T>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        args: impl IntoPyObject<'py, Target = PyTuple>,
+        fallback: T,
+    ) -> T
+    where
+        T: for<'a> FromPyObject<'a>,
+    {
+        if self.failed() {
+            return fallback;
+        }
+
+        let value = self.object.call_method1(py, name, args).and_then(|value| {
+            value.extract(py).map_err(|err| {
+                PyTypeError::new_err(format!(
+                    "the state's '{name}' method returned a value of the wrong type: {err}"
+                ))
+            })
+        });
+
+        value.unwrap_or_else(|err| {
+            self.record_error(err);
+            fallback
+        })
+    }
+
+    /// Stores `err` to be raised once the search returns, unless an exception was already raised.
+    fn record_error(&self, err: PyErr) {
+        self.context.error.borrow_mut().get_or_insert(err);
+    }
+
+    /// The exception raised during the search, if there was one.
+    fn take_error(&self) -> PyResult<()> {
+        match self.context.error.borrow_mut().take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
 
-struct State(PyObject);
+/// An action returned by a state's `actions` method, which can be any Python object.
+#[derive(FromPyObject)]
+struct Action(PyObject);
 
-impl<'a> Default for State {
-    fn default() -> Self {
-        panic!("Default not implemented for PyStateWrapper");
+impl Clone for Action {
+    fn clone(&self) -> Self {
+        Python::with_gil(|py| Action(self.0.clone_ref(py)))
     }
 }
 
-impl minimax::State<f64, String> for State {
+impl minimax::State<f64, Action> for State {
     fn is_terminal(&self) -> bool {
-        Python::with_gil(|py| {
-            self.0
-                .call_method0(py, "is_terminal")
-                .expect("Failed to call Python method 'is_terminal'")
-                .extract(py)
-                .expect("Failed to extract bool from Python")
-        })
+        self.failed()
+            || *self
+                .cache
+                .is_terminal
+                .get_or_init(|| self.call("is_terminal", (), true))
     }
 
     fn evaluation(&self) -> f64 {
-        Python::with_gil(|py| {
-            self.0
-                .call_method0(py, "heuristic_value")
-                .expect("Failed to call Python method 'heuristic_value'")
-                .extract(py)
-                .expect("Failed to extract f64 from Python")
-        })
+        *self
+            .cache
+            .evaluation
+            .get_or_init(|| self.call("heuristic_value", (), 0.0))
     }
 
     fn current_player(&self) -> minimax::Player {
+        let maximising = self
+            .cache
+            .is_maximising_player
+            .get_or_init(|| self.call("is_maximising_player", (), true));
+
+        if *maximising {
+            minimax::Player::Max
+        } else {
+            minimax::Player::Min
+        }
+    }
+
+    fn actions(&self) -> Vec<Action> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Action>) {
         Python::with_gil(|py| {
-            let is_maximising_player = self.0
-                .call_method0(py, "is_maximising_player")
-                .expect("Failed to call Python method 'is_maximising_player'")
-                .extract(py)
-                .expect("Failed to extract bool from Python");
-            if is_maximising_player {
-                minimax::Player::Max
-            } else {
-                minimax::Player::Min
+            if !self.context.has_expand {
+                let generated: Vec<Action> = self.call_with_gil(py, "actions", (), Vec::new());
+                actions.extend(generated);
+                return;
             }
+
+            // Every resulting state is fetched at once, and kept until the search asks for it
+            let children: Vec<(Action, PyObject)> =
+                self.call_with_gil(py, "expand", (), Vec::new());
+            actions.extend(
+                children
+                    .iter()
+                    .map(|(action, _)| Action(action.0.clone_ref(py))),
+            );
+            *self.cache.children.borrow_mut() = children;
         })
     }
 
-    fn actions(&self) -> Vec<String> {
-        Python::with_gil(|py| {
-            self.0
-                .call_method0(py, "actions")
-                .expect("Failed to call Python method 'actions'")
-                .extract(py)
-                .expect("Failed to extract Vec<String> from Python")
+    fn result(&self, action: &Action) -> Self {
+        let object = Python::with_gil(|py| {
+            let children = self.cache.children.borrow();
+            let child = children
+                .iter()
+                .find(|(other, _)| other.0.is(&action.0))
+                .map(|(_, child)| child.clone_ref(py));
+
+            child.unwrap_or_else(|| {
+                let args = (action.0.clone_ref(py),);
+                self.call_with_gil(py, "result", args, self.object.clone_ref(py))
+            })
+        });
+
+        self.child(object)
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        if !self.context.has_hash_key {
+            return None;
+        }
+
+        *self
+            .cache
+            .hash_key
+            .get_or_init(|| self.call("hash_key", (), None))
+    }
+}
+
+/// Configures an engine searching to `depth` plies after the root move with the keyword arguments
+/// described by [`best_move`], and a transposition table of `table_size` entries unless `tt_size`
+/// is given.
+fn engine_builder(
+    depth: Option<u32>,
+    options: Option<&Bound<'_, PyDict>>,
+    table_size: usize,
+) -> PyResult<minimax::EngineBuilder<f64>> {
+    let mut builder = minimax::EngineBuilder::default();
+    builder.transposition_table_size(table_size);
+    let mut limited = false;
+
+    for (key, value) in options.into_iter().flatten() {
+        match key.extract::<String>()?.as_str() {
+            "time_limit" => {
+                let limit = Duration::from_millis(value.extract()?);
+                // An iteration started halfway through is unlikely to finish in time
+                builder.hard_time_limit(limit).soft_time_limit(limit / 2);
+                limited = true;
+            }
+            "node_limit" => {
+                builder.node_limit(value.extract()?);
+                limited = true;
+            }
+            "tt_size" => {
+                builder.transposition_table_size(value.extract()?);
+            }
+            "threads" => {
+                if value.extract::<usize>()? != 1 {
+                    return Err(PyValueError::new_err(
+                        "multi-threaded search isn't supported, so threads must be 1",
+                    ));
+                }
+            }
+            "temperature" => {
+                builder.temperature(value.extract()?);
+            }
+            "seed" => {
+                builder.seed(value.extract()?);
+            }
+            "progress_interval" => {
+                builder.progress_interval(Duration::from_millis(value.extract()?));
+            }
+            key => {
+                return Err(PyTypeError::new_err(format!(
+                    "unexpected keyword argument '{key}'"
+                )))
+            }
+        }
+    }
+
+    match (depth, limited) {
+        // Every available move is searched to the full depth, so the root itself isn't counted
+        (Some(depth), _) => {
+            builder.depth(depth.saturating_add(1));
+        }
+        (None, true) => {
+            builder.depth(u32::MAX);
+        }
+        (None, false) => {}
+    }
+
+    Ok(builder)
+}
+
+/// A player in a two-player game. `MAX` is the player that `heuristic_value` is from the
+/// perspective of, for whom `is_maximising_player` returns true.
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, frozen)]
+#[derive(Clone, Copy, PartialEq)]
+enum Player {
+    #[pyo3(name = "MAX")]
+    Max,
+    #[pyo3(name = "MIN")]
+    Min,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Player {
+    fn opposite(&self) -> Self {
+        minimax::Player::from(*self).opposite().into()
+    }
+}
+
+impl From<Player> for minimax::Player {
+    fn from(player: Player) -> Self {
+        match player {
+            Player::Max => minimax::Player::Max,
+            Player::Min => minimax::Player::Min,
+        }
+    }
+}
+
+impl From<minimax::Player> for Player {
+    fn from(player: minimax::Player) -> Self {
+        match player {
+            minimax::Player::Max => Player::Max,
+            minimax::Player::Min => Player::Min,
+        }
+    }
+}
+
+/// The result of [`search`].
+#[gen_stub_pyclass]
+#[pyclass(frozen)]
+struct SearchResult {
+    best_move: Option<PyObject>,
+    /// The score of the best move, from the perspective of the player to move.
+    #[pyo3(get)]
+    score: Option<f64>,
+    pv: Vec<PyObject>,
+    /// The depth of the last completed iteration, not counting the root move.
+    #[pyo3(get)]
+    depth: u32,
+    /// The number of nodes searched.
+    #[pyo3(get)]
+    nodes: u64,
+    /// The time taken by the search, in seconds.
+    #[pyo3(get)]
+    elapsed: f64,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl SearchResult {
+    /// The best move found, or `None` if there were no moves available.
+    #[getter]
+    fn best_move(&self, py: Python<'_>) -> Option<PyObject> {
+        self.best_move.as_ref().map(|move_| move_.clone_ref(py))
+    }
+
+    /// The principal variation, starting with the best move.
+    #[getter]
+    fn pv(&self, py: Python<'_>) -> Vec<PyObject> {
+        self.pv.iter().map(|move_| move_.clone_ref(py)).collect()
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "SearchResult(best_move={}, score={}, pv={}, depth={}, nodes={}, elapsed={:.3})",
+            self.best_move(py).into_pyobject(py)?.repr()?,
+            self.score.into_pyobject(py)?.repr()?,
+            self.pv(py).into_pyobject(py)?.repr()?,
+            self.depth,
+            self.nodes,
+            self.elapsed
+        ))
+    }
+}
+
+/// A report on a running search, passed to the `progress` callback of [`search`].
+#[gen_stub_pyclass]
+#[pyclass(frozen)]
+struct SearchInfo {
+    /// The depth of the iteration being searched, not counting the root move.
+    #[pyo3(get)]
+    depth: u32,
+    /// The number of nodes searched so far.
+    #[pyo3(get)]
+    nodes: u64,
+    /// The time since the search started, in seconds.
+    #[pyo3(get)]
+    elapsed: f64,
+    /// The score of the best move found so far, if any.
+    #[pyo3(get)]
+    score: Option<f64>,
+    pv: Vec<PyObject>,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl SearchInfo {
+    /// The best move found so far, if any.
+    #[getter]
+    fn best_move(&self, py: Python<'_>) -> Option<PyObject> {
+        self.pv.first().map(|move_| move_.clone_ref(py))
+    }
+
+    /// The principal variation of the best move found so far.
+    #[getter]
+    fn pv(&self, py: Python<'_>) -> Vec<PyObject> {
+        self.pv.iter().map(|move_| move_.clone_ref(py)).collect()
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "SearchInfo(depth={}, nodes={}, elapsed={:.3}, score={}, pv={})",
+            self.depth,
+            self.nodes,
+            self.elapsed,
+            self.score.into_pyobject(py)?.repr()?,
+            self.pv(py).into_pyobject(py)?.repr()?,
+        ))
+    }
+}
+
+impl From<&minimax::SearchInfo<'_, f64, Action>> for SearchInfo {
+    fn from(info: &minimax::SearchInfo<f64, Action>) -> Self {
+        Self {
+            depth: info.depth.saturating_sub(1),
+            nodes: info.nodes,
+            elapsed: info.elapsed.as_secs_f64(),
+            score: info.best_line.map(|line| line.score),
+            pv: info
+                .best_line
+                .map(|line| line.pv.iter().cloned().map(|move_| move_.0).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&minimax::SearchResult<f64, Action>> for SearchResult {
+    fn from(result: &minimax::SearchResult<f64, Action>) -> Self {
+        Self {
+            best_move: result.best_move().cloned().map(|move_| move_.0),
+            score: result.score(),
+            pv: result.pv().iter().cloned().map(|move_| move_.0).collect(),
+            depth: result.depth.saturating_sub(1),
+            nodes: result.nodes,
+            elapsed: result.elapsed.as_secs_f64(),
+        }
+    }
+}
+
+/// A search engine that keeps its transposition table between searches, which makes searching
+/// successive states of the same game faster.
+///
+/// Takes the same arguments as [`best_move`], except that the transposition table is enabled by
+/// default.
+#[gen_stub_pyclass]
+#[pyclass(frozen)]
+struct Engine {
+    engine: minimax::Engine<f64>,
+    table: Mutex<minimax::TranspositionTable<f64>>,
+    stop_token: minimax::StopToken,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Engine {
+    #[new]
+    #[pyo3(signature = (depth=None, **options))]
+    fn new(depth: Option<u32>, options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        Self::with_table_size(depth, options, DEFAULT_TT_SIZE)
+    }
+
+    /// Finds the best move from `state` like [`best_move`].
+    fn best_move(&self, state: PyObject) -> PyResult<PyObject> {
+        let mut table = self.start_search()?;
+        find_best_move(&self.engine, &mut table, state)
+    }
+
+    /// Searches `state`, calling `callback` with the depth, score and principal variation after
+    /// every completed iteration if it's given, and `progress` with a [`SearchInfo`] at most every
+    /// `progress_interval` during iterations.
+    #[pyo3(signature = (state, callback=None, progress=None))]
+    fn search(
+        &self,
+        state: PyObject,
+        callback: Option<PyObject>,
+        progress: Option<PyObject>,
+    ) -> PyResult<SearchResult> {
+        let mut table = self.start_search()?;
+        self.search_with_table(&mut table, state, callback, progress)
+    }
+
+    /// Searches `state` like [`Engine::search`] in the running event loop's default executor,
+    /// returning an asyncio future that completes with the result. The GIL is only held while
+    /// calling into Python, so the event loop keeps running during the search. Cancelling the
+    /// future stops the search.
+    #[pyo3(signature = (state, callback=None, progress=None))]
+    fn search_async(
+        slf: PyRef<'_, Self>,
+        state: PyObject,
+        callback: Option<PyObject>,
+        progress: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let stop_token = slf.stop_token.clone();
+        let engine = Py::from(slf);
+        let stage = Arc::new(Mutex::new(AsyncStage::Queued));
+        let run_stage = Arc::clone(&stage);
+        let run = PyCFunction::new_closure(py, None, None, move |args, _| {
+            let py = args.py();
+            let state = state.clone_ref(py);
+            let callback = callback.as_ref().map(|callback| callback.clone_ref(py));
+            let progress = progress.as_ref().map(|progress| progress.clone_ref(py));
+            let engine = engine.get();
+
+            let mut table = {
+                let mut stage = lock(&run_stage);
+                if *stage == AsyncStage::Cancelled {
+                    // Nothing is waiting for the result
+                    return PyResult::Ok(py.None());
+                }
+                let table = engine.start_search()?;
+                *stage = AsyncStage::Running;
+                table
+            };
+            let table_ref: &mut minimax::TranspositionTable<f64> = &mut table;
+            let result =
+                py.allow_threads(|| engine.search_with_table(table_ref, state, callback, progress));
+            // Set before the search's stop token is cleared, so that cancelling can't stop the
+            // token after it has been
+            *lock(&run_stage) = AsyncStage::Finished;
+            drop(table);
+            Ok(Py::new(py, result?)?.into_any())
+        })?;
+
+        let future = py
+            .import("asyncio")?
+            .call_method0("get_running_loop")?
+            .call_method1("run_in_executor", (py.None(), run))?;
+
+        // Cancelling the future doesn't interrupt a function that's already running, and
+        // stopping the engine's token before this search has started would stop whichever search
+        // runs next instead
+        let on_done = PyCFunction::new_closure(py, None, None, move |args, _| {
+            if args.get_item(0)?.call_method0("cancelled")?.is_truthy()? {
+                let mut stage = lock(&stage);
+                match *stage {
+                    AsyncStage::Queued => *stage = AsyncStage::Cancelled,
+                    AsyncStage::Running => stop_token.stop(),
+                    AsyncStage::Finished | AsyncStage::Cancelled => {}
+                }
+            }
+            PyResult::Ok(())
+        })?;
+        future.call_method1("add_done_callback", (on_done,))?;
+
+        Ok(future.unbind())
+    }
+
+    /// A single alpha-beta search of `state` to `depth` plies with the window `alpha..beta`,
+    /// returning its value from the perspective of the player to move. Values outside the window
+    /// are only bounds on the true value, which lets drivers like MTD(f) be written in Python.
+    ///
+    /// The engine's transposition table is kept between calls, so repeated searches of the same
+    /// state are cheaper, while its time and node limits are ignored.
+    fn alpha_beta(&self, state: PyObject, alpha: f64, beta: f64, depth: u32) -> PyResult<f64> {
+        let mut table = self.start_search()?;
+        let state = State::new(state)?;
+        let value = self.engine.alpha_beta_with_table(
+            &state,
+            &State::evaluation,
+            &mut table,
+            alpha,
+            beta,
+            depth,
+        );
+
+        state.take_error()?;
+        Ok(value)
+    }
+
+    /// Stops the current search, which then returns the result of the last depth it completed.
+    /// If no search is running, the next one is stopped as soon as it starts.
+    ///
+    /// This is meant to be called from another thread, or from a callback passed to
+    /// [`Engine::search`].
+    fn stop(&self) {
+        self.stop_token.stop();
+    }
+
+    /// Clears the transposition table, so that nothing from the previous game is reused.
+    fn new_game(&self) -> PyResult<()> {
+        self.table()?.clear();
+        Ok(())
+    }
+}
+
+impl Engine {
+    fn with_table_size(
+        depth: Option<u32>,
+        options: Option<&Bound<'_, PyDict>>,
+        table_size: usize,
+    ) -> PyResult<Self> {
+        let stop_token = minimax::StopToken::new();
+        let engine = engine_builder(depth, options, table_size)?
+            .stop_token(stop_token.clone())
+            .build()
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        let table = Mutex::new(engine.transposition_table());
+
+        Ok(Self {
+            engine,
+            table,
+            stop_token,
         })
     }
 
-    fn result(&self, action: &String) -> Self {
-        Python::with_gil(|py| {
-            State(self.0
-                .call_method1(py, "result", (action,))
-                .expect("Failed to call Python method 'result'")
-                .extract(py)
-                .expect("Failed to extract new PyStateWrapper from Python")
-            )
+    /// The transposition table, which is locked by the search using it.
+    fn table(&self) -> PyResult<MutexGuard<'_, minimax::TranspositionTable<f64>>> {
+        // Waiting for the lock would hold the GIL that the search needs to finish
+        match self.table.try_lock() {
+            Ok(table) => Ok(table),
+            // The table is only written to while searching, so it's still usable after a panic
+            Err(TryLockError::Poisoned(err)) => Ok(err.into_inner()),
+            Err(TryLockError::WouldBlock) => {
+                Err(PyRuntimeError::new_err("the engine is already searching"))
+            }
+        }
+    }
+
+    /// Prepares for a new search, returning the transposition table for it to use.
+    fn start_search(&self) -> PyResult<Search<'_>> {
+        Ok(Search {
+            table: self.table()?,
+            stop_token: &self.stop_token,
         })
     }
+
+    /// Searches `state` like [`Engine::search`], using `table` as the transposition table.
+    fn search_with_table(
+        &self,
+        table: &mut minimax::TranspositionTable<f64>,
+        state: PyObject,
+        callback: Option<PyObject>,
+        progress: Option<PyObject>,
+    ) -> PyResult<SearchResult> {
+        let state = State::new(state)?;
+        let on_iteration = |result: &minimax::SearchResult<f64, Action>| {
+            let Some(callback) = &callback else {
+                return;
+            };
+            let result = SearchResult::from(result);
+            let args = (result.depth, result.score, result.pv);
+
+            if let Err(err) = Python::with_gil(|py| callback.call1(py, args)) {
+                state.record_error(err);
+            }
+        };
+        let on_progress = |info: &minimax::SearchInfo<f64, Action>| {
+            let Some(progress) = &progress else {
+                return;
+            };
+            let info = SearchInfo::from(info);
+
+            if let Err(err) = Python::with_gil(|py| progress.call1(py, (info,))) {
+                state.record_error(err);
+            }
+        };
+        let result = self.engine.search_with_progress(
+            &state,
+            &State::evaluation,
+            table,
+            on_iteration,
+            on_progress,
+        );
+
+        state.take_error()?;
+        Ok((&result).into())
+    }
+}
+
+/// How far a search started by [`Engine::search_async`] has got, which decides what cancelling it
+/// does.
+#[derive(Clone, Copy, PartialEq)]
+enum AsyncStage {
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+/// Locks `mutex`, whose value is still usable if a thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The transposition table of a running search, which clears the engine's stop token once the
+/// search is over. Clearing it then rather than when the search starts means that a stop made
+/// just before the search started, such as by cancelling [`Engine::search_async`], isn't lost.
+struct Search<'a> {
+    table: MutexGuard<'a, minimax::TranspositionTable<f64>>,
+    stop_token: &'a minimax::StopToken,
+}
+
+impl Deref for Search<'_> {
+    type Target = minimax::TranspositionTable<f64>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.table
+    }
+}
+
+impl DerefMut for Search<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.table
+    }
+}
+
+impl Drop for Search<'_> {
+    fn drop(&mut self) {
+        self.stop_token.reset();
+    }
+}
+
+/// Finds the best move from `state` with `engine`, using `table` as its transposition table.
+fn find_best_move(
+    engine: &minimax::Engine<f64>,
+    table: &mut minimax::TranspositionTable<f64>,
+    state: PyObject,
+) -> PyResult<PyObject> {
+    let state = State::new(state)?;
+    let move_ = engine.best_move_with_table(&state, &State::evaluation, table);
+
+    state.take_error()?;
+    move_
+        .map(|move_| move_.0)
+        .ok_or_else(|| SearchError::new_err("no moves available"))
+}
+
+/// Finds the best move from `state`, searching to `depth` plies after the move if given, and
+/// raises [`SearchError`] if there are no moves. The search is configured by keyword arguments:
+///
+/// - `time_limit`: the time to search for, in milliseconds.
+/// - `node_limit`: the number of nodes to search.
+/// - `tt_size`: the number of transposition table entries, which are only used for states with a
+///   `hash_key` method.
+/// - `threads`: the number of threads to search with, which must be 1 for now.
+/// - `temperature`: if given, moves are sampled by the softmax of their scores divided by the
+///   temperature instead of always playing the best one.
+/// - `seed`: the seed of the random choices made with `temperature`.
+/// - `progress_interval`: the minimum time between calls to a search's `progress` callback, in
+///   milliseconds.
+///
+/// Searches limited by time or nodes keep deepening until they run out, unless `depth` is given.
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (state, depth=None, **options))]
+fn best_move(
+    state: PyObject,
+    depth: Option<u32>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    Engine::with_table_size(depth, options, 0)?.best_move(state)
+}
+
+/// Finds the best moves from each of `states` like [`best_move`], searching them in parallel on
+/// `workers` threads, or on one per CPU if not given. Raises `ValueError` if `workers` is 0.
+///
+/// The states only share the GIL, so the searches can only run in parallel while none of them is
+/// calling into Python.
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (states, depth=None, *, workers=None, **options))]
+fn best_moves(
+    py: Python<'_>,
+    states: Vec<PyObject>,
+    depth: Option<u32>,
+    workers: Option<usize>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<PyObject>> {
+    // Rayon takes 0 threads to mean one per CPU, which is only what not giving `workers` means
+    if workers == Some(0) {
+        return Err(PyValueError::new_err("workers must be at least 1"));
+    }
+    let engine = engine_builder(depth, options, 0)?
+        .build()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(workers.unwrap_or(0))
+        .build()
+        .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+
+    py.allow_threads(|| {
+        pool.install(|| {
+            states
+                .into_par_iter()
+                .map(|state| find_best_move(&engine, &mut engine.transposition_table(), state))
+                .collect()
+        })
+    })
 }
 
+/// Searches `state` like [`best_move`], returning the score and principal variation along with
+/// the best move. See [`Engine::search`] for `callback` and `progress`.
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (state, depth=None, *, callback=None, progress=None, **options))]
+fn search(
+    state: PyObject,
+    depth: Option<u32>,
+    callback: Option<PyObject>,
+    progress: Option<PyObject>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<SearchResult> {
+    Engine::with_table_size(depth, options, 0)?.search(state, callback, progress)
+}
+
+/// Searches `state` like [`Engine::alpha_beta`], without a transposition table.
+#[gen_stub_pyfunction]
+#[pyfunction]
+fn alpha_beta(state: PyObject, alpha: f64, beta: f64, depth: u32) -> PyResult<f64> {
+    Engine::with_table_size(None, None, 0)?.alpha_beta(state, alpha, beta, depth)
+}
 
+/// Searches `state` like [`search`] without blocking the event loop, returning an asyncio future. See
+/// [`Engine::search_async`].
+#[gen_stub_pyfunction]
 #[pyfunction]
-fn best_move(state: PyObject, depth: u32) -> String {
-    minimax::best_move(&State(state), depth)
+#[pyo3(signature = (state, depth=None, *, callback=None, progress=None, **options))]
+fn search_async(
+    py: Python<'_>,
+    state: PyObject,
+    depth: Option<u32>,
+    callback: Option<PyObject>,
+    progress: Option<PyObject>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let engine = Bound::new(py, Engine::with_table_size(depth, options, 0)?)?;
+    Engine::search_async(engine.borrow(), state, callback, progress)
 }
 
+/// The source of the `State` base class.
+const STATE_SOURCE: &str = include_str!("state.py");
+
 /// A Python module implemented in Rust.
 #[pymodule]
 fn djinn_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let source = CString::new(STATE_SOURCE)?;
+    let state = PyModule::from_code(m.py(), &source, c"state.py", c"djinn_py.state")?;
+    let state = state.getattr("State")?;
+    state.setattr("__module__", "djinn_py")?;
+    m.add("State", state)?;
+    m.add("SearchError", m.py().get_type::<SearchError>())?;
+    m.add_class::<Engine>()?;
+    m.add_class::<Player>()?;
+    m.add_class::<SearchInfo>()?;
+    m.add_class::<SearchResult>()?;
     m.add_function(wrap_pyfunction!(best_move, m)?)?;
+    m.add_function(wrap_pyfunction!(best_moves, m)?)?;
+    m.add_function(wrap_pyfunction!(search, m)?)?;
+    m.add_function(wrap_pyfunction!(search_async, m)?)?;
+    m.add_function(wrap_pyfunction!(alpha_beta, m)?)?;
     Ok(())
 }
+
+define_stub_info_gatherer!(stub_info);
+
+/// The contents of `djinn_py.pyi`: the stubs generated from the bindings, followed by the source
+/// of `State`, which is written in Python and so describes itself.
+pub fn stub() -> pyo3_stub_gen::Result<String> {
+    let info = stub_info()?;
+    Ok(format!("{}\n{STATE_SOURCE}", info.modules["djinn_py"]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    use pyo3::ffi::c_str;
+    use pyo3::types::{IntoPyDict, PyList};
+    use pyo3::wrap_pymodule;
+    use pyo3::IntoPyObjectExt;
+
+    /// States for the tests to search: a pile of stones where each player takes one or two in
+    /// turn and whoever takes the last wins, a game that never ends, a state that raises, a pile
+    /// whose actions are tuples, and a pile that's expanded all at once.
+    const STATES: &CStr = c_str!(
+        r#"
+class Pile:
+    def __init__(self, stones, maximising=True):
+        self.stones = stones
+        self.maximising = maximising
+
+    def is_terminal(self):
+        return self.stones == 0
+
+    def heuristic_value(self):
+        if self.stones > 0:
+            return 0.0
+        return float("-inf") if self.maximising else float("inf")
+
+    def is_maximising_player(self):
+        return self.maximising
+
+    def actions(self):
+        return [take for take in (1, 2) if take <= self.stones]
+
+    def result(self, take):
+        return Pile(self.stones - take, not self.maximising)
+
+
+class Endless(Pile):
+    def __init__(self, maximising=True):
+        super().__init__(1, maximising)
+
+    def actions(self):
+        return [1, 2]
+
+    def result(self, take):
+        return Endless(not self.maximising)
+
+
+class Failing(Pile):
+    def heuristic_value(self):
+        raise ValueError("no value for this state")
+
+    def result(self, take):
+        return Failing(self.stones - take, not self.maximising)
+
+
+class Labelled(Pile):
+    def actions(self):
+        return [("take", take) for take in super().actions()]
+
+    def result(self, action):
+        _, take = action
+        return Labelled(self.stones - take, not self.maximising)
+
+
+class Expanding(Pile):
+    def expand(self):
+        return [
+            (take, Expanding(self.stones - take, not self.maximising)) for take in self.actions()
+        ]
+
+    def result(self, take):
+        raise AssertionError("result is only called for states without expand")
+"#
+    );
+
+    /// Runs `test` with the module and the test states imported.
+    fn with_module(test: impl for<'py> FnOnce(&Bound<'py, PyModule>, &Bound<'py, PyModule>)) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = wrap_pymodule!(djinn_py)(py).into_bound(py);
+            let states = PyModule::from_code(py, STATES, c"states.py", c"states").unwrap();
+            test(&module, &states);
+        });
+    }
+
+    fn options<'py>(py: Python<'py>, options: &[(&str, PyObject)]) -> Bound<'py, PyDict> {
+        options.iter().into_py_dict(py).unwrap()
+    }
+
+    #[test]
+    fn stub_is_up_to_date() {
+        let stub = stub().unwrap();
+        let file = include_str!("../djinn_py.pyi");
+        assert!(
+            stub == file,
+            "djinn_py.pyi is out of date, run `cargo run --bin stub_gen`"
+        );
+    }
+
+    #[test]
+    fn checks_search_options() {
+        with_module(|module, _| {
+            let py = module.py();
+            let threads = options(py, &[("threads", 2.into_py_any(py).unwrap())]);
+            let err = module
+                .call_method("best_move", (py.None(),), Some(&threads))
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+
+            let unknown = options(py, &[("depth_limit", 2.into_py_any(py).unwrap())]);
+            let err = module
+                .call_method("Engine", (), Some(&unknown))
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyTypeError>(py));
+            assert!(err.to_string().contains("depth_limit"));
+        });
+    }
+
+    #[test]
+    fn finds_the_winning_move() {
+        with_module(|module, states| {
+            // Taking one leaves a multiple of three, which is lost for the opponent
+            let pile = states.call_method1("Pile", (4,)).unwrap();
+            let move_ = module.call_method1("best_move", (&pile, 4)).unwrap();
+            assert_eq!(move_.extract::<u32>().unwrap(), 1);
+        });
+    }
+
+    #[test]
+    fn raises_exceptions_from_states() {
+        with_module(|module, states| {
+            let py = module.py();
+            let failing = states.call_method1("Failing", (4,)).unwrap();
+            let err = module.call_method1("best_move", (&failing, 2)).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+            assert!(err.to_string().contains("no value for this state"));
+
+            // States missing a required method are reported before searching
+            let err = module.call_method1("best_move", (1, 2)).unwrap_err();
+            assert!(err.is_instance_of::<PyTypeError>(py));
+        });
+    }
+
+    #[test]
+    fn best_moves_needs_a_worker() {
+        with_module(|module, _| {
+            let py = module.py();
+            let workers = options(py, &[("workers", 0.into_py_any(py).unwrap())]);
+            let err = module
+                .call_method("best_moves", (Vec::<PyObject>::new(),), Some(&workers))
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+
+    #[test]
+    fn stops_before_searching_arent_lost() {
+        with_module(|module, states| {
+            let py = module.py();
+            let time_limit = options(py, &[("time_limit", 60_000.into_py_any(py).unwrap())]);
+            let engine = module.call_method("Engine", (), Some(&time_limit)).unwrap();
+            let endless = states.call_method0("Endless").unwrap();
+
+            engine.call_method0("stop").unwrap();
+            let result = engine.call_method1("search", (&endless,)).unwrap();
+            let elapsed: f64 = result.getattr("elapsed").unwrap().extract().unwrap();
+            assert!(elapsed < 10.0);
+        });
+    }
+
+    #[test]
+    fn cancelling_search_async_stops_the_search() {
+        with_module(|module, states| {
+            let py = module.py();
+            let test = PyModule::from_code(
+                py,
+                c_str!(
+                    r#"
+import asyncio
+
+async def cancel(djinn_py, state):
+    engine = djinn_py.Engine(time_limit=60_000)
+    future = engine.search_async(state)
+    await asyncio.sleep(0.2)
+    future.cancel()
+    # The engine refuses to start anything else until the search has stopped
+    for _ in range(100):
+        await asyncio.sleep(0.05)
+        try:
+            engine.new_game()
+            return True
+        except RuntimeError:
+            pass
+    return False
+
+def run(djinn_py, state):
+    return asyncio.run(cancel(djinn_py, state))
+"#
+                ),
+                c"cancel.py",
+                c"cancel",
+            )
+            .unwrap();
+            let endless = states.call_method0("Endless").unwrap();
+            let stopped = test.call_method1("run", (module, endless)).unwrap();
+            assert!(stopped.extract::<bool>().unwrap());
+        });
+    }
+
+    #[test]
+    fn cancelling_a_queued_search_async_doesnt_stop_the_next_search() {
+        with_module(|module, states| {
+            let py = module.py();
+            let test = PyModule::from_code(
+                py,
+                c_str!(
+                    r#"
+import asyncio
+import time
+from concurrent.futures import ThreadPoolExecutor
+
+async def cancel(djinn_py, state):
+    loop = asyncio.get_running_loop()
+    loop.set_default_executor(ThreadPoolExecutor(max_workers=1))
+    engine = djinn_py.Engine(time_limit=200)
+    # Keeps the executor's only thread busy, so that the search is still queued when cancelled
+    busy = loop.run_in_executor(None, time.sleep, 0.2)
+    future = engine.search_async(state)
+    future.cancel()
+    await busy
+    return engine.search(state).elapsed
+
+def run(djinn_py, state):
+    return asyncio.run(cancel(djinn_py, state))
+"#
+                ),
+                c"cancel_queued.py",
+                c"cancel_queued",
+            )
+            .unwrap();
+            let endless = states.call_method0("Endless").unwrap();
+            let elapsed = test.call_method1("run", (module, endless)).unwrap();
+            assert!(elapsed.extract::<f64>().unwrap() >= 0.1);
+        });
+    }
+
+    #[test]
+    fn search_returns_the_score_and_pv() {
+        with_module(|module, states| {
+            let pile = states.call_method1("Pile", (4,)).unwrap();
+            let result = module.call_method1("search", (&pile, 2)).unwrap();
+            let best_move: u32 = result.getattr("best_move").unwrap().extract().unwrap();
+            let score: f64 = result.getattr("score").unwrap().extract().unwrap();
+            let pv: Vec<u32> = result.getattr("pv").unwrap().extract().unwrap();
+            assert_eq!(best_move, 1);
+            // The player to move wins
+            assert_eq!(score, f64::INFINITY);
+            assert_eq!(pv.first(), Some(&1));
+            let depth: u32 = result.getattr("depth").unwrap().extract().unwrap();
+            let nodes: u64 = result.getattr("nodes").unwrap().extract().unwrap();
+            assert_eq!(depth, 2);
+            assert!(nodes > 0);
+        });
+    }
+
+    #[test]
+    fn calls_back_after_every_iteration() {
+        with_module(|module, states| {
+            let py = module.py();
+            let depths = PyList::empty(py);
+            let globals = options(py, &[("depths", depths.clone().into_py_any(py).unwrap())]);
+            let record = py
+                .eval(
+                    c"lambda depth, score, pv: depths.append(depth)",
+                    Some(&globals),
+                    None,
+                )
+                .unwrap();
+            let pile = states.call_method1("Pile", (4,)).unwrap();
+            let callback = options(py, &[("callback", record.into_py_any(py).unwrap())]);
+            module
+                .call_method("search", (&pile, 3), Some(&callback))
+                .unwrap();
+            let depths: Vec<u32> = depths.extract().unwrap();
+            assert_eq!(depths.last(), Some(&3));
+            assert!(depths.windows(2).all(|pair| pair[0] < pair[1]));
+        });
+    }
+
+    #[test]
+    fn passes_any_object_back_as_an_action() {
+        with_module(|module, states| {
+            let pile = states.call_method1("Labelled", (4,)).unwrap();
+            let move_ = module.call_method1("best_move", (&pile, 4)).unwrap();
+            let (label, take): (String, u32) = move_.extract().unwrap();
+            assert_eq!((label.as_str(), take), ("take", 1));
+        });
+    }
+
+    #[test]
+    fn exposes_players_and_alpha_beta() {
+        with_module(|module, states| {
+            let player = module.getattr("Player").unwrap();
+            let max = player.getattr("MAX").unwrap();
+            let min = player.getattr("MIN").unwrap();
+            assert!(max.call_method0("opposite").unwrap().eq(&min).unwrap());
+
+            let won = states.call_method1("Pile", (4,)).unwrap();
+            let lost = states.call_method1("Pile", (3,)).unwrap();
+            let window = (f64::NEG_INFINITY, f64::INFINITY, 4);
+            let value = |state| {
+                let args = (state, window.0, window.1, window.2);
+                let value = module.call_method1("alpha_beta", args).unwrap();
+                value.extract::<f64>().unwrap()
+            };
+            assert_eq!(value(&won), f64::INFINITY);
+            assert_eq!(value(&lost), f64::NEG_INFINITY);
+        });
+    }
+
+    #[test]
+    fn reports_progress_during_searches() {
+        with_module(|module, states| {
+            let py = module.py();
+            let reports = PyList::empty(py);
+            let progress = reports.getattr("append").unwrap();
+            let endless = states.call_method0("Endless").unwrap();
+            let options = options(
+                py,
+                &[
+                    ("progress", progress.into_py_any(py).unwrap()),
+                    ("time_limit", 200.into_py_any(py).unwrap()),
+                    ("progress_interval", 10.into_py_any(py).unwrap()),
+                ],
+            );
+            module
+                .call_method("search", (&endless,), Some(&options))
+                .unwrap();
+            assert!(!reports.is_empty());
+            for info in reports.iter() {
+                assert!(info.getattr("nodes").unwrap().extract::<u64>().unwrap() > 0);
+            }
+        });
+    }
+
+    #[test]
+    fn checks_states_against_the_base_class() {
+        with_module(|module, _| {
+            let py = module.py();
+            let test = PyModule::from_code(
+                py,
+                c_str!(
+                    r#"
+def define(djinn_py):
+    class Incomplete(djinn_py.State):
+        def is_terminal(self):
+            return True
+
+    class Countdown(djinn_py.State):
+        def __init__(self, count):
+            self.count = count
+
+        def is_terminal(self):
+            return self.count == 0
+
+        def heuristic_value(self):
+            return "not a number"
+
+        def is_maximising_player(self):
+            return True
+
+        def actions(self):
+            return [1]
+
+        def result(self, action):
+            return Countdown(self.count - action)
+
+    return Incomplete, Countdown
+"#
+                ),
+                c"base_class.py",
+                c"base_class",
+            )
+            .unwrap();
+            let (incomplete, countdown): (Bound<'_, PyAny>, Bound<'_, PyAny>) = test
+                .call_method1("define", (module,))
+                .unwrap()
+                .extract()
+                .unwrap();
+
+            // Subclasses missing an abstract method can't be made
+            let err = incomplete.call0().unwrap_err();
+            assert!(err.is_instance_of::<PyTypeError>(py));
+
+            let countdown = countdown.call1((2,)).unwrap();
+            let err = module.call_method1("search", (&countdown, 2)).unwrap_err();
+            assert!(err.is_instance_of::<PyTypeError>(py));
+            assert!(err.to_string().contains("'heuristic_value'"));
+        });
+    }
+
+    #[test]
+    fn expands_states_all_at_once() {
+        with_module(|module, states| {
+            let pile = states.call_method1("Expanding", (4,)).unwrap();
+            let move_ = module.call_method1("best_move", (&pile, 4)).unwrap();
+            assert_eq!(move_.extract::<u32>().unwrap(), 1);
+        });
+    }
+}
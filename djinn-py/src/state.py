@@ -0,0 +1,39 @@
+from abc import ABC, abstractmethod
+from typing import Generic, List, TypeVar
+
+A = TypeVar("A")
+
+
+class State(ABC, Generic[A]):
+    """A base class for states searched by djinn_py.
+
+    Subclasses have to implement every abstract method, and can't be instantiated until they do.
+    They may also define `hash_key(self) -> Optional[int]`, returning a 64-bit hash identifying
+    the state (including the player to move), which lets the search store them in the
+    transposition table, and `expand(self) -> List[Tuple[A, State]]`, returning every action
+    along with the state it results in, which fetches all of them at once instead of calling
+    `result` for each.
+    """
+
+    __slots__ = ()
+
+    @abstractmethod
+    def is_terminal(self) -> bool:
+        """Returns True if the game is over in this state."""
+
+    @abstractmethod
+    def heuristic_value(self) -> float:
+        """Returns the value of the state from the perspective of the maximising player."""
+
+    @abstractmethod
+    def is_maximising_player(self) -> bool:
+        """Returns True if the maximising player is to move, and False otherwise."""
+
+    @abstractmethod
+    def actions(self) -> List[A]:
+        """Returns the actions available to the player to move."""
+
+    @abstractmethod
+    def result(self, action: A) -> "State[A]":
+        """Returns the state after playing `action`, which is one of this state's actions."""
+
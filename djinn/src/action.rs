@@ -16,4 +16,21 @@ pub enum Action {
     Help,
     OpenGame(GameId),
     CloseGame,
+    Undo,
+    Redo,
+    Hint,
+    ChangeDifficulty,
+    ExportPgn,
+    CopyPgn,
+    FlipBoard,
+    ReplayBack,
+    ReplayForward,
+    Resign,
+    OfferDraw,
+    StopThinking,
+    ShowStatistics,
+    NextTab,
+    PreviousTab,
+    /// Switches to the open game with this number in the tab bar, counting from 1.
+    SelectTab(usize),
 }
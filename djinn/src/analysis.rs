@@ -0,0 +1,165 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::games::{Game, GameError};
+
+/// How much of the mover's advantage, out of the 2 between losing and winning, a move has to give
+/// away to be called an inaccuracy, a mistake, or a blunder.
+const THRESHOLDS: [(f32, Annotation); 3] = [
+    (0.5, Annotation::Blunder),
+    (0.25, Annotation::Mistake),
+    (0.1, Annotation::Inaccuracy),
+];
+
+/// A mark for a move that threw away some of the mover's advantage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Annotation {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Annotation {
+    /// The annotation for a move by `side` that changed the advantage of the side that moves
+    /// first from `before` to `after`, if it made things worse enough to deserve one.
+    pub fn of(before: f32, after: f32, side: usize) -> Option<Self> {
+        let loss = if side == 0 {
+            before - after
+        } else {
+            after - before
+        };
+        THRESHOLDS
+            .iter()
+            .find(|&&(threshold, _)| loss >= threshold)
+            .map(|&(_, annotation)| annotation)
+    }
+}
+
+impl Display for Annotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Annotation::Inaccuracy => write!(f, "?!"),
+            Annotation::Mistake => write!(f, "?"),
+            Annotation::Blunder => write!(f, "??"),
+        }
+    }
+}
+
+/// A finished game with every position along the way evaluated again, which the player steps
+/// through to see where it was won or lost.
+pub struct Analysis {
+    /// The position before each move, followed by the final position.
+    positions: Vec<Box<dyn Game>>,
+    /// The advantage in each position of the side that moves first, from [`Game::advantage`].
+    advantages: Vec<Option<f32>>,
+    moves: Vec<String>,
+    annotations: Vec<Option<Annotation>>,
+    /// The number of moves played in the position being looked at.
+    ply: usize,
+}
+
+impl Analysis {
+    /// Evaluates every position of `game`, starting out looking at the last one. Fails if the
+    /// moves of the game can't be taken back to get to the earlier positions.
+    pub fn new(game: &dyn Game) -> Result<Self, GameError> {
+        let moves = game.move_history();
+        let mut positions = vec![game.box_clone()];
+        let mut position = game.box_clone();
+        while position.undo()?.is_some() {
+            positions.push(position.box_clone());
+        }
+        positions.reverse();
+
+        let advantages: Vec<_> = positions
+            .iter()
+            .map(|position| position.advantage())
+            .collect();
+        let annotations = advantages
+            .windows(2)
+            .enumerate()
+            .map(|(ply, pair)| match *pair {
+                [Some(before), Some(after)] => {
+                    Annotation::of(before, after, positions[ply].side_to_move())
+                }
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self {
+            ply: moves.len(),
+            positions,
+            advantages,
+            moves,
+            annotations,
+        })
+    }
+
+    /// The position being looked at.
+    pub fn position(&self) -> &dyn Game {
+        &*self.positions[self.ply]
+    }
+
+    /// The advantage of the side that moves first in the position being looked at.
+    pub fn advantage(&self) -> Option<f32> {
+        self.advantages[self.ply]
+    }
+
+    /// Every move of the game followed by its annotation, if it has one.
+    pub fn annotated_moves(&self) -> Vec<String> {
+        self.moves
+            .iter()
+            .zip(&self.annotations)
+            .map(|(move_, annotation)| match annotation {
+                Some(annotation) => format!("{move_}{annotation}"),
+                None => move_.clone(),
+            })
+            .collect()
+    }
+
+    /// The number of moves played in the position being looked at.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Looks at the position `plies` moves later, or earlier if it's negative, stopping at
+    /// either end of the game.
+    pub fn step(&mut self, plies: isize) {
+        self.ply = self.ply.saturating_add_signed(plies).min(self.moves.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::connect_four::ConnectFour;
+
+    #[test]
+    fn annotates_by_the_advantage_lost() {
+        assert_eq!(Annotation::of(0.2, 0.15, 0), None);
+        assert_eq!(Annotation::of(0.2, 0.0, 0), Some(Annotation::Inaccuracy));
+        assert_eq!(Annotation::of(0.2, -0.1, 0), Some(Annotation::Mistake));
+        assert_eq!(Annotation::of(0.2, 0.9, 1), Some(Annotation::Blunder));
+        // Getting further ahead is never a mistake
+        assert_eq!(Annotation::of(0.2, -0.9, 1), None);
+    }
+
+    #[test]
+    fn steps_through_the_game() {
+        let mut game = ConnectFour::default();
+        // The second player lets the first connect four in the first column
+        for move_ in ["a", "b", "a", "b", "a", "g", "a"] {
+            game.play_move(move_).unwrap();
+        }
+        let mut analysis = Analysis::new(&game).unwrap();
+
+        assert_eq!(analysis.ply(), 7);
+        assert_eq!(analysis.annotated_moves()[0], "a");
+        assert!(analysis.annotated_moves()[5].starts_with("g?"));
+        analysis.step(-3);
+        assert_eq!(analysis.position().move_history(), ["a", "b", "a", "b"]);
+        analysis.step(-10);
+        assert!(analysis.position().move_history().is_empty());
+        analysis.step(10);
+        assert_eq!(analysis.ply(), 7);
+    }
+}
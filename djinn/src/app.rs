@@ -1,25 +1,59 @@
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 use pyo3::Python;
+use ratatui::layout::{Constraint, Layout};
 use ratatui::prelude::Rect;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::mpsc;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 use crate::components::game_menu::GameMenu;
 use crate::components::game_screen::GameScreen;
+use crate::components::tab_bar::TabBar;
+use crate::components::toast::Toast;
+use crate::games::backgammon::Backgammon;
+use crate::games::brandubh::Brandubh;
+use crate::games::breakthrough::Breakthrough;
+use crate::games::checkers::Checkers;
+use crate::games::chess;
+use crate::games::chess::polyglot::Book;
+use crate::games::chess::puzzle;
+use crate::games::chess::puzzle::Puzzle;
+use crate::games::chess::uci::Client;
 use crate::games::chess::Chess;
+use crate::games::connect6::Connect6;
+use crate::games::connect_four::ConnectFour;
+use crate::games::dots_and_boxes::DotsAndBoxes;
+use crate::games::go::Go;
+use crate::games::gomoku::Gomoku;
+use crate::games::hexapawn::Hexapawn;
+use crate::games::hex::Hex;
+use crate::games::kalah::Kalah;
+use crate::games::lines_of_action::LinesOfAction;
+use crate::games::minishogi::Minishogi;
+use crate::games::morris::Morris;
+use crate::games::nim::Nim;
+use crate::games::onitama::Onitama;
+use crate::games::order_and_chaos::OrderAndChaos;
+use crate::games::othello::Othello;
+use crate::games::pentago::Pentago;
+use crate::games::quarto::Quarto;
+use crate::games::qubic::Qubic;
 use crate::games::tictactoe::TicTacToe;
+use crate::games::ultimate::Ultimate;
+use crate::games::xiangqi::Xiangqi;
 use crate::games::Game;
+use crate::network::Connection;
 use crate::plugins::python::PythonPluginManager;
+use crate::session::Session;
 use crate::tui::TuiConfigBuilder;
 use crate::{
     action::Action,
     components::{fps::FpsCounter, gamecard::GameCard, Component},
-    config::Config,
+    config::{get_data_dir, Config},
     tui::{Event, Tui},
 };
 
@@ -33,6 +67,10 @@ pub struct App<'a> {
     action_tx: mpsc::UnboundedSender<Action>,
     action_rx: mpsc::UnboundedReceiver<Action>,
     game_screens: BTreeMap<GameId, GameScreen<'a>>,
+    /// The games that have been opened, shown above the one being played.
+    tab_bar: TabBar,
+    /// Errors shown over whatever is on screen.
+    toast: Toast,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -62,10 +100,62 @@ pub enum Screen {
 
 impl App<'_> {
     pub fn new() -> Result<Self> {
+        let config = Config::new()?;
+        if let (Some(path), true) = (&config.chess.book, config.chess.use_book) {
+            match Book::open(path) {
+                Ok(book) => chess::use_book(book),
+                Err(err) => error!("Failed to load opening book {}: {err}", path.display()),
+            }
+        }
+        chess::show_moves(config.chess.show_moves);
+        if let Some(path) = &config.chess.engine {
+            match Client::start(path) {
+                Ok(client) => chess::use_engine(client),
+                Err(err) => error!("Failed to start chess engine {}: {err}", path.display()),
+            }
+        }
+
         let (action_tx, action_rx) = mpsc::unbounded_channel();
+        if config.chess.daily_puzzle {
+            // Fetched in the background so that a slow connection doesn't hold up startup
+            let action_tx = action_tx.clone();
+            std::thread::spawn(move || match puzzle::fetch_daily() {
+                Ok(daily) => puzzle::use_daily(daily),
+                Err(err) => {
+                    let message = format!("Failed to fetch the daily puzzle: {err}");
+                    error!("{message}");
+                    let _ = action_tx.send(Action::Error(message));
+                }
+            });
+        }
         let mut games: Vec<(GameId, Box<dyn Game>)> = vec![
             (GameId::new(), Box::new(TicTacToe::default())),
+            (GameId::new(), Box::new(Hexapawn::default())),
             (GameId::new(), Box::new(Chess::default())),
+            (GameId::new(), Box::new(Puzzle::default())),
+            (GameId::new(), Box::new(ConnectFour::default())),
+            (GameId::new(), Box::new(Checkers::default())),
+            (GameId::new(), Box::new(Othello::default())),
+            (GameId::new(), Box::new(Gomoku::default())),
+            (GameId::new(), Box::new(Connect6::default())),
+            (GameId::new(), Box::new(Morris::default())),
+            (GameId::new(), Box::new(Kalah::default())),
+            (GameId::new(), Box::new(Hex::default())),
+            (GameId::new(), Box::new(Nim::default())),
+            (GameId::new(), Box::new(DotsAndBoxes::default())),
+            (GameId::new(), Box::new(Ultimate::default())),
+            (GameId::new(), Box::new(Quarto::default())),
+            (GameId::new(), Box::new(Breakthrough::default())),
+            (GameId::new(), Box::new(LinesOfAction::default())),
+            (GameId::new(), Box::new(Onitama::default())),
+            (GameId::new(), Box::new(Go::default())),
+            (GameId::new(), Box::new(Backgammon::default())),
+            (GameId::new(), Box::new(Pentago::default())),
+            (GameId::new(), Box::new(Qubic::default())),
+            (GameId::new(), Box::new(OrderAndChaos::default())),
+            (GameId::new(), Box::new(Brandubh::default())),
+            (GameId::new(), Box::new(Minishogi::default())),
+            (GameId::new(), Box::new(Xiangqi::default())),
         ];
 
         let plugin_games = Python::with_gil(Self::load_python_plugins);
@@ -82,20 +172,77 @@ impl App<'_> {
                 .map(|(id, game)| (id, GameScreen::new(game))),
         );
 
-        Ok(Self {
+        let mut app = Self {
             home_components: vec![
                 Box::new(FpsCounter::default()),
                 Box::new(GameMenu::new(game_cards)),
             ],
             should_quit: false,
             should_suspend: false,
-            config: Config::new()?,
+            config,
             screen: Screen::default(),
             last_tick_key_events: Vec::new(),
             action_tx,
             action_rx,
             game_screens,
-        })
+            tab_bar: TabBar::default(),
+            toast: Toast::default(),
+        };
+        if app.config.restore_games {
+            app.restore_session();
+        }
+        Ok(app)
+    }
+
+    /// Carries on with the games that were being played when djinn was last closed.
+    fn restore_session(&mut self) {
+        let session = match Session::load(&get_data_dir()) {
+            Ok(Some(session)) => session,
+            Ok(None) => return,
+            Err(err) => {
+                error!("Failed to load the games from last time: {err}");
+                return;
+            }
+        };
+        for saved in session.games {
+            let name = saved.name.clone();
+            let restored = self.find_game(&name).and_then(|game_id| {
+                self.game_screens
+                    .get_mut(&game_id)
+                    .expect("found games have screens")
+                    .restore(saved)
+                    .map_err(|err| eyre!(err))?;
+                Ok(game_id)
+            });
+            // Restored games are open again, whether or not they're the one being shown
+            match restored {
+                Ok(game_id) => self.tab_bar.open(game_id, name),
+                Err(err) => error!("Failed to restore {name}: {err}"),
+            }
+        }
+        if let Some(name) = session.open {
+            if let Err(err) = self.open_game_from_name(&name) {
+                error!("Failed to open {name}: {err}");
+            }
+        }
+    }
+
+    /// Saves the games being played, for [`App::restore_session`] to carry on with.
+    fn save_session(&self) {
+        let session = Session {
+            games: self
+                .game_screens
+                .values()
+                .filter_map(GameScreen::save)
+                .collect(),
+            open: match self.screen {
+                Screen::Home => None,
+                Screen::Game(id) => self.game_screens.get(&id).map(GameScreen::name),
+            },
+        };
+        if let Err(err) = session.save(&get_data_dir()) {
+            error!("Failed to save the games being played: {err}");
+        }
     }
 
     fn load_python_plugins(py: Python<'_>) -> Vec<Box<dyn Game>> {
@@ -115,7 +262,7 @@ impl App<'_> {
 
     pub async fn run(&mut self) -> Result<()> {
         let tui_config = TuiConfigBuilder::default()
-            // .mouse(true) // uncomment this line to enable mouse support
+            .mouse(self.config.mouse)
             .tick_rate(self.config.tick_rate)
             .frame_rate(self.config.frame_rate)
             .build()?;
@@ -130,6 +277,9 @@ impl App<'_> {
         for component in self.home_components.iter_mut() {
             component.register_config_handler(self.config.clone())?;
         }
+        for game_screen in self.game_screens.values_mut() {
+            game_screen.register_config_handler(self.config.clone())?;
+        }
         for component in self.home_components.iter_mut() {
             component.init(tui.size()?)?;
         }
@@ -150,6 +300,9 @@ impl App<'_> {
             }
         }
         tui.exit()?;
+        if self.config.restore_games {
+            self.save_session();
+        }
         Ok(())
     }
 
@@ -163,18 +316,37 @@ impl App<'_> {
             Event::Tick => action_tx.send(Action::Tick)?,
             Event::Render => action_tx.send(Action::Render)?,
             Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
+            // Esc dismisses an error being shown rather than going to what's underneath it
+            Event::Key(key) if key.code == KeyCode::Esc && self.toast.is_shown() => {
+                self.toast.dismiss();
+                return Ok(());
+            }
             Event::Key(key) => self.handle_key_event(key)?,
             _ => {}
         }
         self.try_perform_on_components(|component| {
-            if let Some(action) = component.handle_events(Some(event.clone()))? {
-                action_tx.send(action)?;
-            }
-            Ok(())
+            Self::report(&action_tx, component.handle_events(Some(event.clone())))
         })?;
         Ok(())
     }
 
+    /// Sends on the action `result` leads to, or shows the player the error it failed with and
+    /// logs everything about it, rather than letting it take down the app.
+    fn report(
+        action_tx: &mpsc::UnboundedSender<Action>,
+        result: Result<Option<Action>>,
+    ) -> Result<()> {
+        match result {
+            Ok(Some(action)) => action_tx.send(action)?,
+            Ok(None) => {}
+            Err(err) => {
+                error!("{err:?}");
+                action_tx.send(Action::Error(err.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
     fn try_perform_on_components(
         &mut self,
         mut func: impl FnMut(&mut dyn Component) -> Result<()>,
@@ -265,15 +437,21 @@ impl App<'_> {
                 Action::Render => self.render(tui)?,
                 Action::OpenGame(game_id) => self.open_game(game_id),
                 Action::Back if matches!(self.screen, Screen::Game(_)) => self.back(),
+                Action::CloseGame => self.close_game()?,
+                Action::NextTab => self.switch_tab(self.tab_bar.step(1))?,
+                Action::PreviousTab => self.switch_tab(self.tab_bar.step(-1))?,
+                Action::SelectTab(number) => {
+                    let id = number.checked_sub(1).and_then(|i| self.tab_bar.get(i));
+                    self.switch_tab(id)?;
+                }
+                Action::Error(ref message) => self.toast.show(message.clone()),
                 _ => {}
             }
 
             let action_tx = self.action_tx.clone();
+            Self::report(&action_tx, self.toast.update(action.clone()))?;
             self.try_perform_on_components(|component| {
-                if let Some(action) = component.update(action.clone())? {
-                    action_tx.send(action)?
-                };
-                Ok(())
+                Self::report(&action_tx, component.update(action.clone()))
             })?;
         }
         Ok(())
@@ -288,36 +466,102 @@ impl App<'_> {
     fn render(&mut self, tui: &mut Tui) -> Result<()> {
         tui.draw(|frame| {
             let action_tx = self.action_tx.clone();
+            let mut area = frame.area();
+            // Games are drawn below the tab bar
+            if let Screen::Game(_) = self.screen {
+                let [tab_bar_area, game_area] =
+                    Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+                let result = self.tab_bar.draw(frame, tab_bar_area);
+                let _ = Self::report(&action_tx, result.map(|()| None));
+                area = game_area;
+            }
             self.perform_on_components(|component| {
-                if let Err(err) = component.draw(frame, frame.area()) {
-                    let _ = action_tx.send(Action::Error(format!("Failed to draw: {:?}", err)));
-                }
-            })
+                let _ = Self::report(&action_tx, component.draw(frame, area).map(|()| None));
+            });
+            let result = self.toast.draw(frame, frame.area());
+            let _ = Self::report(&action_tx, result.map(|()| None));
         })?;
         Ok(())
     }
 
     fn open_game(&mut self, game_id: GameId) {
         self.screen = Screen::Game(game_id);
+        if let Some(game_screen) = self.game_screens.get(&game_id) {
+            self.tab_bar.open(game_id, game_screen.name());
+        }
+        self.tab_bar.select(Some(game_id));
     }
 
     fn back(&mut self) {
         self.screen = Screen::Home;
+        self.tab_bar.select(None);
     }
 
-    pub fn open_game_from_name(&self, name: &str) -> Result<()> {
-        let game_id = self
+    /// Closes the game being shown, once the player has agreed to if it's still being played, and
+    /// moves on to the next open game, or the home screen if there are none left.
+    fn close_game(&mut self) -> Result<()> {
+        let Screen::Game(id) = self.screen else {
+            return Ok(());
+        };
+        let game_screen = self
             .game_screens
+            .get_mut(&id)
+            .expect("couldn't find game with id");
+        if game_screen.ask_to_close() {
+            return Ok(());
+        }
+        game_screen.close();
+        let next = self.tab_bar.close(id);
+        self.back();
+        self.switch_tab(next)
+    }
+
+    /// Opens the game with `id`, if there is one, in place of whatever is being shown.
+    fn switch_tab(&self, id: Option<GameId>) -> Result<()> {
+        if let Some(id) = id {
+            self.action_tx.send(Action::OpenGame(id))?;
+        }
+        Ok(())
+    }
+
+    pub fn open_game_from_name(&self, name: &str) -> Result<()> {
+        let game_id = self.find_game(name)?;
+        self.action_tx.send(Action::OpenGame(game_id))?;
+        Ok(())
+    }
+
+    /// Opens the game called `name` with the moves of `pgn` already played.
+    pub fn open_game_from_pgn(&mut self, name: &str, pgn: &str) -> Result<()> {
+        let game_id = self.find_game(name)?;
+        self.game_screens
+            .get_mut(&game_id)
+            .expect("found games have screens")
+            .load_pgn(pgn)
+            .map_err(|error| eyre!(error))?;
+        self.action_tx.send(Action::OpenGame(game_id))?;
+        Ok(())
+    }
+
+    /// Plays the game called `name` against someone else over `connection`.
+    pub fn play_online(&mut self, name: &str, connection: Connection) -> Result<()> {
+        let game_id = self.find_game(name)?;
+        self.game_screens
+            .get_mut(&game_id)
+            .expect("found games have screens")
+            .play_online(connection);
+        Ok(())
+    }
+
+    fn find_game(&self, name: &str) -> Result<GameId> {
+        self.game_screens
             .iter()
             .find_map(|(id, game_screen)| {
                 if game_screen.name().to_lowercase() == name.to_lowercase() {
-                    Some(id)
+                    Some(*id)
                 } else {
                     None
                 }
             })
-            .ok_or_else(|| eyre!("no game with name \"{name}\" found"))?;
-        self.action_tx.send(Action::OpenGame(*game_id))?;
-        Ok(())
+            .ok_or_else(|| eyre!("no game with name \"{name}\" found"))
     }
 }
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use shakmaty::fen::Fen;
+use shakmaty::CastlingMode;
+
+use crate::games::chess::{Chess, EvalParams};
+
+const POSITIONS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "r2q1rk1/pp2bppp/2n1pn2/3p4/3P4/2NBPN2/PP3PPP/R2Q1RK1 w - - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+];
+
+/// Searches a fixed set of chess positions to `depth` plies, printing statistics for each of them
+/// and in total, so that changes to the search can be compared.
+pub fn run(depth: u32) -> Result<()> {
+    let engine = Chess::engine(depth);
+    let evaluator = EvalParams::default();
+
+    let mut nodes = 0;
+    let mut elapsed = Duration::ZERO;
+
+    println!(
+        "{:>3} {:>6} {:>10} {:>9} {:>6} {:>8} {:>8}",
+        "#", "depth", "nodes", "time", "EBF", "1st cut", "TT hits"
+    );
+
+    for (i, fen) in POSITIONS.iter().enumerate() {
+        let position: shakmaty::Chess = fen
+            .parse::<Fen>()?
+            .into_position(CastlingMode::Standard)
+            .map_err(|err| eyre!("{err}"))?;
+        let result = engine.search_with(&Chess::from(position), &evaluator);
+
+        println!(
+            "{:>3} {:>6} {:>10} {:>9} {:>6} {:>8} {:>8}",
+            i + 1,
+            result.depth,
+            result.nodes,
+            format!("{:.0?}", result.elapsed),
+            format_ratio(result.stats.effective_branching_factor()),
+            format_percentage(result.stats.first_move_cutoff_rate()),
+            format_percentage(result.stats.table_hit_rate()),
+        );
+
+        nodes += result.nodes;
+        elapsed += result.elapsed;
+    }
+
+    println!(
+        "\n{nodes} nodes in {elapsed:.2?} ({:.0} nodes/s)",
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+
+    Ok(())
+}
+
+fn format_ratio(ratio: Option<f64>) -> String {
+    ratio.map_or("-".to_string(), |ratio| format!("{ratio:.2}"))
+}
+
+fn format_percentage(rate: Option<f64>) -> String {
+    rate.map_or("-".to_string(), |rate| format!("{:.1}%", rate * 100.0))
+}
@@ -1,13 +1,49 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
 
 use crate::config::{get_config_dir, get_data_dir};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Open specific game
     #[arg(short, long)]
     pub game: Option<String>,
+
+    /// Open a chess game from a PGN file, to continue playing it
+    #[arg(long, conflicts_with = "game")]
+    pub pgn: Option<PathBuf>,
+
+    /// Host the game given with --game on this port, for someone to join over the network
+    #[arg(long, requires = "game", conflicts_with = "join")]
+    pub host: Option<u16>,
+
+    /// Join the game given with --game hosted at this address, such as 192.168.1.2:7878
+    #[arg(long, requires = "game")]
+    pub join: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Tune the chess evaluation against a file of labelled positions
+    Tune {
+        /// File with one FEN per line, followed by the result of the game
+        positions: PathBuf,
+
+        /// Maximum number of tuning iterations
+        #[arg(short, long, default_value_t = 100)]
+        iterations: usize,
+    },
+    /// Search a fixed set of chess positions and report search statistics
+    Bench {
+        /// Number of plies to search each position to
+        #[arg(short, long, default_value_t = 5)]
+        depth: u32,
+    },
 }
 
 const VERSION_MESSAGE: &str = concat!(
@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+/// A chess clock, counting down the time each of two sides has left while it's their turn.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    remaining: [Duration; 2],
+    /// The time added to a side's clock after each of their moves.
+    increment: Duration,
+    /// The side whose time is running, and when it started.
+    running: Option<(usize, Instant)>,
+}
+
+impl Clock {
+    /// A stopped clock giving each side `base` time, plus `increment` after each move.
+    pub fn new(base: Duration, increment: Duration) -> Self {
+        Self {
+            remaining: [base; 2],
+            increment,
+            running: None,
+        }
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /// Runs the time of `side` from `now`. If the other side's time was running, their move is
+    /// over, so it's stopped and they gain the increment.
+    pub fn start(&mut self, side: usize, now: Instant) {
+        match self.running {
+            Some((running, _)) if running == side => return,
+            Some((running, _)) => {
+                self.stop(now);
+                self.remaining[running] += self.increment;
+            }
+            None => {}
+        }
+        self.running = Some((side, now));
+    }
+
+    /// Stops the clock at `now` without either side gaining any time, such as while the game is
+    /// paused or over.
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((side, started)) = self.running.take() {
+            self.remaining[side] =
+                self.remaining[side].saturating_sub(now.saturating_duration_since(started));
+        }
+    }
+
+    /// The time `side` has left at `now`.
+    pub fn remaining(&self, side: usize, now: Instant) -> Duration {
+        match self.running {
+            Some((running, started)) if running == side => {
+                self.remaining[side].saturating_sub(now.saturating_duration_since(started))
+            }
+            _ => self.remaining[side],
+        }
+    }
+
+    /// The side whose time is running, if either is.
+    pub fn running(&self) -> Option<usize> {
+        self.running.map(|(side, _)| side)
+    }
+
+    /// The side that has run out of time at `now`, if either has.
+    pub fn flagged(&self, now: Instant) -> Option<usize> {
+        (0..2).find(|&side| self.remaining(side, now).is_zero())
+    }
+}
+
+/// Writes `time` the way a clock shows it, such as `4:05`, with tenths of a second once there
+/// are less than ten seconds left.
+pub fn format(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds < 10 {
+        format!("{seconds}.{}", time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_each_side_in_turn() {
+        let start = Instant::now();
+        let seconds = |seconds| start + Duration::from_secs(seconds);
+        let mut clock = Clock::new(Duration::from_secs(60), Duration::from_secs(2));
+
+        clock.start(0, start);
+        assert_eq!(clock.remaining(0, seconds(10)), Duration::from_secs(50));
+        assert_eq!(clock.remaining(1, seconds(10)), Duration::from_secs(60));
+
+        // Starting the side already running changes nothing
+        clock.start(0, seconds(5));
+        clock.start(1, seconds(10));
+        assert_eq!(clock.remaining(0, seconds(20)), Duration::from_secs(52));
+        assert_eq!(clock.remaining(1, seconds(20)), Duration::from_secs(50));
+
+        // Pausing doesn't give the increment
+        clock.stop(seconds(20));
+        assert_eq!(clock.running(), None);
+        clock.start(1, seconds(100));
+        assert_eq!(clock.remaining(1, seconds(100)), Duration::from_secs(50));
+
+        assert_eq!(clock.flagged(seconds(149)), None);
+        assert_eq!(clock.flagged(seconds(150)), Some(1));
+    }
+
+    #[test]
+    fn formats_times() {
+        assert_eq!(format(Duration::from_secs(245)), "4:05");
+        assert_eq!(format(Duration::from_secs(10)), "0:10");
+        assert_eq!(format(Duration::from_millis(9_870)), "9.8");
+    }
+}
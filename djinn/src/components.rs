@@ -12,6 +12,8 @@ pub mod fps;
 pub mod game_menu;
 pub mod game_screen;
 pub mod gamecard;
+pub mod tab_bar;
+pub mod toast;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
 ///
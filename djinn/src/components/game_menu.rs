@@ -1,17 +1,22 @@
 use color_eyre::eyre::eyre;
 use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::layout::{Constraint, Layout, Margin, Rect};
+use ratatui::layout::{Constraint, Layout, Margin, Rect, Size};
 use ratatui::text::Span;
 use ratatui::Frame;
 use tokio::sync::mpsc::UnboundedSender;
+use tracing::error;
 
 use crate::action::Action;
 use crate::components::gamecard::{GameCard, GAMECARD_SIZE};
 use crate::components::Component;
+use crate::config::get_data_dir;
+use crate::stats;
 
 pub struct GameMenu {
     game_cards: Vec<GameCard>,
     selected_game: usize,
+    /// The number of cards in each row of the grid, as of when it was last drawn.
+    column_count: usize,
     action_tx: Option<UnboundedSender<Action>>,
 }
 
@@ -20,6 +25,7 @@ impl GameMenu {
         Self {
             game_cards,
             selected_game: 0,
+            column_count: 1,
             action_tx: None,
         }
     }
@@ -32,10 +38,39 @@ impl GameMenu {
         }
     }
 
+    /// Shows the player's rating in each game, from the results of the games they've finished.
+    fn update_ratings(&mut self) {
+        let records = stats::load_all(&get_data_dir()).unwrap_or_else(|err| {
+            error!("Failed to load the statistics: {err}");
+            Vec::new()
+        });
+        for card in &mut self.game_cards {
+            let rating = stats::rating(records.iter().filter(|record| record.game == card.name()));
+            card.set_rating(rating);
+        }
+    }
+
     fn next_game(&mut self) {
         self.selected_game = (self.selected_game + 1) % self.game_cards.len();
     }
 
+    /// Selects the game above the selected one, if it isn't in the top row.
+    fn game_above(&mut self) {
+        if let Some(above) = self.selected_game.checked_sub(self.column_count) {
+            self.selected_game = above;
+        }
+    }
+
+    /// Selects the game below the selected one, or the last game if the row below is too short to
+    /// have one there.
+    fn game_below(&mut self) {
+        let last = self.game_cards.len() - 1;
+        let last_row = last / self.column_count;
+        if self.selected_game / self.column_count < last_row {
+            self.selected_game = (self.selected_game + self.column_count).min(last);
+        }
+    }
+
     fn open_game(&mut self) -> color_eyre::Result<()> {
         self.action_tx
             .as_ref()
@@ -54,10 +89,27 @@ impl Component for GameMenu {
         Ok(())
     }
 
+    fn init(&mut self, _area: Size) -> color_eyre::Result<()> {
+        self.update_ratings();
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> color_eyre::Result<Option<Action>> {
+        // Coming back from a game, which may have just been finished
+        if action == Action::Back {
+            self.update_ratings();
+        }
+        Ok(None)
+    }
+
     fn handle_key_event(&mut self, key: KeyEvent) -> color_eyre::Result<Option<Action>> {
         match key.code {
             KeyCode::Left => self.previous_game(),
             KeyCode::Right => self.next_game(),
+            KeyCode::Up => self.game_above(),
+            KeyCode::Down => self.game_below(),
+            KeyCode::Home => self.selected_game = 0,
+            KeyCode::End => self.selected_game = self.game_cards.len() - 1,
             KeyCode::Enter | KeyCode::Char(' ') => self.open_game()?,
             _ => {}
         }
@@ -76,6 +128,7 @@ impl Component for GameMenu {
             .areas(area);
 
         let column_count = (grid.width / (GAMECARD_SIZE + GRID_SPACING)) as usize;
+        self.column_count = column_count.max(1);
         let row_count = ((self.game_cards.len() - 1) / column_count) + 1;
 
         let rows = Layout::vertical(vec![GAMECARD_SIZE / 2 + 1; row_count]).split(grid);
@@ -1,34 +1,148 @@
+mod setup;
+
 use crate::action::Action;
+use crate::analysis::Analysis;
+use crate::clipboard;
+use crate::clock;
+use crate::clock::Clock;
 use crate::components::Component;
-use crate::games::{Game, WinState};
+use crate::config;
+use crate::config::{get_data_dir, Config, GameSettings, TimeControl};
+use crate::games::{
+    Click, Difficulty, Game, GameError, Progress, SearchLimits, TimeLeft, WinState,
+};
+use crate::minimax::StopToken;
+use crate::network::{Connection, Message};
+use crate::stats;
+use crate::stats::{Outcome, Record};
 use color_eyre::eyre::eyre;
 use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use itertools::Itertools;
-use ratatui::layout::{Constraint, Flex, Margin, Rect};
+use ratatui::layout::{Constraint, Flex, Margin, Position, Rect};
 use ratatui::prelude::Layout;
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Style, Stylize};
 use ratatui::symbols::border;
-use ratatui::text::Text;
+use ratatui::text::{Line, Text};
 use ratatui::widgets::{Block, Paragraph};
 use ratatui::Frame;
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::iter;
+use std::mem;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tui_textarea::TextArea;
 use tui_widgets::popup::{Popup, PopupState};
 
+use setup::Setup;
+
+/// How many moves a hint suggests.
+const HINT_COUNT: usize = 3;
+/// The depth of the custom difficulty until the player changes it.
+const DEFAULT_CUSTOM_DEPTH: u32 = 4;
+/// The width of the evaluation bar inside its border.
+const EVAL_BAR_WIDTH: u16 = 2;
+/// How little time a clock has left before it's shown as running out.
+const LOW_TIME: Duration = Duration::from_secs(10);
+/// The frames of the spinner shown while the computer is thinking, one a tick.
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 #[derive(Clone, Copy)]
 enum GameOver {
     Win,
     Lose,
     Draw,
+    /// The side at this index in [`Game::sides`] won a game between two players.
+    Winner(usize),
+}
+
+/// How a game ended other than by its rules, which is written at the end of the move history.
+#[derive(Clone, Copy)]
+enum Ending {
+    /// The side at this index in [`Game::sides`] resigned.
+    Resigned(usize),
+    DrawAgreed,
+    /// The side at this index in [`Game::sides`] ran out of time.
+    OutOfTime(usize),
+}
+
+impl Ending {
+    fn win_state(self) -> WinState {
+        match self {
+            Ending::Resigned(side) | Ending::OutOfTime(side) => WinState::won_by(1 - side),
+            Ending::DrawAgreed => WinState::Draw,
+        }
+    }
+}
+
+/// Who plays the game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Mode {
+    /// The player against the computer.
+    #[default]
+    Computer,
+    /// Two players taking turns at the same keyboard.
+    Hotseat,
+    /// The computer playing both sides while the player watches.
+    Spectate,
+    /// The player against someone else over the network, which is chosen when starting djinn
+    /// rather than from the mode selector.
+    Online,
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Computer, Mode::Hotseat, Mode::Spectate];
+
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Computer => "Play against the computer",
+            Mode::Hotseat => "Two players",
+            Mode::Spectate => "Watch the computer play itself",
+            Mode::Online => "Play over the network",
+        }
+    }
 }
 
-#[derive(Clone, Copy, Default)]
+/// How far the player has got with closing a game that's still being played.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Closing {
+    /// Asking whether to give up on the game.
+    Asking,
+    Confirmed,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
 enum InputLabel {
     #[default]
     Invalid,
     Thinking,
+    FindingHint,
+    Hint,
+    Spectating,
+    Analysing,
+    OfferingDraw,
+    WaitingForOpponent,
+    /// Shows [`GameScreen::notice`].
+    Notice,
+    /// Shows [`GameScreen::notice`] as something having gone wrong.
+    Failure,
+}
+
+/// A game in progress, saved when djinn is closed so that it can be carried on with when it's
+/// started again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub name: String,
+    /// The index of the chosen value of each of the game's options.
+    #[serde(default)]
+    options: Vec<usize>,
+    moves: Vec<String>,
+    mode: Mode,
+    plays_second: bool,
+    flipped: bool,
+    difficulty: Difficulty,
 }
 
 pub struct GameScreen<'a> {
@@ -36,8 +150,80 @@ pub struct GameScreen<'a> {
     input: TextArea<'a>,
     input_label: Option<InputLabel>,
     game_over: Option<GameOver>,
+    ending: Option<Ending>,
     popup_state: PopupState,
-    computer_move_thread: Option<JoinHandle<String>>,
+    computer_move_thread: Option<JoinHandle<Result<String, GameError>>>,
+    /// Where the computer's search keeps how far it has got.
+    search_progress: Arc<Mutex<Option<Progress>>>,
+    /// Stops the computer's search, so that it plays the best move it has found so far.
+    search_stop: StopToken,
+    /// When the computer started thinking about its move, and how far it had got at the last
+    /// tick, which the thinking label shows.
+    thinking: Option<(Instant, Option<Progress>)>,
+    /// The frame of [`SPINNER`] being shown, which moves on every tick while the computer thinks.
+    spinner: usize,
+    hint_thread: Option<JoinHandle<Vec<String>>>,
+    /// The moves suggested by the last hint, best first.
+    hints: Vec<String>,
+    /// A message about something the player asked for, such as saving the game.
+    notice: String,
+    advantage_thread: Option<JoinHandle<Option<f32>>>,
+    /// How far ahead the side that moves first is, from -1 to 1, which the evaluation bar shows
+    /// for the side at the bottom of the board.
+    advantage: Option<f32>,
+    /// Evaluates the position for the computer to decide whether to accept a draw.
+    draw_offer_thread: Option<JoinHandle<Option<f32>>>,
+    /// How far behind the computer has to be to accept a draw, from the config.
+    contempt: f32,
+    /// The options screen, which is shown instead of taking moves while the player sets up a new
+    /// game.
+    setup: Option<Setup>,
+    /// The index of the chosen value of each of [`Game::options`] for the game being played.
+    options: Vec<usize>,
+    mode: Mode,
+    /// How long to wait between moves when the computer plays itself.
+    spectate_delay: Duration,
+    /// When the computer playing itself makes its next move.
+    next_computer_move: Option<Instant>,
+    /// Whether the player plays the side that moves second, so the computer moves first.
+    plays_second: bool,
+    /// Whether the board is turned around from the player's side.
+    flipped: bool,
+    /// Where the board was last drawn, inside its border, for finding which part of it is clicked.
+    board_area: Rect,
+    /// The highlighted entry of the difficulty selector, which is shown whenever the player asks
+    /// to change the difficulty.
+    selected_difficulty: Option<usize>,
+    difficulty: Difficulty,
+    /// The ways to finish the move the player entered, such as the piece a pawn promotes to,
+    /// which they choose from with the highlighted entry of the completion selector.
+    completions: Vec<String>,
+    selected_completion: Option<usize>,
+    /// The depth offered by the custom entry of the difficulty selector.
+    custom_depth: u32,
+    /// The length of the move history before each of the player's moves, which is how far back
+    /// each undo goes.
+    turns: Vec<usize>,
+    /// The moves taken back by each undo, starting with the player's own move, most recent last.
+    undone: Vec<Vec<String>>,
+    /// The time control from the config, which each new game's clock starts from.
+    time_control: Option<TimeControl>,
+    /// The clock the game is played with, if it's timed.
+    clock: Option<Clock>,
+    analysis_thread: Option<JoinHandle<Result<Analysis, GameError>>>,
+    /// The finished game's analysis, which is shown in place of the game while it's open.
+    analysis: Option<Analysis>,
+    /// An earlier position being looked back at, and the number of moves played in it, which is
+    /// shown in place of the game without changing it.
+    replay: Option<(usize, Box<dyn Game>)>,
+    /// The connection to the other player of an online game.
+    connection: Option<Connection>,
+    /// When the game started, until its result against the computer has been recorded.
+    started: Option<Instant>,
+    /// The lines of the player's statistics for the game, which are shown over it while they're
+    /// open.
+    statistics: Option<Vec<String>>,
+    closing: Option<Closing>,
 }
 
 impl GameScreen<'_> {
@@ -50,8 +236,44 @@ impl GameScreen<'_> {
             input,
             input_label: None,
             game_over: None,
+            ending: None,
             popup_state: PopupState::default(),
             computer_move_thread: None,
+            search_progress: Arc::default(),
+            search_stop: StopToken::new(),
+            thinking: None,
+            spinner: 0,
+            hint_thread: None,
+            hints: Vec::new(),
+            notice: String::new(),
+            advantage_thread: None,
+            advantage: None,
+            draw_offer_thread: None,
+            contempt: 0.0,
+            setup: None,
+            options: Vec::new(),
+            mode: Mode::default(),
+            spectate_delay: Duration::from_secs(1),
+            next_computer_move: None,
+            plays_second: false,
+            flipped: false,
+            board_area: Rect::default(),
+            selected_difficulty: None,
+            difficulty: Difficulty::default(),
+            completions: Vec::new(),
+            selected_completion: None,
+            custom_depth: DEFAULT_CUSTOM_DEPTH,
+            turns: Vec::new(),
+            undone: Vec::new(),
+            time_control: None,
+            clock: None,
+            analysis_thread: None,
+            analysis: None,
+            replay: None,
+            connection: None,
+            started: None,
+            statistics: None,
+            closing: None,
         }
     }
 
@@ -67,28 +289,262 @@ impl GameScreen<'_> {
         self.game().name()
     }
 
-    fn enter_input(&mut self) {
-        if self.computer_move_thread.is_none() {
+    /// Replaces the game with one read from PGN, which the player carries on with against the
+    /// computer as whichever side is to move.
+    pub fn load_pgn(&mut self, pgn: &str) -> Result<(), String> {
+        self.game_mut().load_pgn(pgn)?;
+        self.mode = Mode::Computer;
+        let side = self.game().side_to_move();
+        self.plays_second = side == 1;
+        self.game_over = None;
+        self.ending = None;
+        self.replay = None;
+        self.turns.clear();
+        self.undone.clear();
+        self.reset_clock();
+        self.update_game_over();
+        // A game that was already over before it was loaded isn't one of the player's results
+        self.started = Some(Instant::now()).filter(|_| self.game_over.is_none());
+        Ok(())
+    }
+
+    /// Plays against someone else over `connection`, starting the game again. The host moves
+    /// first.
+    pub fn play_online(&mut self, connection: Connection) {
+        self.mode = Mode::Online;
+        self.plays_second = !connection.is_hosting();
+        self.flipped = false;
+        self.connection = Some(connection);
+        self.restart();
+    }
+
+    /// The game being played, if it's one to carry on with next time. Finished games, games that
+    /// haven't started, and online games, which can't be joined again, aren't saved.
+    pub fn save(&self) -> Option<SavedGame> {
+        let moves = self.game().move_history();
+        if moves.is_empty() || self.game_over.is_some() || self.mode == Mode::Online {
+            return None;
+        }
+        Some(SavedGame {
+            name: self.name(),
+            options: self.options.clone(),
+            moves,
+            mode: self.mode,
+            plays_second: self.plays_second,
+            flipped: self.flipped,
+            difficulty: self.difficulty,
+        })
+    }
+
+    /// Carries on with a saved game by playing its moves again, starting its clock afresh. If
+    /// the moves can't all be played, the game is left where it started.
+    pub fn restore(&mut self, saved: SavedGame) -> Result<(), GameError> {
+        // Options that have since changed are played with their first value
+        let options = {
+            let mut game = self.game_mut();
+            let offered = game.options();
+            let fits = saved.options.len() == offered.len()
+                && iter::zip(&saved.options, &offered)
+                    .all(|(&choice, option)| choice < option.values.len());
+            let options = if fits {
+                saved.options
+            } else {
+                vec![0; offered.len()]
+            };
+            game.set_options(&options);
+            for move_ in &saved.moves {
+                if let Err(err) = game.play_move(move_) {
+                    game.reset();
+                    return Err(err);
+                }
+            }
+            options
+        };
+
+        self.options = options;
+        self.mode = saved.mode;
+        self.plays_second = saved.plays_second;
+        self.flipped = saved.flipped;
+        self.difficulty = saved.difficulty;
+        self.turns.clear();
+        self.undone.clear();
+        self.reset_clock();
+        self.started = Some(Instant::now());
+        self.update_game_over();
+        self.evaluate();
+
+        match self.mode {
+            Mode::Computer if self.is_computer_turn() => self.start_computer_move(),
+            Mode::Spectate => self.next_computer_move = Some(Instant::now()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Whether the game has been started and isn't over yet, so closing it would lose it.
+    fn is_in_progress(&self) -> bool {
+        self.game_over.is_none() && !self.game().move_history().is_empty()
+    }
+
+    /// Asks the player whether to close the game if it's still being played, returning whether
+    /// closing it has to wait for their answer.
+    pub fn ask_to_close(&mut self) -> bool {
+        match self.closing {
+            Some(Closing::Confirmed) => false,
+            Some(Closing::Asking) => true,
+            None if self.is_in_progress() => {
+                self.closing = Some(Closing::Asking);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Puts the game back as it was before it was first opened, keeping only the settings it was
+    /// last started with. Searches that are still running finish on their own with the old game
+    /// rather than holding up the new one.
+    pub fn close(&mut self) {
+        self.search_stop.stop();
+        let mut game = self.game().box_clone();
+        game.reset();
+        let closed = mem::replace(self, Self::new(game));
+        self.spectate_delay = closed.spectate_delay;
+        self.contempt = closed.contempt;
+        self.time_control = closed.time_control;
+        self.options = closed.options;
+        // Online games can't be started again from the options screen
+        if closed.mode != Mode::Online {
+            self.mode = closed.mode;
+            self.plays_second = closed.plays_second;
+        }
+        self.difficulty = closed.difficulty;
+        self.custom_depth = closed.custom_depth;
+        self.reset_clock();
+    }
+
+    /// Stops the computer's search and leaves every search that's still running to finish on its
+    /// own with a copy of the game, so that the game can be changed straight away rather than
+    /// once they've let go of it.
+    fn detach_searches(&mut self) {
+        self.search_stop.stop();
+        // Not short-circuiting, so that every handle is let go of
+        let searching = self.computer_move_thread.take().is_some()
+            | self.hint_thread.take().is_some()
+            | self.analysis_thread.take().is_some()
+            | self.draw_offer_thread.take().is_some()
+            | self.advantage_thread.take().is_some();
+        if searching {
+            let game = self.game().box_clone();
+            self.game = Arc::new(RwLock::new(game));
+        }
+        self.thinking = None;
+        self.next_computer_move = None;
+        if matches!(
+            self.input_label,
+            Some(
+                InputLabel::Thinking
+                    | InputLabel::FindingHint
+                    | InputLabel::Analysing
+                    | InputLabel::OfferingDraw
+            )
+        ) {
+            self.input_label = None;
+        }
+    }
+
+    /// Whether it's the player's turn in an online game that's still connected.
+    fn is_online_turn(&self) -> bool {
+        let connected = self
+            .connection
+            .as_ref()
+            .is_some_and(Connection::is_connected);
+        connected && self.game().side_to_move() == usize::from(self.plays_second)
+    }
+
+    /// Plays the moves the other player of an online game has sent, and anything else they've
+    /// done since the last tick.
+    fn receive_online(&mut self) -> Result<(), GameError> {
+        let Some(connection) = &mut self.connection else {
+            return Ok(());
+        };
+        for message in connection.receive() {
+            match message {
+                Message::Move { move_ } if !self.is_online_turn() && self.game_over.is_none() => {
+                    if let Err(err) = self.play_computer_move(&move_) {
+                        self.disconnect("the other player sent an invalid move");
+                        return Err(err);
+                    }
+                }
+                Message::Resign if self.game_over.is_none() => {
+                    self.end(Ending::Resigned(usize::from(!self.plays_second)));
+                }
+                // Sent before the other player heard that the game was over, such as when both
+                // resign at once or a move crosses running out of time
+                Message::Move { .. } | Message::Resign if self.game_over.is_some() => {}
+                _ => self.disconnect("the other player sent something unexpected"),
+            }
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self, reason: &str) {
+        if let Some(connection) = &mut self.connection {
+            connection.disconnect(reason.to_string());
+        }
+    }
+
+    /// Whether a search is holding on to the game, in which case the input label says so.
+    fn is_searching(&mut self) -> bool {
+        if self.computer_move_thread.is_some() {
+            self.input_label = Some(InputLabel::Thinking);
+        } else if self.hint_thread.is_some() {
+            self.input_label = Some(InputLabel::FindingHint);
+        } else if self.analysis_thread.is_some() {
+            self.input_label = Some(InputLabel::Analysing);
+        } else if self.draw_offer_thread.is_some() {
+            self.input_label = Some(InputLabel::OfferingDraw);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn enter_input(&mut self) -> Result<(), GameError> {
+        // Moves are played in the game, so it's brought back into view
+        self.replay = None;
+        if self.mode == Mode::Spectate {
+            self.input_label = Some(InputLabel::Spectating);
+        } else if self.mode == Mode::Online && !self.is_online_turn() {
+            self.input_label = Some(InputLabel::WaitingForOpponent);
+        } else if !self.is_searching() {
+            let completions = self.game().completions(&self.input.lines()[0]);
             if self.game().is_valid_move(&self.input.lines()[0]) {
                 self.input_label = None;
 
-                self.game_mut().play_move(&self.input.lines()[0]);
+                let turn = self.game().move_history().len();
+                self.game_mut().play_move(&self.input.lines()[0])?;
+                if let Some(connection) = &mut self.connection {
+                    connection.send(&Message::Move {
+                        move_: self.input.lines()[0].clone(),
+                    });
+                }
+                self.turns.push(turn);
+                // A new move replaces the ones that were taken back
+                self.undone.clear();
                 self.update_game_over();
+                self.update_clock();
+                self.evaluate();
 
-                if self.game_over.is_none() {
-                    self.computer_move_thread = Some(thread::spawn({
-                        let game = Arc::clone(&self.game);
-                        move || {
-                            game.read()
-                                .expect("Failed to access the game state")
-                                .computer_move()
-                        }
-                    }));
+                if self.game_over.is_none() && self.is_computer_turn() {
+                    self.start_computer_move();
                 }
 
                 // clear the input
                 self.input.select_all();
                 self.input.cut();
+            } else if !completions.is_empty() {
+                self.completions = completions;
+                self.selected_completion = Some(0);
             } else {
                 self.input_label = Some(InputLabel::Invalid);
                 self.input.set_block(
@@ -97,32 +553,747 @@ impl GameScreen<'_> {
                         .title_style(Color::LightRed),
                 );
             }
+        }
+        Ok(())
+    }
+
+    /// The legal moves that start with what the player has typed, if it's their turn.
+    fn matching_moves(&self) -> Vec<String> {
+        let waiting = self.computer_move_thread.is_some()
+            || self.mode == Mode::Spectate
+            || (self.mode == Mode::Online && !self.is_online_turn());
+        if waiting || self.game_over.is_some() {
+            return Vec::new();
+        }
+        let typed = &self.input.lines()[0];
+        let mut moves = self.game().legal_moves();
+        moves.retain(|move_| move_.starts_with(typed.as_str()));
+        moves
+    }
+
+    /// Fills in as much of the move being typed as every matching legal move has in common.
+    fn complete_input(&mut self) {
+        let moves = self.matching_moves();
+        let Some((first, rest)) = moves.split_first() else {
+            return;
+        };
+        let common = rest.iter().fold(first.as_str(), |common, move_| {
+            let length = common
+                .chars()
+                .zip(move_.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a.len_utf8())
+                .sum();
+            &common[..length]
+        });
+        if common.len() > self.input.lines()[0].len() {
+            let common = common.to_string();
+            self.input.select_all();
+            self.input.cut();
+            self.input.insert_str(common);
+            self.input_label = None;
+        }
+    }
+
+    fn handle_completion_key(&mut self, key: KeyEvent, selected: usize) -> Result<(), GameError> {
+        let count = self.completions.len();
+        match key.code {
+            KeyCode::Up => self.selected_completion = Some((selected + count - 1) % count),
+            KeyCode::Down => self.selected_completion = Some((selected + 1) % count),
+            KeyCode::Enter => {
+                self.selected_completion = None;
+                let completion = std::mem::take(&mut self.completions).swap_remove(selected);
+                let move_ = format!("{}{completion}", self.input.lines()[0]);
+                self.input.select_all();
+                self.input.cut();
+                self.input.insert_str(move_);
+                return self.enter_input();
+            }
+            KeyCode::Esc => {
+                self.selected_completion = None;
+                self.completions.clear();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Plays or starts a move on the board at the clicked position of the terminal.
+    fn click_board(&mut self, column: u16, row: u16) -> Result<(), GameError> {
+        let on_board = self.board_area.contains(Position::new(column, row));
+        if self.is_choosing()
+            || self.selected_completion.is_some()
+            || self.replay.is_some()
+            || !on_board
+            || self.game_over.is_some()
+            || self.mode == Mode::Spectate
+        {
+            return Ok(());
+        }
+
+        let click = self.game().click(
+            &self.input.lines()[0],
+            column - self.board_area.x,
+            row - self.board_area.y,
+            self.board_side(),
+        );
+        let Some(Click::Select(input) | Click::Play(input)) = &click else {
+            return Ok(());
+        };
+        self.input.select_all();
+        self.input.cut();
+        self.input.insert_str(input);
+        self.input_label = None;
+        if matches!(click, Some(Click::Play(_))) {
+            self.enter_input()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the options screen or the difficulty selector is open, or the player is being
+    /// asked about closing the game, which pauses it.
+    fn is_choosing(&self) -> bool {
+        self.setup.is_some() || self.selected_difficulty.is_some() || self.closing.is_some()
+    }
+
+    /// The side whose pieces are drawn at the bottom of the board, which faces whoever plays
+    /// against the computer, or the first player otherwise.
+    fn board_side(&self) -> usize {
+        usize::from(self.plays_second != self.flipped)
+    }
+
+    /// Whether it's the computer's turn in a game against it.
+    fn is_computer_turn(&self) -> bool {
+        self.mode == Mode::Computer && self.game().side_to_move() != usize::from(self.plays_second)
+    }
+
+    fn start_computer_move(&mut self) {
+        let side = self.game().side_to_move();
+        let time = self.clock.as_ref().map(|clock| TimeLeft {
+            remaining: clock.remaining(side, Instant::now()),
+            increment: clock.increment(),
+        });
+        *self
+            .search_progress
+            .lock()
+            .expect("Failed to reset the search's progress") = None;
+        self.search_stop = StopToken::new();
+        self.thinking = Some((Instant::now(), None));
+        self.input_label = Some(InputLabel::Thinking);
+        self.computer_move_thread = Some(thread::spawn({
+            let game = Arc::clone(&self.game);
+            let limits = SearchLimits {
+                difficulty: self.difficulty,
+                time,
+                progress: Some(Arc::clone(&self.search_progress)),
+                stop: Some(self.search_stop.clone()),
+            };
+            move || {
+                game.read()
+                    .expect("Failed to access the game state")
+                    .computer_move(limits)
+            }
+        }));
+    }
+
+    /// Cuts the computer's search short, so that it plays the best move it has found so far.
+    fn stop_thinking(&mut self) {
+        if self.computer_move_thread.is_some() {
+            self.search_stop.stop();
+        }
+    }
+
+    /// Shows that the computer is still working on its move with a spinner, followed by how far it
+    /// has got.
+    fn thinking_text(&self) -> String {
+        let spinner = SPINNER[self.spinner % SPINNER.len()];
+        format!("{spinner} {}", self.thinking_progress())
+    }
+
+    /// Describes how far the computer has got with its move, such as its search depth, its
+    /// evaluation and how long it has been thinking.
+    fn thinking_progress(&self) -> String {
+        let Some((since, progress)) = self.thinking else {
+            return "Computer is thinking".to_string();
+        };
+        let elapsed = format!("{:.1}s", since.elapsed().as_secs_f32());
+        let Some(Progress { depth, score }) = progress else {
+            return format!("Computer is thinking, {elapsed}");
+        };
+        let score = match score {
+            Some(score) if score.is_infinite() && score > 0.0 => ", winning".to_string(),
+            Some(score) if score.is_infinite() => ", losing".to_string(),
+            Some(score) => format!(", {score:+.1}"),
+            None => String::new(),
+        };
+        format!("Computer is thinking: depth {depth}{score}, {elapsed}")
+    }
+
+    /// Starts a new clock from the time control, if games are timed.
+    fn reset_clock(&mut self) {
+        self.clock = self.time_control.map(|time_control| {
+            Clock::new(
+                Duration::from_secs_f64(time_control.base),
+                Duration::from_secs_f64(time_control.increment),
+            )
+        });
+    }
+
+    /// Runs the clock of the side to move, or stops it while the game is paused or over, and
+    /// ends the game once a side runs out of time.
+    fn update_clock(&mut self) {
+        let now = Instant::now();
+        let paused = self.is_choosing() || self.game_over.is_some();
+        let side = self.game().side_to_move();
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        if paused {
+            clock.stop(now);
+            return;
+        }
+
+        clock.start(side, now);
+        if let Some(side) = clock.flagged(now) {
+            clock.stop(now);
+            self.end(Ending::OutOfTime(side));
+            let name = self.game().sides()[side].clone();
+            self.show_notice(format!("{name} ran out of time"), InputLabel::Notice);
+        }
+    }
+
+    /// Starts searching for moves to suggest to the player.
+    fn hint(&mut self) {
+        if self.game_over.is_some() || self.is_searching() {
+            return;
+        }
+        self.input_label = Some(InputLabel::FindingHint);
+        self.hint_thread = Some(thread::spawn({
+            let game = Arc::clone(&self.game);
+            move || {
+                game.read()
+                    .expect("Failed to access the game state")
+                    .hints(HINT_COUNT)
+            }
+        }));
+    }
+
+    /// Starts evaluating every position of the finished game again, to show where it went wrong.
+    fn analyse(&mut self) {
+        if self.analysis_thread.is_some() {
+            return;
+        }
+        self.input_label = Some(InputLabel::Analysing);
+        self.analysis_thread = Some(thread::spawn({
+            let game = Arc::clone(&self.game);
+            move || Analysis::new(&**game.read().expect("Failed to access the game state"))
+        }));
+    }
+
+    fn handle_analysis_key(&mut self, key: KeyEvent) {
+        let Some(analysis) = &mut self.analysis else {
+            return;
+        };
+        match key.code {
+            KeyCode::Left => analysis.step(-1),
+            KeyCode::Right => analysis.step(1),
+            KeyCode::Home => analysis.step(isize::MIN),
+            KeyCode::End => analysis.step(isize::MAX),
+            KeyCode::Esc => {
+                self.analysis = None;
+                self.input_label = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Gives up the game, for the player against the computer or for whoever is to move between
+    /// two players.
+    fn resign(&mut self) {
+        if self.game_over.is_some() || self.is_choosing() {
+            return;
+        }
+        let side = match self.mode {
+            Mode::Computer => usize::from(self.plays_second),
+            Mode::Hotseat => self.game().side_to_move(),
+            Mode::Spectate => return,
+            Mode::Online => {
+                if let Some(connection) = &mut self.connection {
+                    connection.send(&Message::Resign);
+                }
+                usize::from(self.plays_second)
+            }
+        };
+        self.end(Ending::Resigned(side));
+    }
+
+    /// Ends the game other than by its rules.
+    fn end(&mut self, ending: Ending) {
+        self.ending = Some(ending);
+        self.game_over = Some(self.game_over_for(ending.win_state()));
+        self.record_result();
+    }
+
+    /// Adds the result of a finished game against the computer to the player's statistics, once
+    /// for each game however many times it's taken back and finished again.
+    fn record_result(&mut self) {
+        let outcome = match self.game_over {
+            Some(GameOver::Win) => Outcome::Win,
+            Some(GameOver::Lose) => Outcome::Loss,
+            Some(GameOver::Draw) => Outcome::Draw,
+            Some(GameOver::Winner(_)) | None => return,
+        };
+        if self.mode != Mode::Computer {
+            return;
+        }
+        let Some(started) = self.started.take() else {
+            return;
+        };
+
+        let record = Record {
+            game: self.name(),
+            outcome,
+            difficulty: self.difficulty,
+            duration: started.elapsed(),
+            moves: self.game().move_history().len(),
+            finished: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+        };
+        if let Err(err) = stats::record(&get_data_dir(), &record) {
+            let notice = format!("Couldn't save the result: {err}");
+            self.show_notice(notice, InputLabel::Failure);
+        }
+    }
+
+    /// Opens the player's statistics for the game.
+    fn show_statistics(&mut self) {
+        self.statistics = Some(match stats::load(&get_data_dir(), &self.name()) {
+            Ok(records) => stats::summarise(&records),
+            Err(err) => vec![format!("Couldn't load the statistics: {err}")],
+        });
+    }
+
+    /// Offers a draw, which the computer decides on once it has evaluated the position. Two
+    /// players at the same keyboard have already agreed to it.
+    fn offer_draw(&mut self) {
+        if self.game_over.is_some() || self.is_choosing() {
+            return;
+        }
+        match self.mode {
+            Mode::Computer if self.draw_offer_thread.is_none() => {
+                self.input_label = Some(InputLabel::OfferingDraw);
+                self.draw_offer_thread = Some(thread::spawn({
+                    let game = Arc::clone(&self.game);
+                    move || {
+                        game.read()
+                            .expect("Failed to access the game state")
+                            .advantage()
+                    }
+                }));
+            }
+            Mode::Hotseat => self.agree_draw(),
+            _ => {}
+        }
+    }
+
+    fn agree_draw(&mut self) {
+        self.end(Ending::DrawAgreed);
+    }
+
+    /// Accepts the player's draw offer if the computer is behind by more than its contempt, by
+    /// the evaluation `advantage` of the side that moves first.
+    fn answer_draw_offer(&mut self, advantage: Option<f32>) {
+        if self.game_over.is_some() {
+            return;
+        }
+        let computer_advantage = advantage.map(|advantage| {
+            if self.plays_second {
+                advantage
+            } else {
+                -advantage
+            }
+        });
+        // Without an evaluation, the computer has no reason to stop
+        if computer_advantage.is_some_and(|advantage| advantage < -self.contempt) {
+            self.agree_draw();
+            self.input_label = None;
         } else {
-            self.input_label = Some(InputLabel::Thinking);
+            self.show_notice(
+                "The computer declines the draw".to_string(),
+                InputLabel::Notice,
+            );
+        }
+    }
+
+    /// Writes out how the game ended, if it has.
+    fn result_text(&self) -> Option<String> {
+        let game = self.game();
+        let sides = game.sides();
+        let text = match self.ending {
+            Some(Ending::Resigned(side)) => format!("{} resigns", sides[side]),
+            Some(Ending::DrawAgreed) => "Draw agreed".to_string(),
+            Some(Ending::OutOfTime(side)) => format!("{} ran out of time", sides[side]),
+            None => match game.win_state()? {
+                WinState::Decisive(winner) => format!("{} wins", sides[winner as usize]),
+                WinState::Draw => "Draw".to_string(),
+            },
+        };
+        Some(text)
+    }
+
+    /// Looks at the position `plies` moves after the one shown, or before it if it's negative,
+    /// going back to the game once past its last move.
+    fn step_replay(&mut self, plies: isize) {
+        if self.analysis.is_some() {
+            return;
+        }
+        let moves = self.game().move_history().len();
+        let shown = self.replay.as_ref().map_or(moves, |&(ply, _)| ply);
+        let ply = shown.saturating_add_signed(plies);
+        self.replay = if ply >= moves {
+            None
+        } else {
+            Some((ply, self.game().replay(ply)))
+        };
+    }
+
+    /// The names of whoever plays each side, for recording the game.
+    fn player_names(&self) -> [String; 2] {
+        let computer = format!("djinn ({})", self.difficulty);
+        match self.mode {
+            Mode::Computer if self.plays_second => [computer, "Player".to_string()],
+            Mode::Computer => ["Player".to_string(), computer],
+            Mode::Hotseat => ["Player 1", "Player 2"].map(String::from),
+            Mode::Spectate => [computer.clone(), computer],
+            Mode::Online if self.plays_second => ["Opponent", "Player"].map(String::from),
+            Mode::Online => ["Player", "Opponent"].map(String::from),
+        }
+    }
+
+    fn show_notice(&mut self, notice: String, label: InputLabel) {
+        self.notice = notice;
+        self.input_label = Some(label);
+    }
+
+    /// Saves the game as PGN to a new file in the data directory.
+    fn export_pgn(&mut self) {
+        let [first, second] = self.player_names();
+        let result = self.ending.map(Ending::win_state);
+        let Some(pgn) = self.game().pgn([&first, &second], result) else {
+            return;
+        };
+
+        let directory = get_data_dir().join("games");
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let name = self.name().to_lowercase().replace(' ', "-");
+        let path = directory.join(format!("{name}-{time}.pgn"));
+        match fs::create_dir_all(&directory).and_then(|()| fs::write(&path, pgn)) {
+            Ok(()) => self.show_notice(format!("Saved to {}", path.display()), InputLabel::Notice),
+            Err(error) => self.show_notice(format!("Couldn't save: {error}"), InputLabel::Failure),
+        }
+    }
+
+    fn copy_pgn(&mut self) {
+        let [first, second] = self.player_names();
+        let result = self.ending.map(Ending::win_state);
+        let Some(pgn) = self.game().pgn([&first, &second], result) else {
+            return;
+        };
+        match clipboard::copy(&pgn) {
+            Ok(()) => self.show_notice("Copied the game as PGN".to_string(), InputLabel::Notice),
+            Err(error) => self.show_notice(format!("Couldn't copy: {error}"), InputLabel::Failure),
+        }
+    }
+
+    /// Starts a shallow search of the position for the evaluation bar, replacing any earlier
+    /// one that hasn't finished.
+    fn evaluate(&mut self) {
+        self.advantage_thread = Some(thread::spawn({
+            let game = Arc::clone(&self.game);
+            move || {
+                game.read()
+                    .expect("Failed to access the game state")
+                    .advantage()
+            }
+        }));
+    }
+
+    fn play_computer_move(&mut self, computer_move: &str) -> Result<(), GameError> {
+        self.game_mut().play_move(computer_move)?;
+        self.update_game_over();
+        self.update_clock();
+        self.evaluate();
+        Ok(())
+    }
+
+    /// Takes back the player's last move, along with the computer's reply to it. Online games
+    /// can't be taken back, and neither can running out of time, since the clock would still be
+    /// at zero.
+    fn undo(&mut self) -> Result<(), GameError> {
+        if self.is_searching() || self.mode == Mode::Online {
+            return Ok(());
+        }
+        if let Some(Ending::OutOfTime(_)) = self.ending {
+            self.show_notice(
+                "Running out of time can't be taken back".to_string(),
+                InputLabel::Notice,
+            );
+            return Ok(());
+        }
+        let Some(turn) = self.turns.pop() else {
+            return Ok(());
+        };
+        self.analysis = None;
+
+        let mut undone = Vec::new();
+        let mut result = Ok(());
+        {
+            let mut game = self.game_mut();
+            while game.move_history().len() > turn {
+                match game.undo() {
+                    Ok(move_) => undone.extend(move_),
+                    Err(err) => {
+                        result = Err(err);
+                        break;
+                    }
+                }
+            }
+        }
+        undone.reverse();
+        self.undone.push(undone);
+        self.game_over = None;
+        self.ending = None;
+        self.replay = None;
+        self.evaluate();
+        result
+    }
+
+    /// Plays the moves taken back by the last undo again.
+    fn redo(&mut self) -> Result<(), GameError> {
+        if self.is_searching() || self.mode == Mode::Online {
+            return Ok(());
+        }
+        let Some(moves) = self.undone.pop() else {
+            return Ok(());
+        };
+        let Some((player_move, computer_moves)) = moves.split_first() else {
+            return Ok(());
+        };
+
+        let turn = self.game().move_history().len();
+        self.game_mut().play_move(player_move)?;
+        self.turns.push(turn);
+        self.update_game_over();
+        self.evaluate();
+        for computer_move in computer_moves {
+            self.play_computer_move(computer_move)?;
+        }
+        Ok(())
+    }
+
+    /// Shows the player what went wrong with the game, such as a move that isn't legal, below the
+    /// board.
+    fn report(&mut self, result: Result<(), GameError>) {
+        if let Err(err) = result {
+            self.show_notice(err.to_string(), InputLabel::Failure);
         }
     }
 
     fn update_game_over(&mut self) {
         let win_state = { self.game().win_state() };
         if let Some(win_state) = win_state {
-            self.game_over = Some(match win_state {
-                WinState::Decisive => GameOver::Win,
-                WinState::Draw => GameOver::Draw,
+            self.game_over = Some(self.game_over_for(win_state));
+            self.record_result();
+        }
+    }
+
+    /// What a game ending in `win_state` means for whoever is playing it.
+    fn game_over_for(&self, win_state: WinState) -> GameOver {
+        match (win_state, self.mode) {
+            (WinState::Draw, _) => GameOver::Draw,
+            (WinState::Decisive(winner), Mode::Computer | Mode::Online) => {
+                if winner as usize == usize::from(self.plays_second) {
+                    GameOver::Win
+                } else {
+                    GameOver::Lose
+                }
+            }
+            (WinState::Decisive(winner), Mode::Hotseat | Mode::Spectate) => {
+                GameOver::Winner(winner as usize)
+            }
+        }
+    }
+
+    /// Shows the options screen before the first move, starting from the options of the last
+    /// game.
+    fn open(&mut self) {
+        let new_game = self.game().move_history().is_empty();
+        // Online games are set up before they're opened
+        if new_game && self.mode != Mode::Online {
+            let options = self.game().options();
+            self.setup = Some(Setup::new(
+                options,
+                &self.options,
+                self.mode,
+                self.plays_second,
+                self.difficulty,
+                self.time_control,
+            ));
+        }
+        if new_game {
+            self.reset_clock();
+            self.started = Some(Instant::now());
+        }
+        self.evaluate();
+    }
+
+    fn handle_setup_key(&mut self, key: KeyEvent) {
+        let Some(setup) = &mut self.setup else {
+            return;
+        };
+        match key.code {
+            KeyCode::Up => setup.select(-1),
+            KeyCode::Down => setup.select(1),
+            KeyCode::Left => setup.change(-1),
+            KeyCode::Right => setup.change(1),
+            KeyCode::Enter => {
+                if let Some(setup) = self.setup.take() {
+                    self.start(setup);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts the options screen from the settings the game was last started with, unless it's
+    /// already being played.
+    fn apply_settings(&mut self, settings: &GameSettings) {
+        if !self.game().move_history().is_empty() {
+            return;
+        }
+        let options = self.game().options();
+        self.options = options
+            .iter()
+            .map(|option| {
+                let value = settings.options.get(&option.name.to_lowercase());
+                value
+                    .and_then(|value| option.values.iter().position(|offered| offered == value))
+                    .unwrap_or_default()
             })
+            .collect();
+        if let Some(difficulty) = settings.difficulty {
+            self.difficulty = difficulty;
+            if let Difficulty::Custom(depth) = difficulty {
+                self.custom_depth = depth;
+            }
+        }
+        if let Some(plays_second) = settings.plays_second {
+            self.plays_second = plays_second;
+        }
+    }
+
+    /// Remembers the options chosen on the options screen for the next time the game is set up.
+    fn save_settings(&mut self, setup: &Setup) {
+        let options = self.game().options();
+        let settings = GameSettings {
+            options: iter::zip(options, &setup.choices)
+                .map(|(option, &choice)| {
+                    (option.name.to_lowercase(), option.values[choice].clone())
+                })
+                .collect(),
+            difficulty: Some(setup.difficulty),
+            plays_second: Some(setup.plays_second),
+        };
+        if let Err(err) = config::save_game_settings(&self.name(), settings) {
+            let notice = format!("Couldn't save the settings: {err}");
+            self.show_notice(notice, InputLabel::Failure);
+        }
+    }
+
+    /// Starts a new game with the options chosen on the options screen.
+    fn start(&mut self, setup: Setup) {
+        self.save_settings(&setup);
+        self.detach_searches();
+        self.game_mut().set_options(&setup.choices);
+        self.time_control = setup.time_control();
+        self.options = setup.choices;
+        self.mode = setup.mode;
+        self.plays_second = setup.plays_second && setup.mode == Mode::Computer;
+        self.flipped = false;
+        self.difficulty = setup.difficulty;
+        if let Difficulty::Custom(depth) = setup.difficulty {
+            self.custom_depth = depth;
+        }
+        self.restart();
+    }
+
+    /// The entries of the difficulty selector.
+    fn difficulties(&self) -> [Difficulty; 4] {
+        let [easy, medium, hard] = Difficulty::PRESETS;
+        [easy, medium, hard, Difficulty::Custom(self.custom_depth)]
+    }
+
+    /// Opens the difficulty selector, unless there's no computer to play against.
+    fn change_difficulty(&mut self) {
+        if matches!(self.mode, Mode::Hotseat | Mode::Online) || self.game_over.is_some() {
+            return;
+        }
+        let selected = match self.difficulty {
+            Difficulty::Custom(_) => Difficulty::PRESETS.len(),
+            difficulty => Difficulty::PRESETS
+                .iter()
+                .position(|&preset| preset == difficulty)
+                .unwrap_or_default(),
+        };
+        self.selected_difficulty = Some(selected);
+    }
+
+    fn handle_difficulty_key(&mut self, key: KeyEvent, selected: usize) {
+        let count = self.difficulties().len();
+        let custom = selected == count - 1;
+        match key.code {
+            KeyCode::Up => self.selected_difficulty = Some((selected + count - 1) % count),
+            KeyCode::Down => self.selected_difficulty = Some((selected + 1) % count),
+            KeyCode::Left if custom => self.custom_depth = (self.custom_depth - 1).max(1),
+            KeyCode::Right if custom => self.custom_depth += 1,
+            KeyCode::Enter => {
+                self.selected_difficulty = None;
+                self.difficulty = self.difficulties()[selected];
+            }
+            _ => {}
         }
     }
 
     fn restart(&mut self) {
+        self.detach_searches();
+        self.analysis = None;
+        self.replay = None;
         self.game_over = None;
+        self.ending = None;
+        self.turns.clear();
+        self.undone.clear();
+        self.reset_clock();
+        self.started = Some(Instant::now());
         self.game_mut().reset();
+        self.evaluate();
+        if self.is_computer_turn() || self.mode == Mode::Spectate {
+            self.start_computer_move();
+        }
     }
 
-    /// Splits the rect into 3 areas (game view, move history, and move input, returned in that order)
-    fn layout_areas(area: Rect, display_size: (u16, u16)) -> [Rect; 3] {
-        let [game_area, input_area] =
-            Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
-                .flex(Flex::SpaceAround)
-                .areas(area);
+    /// Splits the rect into 4 areas (game view, move history, move input, and the legal moves
+    /// matching the input, returned in that order)
+    fn layout_areas(area: Rect, display_size: (u16, u16)) -> [Rect; 4] {
+        let [game_area, input_area, moves_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .flex(Flex::SpaceAround)
+        .areas(area);
 
         let [_, game_view_area, move_history_area] = Layout::horizontal([
             Constraint::Fill(1),
@@ -140,7 +1311,54 @@ impl GameScreen<'_> {
             vertical: 2,
         });
 
-        [game_view_area, move_history_area, input_area]
+        [game_view_area, move_history_area, input_area, moves_area]
+    }
+
+    /// `advantage`, an evaluation for the side that moves first, turned around to be for the side
+    /// whose pieces are at the bottom of the board.
+    fn bottom_advantage(&self, advantage: Option<f32>) -> Option<f32> {
+        advantage.map(|advantage| {
+            if self.board_side() == 1 {
+                -advantage
+            } else {
+                advantage
+            }
+        })
+    }
+
+    /// Draws the advantage of the side at the bottom of the board as a bar that fills up from the
+    /// bottom as they get ahead.
+    fn draw_eval_bar(frame: &mut Frame, game_view_area: Rect, advantage: f32) {
+        let area = Rect {
+            x: game_view_area.x.saturating_sub(EVAL_BAR_WIDTH + 3),
+            width: EVAL_BAR_WIDTH + 2,
+            ..game_view_area
+        };
+        let height = area.height.saturating_sub(2);
+        let filled = (f32::from(height) * (advantage + 1.0) / 2.0).round() as u16;
+        let lines = (0..height).map(|row| {
+            if row < height - filled {
+                Line::from(" ".repeat(EVAL_BAR_WIDTH.into()))
+            } else {
+                Line::from("█".repeat(EVAL_BAR_WIDTH.into()))
+            }
+        });
+        let eval_bar = Paragraph::new(Text::from_iter(lines)).block(Block::bordered());
+        frame.render_widget(eval_bar, area);
+    }
+
+    /// Draws a popup listing `options` with the `selected` one highlighted.
+    fn draw_choice(&mut self, frame: &mut Frame, title: &str, options: &[String], selected: usize) {
+        let lines = options.iter().enumerate().map(|(i, option)| {
+            if i == selected {
+                Line::from(format!("> {option}")).light_blue()
+            } else {
+                Line::from(format!("  {option}"))
+            }
+        });
+        let mut popup = Popup::new(Text::from_iter(lines)).title(title);
+        popup.border_set = border::THICK;
+        frame.render_stateful_widget_ref(popup, frame.area(), &mut self.popup_state);
     }
 
     fn format_move_history(moves: &[String]) -> String {
@@ -161,15 +1379,50 @@ impl GameScreen<'_> {
 }
 
 impl Component for GameScreen<'_> {
+    fn register_config_handler(&mut self, config: Config) -> color_eyre::Result<()> {
+        self.spectate_delay = Duration::from_secs_f64(config.spectate_delay);
+        self.time_control = config.time_control;
+        self.contempt = config.contempt;
+        if let Some(settings) = config.games.get(&self.name().to_lowercase()) {
+            self.apply_settings(settings);
+        }
+        self.reset_clock();
+        Ok(())
+    }
+
     fn handle_key_event(&mut self, key: KeyEvent) -> color_eyre::Result<Option<Action>> {
-        if self.game_over.is_some() {
-            #[allow(clippy::single_match)]
+        if self.closing == Some(Closing::Asking) {
+            match key.code {
+                KeyCode::Char('y') => {
+                    self.closing = Some(Closing::Confirmed);
+                    return Ok(Some(Action::CloseGame));
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.closing = None,
+                _ => {}
+            }
+        } else if self.setup.is_some() {
+            self.handle_setup_key(key);
+        } else if let Some(selected) = self.selected_difficulty {
+            self.handle_difficulty_key(key, selected);
+        } else if let Some(selected) = self.selected_completion {
+            let result = self.handle_completion_key(key, selected);
+            self.report(result);
+        } else if self.statistics.is_some() {
+            self.statistics = None;
+        } else if self.analysis.is_some() {
+            self.handle_analysis_key(key);
+        } else if self.game_over.is_some() {
             match key.code {
-                KeyCode::Char('r') => self.restart(),
+                // Both players of an online game would have to agree to play again
+                KeyCode::Char('r') if self.mode != Mode::Online => self.restart(),
+                KeyCode::Char('a') => self.analyse(),
                 _ => {}
             }
         } else if let KeyCode::Enter = key.code {
-            self.enter_input();
+            let result = self.enter_input();
+            self.report(result);
+        } else if let KeyCode::Tab = key.code {
+            self.complete_input();
         } else if self.input.input_without_shortcuts(key) {
             self.input_label = None;
         }
@@ -181,6 +1434,8 @@ impl Component for GameScreen<'_> {
         match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
                 self.popup_state.mouse_down(mouse.column, mouse.row);
+                let result = self.click_board(mouse.column, mouse.row);
+                self.report(result);
             }
             MouseEventKind::Up(MouseButton::Left) => {
                 self.popup_state.mouse_up(mouse.column, mouse.row);
@@ -194,17 +1449,106 @@ impl Component for GameScreen<'_> {
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> color_eyre::Result<()> {
-        let [game_view_area, move_history_area, input_area] =
+        let [game_view_area, move_history_area, input_area, moves_area] =
             Self::layout_areas(area, self.game().display_size());
 
-        let game_view = Paragraph::new(self.game().display()).block(Block::bordered());
+        // The analysis and replays show whichever earlier position is being looked at in place of
+        // the game
+        let (board, advantage, captures, moves) = match (&self.analysis, &self.replay) {
+            (Some(analysis), _) => {
+                let position = analysis.position();
+                let mut moves = analysis.annotated_moves();
+                moves.truncate(analysis.ply());
+                (
+                    position.display_styled(self.board_side(), ""),
+                    analysis.advantage(),
+                    position.captures(),
+                    moves,
+                )
+            }
+            (None, Some((ply, position))) => {
+                let mut moves = self.game().move_history();
+                moves.truncate(*ply);
+                (
+                    position.display_styled(self.board_side(), ""),
+                    None,
+                    position.captures(),
+                    moves,
+                )
+            }
+            (None, None) => {
+                let game = self.game();
+                (
+                    game.display_styled(self.board_side(), &self.input.lines()[0]),
+                    self.advantage,
+                    game.captures(),
+                    game.move_history(),
+                )
+            }
+        };
+        let game_view = Paragraph::new(board).block(Block::bordered());
         frame.render_widget(game_view, game_view_area);
+        self.board_area = game_view_area.inner(Margin::new(1, 1));
+
+        if let Some(advantage) = self.bottom_advantage(advantage) {
+            Self::draw_eval_bar(frame, game_view_area, advantage);
+        }
+
+        // The clock and captures go above the moves, with the side drawn at the top of the
+        // board first
+        let move_history_area = if let Some(clock) = &self.clock {
+            let [clock_area, move_history_area] =
+                Layout::vertical([Constraint::Length(4), Constraint::Fill(1)])
+                    .areas(move_history_area);
+            let now = Instant::now();
+            let sides = self.game().sides();
+            let mut lines: Vec<_> = (0..2)
+                .map(|side| {
+                    let remaining = clock.remaining(side, now);
+                    let line = Line::from(format!("{} {}", sides[side], clock::format(remaining)));
+                    match clock.running() {
+                        Some(running) if running == side && remaining < LOW_TIME => {
+                            line.light_red().bold()
+                        }
+                        Some(running) if running == side => line.light_yellow().bold(),
+                        _ => line,
+                    }
+                })
+                .collect();
+            if self.board_side() == 0 {
+                lines.reverse();
+            }
+            let clock = Paragraph::new(Text::from(lines)).block(Block::bordered());
+            frame.render_widget(clock, clock_area);
+            move_history_area
+        } else {
+            move_history_area
+        };
+
+        let move_history_area = if let Some(mut captures) = captures {
+            let [captures_area, move_history_area] =
+                Layout::vertical([Constraint::Length(4), Constraint::Fill(1)])
+                    .areas(move_history_area);
+            if self.board_side() == 0 {
+                captures.reverse();
+            }
+            let captures = Paragraph::new(captures.join("\n")).block(Block::bordered());
+            frame.render_widget(captures, captures_area);
+            move_history_area
+        } else {
+            move_history_area
+        };
 
-        let move_history_text = Text::from(Self::format_move_history(&self.game().move_history()));
+        let mut move_history_text = Text::from(Self::format_move_history(&moves));
+        // Earlier positions are looked at from before the game ended
+        let looking_back = self.analysis.is_some() || self.replay.is_some();
+        if let Some(result) = self.result_text().filter(|_| !looking_back) {
+            move_history_text.push_line(Line::from(result).italic());
+        }
 
         let line_count =
             u16::try_from(move_history_text.lines.len()).expect("too many lines in move history");
-        let move_history_height = move_history_area.as_size().height - 2;
+        let move_history_height = move_history_area.as_size().height.saturating_sub(2);
 
         let scroll = line_count.saturating_sub(move_history_height);
 
@@ -213,27 +1557,108 @@ impl Component for GameScreen<'_> {
             .block(Block::bordered());
         frame.render_widget(move_history, move_history_area);
 
-        if let Some(input_label) = self.input_label {
+        if let Some(analysis) = &self.analysis {
+            let evaluation = match analysis.advantage() {
+                Some(advantage) => format!("evaluation {advantage:+.2}"),
+                None => "no evaluation".to_string(),
+            };
+            let title = format!(
+                "Move {}, {evaluation} (←/→ to step, Esc to stop)",
+                analysis.ply()
+            );
+            self.input
+                .set_block(Block::bordered().title(title).title_style(Color::LightBlue));
+        } else if let Some((ply, _)) = &self.replay {
+            let moves = self.game().move_history().len();
+            let title = format!("Looking back at move {ply} of {moves}");
+            self.input
+                .set_block(Block::bordered().title(title).title_style(Color::LightBlue));
+        } else if let Some(input_label) = self.input_label {
             let (input_text, color) = match input_label {
-                InputLabel::Invalid => ("Invalid move", Color::LightRed),
-                InputLabel::Thinking => ("Computer is thinking", Color::LightBlue),
+                InputLabel::Invalid => ("Invalid move".to_string(), Color::LightRed),
+                InputLabel::Thinking => (self.thinking_text(), Color::LightBlue),
+                InputLabel::FindingHint => ("Looking for a hint".to_string(), Color::LightBlue),
+                InputLabel::Analysing => ("Analysing the game".to_string(), Color::LightBlue),
+                InputLabel::OfferingDraw => ("Offering a draw".to_string(), Color::LightBlue),
+                InputLabel::WaitingForOpponent => {
+                    ("Waiting for the other player".to_string(), Color::LightBlue)
+                }
+                InputLabel::Notice => (self.notice.clone(), Color::LightGreen),
+                InputLabel::Failure => (self.notice.clone(), Color::LightRed),
+                InputLabel::Spectating => (
+                    "The computer is playing itself".to_string(),
+                    Color::LightBlue,
+                ),
+                InputLabel::Hint => (
+                    format!("Hint: {}", self.hints.join(", ")),
+                    Color::LightGreen,
+                ),
             };
 
             self.input
                 .set_block(Block::bordered().title(input_text).title_style(color))
+        } else if let Some(connection) = &self.connection {
+            let color = if connection.is_connected() {
+                Color::LightBlue
+            } else {
+                Color::LightRed
+            };
+            let status = connection.status().to_string();
+            self.input
+                .set_block(Block::bordered().title(status).title_style(color));
         } else {
             self.input.set_block(Block::bordered());
         }
         frame.render_widget(&self.input, input_area);
 
-        if let Some(game_over) = self.game_over {
+        let moves = Paragraph::new(self.matching_moves().join("  ")).dark_gray();
+        frame.render_widget(moves, moves_area.inner(Margin::new(1, 0)));
+
+        if let Some(game_over) = self.game_over.filter(|_| self.analysis.is_none()) {
             let title = match game_over {
-                GameOver::Win => "You win!",
-                GameOver::Lose => "You lost!",
-                GameOver::Draw => "Draw",
+                GameOver::Win => "You win!".to_string(),
+                GameOver::Lose => "You lost!".to_string(),
+                GameOver::Draw => "Draw".to_string(),
+                GameOver::Winner(side) => {
+                    let [first, second] = self.game().sides();
+                    format!("{} wins!", if side == 0 { first } else { second })
+                }
             };
-            let mut popup =
-                Popup::new(Text::raw("<r> - retry\n<Ctrl-b> - back\n<Ctrl-q> - quit")).title(title);
+            let mut popup = Popup::new(Text::raw(
+                "<r> - retry\n<a> - analyse\n<Ctrl-u> - undo\n<Ctrl-b> - back\n<Ctrl-q> - quit",
+            ))
+            .title(title);
+            popup.border_set = border::THICK;
+            frame.render_stateful_widget_ref(popup, frame.area(), &mut self.popup_state);
+        }
+
+        if self.closing == Some(Closing::Asking) {
+            let mut popup = Popup::new(Text::raw(
+                "The game will be lost.\n<y> - close\n<n> - keep playing",
+            ))
+            .title(format!("Close {}?", self.name()));
+            popup.border_set = border::THICK;
+            frame.render_stateful_widget_ref(popup, frame.area(), &mut self.popup_state);
+        } else if let Some(setup) = &self.setup {
+            let lines = setup.lines(&self.game().sides());
+            let selected = setup.selected();
+            let title = "New game (←/→ to change, Enter to start)";
+            self.draw_choice(frame, title, &lines, selected);
+        } else if let Some(selected) = self.selected_difficulty {
+            let mut difficulties = self.difficulties().map(|difficulty| difficulty.to_string());
+            difficulties[difficulties.len() - 1].push_str(" (←/→)");
+            self.draw_choice(frame, "Choose a difficulty", &difficulties, selected);
+        } else if let Some(selected) = self.selected_completion {
+            let typed = &self.input.lines()[0];
+            let moves: Vec<_> = self
+                .completions
+                .iter()
+                .map(|completion| format!("{typed}{completion}"))
+                .collect();
+            self.draw_choice(frame, "Choose the move", &moves, selected);
+        } else if let Some(statistics) = &self.statistics {
+            let mut popup = Popup::new(Text::from_iter(statistics.iter().map(String::as_str)))
+                .title(format!("{} statistics", self.name()));
             popup.border_set = border::THICK;
             frame.render_stateful_widget_ref(popup, frame.area(), &mut self.popup_state);
         }
@@ -242,6 +1667,43 @@ impl Component for GameScreen<'_> {
     }
 
     fn update(&mut self, action: Action) -> color_eyre::Result<Option<Action>> {
+        let mut result = Ok(());
+        match &action {
+            Action::OpenGame(_) => self.open(),
+            Action::Undo => result = self.undo(),
+            Action::Redo => result = self.redo(),
+            Action::Hint => self.hint(),
+            Action::ChangeDifficulty => self.change_difficulty(),
+            Action::ExportPgn => self.export_pgn(),
+            Action::CopyPgn => self.copy_pgn(),
+            Action::FlipBoard => self.flipped = !self.flipped,
+            Action::ReplayBack => self.step_replay(-1),
+            Action::ReplayForward => self.step_replay(1),
+            Action::Resign => self.resign(),
+            Action::OfferDraw => self.offer_draw(),
+            Action::StopThinking => self.stop_thinking(),
+            Action::ShowStatistics => self.show_statistics(),
+            _ => {}
+        }
+
+        if action == Action::Tick
+            && self
+                .advantage_thread
+                .as_ref()
+                .is_some_and(|t| t.is_finished())
+        {
+            let handle = self.advantage_thread.take().unwrap(); // guaranteed to be Some
+            self.advantage = handle
+                .join()
+                .map_err(|_| eyre!("Failed to evaluate the position"))?;
+        }
+
+        if action == Action::Tick && self.hint_thread.as_ref().is_some_and(|t| t.is_finished()) {
+            let handle = self.hint_thread.take().unwrap(); // guaranteed to be Some
+            self.hints = handle.join().map_err(|_| eyre!("Failed to find a hint"))?;
+            self.input_label = Some(InputLabel::Hint);
+        }
+
         if action == Action::Tick
             && self
                 .computer_move_thread
@@ -252,17 +1714,143 @@ impl Component for GameScreen<'_> {
             let computer_move = handle
                 .join()
                 .map_err(|_| eyre!("Failed to make computer move"))?;
+            self.thinking = None;
+            if self.input_label == Some(InputLabel::Thinking) {
+                self.input_label = None;
+            }
 
-            self.game_mut().play_move(&computer_move);
-            let win_state = { self.game().win_state() };
-            if let Some(win_state) = win_state {
-                self.game_over = Some(match win_state {
-                    WinState::Decisive => GameOver::Lose,
-                    WinState::Draw => GameOver::Draw,
-                })
+            // The computer may have run out of time while it was thinking
+            if self.game_over.is_none() {
+                result =
+                    computer_move.and_then(|computer_move| self.play_computer_move(&computer_move));
+            }
+            if self.game_over.is_none() && result.is_ok() {
+                if self.mode == Mode::Spectate {
+                    self.next_computer_move = Some(Instant::now() + self.spectate_delay);
+                } else if self.is_computer_turn() {
+                    // The computer's move gave it another turn
+                    self.start_computer_move();
+                }
+            }
+        }
+
+        if action == Action::Tick
+            && self
+                .draw_offer_thread
+                .as_ref()
+                .is_some_and(|t| t.is_finished())
+        {
+            let handle = self.draw_offer_thread.take().unwrap(); // guaranteed to be Some
+            let advantage = handle
+                .join()
+                .map_err(|_| eyre!("Failed to consider the draw offer"))?;
+            self.answer_draw_offer(advantage);
+        }
+
+        if action == Action::Tick
+            && self
+                .analysis_thread
+                .as_ref()
+                .is_some_and(|t| t.is_finished())
+        {
+            let handle = self.analysis_thread.take().unwrap(); // guaranteed to be Some
+            let analysis = handle
+                .join()
+                .map_err(|_| eyre!("Failed to analyse the game"))?;
+            // The game may have been taken back while it was being analysed
+            if self.game_over.is_some() {
+                self.input_label = None;
+                match analysis {
+                    Ok(analysis) => self.analysis = Some(analysis),
+                    Err(err) => result = Err(err),
+                }
+            }
+        }
+
+        if action == Action::Tick
+            && self
+                .next_computer_move
+                .is_some_and(|time| time <= Instant::now())
+        {
+            self.next_computer_move = None;
+            self.start_computer_move();
+        }
+
+        if action == Action::Tick {
+            if let Err(err) = self.receive_online() {
+                result = Err(err);
+            }
+            self.update_clock();
+            if let Some((_, progress)) = &mut self.thinking {
+                self.spinner = self.spinner.wrapping_add(1);
+                *progress = *self
+                    .search_progress
+                    .lock()
+                    .expect("Failed to read the search's progress");
             }
         }
 
+        self.report(result);
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::kalah::Kalah;
+    use crate::games::tictactoe::TicTacToe;
+
+    #[test]
+    fn eval_bar_is_for_the_side_at_the_bottom() {
+        let mut screen = GameScreen::new(Box::new(TicTacToe::default()));
+        screen.advantage = Some(0.5);
+        assert_eq!(screen.bottom_advantage(screen.advantage), Some(0.5));
+
+        // Playing second, the computer moving first is ahead
+        screen.plays_second = true;
+        assert_eq!(screen.bottom_advantage(screen.advantage), Some(-0.5));
+        screen.flipped = true;
+        assert_eq!(screen.bottom_advantage(screen.advantage), Some(0.5));
+    }
+
+    #[test]
+    fn running_out_of_time_cant_be_undone() {
+        let mut screen = GameScreen::new(Box::new(TicTacToe::default()));
+        screen.turns.push(0);
+        screen.game_mut().play_move("b2").unwrap();
+        screen.end(Ending::OutOfTime(1));
+
+        screen.undo().unwrap();
+        assert!(screen.game_over.is_some());
+        assert_eq!(screen.game().move_history(), ["b2"]);
+    }
+
+    #[test]
+    fn extra_turns_are_played_by_the_same_side() {
+        let mut screen = GameScreen::new(Box::new(Kalah::default()));
+        screen.mode = Mode::Computer;
+        screen.difficulty = Difficulty::Custom(2);
+        let play = |screen: &mut GameScreen, pit: &str| {
+            screen.input.insert_str(pit);
+            screen.enter_input().unwrap();
+        };
+
+        // The last seed from pit 3 lands in the player's store, so they move again
+        play(&mut screen, "3");
+        assert_eq!(screen.game().side_to_move(), 0);
+        assert!(screen.computer_move_thread.is_none());
+
+        play(&mut screen, "1");
+        assert!(screen.computer_move_thread.is_some());
+        // The computer keeps moving for as long as it has another turn
+        let start = Instant::now();
+        while screen.game().side_to_move() == 1 || screen.computer_move_thread.is_some() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+            screen.update(Action::Tick).unwrap();
+        }
+        // Pit 3 gave the computer another turn as well
+        assert_eq!(screen.game().move_history(), ["3", "1", "3", "4"]);
+    }
+}
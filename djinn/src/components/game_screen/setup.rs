@@ -0,0 +1,261 @@
+use std::time::Duration;
+
+use crate::clock;
+use crate::config::TimeControl;
+use crate::games::{Difficulty, GameOption};
+
+use super::Mode;
+
+/// The deepest custom difficulty offered on the options screen.
+const MAX_CUSTOM_DEPTH: u32 = 20;
+/// The time controls offered on the options screen besides playing untimed, as the minutes each
+/// side starts with and the seconds added after each move.
+const TIME_CONTROLS: [(f64, f64); 5] = [
+    (1.0, 0.0),
+    (3.0, 2.0),
+    (5.0, 3.0),
+    (10.0, 5.0),
+    (15.0, 10.0),
+];
+
+/// A row of the options screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Row {
+    /// The option at this index in [`Game::options`](crate::games::Game::options).
+    Game(usize),
+    Mode,
+    /// The side the player takes against the computer.
+    Side,
+    Difficulty,
+    TimeControl,
+}
+
+/// The options screen shown before a game starts, where the player chooses the rules, who plays,
+/// and how, one row at a time.
+pub(super) struct Setup {
+    options: Vec<GameOption>,
+    /// The index of the chosen value of each of the game's options.
+    pub choices: Vec<usize>,
+    pub mode: Mode,
+    /// Whether the player takes the side that moves second against the computer.
+    pub plays_second: bool,
+    pub difficulty: Difficulty,
+    /// The time controls to choose between, starting with playing untimed.
+    time_controls: Vec<Option<TimeControl>>,
+    time_control: usize,
+    /// The highlighted row, as an index into [`Setup::rows`].
+    selected: usize,
+}
+
+impl Setup {
+    /// The options screen for a game with `options`, starting from the choices of the last game,
+    /// or the first of each where they no longer fit.
+    pub fn new(
+        options: Vec<GameOption>,
+        choices: &[usize],
+        mode: Mode,
+        plays_second: bool,
+        difficulty: Difficulty,
+        time_control: Option<TimeControl>,
+    ) -> Self {
+        let choices = (0..options.len())
+            .map(|i| {
+                choices
+                    .get(i)
+                    .copied()
+                    .filter(|&choice| choice < options[i].values.len())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let mut time_controls = vec![None];
+        time_controls.extend(TIME_CONTROLS.map(|(minutes, increment)| {
+            Some(TimeControl {
+                base: minutes * 60.0,
+                increment,
+            })
+        }));
+        if !time_controls.contains(&time_control) {
+            time_controls.push(time_control);
+        }
+        let time_control = time_controls
+            .iter()
+            .position(|&offered| offered == time_control)
+            .unwrap_or_default();
+
+        Self {
+            options,
+            choices,
+            // Online games aren't set up here
+            mode: if mode == Mode::Online {
+                Mode::default()
+            } else {
+                mode
+            },
+            plays_second,
+            difficulty,
+            time_controls,
+            time_control,
+            selected: 0,
+        }
+    }
+
+    /// The rows shown for the choices made so far, since there's no side or difficulty to
+    /// choose without a computer to play against.
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows: Vec<_> = (0..self.options.len()).map(Row::Game).collect();
+        rows.push(Row::Mode);
+        if self.mode == Mode::Computer {
+            rows.push(Row::Side);
+        }
+        if self.mode != Mode::Hotseat {
+            rows.push(Row::Difficulty);
+        }
+        rows.push(Row::TimeControl);
+        rows
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_controls[self.time_control]
+    }
+
+    /// Highlights the row `step` rows further down, wrapping around.
+    pub fn select(&mut self, step: isize) {
+        self.selected = wrap(self.selected, step, self.rows().len());
+    }
+
+    /// Changes the value of the highlighted row to the one `step` values later, wrapping around.
+    pub fn change(&mut self, step: isize) {
+        match self.rows()[self.selected] {
+            Row::Game(i) => {
+                self.choices[i] = wrap(self.choices[i], step, self.options[i].values.len());
+            }
+            Row::Mode => {
+                let mode = Mode::ALL
+                    .iter()
+                    .position(|&mode| mode == self.mode)
+                    .unwrap_or_default();
+                self.mode = Mode::ALL[wrap(mode, step, Mode::ALL.len())];
+            }
+            Row::Side => self.plays_second = !self.plays_second,
+            Row::Difficulty => {
+                let difficulties: Vec<_> = Difficulty::PRESETS
+                    .into_iter()
+                    .chain((1..=MAX_CUSTOM_DEPTH).map(Difficulty::Custom))
+                    .collect();
+                let difficulty = difficulties
+                    .iter()
+                    .position(|&difficulty| difficulty == self.difficulty)
+                    .unwrap_or_default();
+                self.difficulty = difficulties[wrap(difficulty, step, difficulties.len())];
+            }
+            Row::TimeControl => {
+                self.time_control = wrap(self.time_control, step, self.time_controls.len());
+            }
+        }
+    }
+
+    /// Each row written out with its value, for the game whose sides are called `sides`.
+    pub fn lines(&self, sides: &[String; 2]) -> Vec<String> {
+        self.rows()
+            .into_iter()
+            .map(|row| match row {
+                Row::Game(i) => {
+                    let option = &self.options[i];
+                    format!("{}: {}", option.name, option.values[self.choices[i]])
+                }
+                Row::Mode => format!("Who plays: {}", self.mode.name()),
+                Row::Side => format!("Side: {}", sides[usize::from(self.plays_second)]),
+                Row::Difficulty => format!("Difficulty: {}", self.difficulty),
+                Row::TimeControl => match self.time_control() {
+                    Some(TimeControl { base, increment }) => format!(
+                        "Time control: {} + {increment}s",
+                        clock::format(Duration::from_secs_f64(base))
+                    ),
+                    None => "Time control: Untimed".to_string(),
+                },
+            })
+            .collect()
+    }
+}
+
+/// The index `step` places on from `index` among `count`, wrapping around at either end.
+fn wrap(index: usize, step: isize, count: usize) -> usize {
+    (index as isize + step).rem_euclid(count as isize) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Setup {
+        let sizes = ["Small", "Large"].map(String::from).to_vec();
+        Setup::new(
+            vec![GameOption::new("Board size", sizes)],
+            &[5],
+            Mode::Computer,
+            false,
+            Difficulty::Hard,
+            None,
+        )
+    }
+
+    #[test]
+    fn rows_follow_who_plays() {
+        let mut setup = setup();
+        assert_eq!(
+            setup.rows(),
+            [
+                Row::Game(0),
+                Row::Mode,
+                Row::Side,
+                Row::Difficulty,
+                Row::TimeControl
+            ]
+        );
+        setup.select(1);
+        setup.change(1);
+        assert_eq!(setup.mode, Mode::Hotseat);
+        assert_eq!(setup.rows(), [Row::Game(0), Row::Mode, Row::TimeControl]);
+        setup.change(1);
+        assert_eq!(setup.mode, Mode::Spectate);
+        assert_eq!(
+            setup.rows(),
+            [Row::Game(0), Row::Mode, Row::Difficulty, Row::TimeControl]
+        );
+    }
+
+    #[test]
+    fn changes_wrap_around() {
+        let mut setup = setup();
+        // The last game's choice doesn't fit, so the first value is chosen instead
+        assert_eq!(setup.choices, [0]);
+        setup.change(-1);
+        assert_eq!(setup.choices, [1]);
+
+        setup.select(-2);
+        assert_eq!(setup.rows()[setup.selected()], Row::Difficulty);
+        setup.change(1);
+        assert_eq!(setup.difficulty, Difficulty::Custom(1));
+        setup.change(-2);
+        assert_eq!(setup.difficulty, Difficulty::Medium);
+
+        setup.select(1);
+        setup.change(-1);
+        assert_eq!(setup.time_control().map(|time| time.base), Some(900.0));
+        assert_eq!(
+            setup.lines(&["White".to_string(), "Black".to_string()]),
+            [
+                "Board size: Large",
+                "Who plays: Play against the computer",
+                "Side: White",
+                "Difficulty: Medium",
+                "Time control: 15:00 + 10s",
+            ]
+        );
+    }
+}
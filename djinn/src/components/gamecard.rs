@@ -3,6 +3,7 @@ use crate::components::Component;
 use crate::games::Game;
 use ratatui::layout::Rect;
 use ratatui::prelude::{Alignment, Modifier, Style};
+use ratatui::text::Line;
 use ratatui::widgets::{Block, Paragraph};
 use ratatui::Frame;
 
@@ -17,6 +18,8 @@ pub struct GameCard {
     name: String,
     thumbnail: String,
     selected: bool,
+    /// The player's rating against the computer, once they've finished a rated game.
+    rating: Option<u32>,
 }
 
 impl GameCard {
@@ -24,6 +27,10 @@ impl GameCard {
         self.selected = selected;
     }
 
+    pub fn set_rating(&mut self, rating: Option<u32>) {
+        self.rating = rating;
+    }
+
     pub fn from_game_with_id(value: &dyn Game, id: GameId) -> Self {
         Self {
             id,
@@ -31,29 +38,33 @@ impl GameCard {
             name: value.name(),
             thumbnail: value.thumbnail(),
             selected: false,
+            rating: None,
         }
     }
 
     pub fn id(&self) -> GameId {
         self.id
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl Component for GameCard {
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> color_eyre::Result<()> {
-        frame.render_widget(
-            Paragraph::new(&*self.thumbnail).block(
-                Block::bordered()
-                    .title(&*self.name)
-                    .title_alignment(Alignment::Center)
-                    .title_style(if self.selected {
-                        (Modifier::BOLD | Modifier::UNDERLINED).into()
-                    } else {
-                        Style::new()
-                    }),
-            ),
-            area,
-        );
+        let mut block = Block::bordered()
+            .title(&*self.name)
+            .title_alignment(Alignment::Center)
+            .title_style(if self.selected {
+                (Modifier::BOLD | Modifier::UNDERLINED).into()
+            } else {
+                Style::new()
+            });
+        if let Some(rating) = self.rating {
+            block = block.title_bottom(Line::from(rating.to_string()).right_aligned());
+        }
+        frame.render_widget(Paragraph::new(&*self.thumbnail).block(block), area);
         Ok(())
     }
 }
@@ -0,0 +1,70 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Style, Stylize};
+use ratatui::widgets::Tabs;
+use ratatui::Frame;
+
+use crate::app::GameId;
+use crate::components::Component;
+
+/// The games that are open, shown along the top of the game screen with the one being played
+/// highlighted. Each is numbered for switching to it with Alt and its number.
+#[derive(Debug, Default)]
+pub struct TabBar {
+    tabs: Vec<(GameId, String)>,
+    selected: Option<GameId>,
+}
+
+impl TabBar {
+    /// Adds the game with `id` after the other open games, unless it's already open.
+    pub fn open(&mut self, id: GameId, name: String) {
+        if !self.tabs.iter().any(|&(open, _)| open == id) {
+            self.tabs.push((id, name));
+        }
+    }
+
+    pub fn select(&mut self, id: Option<GameId>) {
+        self.selected = id;
+    }
+
+    /// Removes the game with `id`, returning the open game that takes its place: the one after it,
+    /// or the one before it if it was the last.
+    pub fn close(&mut self, id: GameId) -> Option<GameId> {
+        let position = self.position(id)?;
+        self.tabs.remove(position);
+        self.get(position.min(self.tabs.len().saturating_sub(1)))
+    }
+
+    /// The open game at `index`, counting from 0.
+    pub fn get(&self, index: usize) -> Option<GameId> {
+        self.tabs.get(index).map(|&(id, _)| id)
+    }
+
+    /// The open game `step` tabs along from the selected one, wrapping around at either end.
+    pub fn step(&self, step: isize) -> Option<GameId> {
+        let count = self.tabs.len() as isize;
+        let selected = self.position(self.selected?)? as isize;
+        self.get((selected + step).rem_euclid(count) as usize)
+    }
+
+    fn position(&self, id: GameId) -> Option<usize> {
+        self.tabs.iter().position(|&(open, _)| open == id)
+    }
+}
+
+impl Component for TabBar {
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> color_eyre::Result<()> {
+        let titles = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(i, (_, name))| format!("{} {name}", i + 1));
+        // The tab bar is only shown over a game, so one is always selected
+        let selected = self.selected.and_then(|id| self.position(id));
+        let tabs = Tabs::new(titles)
+            .select(selected.unwrap_or_default())
+            .style(Style::new().dark_gray())
+            .highlight_style(Style::new().white().bold());
+        frame.render_widget(tabs, area);
+        Ok(())
+    }
+}
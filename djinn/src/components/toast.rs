@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::widgets::{Block, Clear, Paragraph, Wrap};
+use ratatui::Frame;
+
+use crate::action::Action;
+use crate::components::Component;
+
+/// How long an error is shown for before it's dismissed on its own.
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+/// The widest a toast is drawn, including its border.
+const TOAST_WIDTH: u16 = 50;
+
+/// Errors that went wrong outside of a game's rules, shown one at a time in the corner of the
+/// screen until they're dismissed with Esc or time out.
+#[derive(Debug, Default)]
+pub struct Toast {
+    errors: VecDeque<String>,
+    /// When the error at the front started being shown.
+    shown_since: Option<Instant>,
+}
+
+impl Toast {
+    /// Adds `message` to the errors to show, after any that are already waiting, unless it's the
+    /// same as the last of them. Errors that keep happening, such as failing to draw every frame,
+    /// are only shown once.
+    pub fn show(&mut self, message: String) {
+        if self.errors.back() == Some(&message) {
+            return;
+        }
+        self.errors.push_back(message);
+        self.shown_since.get_or_insert_with(Instant::now);
+    }
+
+    pub fn is_shown(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Hides the error being shown, moving on to the next one.
+    pub fn dismiss(&mut self) {
+        self.errors.pop_front();
+        self.shown_since = Some(Instant::now()).filter(|_| self.is_shown());
+    }
+}
+
+impl Component for Toast {
+    fn update(&mut self, action: Action) -> color_eyre::Result<Option<Action>> {
+        if action == Action::Tick
+            && self
+                .shown_since
+                .is_some_and(|since| since.elapsed() >= TOAST_DURATION)
+        {
+            self.dismiss();
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> color_eyre::Result<()> {
+        let Some(error) = self.errors.front() else {
+            return Ok(());
+        };
+        let width = TOAST_WIDTH.min(area.width);
+        // Roughly how many lines the error wraps onto inside the border
+        let inner_width = usize::from(width.saturating_sub(2).max(1));
+        let lines = error.chars().count().div_ceil(inner_width).max(1);
+        let height = (lines as u16).saturating_add(2).min(area.height);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::End)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::End)
+            .areas(area);
+
+        let waiting = self.errors.len() - 1;
+        let mut block = Block::bordered()
+            .title("Error")
+            .title_style(Style::new().bold())
+            .title_bottom("Esc to dismiss")
+            .border_style(Style::new().fg(Color::LightRed));
+        if waiting > 0 {
+            block = block.title(format!("{waiting} more"));
+        }
+        let toast = Paragraph::new(error.as_str())
+            .wrap(Wrap { trim: true })
+            .block(block);
+        frame.render_widget(Clear, area);
+        frame.render_widget(toast, area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_errors_in_turn() {
+        let mut toast = Toast::default();
+        assert!(!toast.is_shown());
+        toast.show("first".to_string());
+        toast.show("second".to_string());
+        toast.show("second".to_string());
+        assert_eq!(toast.errors.len(), 2);
+        toast.dismiss();
+        assert_eq!(toast.errors.front().map(String::as_str), Some("second"));
+        toast.dismiss();
+        assert!(!toast.is_shown());
+        assert_eq!(toast.shown_since, None);
+    }
+}
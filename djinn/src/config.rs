@@ -1,6 +1,10 @@
 #![allow(dead_code)] // Remove this once you start using the code
 
-use std::{collections::HashMap, env, path::PathBuf};
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
 
 use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
@@ -8,12 +12,15 @@ use derive_deref::{Deref, DerefMut};
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
 use ratatui::style::{Color, Modifier, Style};
-use serde::{de::Deserializer, Deserialize};
+use serde::{de::Deserializer, Deserialize, Serialize};
 use tracing::error;
 
-use crate::{action::Action, app::Mode};
+use crate::{action::Action, app::Mode, games::Difficulty};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
+/// The file in the config directory that djinn keeps the settings each game was last started with
+/// in, which is read after the config files so that they take precedence.
+const GAME_SETTINGS_FILE: &str = "games.json";
 
 #[derive(Clone, Debug, Deserialize, Default)]
 pub struct AppConfig {
@@ -35,6 +42,119 @@ pub struct Config {
     pub tick_rate: f64,
     #[serde(default = "Config::default_frame_rate")]
     pub frame_rate: f64,
+    #[serde(default)]
+    pub chess: ChessConfig,
+    /// Whether to take clicks from the mouse, which stops the terminal from selecting text.
+    #[serde(default = "Config::default_mouse")]
+    pub mouse: bool,
+    /// The clock each game is played with, if any.
+    #[serde(default)]
+    pub time_control: Option<TimeControl>,
+    /// How much the computer would rather play on than agree to a draw, on the scale of the
+    /// evaluation bar from -1 to 1. It only accepts a draw when it's behind by more than this.
+    #[serde(default = "Config::default_contempt")]
+    pub contempt: f32,
+    /// The number of seconds between moves when watching the computer play itself.
+    #[serde(default = "Config::default_spectate_delay")]
+    pub spectate_delay: f64,
+    /// The settings each game was last started with, by its name in lowercase.
+    #[serde(default)]
+    pub games: HashMap<String, GameSettings>,
+    /// Whether to save the games being played when quitting, and carry on with them the next
+    /// time djinn starts.
+    #[serde(default)]
+    pub restore_games: bool,
+}
+
+/// The time on each side's clock, in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct TimeControl {
+    /// The time each side starts with.
+    pub base: f64,
+    /// The time added after each move.
+    #[serde(default)]
+    pub increment: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChessConfig {
+    /// A Polyglot opening book for the computer to play its first moves from.
+    #[serde(default)]
+    pub book: Option<PathBuf>,
+    /// Whether to use the opening book, so that it can be turned off without forgetting it.
+    #[serde(default = "ChessConfig::default_use_book")]
+    pub use_book: bool,
+    /// An external UCI engine, such as Stockfish, for the computer to play with instead of
+    /// searching itself.
+    #[serde(default)]
+    pub engine: Option<PathBuf>,
+    /// Whether to pick out the squares a piece can move to once its square is typed or clicked.
+    #[serde(default = "ChessConfig::default_show_moves")]
+    pub show_moves: bool,
+    /// Whether to fetch the puzzle of the day from Lichess at startup, which needs `curl` to be
+    /// installed. Without it, or while offline, the puzzle of the day is one of those that come
+    /// with djinn.
+    #[serde(default)]
+    pub daily_puzzle: bool,
+}
+
+impl Default for ChessConfig {
+    fn default() -> Self {
+        Self {
+            book: None,
+            use_book: Self::default_use_book(),
+            engine: None,
+            show_moves: Self::default_show_moves(),
+            daily_puzzle: false,
+        }
+    }
+}
+
+impl ChessConfig {
+    fn default_use_book() -> bool {
+        true
+    }
+
+    fn default_show_moves() -> bool {
+        true
+    }
+}
+
+/// The settings a game was last started with, which its options screen starts from.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameSettings {
+    /// The chosen value of each of the game's options, by the option's name in lowercase.
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// Whether the player takes the side that moves second against the computer.
+    #[serde(default)]
+    pub plays_second: Option<bool>,
+}
+
+/// The contents of [`GAME_SETTINGS_FILE`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GameSettingsFile {
+    #[serde(default)]
+    games: HashMap<String, GameSettings>,
+}
+
+/// Remembers `settings` as the ones the game called `name` was last started with.
+pub fn save_game_settings(name: &str, settings: GameSettings) -> io::Result<()> {
+    save_game_settings_in(&get_config_dir(), name, settings)
+}
+
+fn save_game_settings_in(directory: &Path, name: &str, settings: GameSettings) -> io::Result<()> {
+    let path = directory.join(GAME_SETTINGS_FILE);
+    let mut file: GameSettingsFile = match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => GameSettingsFile::default(),
+        Err(err) => return Err(err),
+    };
+    file.games.insert(name.to_lowercase(), settings);
+    fs::create_dir_all(directory)?;
+    fs::write(path, serde_json::to_string_pretty(&file)?)
 }
 
 lazy_static! {
@@ -78,6 +198,10 @@ impl Config {
         if !found_config {
             error!("No configuration file found. Application may not behave as expected");
         }
+        let game_settings = config::File::from(config_dir.join(GAME_SETTINGS_FILE))
+            .format(config::FileFormat::Json)
+            .required(false);
+        builder = builder.add_source(game_settings);
 
         let mut cfg: Self = builder.build()?.try_deserialize()?;
 
@@ -106,6 +230,18 @@ impl Config {
     fn default_frame_rate() -> f64 {
         60.0
     }
+
+    fn default_spectate_delay() -> f64 {
+        1.0
+    }
+
+    fn default_mouse() -> bool {
+        true
+    }
+
+    fn default_contempt() -> f32 {
+        0.1
+    }
 }
 
 pub fn get_data_dir() -> PathBuf {
@@ -526,6 +662,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn game_settings_are_read_with_the_config() -> Result<()> {
+        let directory = env::temp_dir().join(format!("djinn-config-{}", std::process::id()));
+        let settings = GameSettings {
+            options: HashMap::from([("board size".to_string(), "4×4, 4 in a row".to_string())]),
+            difficulty: Some(Difficulty::Custom(5)),
+            plays_second: Some(true),
+        };
+        save_game_settings_in(&directory, "Tic Tac Toe", GameSettings::default())?;
+        save_game_settings_in(&directory, "Connect Four", GameSettings::default())?;
+        save_game_settings_in(&directory, "Tic Tac Toe", settings.clone())?;
+
+        let source = config::File::from(directory.join(GAME_SETTINGS_FILE));
+        let config: Config = config::Config::builder()
+            .add_source(source)
+            .build()?
+            .try_deserialize()?;
+        fs::remove_dir_all(&directory)?;
+        assert_eq!(config.games.len(), 2);
+        assert_eq!(config.games["tic tac toe"], settings);
+        Ok(())
+    }
+
     #[test]
     fn simple_keys() {
         assert_eq!(
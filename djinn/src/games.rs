@@ -1,23 +1,619 @@
+pub mod backgammon;
+pub mod brandubh;
+pub mod breakthrough;
+pub mod checkers;
 pub mod chess;
+pub mod connect6;
+pub mod connect_four;
+pub mod dots_and_boxes;
+pub mod go;
+pub mod gomoku;
+pub mod hex;
+pub mod hexapawn;
+pub mod kalah;
+pub mod lines_of_action;
+pub mod minishogi;
+pub mod morris;
+pub mod nim;
+pub mod onitama;
+pub mod order_and_chaos;
+pub mod othello;
+pub mod pentago;
+pub mod quarto;
+pub mod qubic;
 pub mod tictactoe;
+pub mod ultimate;
+pub mod xiangqi;
 
-use std::fmt::Debug;
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Copy, Clone, Debug)]
+use ratatui::text::Text;
+use serde::{Deserialize, Serialize};
+
+use crate::minimax::{Engine, Evaluator, Player, State, StopToken};
+
+/// How a finished game ended.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum WinState {
-    Decisive,
+    /// Won by this player, where [`Player::Max`] is the side that moves first.
+    Decisive(Player),
     Draw,
 }
 
-pub trait Game: Send + Sync {
+impl WinState {
+    /// A win for the side at this index in [`Game::sides`].
+    pub fn won_by(side: usize) -> Self {
+        WinState::Decisive(if side == 0 { Player::Max } else { Player::Min })
+    }
+
+    /// The result of a finished game from its evaluation, which is infinite in favour of the
+    /// winner, or zero for a draw.
+    pub(crate) fn from_evaluation(evaluation: f32) -> Self {
+        if evaluation > 0.0 {
+            WinState::Decisive(Player::Max)
+        } else if evaluation < 0.0 {
+            WinState::Decisive(Player::Min)
+        } else {
+            WinState::Draw
+        }
+    }
+}
+
+/// How strongly the computer plays, which each game turns into how far ahead it searches.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    #[default]
+    Hard,
+    /// Searching exactly this many plies ahead.
+    Custom(u32),
+}
+
+impl Difficulty {
+    pub const PRESETS: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    /// The number of plies to search in a game that searches `hard` plies on the hardest preset.
+    pub fn depth(self, hard: u32) -> u32 {
+        match self {
+            Difficulty::Easy => hard.div_ceil(3),
+            Difficulty::Medium => (hard.div_ceil(3) * 2).min(hard),
+            Difficulty::Hard => hard,
+            Difficulty::Custom(depth) => depth,
+        }
+        .max(1)
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "Easy"),
+            Difficulty::Medium => write!(f, "Medium"),
+            Difficulty::Hard => write!(f, "Hard"),
+            Difficulty::Custom(depth) => write!(f, "Custom: {depth} plies"),
+        }
+    }
+}
+
+/// Something that stopped a game from doing what it was asked.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum GameError {
+    #[error("{0} isn't a valid move")]
+    InvalidMove(String),
+    #[error("there are no moves to play")]
+    NoMoves,
+    /// A game written outside of djinn, such as a Python plugin, failed.
+    #[error("{0}")]
+    Plugin(String),
+}
+
+impl GameError {
+    pub(crate) fn invalid_move(move_: &str) -> Self {
+        GameError::InvalidMove(move_.to_string())
+    }
+}
+
+/// The time the player to move has left on their clock, in a game played with a time control.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeLeft {
+    pub remaining: Duration,
+    /// The time added to the clock after each move.
+    pub increment: Duration,
+}
+
+/// How far a search for the computer's move has got.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// The depth of the iteration being searched.
+    pub depth: u32,
+    /// The value of the best line found so far, for the computer.
+    pub score: Option<f32>,
+}
+
+/// How hard the computer searches for its move.
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub difficulty: Difficulty,
+    /// The time left on the computer's clock, in a game played with a time control.
+    pub time: Option<TimeLeft>,
+    /// Where the search keeps its latest [`Progress`], for another thread to show while it runs.
+    pub progress: Option<Arc<Mutex<Option<Progress>>>>,
+    /// Stops the search from another thread, so that the best move found so far is played.
+    pub stop: Option<StopToken>,
+}
+
+impl SearchLimits {
+    /// The number of plies to search in a game that searches `hard` plies on the hardest preset.
+    pub fn depth(&self, hard: u32) -> u32 {
+        self.difficulty.depth(hard)
+    }
+}
+
+impl From<Difficulty> for SearchLimits {
+    fn from(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty,
+            ..Self::default()
+        }
+    }
+}
+
+/// What clicking on the board does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Click {
+    /// Fills the move input with part of a move, which later clicks finish.
+    Select(String),
+    /// Plays a move.
+    Play(String),
+}
+
+/// A choice about the rules the player makes before starting a game, such as the size of the
+/// board, as described by [`Game::options`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameOption {
+    pub name: String,
+    /// The values to choose between, with the first chosen unless the player changes it.
+    pub values: Vec<String>,
+}
+
+impl GameOption {
+    pub fn new(name: &str, values: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            values,
+        }
+    }
+}
+
+/// Copies a game into a new box, which [`Game`] needs in order to be cloned as a trait object.
+pub trait BoxClone {
+    fn box_clone(&self) -> Box<dyn Game>;
+}
+
+impl<T: Game + Clone + 'static> BoxClone for T {
+    fn box_clone(&self) -> Box<dyn Game> {
+        Box::new(self.clone())
+    }
+}
+
+pub trait Game: BoxClone + Send + Sync {
     fn name(&self) -> String;
     fn thumbnail(&self) -> String;
     fn display(&self) -> String;
     fn display_size(&self) -> (u16, u16);
     fn move_history(&self) -> Vec<String>;
+    /// The index in [`Game::sides`] of the side whose turn it is. Turns don't always alternate,
+    /// such as when sowing into their own store gives a Kalah player another one.
+    fn side_to_move(&self) -> usize;
     fn win_state(&self) -> Option<WinState>;
     fn is_valid_move(&self, move_: &str) -> bool;
-    fn play_move(&mut self, move_: &str);
-    fn computer_move(&self) -> String;
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError>;
+    /// The move the computer plays, searching within `limits`. Games whose searches are quick at
+    /// every difficulty can ignore the time left on the clock, while slower ones should pace
+    /// themselves to it. Searches that can't be stopped early, such as by an external engine,
+    /// finish as usual when asked to stop.
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError>;
     fn reset(&mut self);
+
+    /// The names of the variants the game can be played as, which the player picks from when
+    /// opening it. Games with only one set of rules have none.
+    fn variants(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Starts a new game of the variant at `index` in [`Game::variants`].
+    fn set_variant(&mut self, _index: usize) {}
+
+    /// The choices about the rules offered on the options screen before a game starts. By
+    /// default, the variant to play, if the game has any.
+    fn options(&self) -> Vec<GameOption> {
+        let variants = self.variants();
+        if variants.is_empty() {
+            Vec::new()
+        } else {
+            vec![GameOption::new("Variant", variants)]
+        }
+    }
+
+    /// Starts a new game with the rules chosen on the options screen, given as the index of the
+    /// chosen value of each of [`Game::options`]. Games that don't override [`Game::options`]
+    /// play the chosen variant.
+    fn set_options(&mut self, choices: &[usize]) {
+        if let Some(&variant) = choices.first() {
+            self.set_variant(variant);
+        }
+    }
+
+    /// The board as seen by whoever plays the side at `side` in [`Game::sides`], with their
+    /// pieces at the bottom, for games where it can be turned around.
+    fn display_for(&self, _side: usize) -> String {
+        self.display()
+    }
+
+    /// [`Game::display_for`] in colour, for games that have colours. What the player has
+    /// `typed` so far is given so that whatever they've started to move can be picked out.
+    fn display_styled(&self, side: usize, _typed: &str) -> Text<'static> {
+        Text::raw(self.display_for(side))
+    }
+
+    /// What the player's click at `column` and `row` does, counted in characters from the top
+    /// left of the board drawn by [`Game::display_for`] `side`, when they had typed `typed`.
+    /// Clicks that don't land on anything give `None`.
+    fn click(&self, _typed: &str, _column: u16, _row: u16, _side: usize) -> Option<Click> {
+        None
+    }
+
+    /// The names of the two sides, starting with the one that moves first.
+    fn sides(&self) -> [String; 2] {
+        ["Player 1", "Player 2"].map(String::from)
+    }
+
+    /// The ways to finish `move_` when it's missing a choice the player has to make, such as the
+    /// piece a pawn promotes to, each written to be appended to it. There are none if the move is
+    /// already complete or can't be finished.
+    fn completions(&self, _move_: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Every move the player to move can make, written the way they would enter it, for games
+    /// that can list them.
+    fn legal_moves(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Up to `count` moves suggested for the player to move, best first.
+    ///
+    /// By default this is only the move the computer would play in their place.
+    fn hints(&self, _count: usize) -> Vec<String> {
+        self.computer_move(Difficulty::Hard.into())
+            .into_iter()
+            .collect()
+    }
+
+    /// How far ahead the player who moved first is according to a shallow search, from -1 when
+    /// the other player is winning to 1 when they are, or `None` if the game has no evaluation.
+    fn advantage(&self) -> Option<f32> {
+        None
+    }
+
+    /// What each of the [`Game::sides`] has captured, such as the pieces taken and how far ahead
+    /// in material that leaves them, for games where it's worth showing.
+    fn captures(&self) -> Option<[String; 2]> {
+        None
+    }
+
+    /// The game written in Portable Game Notation, for games that have it, with `players` naming
+    /// whoever plays each of the [`Game::sides`]. A `result` overrides the position's own, for
+    /// games that ended early, such as by resigning.
+    fn pgn(&self, _players: [&str; 2], _result: Option<WinState>) -> Option<String> {
+        None
+    }
+
+    /// Replaces the game with one read from Portable Game Notation, playing its moves so that it
+    /// can be continued.
+    fn load_pgn(&mut self, _pgn: &str) -> Result<(), String> {
+        Err(format!("{} games can't be read from PGN", self.name()))
+    }
+
+    /// A copy of the game as it was after the first `plies` moves of its history, which can be
+    /// looked at without changing the game itself. If its moves can't be taken back that far, the
+    /// copy is left wherever taking them back stopped.
+    fn replay(&self, plies: usize) -> Box<dyn Game> {
+        let mut game = self.box_clone();
+        while game.move_history().len() > plies && matches!(game.undo(), Ok(Some(_))) {}
+        game
+    }
+
+    /// Takes back the last move played and returns it, or `None` if no moves have been played.
+    ///
+    /// By default this starts a new game and plays every earlier move again, so games that start
+    /// from a random position have to take moves back themselves. If one of the moves can't be
+    /// played again, the game is left with the moves before it and the error is returned.
+    fn undo(&mut self) -> Result<Option<String>, GameError> {
+        let mut history = self.move_history();
+        let Some(last) = history.pop() else {
+            return Ok(None);
+        };
+        self.reset();
+        for move_ in &history {
+            self.play_move(move_)?;
+        }
+        Ok(Some(last))
+    }
+}
+
+/// A pseudorandom key for a piece of `player` on the square with `index`, for building Zobrist
+/// hashes of boards.
+pub(crate) fn zobrist_key(index: usize, player: Player) -> u64 {
+    let mut x = (2 * index as u64 + player as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Squashes `value`, an engine's value for `player` to move, into an advantage for the player
+/// who moved first, where a lead of `scale` is worth about three quarters of the way to winning.
+pub(crate) fn scaled_advantage(value: f32, player: Player, scale: f32) -> f32 {
+    let value = match player {
+        Player::Max => value,
+        Player::Min => -value,
+    };
+    (value / scale).tanh()
+}
+
+/// The column and row, counting from the top left, of the cell of a board drawn with
+/// box-drawing characters that the character at `column` and `row` of the display is in.
+///
+/// Cells are 3 characters wide between the lines separating them, and `line_height` lines apart.
+/// The first cell starts at `origin`, and the board has `size` columns and rows.
+pub(crate) fn grid_cell(
+    column: u16,
+    row: u16,
+    origin: (u16, u16),
+    size: (usize, usize),
+    line_height: u16,
+) -> Option<(usize, usize)> {
+    let x = column.checked_sub(origin.0)? / 4;
+    let y = row.checked_sub(origin.1)?;
+    if y % line_height != 0 {
+        return None;
+    }
+    let (x, y) = (usize::from(x), usize::from(y / line_height));
+    (x < size.0 && y < size.1).then_some((x, y))
+}
+
+/// The move `engine` finds for the player to move in `state`, keeping the search's progress
+/// where `limits` asks for it and stopping when its token is stopped.
+pub(crate) fn search_move<S, A>(engine: &Engine<f32>, state: &S, limits: &SearchLimits) -> Option<A>
+where
+    S: State<f32, A>,
+    A: Clone,
+{
+    search_move_with(engine, state, &S::evaluation, limits)
+}
+
+/// Like [`search_move`], but evaluating non-terminal states with `evaluator`.
+pub(crate) fn search_move_with<S, A, E>(
+    engine: &Engine<f32>,
+    state: &S,
+    evaluator: &E,
+    limits: &SearchLimits,
+) -> Option<A>
+where
+    S: State<f32, A>,
+    A: Clone,
+    E: Evaluator<S, f32>,
+{
+    let engine = match &limits.stop {
+        Some(token) => Cow::Owned(engine.with_stop_token(token.clone())),
+        None => Cow::Borrowed(engine),
+    };
+    let Some(progress) = &limits.progress else {
+        return engine.best_move_with(state, evaluator);
+    };
+    let report = |depth, score| {
+        *progress.lock().expect("Failed to report the search's progress") =
+            Some(Progress { depth, score });
+    };
+    engine
+        .search_with_progress(
+            state,
+            evaluator,
+            &mut engine.transposition_table(),
+            |result| report(result.depth, result.score()),
+            |info| report(info.depth, info.best_line.map(|line| line.score)),
+        )
+        .best_move()
+        .cloned()
+}
+
+/// A seed that differs between calls, for games that deal or roll something at random.
+pub(crate) fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difficulties_scale_the_depth() {
+        let depths = Difficulty::PRESETS.map(|difficulty| difficulty.depth(10));
+        assert_eq!(depths, [4, 8, 10]);
+        assert_eq!(Difficulty::PRESETS.map(|difficulty| difficulty.depth(1)), [1; 3]);
+        assert_eq!(Difficulty::Medium.depth(u32::MAX), 2_863_311_530);
+        assert_eq!(Difficulty::Custom(12).depth(4), 12);
+        assert_eq!(Difficulty::Custom(0).depth(4), 1);
+    }
+
+    #[test]
+    fn finds_clicked_cells() {
+        // ┌───┬───┐
+        // │ X │ O │
+        // ├───┼───┤
+        // │   │ X │
+        // └───┴───┘
+        assert_eq!(grid_cell(1, 1, (1, 1), (2, 2), 2), Some((0, 0)));
+        assert_eq!(grid_cell(4, 1, (1, 1), (2, 2), 2), Some((0, 0)));
+        assert_eq!(grid_cell(6, 3, (1, 1), (2, 2), 2), Some((1, 1)));
+        assert_eq!(grid_cell(6, 2, (1, 1), (2, 2), 2), None);
+        assert_eq!(grid_cell(0, 1, (1, 1), (2, 2), 2), None);
+        assert_eq!(grid_cell(9, 1, (1, 1), (2, 2), 2), None);
+        assert_eq!(grid_cell(2, 5, (1, 1), (2, 2), 2), None);
+    }
+
+    #[test]
+    fn invalid_moves_are_errors() {
+        let mut game = connect_four::ConnectFour::default();
+        let error = game.play_move("z").unwrap_err();
+        assert_eq!(error, GameError::InvalidMove("z".to_string()));
+        assert_eq!(error.to_string(), "z isn't a valid move");
+        assert!(game.move_history().is_empty());
+    }
+
+    #[test]
+    fn replays_earlier_positions() {
+        let mut game = connect_four::ConnectFour::default();
+        for move_ in ["d", "c", "d"] {
+            game.play_move(move_).unwrap();
+        }
+
+        assert_eq!(game.replay(1).move_history(), ["d"]);
+        assert_eq!(game.replay(1).display(), {
+            let mut first = connect_four::ConnectFour::default();
+            first.play_move("d").unwrap();
+            first.display()
+        });
+        assert!(game.replay(0).move_history().is_empty());
+        assert_eq!(game.replay(5).move_history(), ["d", "c", "d"]);
+        // The game itself is left alone
+        assert_eq!(game.move_history(), ["d", "c", "d"]);
+    }
+
+    #[test]
+    fn searches_report_their_progress() {
+        let progress = Arc::default();
+        let limits = SearchLimits {
+            difficulty: Difficulty::Custom(3),
+            progress: Some(Arc::clone(&progress)),
+            ..SearchLimits::default()
+        };
+        let game = connect_four::ConnectFour::default();
+        assert!(game.computer_move(limits).is_ok());
+
+        let progress = progress.lock().unwrap().expect("the search reported its progress");
+        assert_eq!(progress.depth, 3);
+        assert!(progress.score.is_some());
+    }
+
+    #[test]
+    fn stopped_searches_play_the_best_move_so_far() {
+        let stop = StopToken::new();
+        stop.stop();
+        let limits = SearchLimits {
+            difficulty: Difficulty::Custom(100),
+            stop: Some(stop),
+            ..SearchLimits::default()
+        };
+        let game = connect_four::ConnectFour::default();
+        assert!(game.is_valid_move(&game.computer_move(limits).unwrap()));
+    }
+
+    #[test]
+    fn win_states_name_the_winner() {
+        let mut game = connect_four::ConnectFour::default();
+        for move_ in ["a", "b", "a", "b", "a", "b", "g"] {
+            game.play_move(move_).unwrap();
+        }
+        assert_eq!(game.win_state(), None);
+        game.play_move("b").unwrap();
+        assert_eq!(game.win_state(), Some(WinState::Decisive(Player::Min)));
+
+        assert_eq!(WinState::won_by(0), WinState::Decisive(Player::Max));
+        assert_eq!(WinState::won_by(1), WinState::Decisive(Player::Min));
+        assert_eq!(
+            WinState::from_evaluation(f32::NEG_INFINITY),
+            WinState::Decisive(Player::Min)
+        );
+        assert_eq!(WinState::from_evaluation(0.0), WinState::Draw);
+    }
+
+    #[test]
+    fn undo_fails_when_moves_cant_be_replayed() {
+        /// A game that can only play the moves it was set up with, which it forgets when it's
+        /// reset, like a game starting from a random position that doesn't take moves back itself.
+        #[derive(Clone, Default)]
+        struct Forgetful {
+            playable: Vec<String>,
+            moves: Vec<String>,
+        }
+
+        impl Game for Forgetful {
+            fn name(&self) -> String {
+                "Forgetful".to_string()
+            }
+
+            fn thumbnail(&self) -> String {
+                String::new()
+            }
+
+            fn display(&self) -> String {
+                self.moves.join(" ")
+            }
+
+            fn display_size(&self) -> (u16, u16) {
+                (0, 0)
+            }
+
+            fn move_history(&self) -> Vec<String> {
+                self.moves.clone()
+            }
+
+            fn side_to_move(&self) -> usize {
+                self.moves.len() % 2
+            }
+
+            fn win_state(&self) -> Option<WinState> {
+                None
+            }
+
+            fn is_valid_move(&self, move_: &str) -> bool {
+                self.playable.iter().any(|playable| playable == move_)
+            }
+
+            fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+                if !self.is_valid_move(move_) {
+                    return Err(GameError::invalid_move(move_));
+                }
+                self.moves.push(move_.to_string());
+                Ok(())
+            }
+
+            fn computer_move(&self, _limits: SearchLimits) -> Result<String, GameError> {
+                Err(GameError::NoMoves)
+            }
+
+            fn reset(&mut self) {
+                *self = Self::default();
+            }
+        }
+
+        let mut game = Forgetful {
+            playable: vec!["a".to_string()],
+            moves: Vec::new(),
+        };
+        assert_eq!(game.undo().unwrap(), None);
+        game.play_move("a").unwrap();
+        game.play_move("a").unwrap();
+
+        assert!(matches!(game.undo(), Err(GameError::InvalidMove(move_)) if move_ == "a"));
+        assert!(game.move_history().is_empty());
+    }
 }
@@ -0,0 +1,697 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{
+    random_seed, search_move, zobrist_key, Game, GameError, SearchLimits, WinState,
+};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+/// The point checkers on the bar move from, as if it were past the last point.
+const BAR: usize = 25;
+/// The point checkers are borne off to, as if it were before the first point.
+const OFF: usize = 0;
+const CHECKERS: u8 = 15;
+
+/// The value of each pip a player is ahead in the race.
+const PIP_VALUE: f32 = 1.0;
+/// The value of each single checker the opponent has a chance to hit.
+const BLOT_VALUE: f32 = 4.0;
+/// The value of each point made in the player's home board, which blocks checkers entering
+/// from the bar.
+const HOME_POINT_VALUE: f32 = 3.0;
+/// The value of bearing off every checker. It isn't infinite like other games' wins, so that
+/// it can be averaged with the other rolls of the dice.
+const WIN_VALUE: f32 = 10_000.0;
+
+/// The two dice rolled with `seed`.
+fn roll(seed: u64) -> [u8; 2] {
+    let key = zobrist_key(seed as usize, Player::Max);
+    [(key % 6) as u8 + 1, (key >> 32) as u8 % 6 + 1]
+}
+
+/// The state, and the moves played along with the state before each of them.
+#[derive(Clone)]
+pub struct Backgammon(BackgammonState, Vec<(Move, BackgammonState)>);
+
+impl Default for Backgammon {
+    fn default() -> Self {
+        Self(BackgammonState::new(random_seed()), Vec::new())
+    }
+}
+
+impl fmt::Debug for Backgammon {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Backgammon")
+            .field(&self.0)
+            .field(&self.1)
+            .finish()
+    }
+}
+
+impl Game for Backgammon {
+    fn name(&self) -> String {
+        "Backgammon".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " ○  ·  ● │ ●  ·  ○
+ ○     ● │ ●     ○
+         │
+ ●     ○ │ ○     ●
+ ●  ·  ○ │ ○  ·  ●"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (51, 15)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(|(move_, _)| move_.to_string()).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // There's no way to draw, since one player always bears off their last checker first
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.find_play(&move_).is_some())
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .ok()
+            .and_then(|move_| self.0.find_play(&move_))
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        let state = self.0.result(&move_).rolled();
+        self.1.push((move_, std::mem::replace(&mut self.0, state)));
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(2)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn undo(&mut self) -> Result<Option<String>, GameError> {
+        // A new game would roll different dice, so the state before the move is restored instead of replaying the game
+        let Some((move_, state)) = self.1.pop() else {
+            return Ok(None);
+        };
+        self.0 = state;
+        Ok(Some(move_.to_string()))
+    }
+}
+
+impl Backgammon {
+    /// The engine used to find computer moves, searching to `depth` plies. The rolls after the
+    /// dice already rolled are averaged over, so each ply after the first is worth every roll.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .build()
+            .expect("failed to build backgammon engine")
+    }
+}
+
+/// Moving a checker between two points, numbered from the side of the player moving it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    from: u8,
+    to: u8,
+    /// Whether the step lands on and hits an opponent's single checker.
+    hit: bool,
+}
+
+impl FromStr for Step {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected steps between points, such as `24/18` or `bar/22`";
+
+        let (from, to) = s.split_once('/').ok_or(EXPECTED)?;
+        let (to, hit) = match to.strip_suffix('*') {
+            Some(to) => (to, true),
+            None => (to, false),
+        };
+        let from = match from {
+            "bar" => BAR as u8,
+            _ => from
+                .parse()
+                .ok()
+                .filter(|from| (1..=24).contains(from))
+                .ok_or(EXPECTED)?,
+        };
+        let to = match to {
+            "off" => OFF as u8,
+            _ => to
+                .parse()
+                .ok()
+                .filter(|&to| (1..from).contains(&to))
+                .ok_or(EXPECTED)?,
+        };
+        Ok(Step { from, to, hit })
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.from as usize {
+            BAR => write!(f, "bar/")?,
+            from => write!(f, "{from}/")?,
+        }
+        match self.to as usize {
+            OFF => write!(f, "off")?,
+            to => write!(f, "{to}")?,
+        }
+        if self.hit {
+            write!(f, "*")?;
+        }
+        Ok(())
+    }
+}
+
+/// The checkers moved for a roll of the dice, such as `24/18 13/11`, or `pass` if none can be.
+///
+/// Points are numbered from 24 down to 1 in the direction the player moves, with their home board
+/// last.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move(Vec<Step>);
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        if s == "pass" {
+            return Ok(Move(Vec::new()));
+        }
+        let steps = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if steps.is_empty() || steps.len() > 4 {
+            return Err("expected between one and four steps, or `pass`");
+        }
+        Ok(Move(steps))
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "pass");
+        }
+        let steps = self.0.iter().map(Step::to_string).collect::<Vec<_>>();
+        write!(f, "{}", steps.join(" "))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BackgammonState {
+    /// The number of each player's checkers on each point, numbered from their own side so that
+    /// they always move towards point 1. Max plays the filled checkers and moves first.
+    checkers: [[u8; 26]; 2],
+    /// The dice the player to move has rolled, or `None` if they have yet to roll them.
+    dice: Option<[u8; 2]>,
+    /// The seed the game rolls the dice with next, which is a different roll each time it goes
+    /// up. The search never looks at it, since it would be looking into the future.
+    seed: u64,
+    player: Player,
+}
+
+impl BackgammonState {
+    fn new(seed: u64) -> Self {
+        let mut checkers = [0; 26];
+        for (point, count) in [(24, 2), (13, 5), (8, 3), (6, 5)] {
+            checkers[point] = count;
+        }
+        Self {
+            checkers: [checkers; 2],
+            dice: None,
+            seed,
+            player: Player::Max,
+        }
+        .rolled()
+    }
+
+    /// The state with the dice rolled by the game for the player to move.
+    fn rolled(&self) -> Self {
+        Self {
+            dice: Some(roll(self.seed)),
+            seed: self.seed.wrapping_add(1),
+            ..self.clone()
+        }
+    }
+
+    /// The number of checkers `player` has on `point`, numbered from Max's side.
+    fn checkers_on(&self, player: Player, point: usize) -> u8 {
+        match player {
+            Player::Max => self.checkers[0][point],
+            Player::Min => self.checkers[1][25 - point],
+        }
+    }
+
+    /// Moves a checker of the player to move along `step`, hitting any single checker it lands
+    /// on.
+    fn apply(&mut self, step: Step) {
+        let (own, opponent) = (self.player as usize, self.player.opposite() as usize);
+        self.checkers[own][step.from as usize] -= 1;
+        self.checkers[own][step.to as usize] += 1;
+        if step.to as usize != OFF && self.checkers[opponent][25 - step.to as usize] == 1 {
+            self.checkers[opponent][25 - step.to as usize] = 0;
+            self.checkers[opponent][BAR] += 1;
+        }
+    }
+
+    /// The step the player to move can take from `from` with `die`, if there is one.
+    fn step(&self, from: usize, die: u8) -> Option<Step> {
+        let (own, opponent) = (
+            &self.checkers[self.player as usize],
+            &self.checkers[self.player.opposite() as usize],
+        );
+        // Checkers on the bar have to come back onto the board before any others can move
+        if own[from] == 0 || own[BAR] > 0 && from != BAR {
+            return None;
+        }
+
+        let to = from as i32 - die as i32;
+        if to <= 0 {
+            // Bearing off needs every checker to be in the home board, and only the furthest
+            // checker can use a die higher than it needs
+            let home = own[7..].iter().all(|&count| count == 0);
+            let furthest = own[from + 1..7].iter().all(|&count| count == 0);
+            return (home && (to == 0 || furthest)).then_some(Step {
+                from: from as u8,
+                to: OFF as u8,
+                hit: false,
+            });
+        }
+
+        let to = to as usize;
+        (opponent[25 - to] <= 1).then_some(Step {
+            from: from as u8,
+            to: to as u8,
+            hit: opponent[25 - to] == 1,
+        })
+    }
+
+    /// Every distinct play for the dice rolled, along with the state it leaves before the
+    /// opponent rolls.
+    ///
+    /// As many dice as possible have to be used, and if only one die can be, it has to be the
+    /// higher one where possible.
+    fn plays(&self) -> Vec<(Move, Self)> {
+        let Some([first, second]) = self.dice else {
+            return Vec::new();
+        };
+        let orders = if first == second {
+            vec![vec![first; 4]]
+        } else {
+            vec![vec![first, second], vec![second, first]]
+        };
+
+        let mut plays = Vec::new();
+        for order in orders {
+            let mut order_plays = Vec::new();
+            self.extend_plays(&order, &mut Vec::new(), &mut order_plays);
+            plays.extend(
+                order_plays
+                    .into_iter()
+                    .map(|(steps, state)| (steps, order[0], state)),
+            );
+        }
+
+        let most = plays
+            .iter()
+            .map(|(steps, _, _)| steps.len())
+            .max()
+            .unwrap_or(0);
+        plays.retain(|(steps, _, _)| steps.len() == most);
+        let highest = first.max(second);
+        if most == 1 && plays.iter().any(|&(_, die, _)| die == highest) {
+            plays.retain(|&(_, die, _)| die == highest);
+        }
+
+        // Different orders of steps often end in the same position, which is only searched once
+        let mut seen = HashSet::new();
+        plays
+            .into_iter()
+            .filter(|(_, _, state)| seen.insert(state.checkers))
+            .map(|(steps, _, state)| (Move(steps), state))
+            .collect()
+    }
+
+    /// Adds the plays that follow `steps` using the dice in `dice`, in order, together with the
+    /// state they leave.
+    fn extend_plays(&self, dice: &[u8], steps: &mut Vec<Step>, plays: &mut Vec<(Vec<Step>, Self)>) {
+        let mut moved = false;
+        if let Some((&die, rest)) = dice.split_first() {
+            for from in 1..=BAR {
+                if let Some(step) = self.step(from, die) {
+                    moved = true;
+                    let mut state = self.clone();
+                    state.apply(step);
+                    steps.push(step);
+                    state.extend_plays(rest, steps, plays);
+                    steps.pop();
+                }
+            }
+        }
+        if !moved {
+            plays.push((steps.clone(), self.clone()));
+        }
+    }
+
+    /// The legal play that `move_` describes, which has to end in the same position but can
+    /// take its steps in any order.
+    fn find_play(&self, move_: &Move) -> Option<Move> {
+        let mut state = self.clone();
+        for &step in &move_.0 {
+            if state.checkers[self.player as usize][step.from as usize] == 0 {
+                return None;
+            }
+            state.apply(step);
+        }
+        self.plays()
+            .into_iter()
+            .find(|(_, played)| played.checkers == state.checkers)
+            .map(|(play, _)| play)
+    }
+
+    /// The number of pips `player` has to move to bear off all their checkers.
+    fn pips(&self, player: Player) -> u32 {
+        let own = &self.checkers[player as usize];
+        (1..=BAR)
+            .map(|point| point as u32 * own[point] as u32)
+            .sum()
+    }
+
+    /// The number of single checkers `player` has within direct reach of an opponent's checker
+    /// behind them.
+    fn blots(&self, player: Player) -> u32 {
+        let (own, opponent) = (
+            &self.checkers[player as usize],
+            &self.checkers[player.opposite() as usize],
+        );
+        (1..=24)
+            .filter(|&point| {
+                // The opponent's checkers on their bar enter as if from just before point 1
+                own[point] == 1
+                    && (point.saturating_sub(12)..point).any(|behind| opponent[25 - behind] > 0)
+            })
+            .count() as u32
+    }
+
+    fn home_points(&self, player: Player) -> u32 {
+        let own = &self.checkers[player as usize];
+        own[1..7].iter().filter(|&&count| count >= 2).count() as u32
+    }
+}
+
+impl minimax::State<f32, Move> for BackgammonState {
+    fn is_terminal(&self) -> bool {
+        self.checkers
+            .iter()
+            .any(|checkers| checkers[OFF] == CHECKERS)
+    }
+
+    fn evaluation(&self) -> f32 {
+        if self.checkers[Player::Max as usize][OFF] == CHECKERS {
+            return WIN_VALUE;
+        } else if self.checkers[Player::Min as usize][OFF] == CHECKERS {
+            return -WIN_VALUE;
+        }
+
+        let [max, min] = [Player::Max, Player::Min].map(|player| {
+            -(self.pips(player) as f32) * PIP_VALUE - self.blots(player) as f32 * BLOT_VALUE
+                + self.home_points(player) as f32 * HOME_POINT_VALUE
+        });
+        max - min
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        self.plays().into_iter().map(|(play, _)| play).collect()
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let mut state = self.clone();
+        for &step in &action.0 {
+            assert!(
+                state.checkers[self.player as usize][step.from as usize] > 0,
+                "{action} is not a legal move"
+            );
+            state.apply(step);
+        }
+        state.player = self.player.opposite();
+        state.dice = None;
+        state
+    }
+
+    fn is_chance(&self) -> bool {
+        self.dice.is_none()
+    }
+
+    /// Every roll of the dice, with doubles half as likely as each other roll since the dice
+    /// can only land one way for them.
+    fn outcomes(&self) -> Vec<(f32, Self)> {
+        let mut outcomes = Vec::with_capacity(21);
+        for first in 1..=6 {
+            for second in first..=6 {
+                let ways = if first == second { 1.0 } else { 2.0 };
+                let state = Self {
+                    dice: Some([first, second]),
+                    ..self.clone()
+                };
+                outcomes.push((ways / 36.0, state));
+            }
+        }
+        outcomes
+    }
+}
+
+impl Display for BackgammonState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let cell = |point: usize, row: usize| {
+            let (player, symbol) = if self.checkers_on(Player::Max, point) > 0 {
+                (Player::Max, '●')
+            } else {
+                (Player::Min, '○')
+            };
+            match self.checkers_on(player, point) {
+                count if count > 5 && row == 4 => format!("{count:^3}"),
+                count if count as usize > row => format!(" {symbol} "),
+                _ => " · ".to_string(),
+            }
+        };
+        let bar = |player: Player, row: usize| match self.checkers[player as usize][BAR] {
+            count if count > 1 && row == 0 => format!("{count:^3}"),
+            count if count > 0 && row <= 1 => {
+                format!(" {} ", if player == Player::Max { '●' } else { '○' })
+            }
+            _ => "   ".to_string(),
+        };
+        let off = |player: Player| match self.checkers[player as usize][OFF] {
+            0 => String::new(),
+            count => format!(" {count} off"),
+        };
+        let half = |points: [usize; 6], row: usize| points.map(|point| cell(point, row)).concat();
+
+        writeln!(f, " 13 14 15 16 17 18       19 20 21 22 23 24")?;
+        writeln!(f, "┌──────────────────┬───┬──────────────────┐")?;
+        for row in 0..5 {
+            write!(
+                f,
+                "│{}│{}│{}│",
+                half([13, 14, 15, 16, 17, 18], row),
+                bar(Player::Min, row),
+                half([19, 20, 21, 22, 23, 24], row)
+            )?;
+            if row == 0 {
+                write!(f, "{}", off(Player::Min))?;
+            }
+            writeln!(f)?;
+        }
+        let symbol = if self.player == Player::Max {
+            '●'
+        } else {
+            '○'
+        };
+        let dice = match self.dice {
+            Some([first, second]) => format!("{symbol} rolled {first} {second}"),
+            None => format!("{symbol} to roll"),
+        };
+        writeln!(f, "│{:18}│   │{dice:^18}│", "")?;
+        for row in (0..5).rev() {
+            write!(
+                f,
+                "│{}│{}│{}│",
+                half([12, 11, 10, 9, 8, 7], row),
+                bar(Player::Max, row),
+                half([6, 5, 4, 3, 2, 1], row)
+            )?;
+            if row == 0 {
+                write!(f, "{}", off(Player::Max))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "└──────────────────┴───┴──────────────────┘")?;
+        write!(f, " 12 11 10  9  8  7        6  5  4  3  2  1")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(dice: [u8; 2]) -> BackgammonState {
+        BackgammonState {
+            dice: Some(dice),
+            ..BackgammonState::new(0)
+        }
+    }
+
+    fn has_play(state: &BackgammonState, play: &str) -> bool {
+        state.find_play(&play.parse().unwrap()).is_some()
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "24/18 13/11".parse::<Move>().unwrap();
+        assert_eq!(
+            move_.0[0],
+            Step {
+                from: 24,
+                to: 18,
+                hit: false
+            }
+        );
+        assert_eq!(move_.to_string(), "24/18 13/11");
+        assert_eq!(
+            "bar/22* 6/off".parse::<Move>().unwrap().to_string(),
+            "bar/22* 6/off"
+        );
+        assert_eq!("Pass".parse::<Move>().unwrap(), Move(Vec::new()));
+        assert!("24/25".parse::<Move>().is_err());
+        assert!("18/24".parse::<Move>().is_err());
+        assert!("".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn plays_use_both_dice_in_either_order() {
+        let state = state([3, 1]);
+        assert!(has_play(&state, "8/5 6/5"));
+        assert!(has_play(&state, "6/5 8/5"));
+        assert!(has_play(&state, "24/21 21/20"));
+        // A checker can move the whole roll at once
+        assert!(has_play(&state, "24/20"));
+        assert!(!has_play(&state, "8/5"));
+        assert!(!has_play(&state, "6/2 6/5"));
+
+        let doubles = BackgammonState {
+            dice: Some([6, 6]),
+            ..state
+        };
+        assert!(has_play(&doubles, "24/18 24/18 13/7 13/7"));
+        assert!(doubles.plays().iter().all(|(play, _)| play.0.len() == 4));
+    }
+
+    #[test]
+    fn hit_checkers_enter_from_the_bar() {
+        let mut state = state([5, 2]);
+        state.checkers[1] = [0; 26];
+        state.checkers[1][24] = 1;
+        state.checkers[1][25 - 3] = 1;
+        state.checkers[1][OFF] = 13;
+
+        let play = state.find_play(&"8/3 6/4".parse().unwrap()).unwrap();
+        assert_eq!(play.to_string(), "8/3* 6/4");
+        let mut state = state.result(&play);
+        assert_eq!(state.checkers[1][BAR], 1);
+        assert_eq!(state.blots(Player::Max), 2);
+
+        // Min's checker on the bar has to come in first, and it can hit back on entering
+        state.dice = Some([3, 4]);
+        assert!(has_play(&state, "bar/22* 24/20"));
+        assert!(!has_play(&state, "24/17"));
+    }
+
+    #[test]
+    fn bearing_off_needs_every_checker_home() {
+        let mut state = state([6, 2]);
+        state.checkers[0] = [0; 26];
+        state.checkers[0][4] = 2;
+        state.checkers[0][OFF] = 13;
+
+        // A six can bear off from the four point since there's nothing further back
+        assert!(has_play(&state, "4/off 4/2"));
+        state.checkers[0][4] = 1;
+        state.checkers[0][9] = 1;
+        assert!(has_play(&state, "9/3 4/2"));
+        assert!(!has_play(&state, "9/7 4/off"));
+
+        state.checkers[0] = [0; 26];
+        state.checkers[0][2] = 1;
+        state.checkers[0][OFF] = 14;
+        state.dice = Some([2, 3]);
+        let state = state.result(&state.find_play(&"2/off".parse().unwrap()).unwrap());
+        assert!(state.is_terminal());
+        assert_eq!(state.evaluation(), WIN_VALUE);
+    }
+
+    #[test]
+    fn the_next_roll_is_left_to_chance() {
+        let state = state([3, 1]);
+        let played = state.result(&state.find_play(&"8/5 6/5".parse().unwrap()).unwrap());
+        assert!(played.is_chance());
+        assert!(played.actions().is_empty());
+        let outcomes = played.outcomes();
+        assert_eq!(outcomes.len(), 21);
+        let total = outcomes
+            .iter()
+            .map(|(probability, _)| probability)
+            .sum::<f32>();
+        assert!((total - 1.0).abs() < 1e-6);
+
+        // Only the game itself rolls the dice with its seed
+        let mut game = Backgammon(state, Vec::new());
+        game.play_move("8/5 6/5").unwrap();
+        assert_eq!(game.0.player, Player::Min);
+        assert!(game.0.dice.is_some());
+    }
+
+    #[test]
+    fn engine_makes_the_five_point_with_three_one() {
+        let move_ = Backgammon::engine(2).best_move(&state([3, 1])).unwrap();
+        assert!(["8/5 6/5", "6/5 8/5"].contains(&move_.to_string().as_str()));
+    }
+}
@@ -0,0 +1,499 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const SIZE: i8 = 7;
+const THRONE: u8 = 24;
+const CORNERS: u64 = 1 | 1 << 6 | 1 << 42 | 1 << 48;
+const DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+const ATTACKER_VALUE: f32 = 1.0;
+/// Defenders are worth more than attackers since there are half as many of them.
+const DEFENDER_VALUE: f32 = 2.0;
+/// The value of each attacker next to the king, which is how the king ends up captured.
+const SURROUNDING_VALUE: f32 = 1.5;
+/// The value of each square the king can move to, since a free king is close to escaping.
+const KING_MOBILITY_VALUE: f32 = 0.25;
+
+/// The index of each square in `bitboard`, from lowest to highest.
+fn squares(mut bitboard: u64) -> impl Iterator<Item = u8> {
+    std::iter::from_fn(move || {
+        let square = (bitboard != 0).then(|| bitboard.trailing_zeros() as u8)?;
+        bitboard &= bitboard - 1;
+        Some(square)
+    })
+}
+
+/// The square one step from `square` along `(file, rank)`, if it's on the board.
+fn step(square: u8, (file, rank): (i8, i8)) -> Option<u8> {
+    let file = (square as i8 % SIZE) + file;
+    let rank = (square as i8 / SIZE) + rank;
+    ((0..SIZE).contains(&file) && (0..SIZE).contains(&rank)).then_some((rank * SIZE + file) as u8)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Brandubh(BrandubhState, Vec<Move>);
+
+impl Game for Brandubh {
+    fn name(&self) -> String {
+        "Brandubh".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "   │ ♟ │
+───┼───┼───
+ ♟ │ ♔ │ ♙
+───┼───┼───
+   │ ♙ │ ♟"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (32, 16)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // The king either escapes, is captured, or one side runs out of moves
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.actions().contains(&move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(5)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["Attackers", "Defenders"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Brandubh {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(2)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build brandubh engine")
+    }
+}
+
+/// A piece moving any distance along a rank or file, written as the squares it moves between,
+/// such as `d2-b2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    from: u8,
+    to: u8,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected two squares, such as `d2-b2`";
+        let s = s.trim().to_ascii_lowercase();
+        let square = |name: &str| match *name.as_bytes() {
+            [file @ b'a'..=b'g', rank @ b'1'..=b'7'] => Ok((rank - b'1') * 7 + file - b'a'),
+            _ => Err(EXPECTED),
+        };
+
+        let (from, to) = s.split_once('-').ok_or(EXPECTED)?;
+        Ok(Move {
+            from: square(from)?,
+            to: square(to)?,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let square = |square: u8| format!("{}{}", (b'a' + square % 7) as char, square / 7 + 1);
+        write!(f, "{}-{}", square(self.from), square(self.to))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BrandubhState {
+    /// The attackers, as a bitmask of the squares going along each rank from a1. Max plays the
+    /// attackers and moves first.
+    attackers: u64,
+    /// The defenders other than the king.
+    defenders: u64,
+    king: u8,
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl Default for BrandubhState {
+    fn default() -> Self {
+        let squares = |squares: &[u8]| squares.iter().map(|&square| 1 << square).sum();
+        Self {
+            attackers: squares(&[3, 10, 21, 22, 26, 27, 38, 45]),
+            defenders: squares(&[17, 23, 25, 31]),
+            king: THRONE,
+            player: Player::Max,
+            winner: None,
+        }
+    }
+}
+
+impl BrandubhState {
+    fn occupied(&self) -> u64 {
+        self.attackers | self.defenders | 1 << self.king
+    }
+
+    /// The pieces of `player`, with the king counted among the defenders.
+    fn pieces(&self, player: Player) -> u64 {
+        match player {
+            Player::Max => self.attackers,
+            Player::Min => self.defenders | 1 << self.king,
+        }
+    }
+
+    /// Whether `square` counts against a piece of `player`'s opponent in a capture: one of
+    /// `player`'s own pieces, a corner, or the throne once the king has left it.
+    fn is_hostile(&self, square: u8, player: Player) -> bool {
+        self.pieces(player) & 1 << square != 0
+            || CORNERS & 1 << square != 0
+            || square == THRONE && self.king != THRONE
+    }
+
+    /// The squares the piece on `from` can move to.
+    fn targets(&self, from: u8) -> impl Iterator<Item = u8> + '_ {
+        // Only the king can stop on the throne or in a corner, but others can pass over the
+        // empty throne
+        let restricted = if from == self.king {
+            0
+        } else {
+            CORNERS | 1 << THRONE
+        };
+        DIRECTIONS.into_iter().flat_map(move |direction| {
+            std::iter::successors(step(from, direction), move |&square| {
+                step(square, direction)
+            })
+            .take_while(|&square| self.occupied() & 1 << square == 0)
+            .filter(move |&square| restricted & 1 << square == 0)
+        })
+    }
+
+    /// The opponent's pieces that `move_` captures, other than the king.
+    fn captures(&self, move_: &Move) -> u64 {
+        let mut state = self.clone();
+        state.move_piece(move_);
+        let taken = match self.player {
+            Player::Max => self.defenders,
+            Player::Min => self.attackers,
+        };
+
+        let mut captured = 0;
+        for direction in DIRECTIONS {
+            let Some(neighbour) = step(move_.to, direction) else {
+                continue;
+            };
+            if taken & 1 << neighbour != 0
+                && step(neighbour, direction)
+                    .is_some_and(|beyond| state.is_hostile(beyond, self.player))
+            {
+                captured |= 1 << neighbour;
+            }
+        }
+        captured
+    }
+
+    /// Whether the king is surrounded once the attackers have made their move. Away from the
+    /// throne, the king is captured between two attackers like any other piece, but on or next
+    /// to it the king has to be surrounded on every side, with the empty throne counting as one.
+    fn is_king_captured(&self, last: u8) -> bool {
+        let neighbours = DIRECTIONS.map(|direction| step(self.king, direction));
+        if self.king == THRONE || neighbours.contains(&Some(THRONE)) {
+            neighbours.iter().all(|&neighbour| {
+                neighbour
+                    .is_some_and(|square| square == THRONE || self.attackers & 1 << square != 0)
+            })
+        } else {
+            DIRECTIONS.into_iter().any(|direction| {
+                step(self.king, direction) == Some(last)
+                    && step(self.king, (-direction.0, -direction.1))
+                        .is_some_and(|beyond| self.is_hostile(beyond, Player::Max))
+            })
+        }
+    }
+
+    fn move_piece(&mut self, move_: &Move) {
+        let squares = 1 << move_.from | 1 << move_.to;
+        if self.king == move_.from {
+            self.king = move_.to;
+        } else if self.attackers & 1 << move_.from != 0 {
+            self.attackers ^= squares;
+        } else {
+            self.defenders ^= squares;
+        }
+    }
+}
+
+impl minimax::State<f32, Move> for BrandubhState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        let surrounding = DIRECTIONS
+            .into_iter()
+            .filter_map(|direction| step(self.king, direction))
+            .filter(|&square| self.attackers & 1 << square != 0)
+            .count();
+        self.attackers.count_ones() as f32 * ATTACKER_VALUE
+            - self.defenders.count_ones() as f32 * DEFENDER_VALUE
+            + surrounding as f32 * SURROUNDING_VALUE
+            - self.targets(self.king).count() as f32 * KING_MOBILITY_VALUE
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        for from in squares(self.pieces(self.player)) {
+            actions.extend(self.targets(from).map(|to| Move { from, to }));
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        assert!(
+            self.pieces(self.player) & 1 << action.from != 0
+                && self.occupied() & 1 << action.to == 0,
+            "{action} is not a legal move"
+        );
+
+        let captured = self.captures(action);
+        let mut state = self.clone();
+        state.move_piece(action);
+        state.attackers &= !captured;
+        state.defenders &= !captured;
+        state.player = self.player.opposite();
+
+        // The king escaping or being captured ends the game, as does leaving the opponent
+        // without a move
+        if CORNERS & 1 << state.king != 0
+            || self.player == Player::Max && state.is_king_captured(action.to)
+            || state.actions().is_empty()
+        {
+            state.winner = Some(self.player);
+        }
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        self.captures(action) == 0
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        let value = match self.player {
+            Player::Max => DEFENDER_VALUE,
+            Player::Min => ATTACKER_VALUE,
+        };
+        self.captures(action).count_ones() as f32 * value
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let defenders = self.defenders ^ (self.king as u64) << 50 ^ (self.player as u64) << 57;
+        Some(mix(self.attackers) ^ mix(defenders).rotate_left(32))
+    }
+}
+
+impl Display for BrandubhState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ┌───┬───┬───┬───┬───┬───┬───┐")?;
+        for rank in (0..7).rev() {
+            write!(f, "{} │", rank + 1)?;
+            for file in 0..7 {
+                let square = rank * 7 + file;
+                let piece = if self.king == square {
+                    '♔'
+                } else if self.attackers & 1 << square != 0 {
+                    '♟'
+                } else if self.defenders & 1 << square != 0 {
+                    '♙'
+                } else if (CORNERS | 1 << THRONE) & 1 << square != 0 {
+                    '·'
+                } else {
+                    ' '
+                };
+                write!(f, " {piece} │")?;
+            }
+            if rank != 0 {
+                writeln!(f, "\n  ├───┼───┼───┼───┼───┼───┼───┤")?;
+            }
+        }
+        write!(
+            f,
+            "\n  └───┴───┴───┴───┴───┴───┴───┘\n    a   b   c   d   e   f   g"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> u8 {
+        format!("{name}-{name}").parse::<Move>().unwrap().from
+    }
+
+    fn squares(names: &[&str]) -> u64 {
+        names.iter().map(|&name| 1 << square(name)).sum()
+    }
+
+    fn targets(state: &BrandubhState, from: &str) -> Vec<String> {
+        let mut targets = state
+            .targets(square(from))
+            .map(|to| {
+                Move {
+                    from: square(from),
+                    to,
+                }
+                .to_string()
+            })
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "d2-b2".parse::<Move>().unwrap();
+        assert_eq!((move_.from, move_.to), (10, 8));
+        assert_eq!(move_.to_string(), "d2-b2");
+        assert!("d2-h2".parse::<Move>().is_err());
+        assert!("d2b2".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn pieces_move_like_rooks_but_only_the_king_stops_in_corners() {
+        let state = BrandubhState::default();
+        assert_eq!(targets(&state, "d1"), ["d1-b1", "d1-c1", "d1-e1", "d1-f1"]);
+        assert_eq!(targets(&state, "d4"), Vec::<String>::new());
+
+        // Other pieces can pass over the empty throne
+        let state = BrandubhState {
+            attackers: squares(&["d1"]),
+            defenders: 0,
+            king: square("g4"),
+            ..state
+        };
+        assert!(targets(&state, "d1").contains(&"d1-d7".to_string()));
+        assert!(!targets(&state, "d1").contains(&"d1-d4".to_string()));
+        assert!(!targets(&state, "d1").contains(&"d1-a1".to_string()));
+        assert!(targets(&state, "g4").contains(&"g4-g7".to_string()));
+    }
+
+    fn state(attackers: &[&str], defenders: &[&str], king: &str, player: Player) -> BrandubhState {
+        BrandubhState {
+            attackers: squares(attackers),
+            defenders: squares(defenders),
+            king: square(king),
+            player,
+            winner: None,
+        }
+    }
+
+    #[test]
+    fn pieces_are_captured_between_enemies_or_hostile_squares() {
+        let captured =
+            state(&["c2", "a4"], &["c3"], "f6", Player::Max).result(&"a4-c4".parse().unwrap());
+        assert_eq!(captured.defenders, 0);
+
+        // Moving in between two enemies is safe
+        let safe =
+            state(&["b5", "d5"], &["c7"], "f6", Player::Min).result(&"c7-c5".parse().unwrap());
+        assert_eq!(safe.defenders, squares(&["c5"]));
+        assert_eq!(safe.attackers, squares(&["b5", "d5"]));
+
+        // Corners and the empty throne count against both sides
+        let state = state(&["c3", "b6"], &["b1", "c4"], "f6", Player::Max);
+        let corner = state.result(&"c3-c1".parse().unwrap());
+        assert_eq!(corner.defenders, squares(&["c4"]));
+        let throne = state.result(&"b6-b4".parse().unwrap());
+        assert_eq!(throne.defenders, squares(&["b1"]));
+    }
+
+    #[test]
+    fn king_escapes_to_a_corner_or_is_captured() {
+        let escaped = state(&["b2"], &[], "f7", Player::Min).result(&"f7-g7".parse().unwrap());
+        assert_eq!(escaped.winner, Some(Player::Min));
+
+        let state = state(&["e6", "g5"], &[], "f6", Player::Max);
+        let captured = state.result(&"g5-g6".parse().unwrap());
+        assert_eq!(captured.winner, Some(Player::Max));
+        let move_ = Brandubh::engine(2).best_move(&state).unwrap();
+        assert_eq!(move_.to_string(), "g5-g6");
+    }
+
+    #[test]
+    fn king_next_to_the_throne_has_to_be_surrounded() {
+        let surrounded = state(&["c5", "e5", "c6"], &[], "d5", Player::Max);
+        let surrounded = surrounded.result(&"c6-d6".parse().unwrap());
+        assert_eq!(surrounded.winner, Some(Player::Max));
+
+        let open = state(&["c5", "d7"], &[], "d5", Player::Max);
+        let open = open.result(&"d7-d6".parse().unwrap());
+        assert_eq!(open.winner, None);
+    }
+}
@@ -0,0 +1,440 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+const RANK_1: u64 = 0xff;
+const RANK_8: u64 = RANK_1 << 56;
+
+const PIECE_VALUE: f32 = 10.0;
+/// The value of a piece for the square of the number of ranks it has advanced, since pieces
+/// close to the other side are the ones that can break through.
+const ADVANCEMENT_VALUE: f32 = 0.25;
+
+#[derive(Clone, Debug, Default)]
+pub struct Breakthrough(BreakthroughState, Vec<Move>);
+
+impl Game for Breakthrough {
+    fn name(&self) -> String {
+        "Breakthrough".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " ♟ │ ♟ │ ♟
+───┼───┼───
+   │ ♙ │
+───┼───┼───
+ ♙ │   │ ♙"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (36, 18)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // Pieces can only move forwards, so someone always breaks through in the end
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.find_move(move_).is_some())
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .ok()
+            .and_then(|move_| self.0.find_move(move_))
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(6)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["White", "Black"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Breakthrough {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(4)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build breakthrough engine")
+    }
+}
+
+/// A piece moving one square straight or diagonally forwards, written as the squares it moves
+/// between, such as `e2-e3`, or `d4xe5` for a capture. Pieces can only capture diagonally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    from: u8,
+    to: u8,
+    capture: bool,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected two squares, such as `e2-e3`";
+        let s = s.trim().to_ascii_lowercase();
+        let square = |name: &[u8]| match *name {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Ok((rank - b'1') * 8 + file - b'a'),
+            _ => Err(EXPECTED),
+        };
+
+        let bytes = s.as_bytes();
+        let (from, to, capture) = match bytes.len() {
+            4 => (&bytes[..2], &bytes[2..], false),
+            5 if matches!(bytes[2], b'-' | b'x') => (&bytes[..2], &bytes[3..], bytes[2] == b'x'),
+            _ => Err(EXPECTED)?,
+        };
+        Ok(Move {
+            from: square(from)?,
+            to: square(to)?,
+            capture,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let square = |square: u8| format!("{}{}", (b'a' + square % 8) as char, square / 8 + 1);
+        let separator = if self.capture { 'x' } else { '-' };
+        write!(f, "{}{separator}{}", square(self.from), square(self.to))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BreakthroughState {
+    /// The pieces of each player, indexed by `Player`. White moves first from the first two ranks
+    /// and is the maximising player, while Black starts on the last two.
+    pieces: [u64; 2],
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl Default for BreakthroughState {
+    fn default() -> Self {
+        Self {
+            pieces: [RANK_1 | RANK_1 << 8, RANK_8 | RANK_8 >> 8],
+            player: Player::Max,
+            winner: None,
+        }
+    }
+}
+
+impl BreakthroughState {
+    /// The squares `player`'s pieces can move to from `pieces`, straight ahead and to the left
+    /// and right diagonally, without leaving the board.
+    fn targets(player: Player, pieces: u64) -> [u64; 3] {
+        match player {
+            Player::Max => [
+                pieces << 8,
+                (pieces & !FILE_A) << 7,
+                (pieces & !FILE_H) << 9,
+            ],
+            Player::Min => [
+                pieces >> 8,
+                (pieces & !FILE_A) >> 9,
+                (pieces & !FILE_H) >> 7,
+            ],
+        }
+    }
+
+    /// The shift from a target back to the square it was moved to from, for each direction
+    /// returned by [`BreakthroughState::targets`].
+    fn steps(player: Player) -> [i8; 3] {
+        match player {
+            Player::Max => [8, 7, 9],
+            Player::Min => [-8, -9, -7],
+        }
+    }
+
+    /// The rank `player` has to reach to win.
+    fn goal(player: Player) -> u64 {
+        match player {
+            Player::Max => RANK_8,
+            Player::Min => RANK_1,
+        }
+    }
+
+    fn has_moves(&self, player: Player) -> bool {
+        let own = self.pieces[player as usize];
+        let opponent = self.pieces[player.opposite() as usize];
+        let [ahead, left, right] = Self::targets(player, own);
+        ahead & !(own | opponent) != 0 || (left | right) & !own != 0
+    }
+
+    /// The legal move matching the squares of `move_`, whether or not it was written as a capture.
+    fn find_move(&self, move_: Move) -> Option<Move> {
+        self.actions()
+            .into_iter()
+            .find(|action| action.from == move_.from && action.to == move_.to)
+    }
+
+    /// The number of ranks a piece on `square` has advanced from `player`'s first rank.
+    fn advancement(player: Player, square: u32) -> u32 {
+        match player {
+            Player::Max => square / 8,
+            Player::Min => 7 - square / 8,
+        }
+    }
+}
+
+impl minimax::State<f32, Move> for BreakthroughState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        let [max, min] = [Player::Max, Player::Min].map(|player| {
+            let mut pieces = self.pieces[player as usize];
+            let mut value = 0.0;
+            while pieces != 0 {
+                let advancement = Self::advancement(player, pieces.trailing_zeros()) as f32;
+                value += PIECE_VALUE + advancement * advancement * ADVANCEMENT_VALUE;
+                pieces &= pieces - 1;
+            }
+            value
+        });
+        max - min
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let own = self.pieces[self.player as usize];
+        let opponent = self.pieces[self.player.opposite() as usize];
+        let [ahead, left, right] = Self::targets(self.player, own);
+        let steps = Self::steps(self.player);
+
+        // Moves that win come first, then captures, then everything else
+        let goal = Self::goal(self.player);
+        let mut moves = [
+            (left & opponent, steps[1]),
+            (right & opponent, steps[2]),
+            (ahead & !(own | opponent), steps[0]),
+            (left & !(own | opponent), steps[1]),
+            (right & !(own | opponent), steps[2]),
+        ];
+        moves.sort_by_key(|&(targets, _)| targets & goal == 0);
+
+        for (mut targets, step) in moves {
+            while targets != 0 {
+                let to = targets.trailing_zeros() as u8;
+                actions.push(Move {
+                    from: (to as i8 - step) as u8,
+                    to,
+                    capture: opponent & 1 << to != 0,
+                });
+                targets &= targets - 1;
+            }
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let own = self.player as usize;
+        let opponent = self.player.opposite() as usize;
+        assert!(
+            self.pieces[own] & 1 << action.from != 0 && self.pieces[own] & 1 << action.to == 0,
+            "{action} is not a legal move"
+        );
+
+        let mut state = self.clone();
+        state.pieces[own] ^= 1 << action.from | 1 << action.to;
+        state.pieces[opponent] &= !(1 << action.to);
+
+        // Reaching the far rank wins, as does leaving the opponent without a move
+        if Self::goal(self.player) & 1 << action.to != 0 || !state.has_moves(self.player.opposite())
+        {
+            state.winner = Some(self.player);
+        }
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        !action.capture && Self::goal(self.player) & 1 << action.to == 0
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        if Self::goal(self.player) & 1 << action.to != 0 {
+            f32::INFINITY
+        } else {
+            PIECE_VALUE
+        }
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let [max, min] = self.pieces;
+        Some(mix(max) ^ mix(min ^ self.player as u64).rotate_left(32))
+    }
+}
+
+impl Display for BreakthroughState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ┌───┬───┬───┬───┬───┬───┬───┬───┐")?;
+        for rank in (0..8).rev() {
+            write!(f, "{} │", rank + 1)?;
+            for file in 0..8 {
+                let square = 1 << (rank * 8 + file);
+                let piece = if self.pieces[Player::Max as usize] & square != 0 {
+                    '♙'
+                } else if self.pieces[Player::Min as usize] & square != 0 {
+                    '♟'
+                } else {
+                    ' '
+                };
+                write!(f, " {piece} │")?;
+            }
+            if rank != 0 {
+                writeln!(f, "\n  ├───┼───┼───┼───┼───┼───┼───┼───┤")?;
+            }
+        }
+        write!(
+            f,
+            "\n  └───┴───┴───┴───┴───┴───┴───┴───┘\n    a   b   c   d   e   f   g   h"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> u64 {
+        let move_ = format!("{name}-{name}").parse::<Move>().unwrap();
+        1 << move_.from
+    }
+
+    fn position(white: &[&str], black: &[&str], player: Player) -> BreakthroughState {
+        let pieces = |squares: &[&str]| {
+            squares
+                .iter()
+                .fold(0, |pieces, &name| pieces | square(name))
+        };
+        BreakthroughState {
+            pieces: [pieces(white), pieces(black)],
+            player,
+            winner: None,
+        }
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "e2-e3".parse::<Move>().unwrap();
+        assert_eq!((move_.from, move_.to, move_.capture), (12, 20, false));
+        assert_eq!("D4XE5".parse::<Move>().unwrap().to_string(), "d4xe5");
+        assert_eq!("a2b3".parse::<Move>().unwrap().to_string(), "a2-b3");
+        assert!("e2-e9".parse::<Move>().is_err());
+        assert!("e2/e3".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn opening_moves() {
+        let state = BreakthroughState::default();
+        // Every piece on the second rank can move to three squares, except on the edges
+        assert_eq!(state.actions().len(), 22);
+        assert!(state.find_move("a2-b3".parse().unwrap()).is_some());
+        assert!(state.find_move("a2-a4".parse().unwrap()).is_none());
+        assert!(state.find_move("a1-a2".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn captures_only_diagonally() {
+        let state = position(&["d4"], &["d5", "e5", "h8"], Player::Max);
+        let moves = state
+            .actions()
+            .iter()
+            .map(Move::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(moves, ["d4xe5", "d4-c5"]);
+
+        // Writing a capture without the `x` finds the same move
+        let capture = state.find_move("d4-e5".parse().unwrap()).unwrap();
+        let state = state.result(&capture);
+        assert_eq!(
+            state.pieces[Player::Min as usize],
+            square("d5") | square("h8")
+        );
+    }
+
+    #[test]
+    fn reaching_the_last_rank_wins() {
+        let state = position(&["a7", "b2"], &["h7"], Player::Max);
+        let state = state.result(&"a7-a8".parse().unwrap());
+        assert_eq!(state.winner, Some(Player::Max));
+        assert!(state.is_terminal());
+
+        // Capturing the last piece wins as well
+        let state = position(&["a2"], &["b3"], Player::Max);
+        let state = state.result(&"a2xb3".parse().unwrap());
+        assert_eq!(state.winner, Some(Player::Max));
+    }
+
+    #[test]
+    fn engine_stops_a_breakthrough() {
+        // Black's piece on f2 breaks through next move unless White captures it
+        let state = position(&["e1", "h2", "a5"], &["f2", "a7", "b7"], Player::Max);
+        let best = Breakthrough::engine(2).best_move(&state).unwrap();
+        assert_eq!(best.to_string(), "e1xf2");
+    }
+}
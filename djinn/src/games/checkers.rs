@@ -0,0 +1,640 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
+
+use crate::games::{
+    grid_cell, scaled_advantage, search_move, Click, Game, GameError, SearchLimits, WinState,
+};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+/// The dark squares, numbered from 0 in reading order with Black's pieces at the top. Standard
+/// notation numbers them from 1.
+type Square = u8;
+
+const SQUARES: usize = 32;
+
+/// The colours of the squares, from the 256-colour palette.
+const LIGHT_SQUARE: Color = Color::Indexed(180);
+const DARK_SQUARE: Color = Color::Indexed(137);
+/// The colour of the squares on the path of the last move.
+const MOVED_SQUARE: Color = Color::Indexed(143);
+
+/// Diagonal directions: down-left and down-right are forward for Black, and up-left and up-right
+/// are forward for White.
+const DIRECTIONS: [(i8, i8); 4] = [(1, -1), (1, 1), (-1, -1), (-1, 1)];
+
+/// The squares reached by stepping once and twice in each direction from each square.
+const NEIGHBOURS: [[[Option<Square>; 4]; SQUARES]; 2] = {
+    let mut neighbours = [[[None; 4]; SQUARES]; 2];
+    let mut square = 0;
+    while square < SQUARES {
+        let (row, column) = coordinates(square as Square);
+        let mut dir = 0;
+        while dir < DIRECTIONS.len() {
+            let (dy, dx) = DIRECTIONS[dir];
+            let mut distance = 1;
+            while distance <= 2 {
+                let row = row as i8 + dy * distance;
+                let column = column as i8 + dx * distance;
+                if row >= 0 && row < 8 && column >= 0 && column < 8 {
+                    neighbours[distance as usize - 1][square][dir] =
+                        Some((row * 4 + column / 2) as Square);
+                }
+                distance += 1;
+            }
+            dir += 1;
+        }
+        square += 1;
+    }
+    neighbours
+};
+
+const MAN_VALUE: f32 = 100.0;
+const KING_VALUE: f32 = 160.0;
+/// The value of each row a man has advanced towards being crowned.
+const ADVANCEMENT_VALUE: f32 = 4.0;
+
+/// Without a capture or a man moving for this many plies (40 moves each), the game is drawn.
+const DRAW_PLIES: u8 = 80;
+
+/// The row and column of a square, counting from the top left.
+const fn coordinates(square: Square) -> (u8, u8) {
+    let row = square / 4;
+    let column = (square % 4) * 2 + (row + 1) % 2;
+    (row, column)
+}
+
+const fn bit(square: Square) -> u32 {
+    1 << square
+}
+
+/// The row a player's men are crowned on.
+fn crowning_row(player: Player) -> u8 {
+    match player {
+        Player::Max => 7,
+        Player::Min => 0,
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Checkers(CheckersState, Vec<Move>);
+
+impl Game for Checkers {
+    fn name(&self) -> String {
+        "Checkers".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " ○ │   │ ○
+───┼───┼───
+   │ ● │
+───┼───┼───
+ ● │   │ ◆ "
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_for(&self, side: usize) -> String {
+        self.display_styled(side, "").to_string()
+    }
+
+    fn display_styled(&self, side: usize, _typed: &str) -> Text<'static> {
+        // Black moves first and starts at the top
+        let moved = self.1.last().map_or(0, |move_| move_.path().map(bit).sum());
+        self.0.board(side == 0, moved)
+    }
+
+    fn click(&self, typed: &str, column: u16, row: u16, side: usize) -> Option<Click> {
+        let (column, row) = grid_cell(column, row, (1, 1), (8, 8), 2)?;
+        // Black's side of the board is drawn at the bottom for Black
+        let (column, row) = match side {
+            0 => (7 - column, 7 - row),
+            _ => (column, row),
+        };
+        if (row + column) % 2 == 0 {
+            return None;
+        }
+        let square = (row * 4 + column / 2 + 1).to_string();
+        if typed.is_empty() {
+            return Some(Click::Select(square));
+        }
+
+        // Each click adds a square to the path of the move, unless no move goes that way, in
+        // which case the click starts a new one
+        let path = format!("{typed}-{square}");
+        if self.0.parse_move(&path).is_some() {
+            return Some(Click::Play(path));
+        }
+        let squares: Vec<_> = path.split(['-', 'x', 'X']).collect();
+        let continues = self.0.actions().iter().any(|move_| {
+            let notation = move_.to_string();
+            notation
+                .split(['-', 'x'])
+                .take(squares.len())
+                .eq(squares.iter().copied())
+        });
+        Some(Click::Select(if continues { path } else { square }))
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (34, 17)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if !self.0.is_terminal() {
+            None
+        } else if self.0.quiet_plies >= DRAW_PLIES {
+            Some(WinState::Draw)
+        } else {
+            // The player to move has lost
+            Some(WinState::Decisive(self.0.player.opposite()))
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        !self.0.is_terminal() && self.0.parse_move(move_).is_some()
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = self
+            .0
+            .parse_move(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(10)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn advantage(&self) -> Option<f32> {
+        let value = Self::engine(4).value(&self.0);
+        Some(scaled_advantage(value, self.0.player, 150.0))
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["Black", "White"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Checkers {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(6)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build checkers engine")
+    }
+}
+
+/// A move of a single piece, which is either a step to a neighbouring square or a sequence of
+/// jumps capturing the pieces jumped over.
+///
+/// Moves are written in standard notation, such as `11-15` for a step and `15x22x29` for a double
+/// jump.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    from: Square,
+    to: Square,
+    /// The squares landed on before the last jump of a multiple jump.
+    via: Vec<Square>,
+    /// The pieces jumped over.
+    captured: u32,
+}
+
+impl Move {
+    /// Every square the piece stands on during the move.
+    fn path(&self) -> impl Iterator<Item = Square> + '_ {
+        [self.from]
+            .into_iter()
+            .chain(self.via.iter().copied())
+            .chain([self.to])
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let separator = if self.captured != 0 { "x" } else { "-" };
+        let squares: Vec<_> = self.path().map(|square| (square + 1).to_string()).collect();
+        write!(f, "{}", squares.join(separator))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CheckersState {
+    /// The pieces of each player, indexed by `Player`, as bitboards of squares.
+    pieces: [u32; 2],
+    kings: u32,
+    /// Black moves first, and is the maximising player.
+    player: Player,
+    /// The number of plies since the last capture or move by a man.
+    quiet_plies: u8,
+}
+
+impl Default for CheckersState {
+    fn default() -> Self {
+        Self {
+            pieces: [0x0000_0fff, 0xfff0_0000],
+            kings: 0,
+            player: Player::Max,
+            quiet_plies: 0,
+        }
+    }
+}
+
+impl CheckersState {
+    fn occupied(&self) -> u32 {
+        self.pieces[0] | self.pieces[1]
+    }
+
+    fn piece_at(&self, square: Square) -> Option<(Player, bool)> {
+        let king = self.kings & bit(square) != 0;
+        [Player::Max, Player::Min]
+            .into_iter()
+            .find(|&player| self.pieces[player as usize] & bit(square) != 0)
+            .map(|player| (player, king))
+    }
+
+    /// The directions a piece can move in.
+    fn directions(player: Player, king: bool) -> &'static [usize] {
+        match (king, player) {
+            (true, _) => &[0, 1, 2, 3],
+            (false, Player::Max) => &[0, 1],
+            (false, Player::Min) => &[2, 3],
+        }
+    }
+
+    fn squares(bitboard: u32) -> impl Iterator<Item = Square> {
+        (0..SQUARES as Square).filter(move |&square| bitboard & bit(square) != 0)
+    }
+
+    /// Adds every jump sequence the piece on `from` can make to `moves`, returning whether there
+    /// were any.
+    fn add_jumps(&self, from: Square, moves: &mut Vec<Move>) -> bool {
+        let king = self.kings & bit(from) != 0;
+        let count = moves.len();
+        self.extend_jumps(from, from, king, 0, &mut Vec::new(), moves);
+        moves.len() > count
+    }
+
+    /// Continues a jump sequence from `from` that has reached `at` through `path`, adding it to
+    /// `moves` once no more jumps can be made.
+    fn extend_jumps(
+        &self,
+        from: Square,
+        at: Square,
+        king: bool,
+        captured: u32,
+        path: &mut Vec<Square>,
+        moves: &mut Vec<Move>,
+    ) {
+        let opponent = self.pieces[self.player.opposite() as usize];
+        // The moving piece has left its square, so it can be landed on again
+        let empty = !self.occupied() | bit(from);
+        let mut extended = false;
+
+        for &dir in Self::directions(self.player, king) {
+            let (Some(over), Some(land)) = (
+                NEIGHBOURS[0][at as usize][dir],
+                NEIGHBOURS[1][at as usize][dir],
+            ) else {
+                continue;
+            };
+            if opponent & !captured & bit(over) == 0 || empty & bit(land) == 0 {
+                continue;
+            }
+
+            extended = true;
+            path.push(land);
+            // A man that is crowned by jumping ends its move there
+            if !king && coordinates(land).0 == crowning_row(self.player) {
+                moves.push(Move {
+                    from,
+                    to: land,
+                    via: path[..path.len() - 1].to_vec(),
+                    captured: captured | bit(over),
+                });
+            } else {
+                self.extend_jumps(from, land, king, captured | bit(over), path, moves);
+            }
+            path.pop();
+        }
+
+        if let (false, Some(&to)) = (extended, path.last()) {
+            moves.push(Move {
+                from,
+                to,
+                via: path[..path.len() - 1].to_vec(),
+                captured,
+            });
+        }
+    }
+
+    fn has_moves(&self) -> bool {
+        let opponent = self.pieces[self.player.opposite() as usize];
+        let empty = !self.occupied();
+
+        Self::squares(self.pieces[self.player as usize]).any(|square| {
+            let king = self.kings & bit(square) != 0;
+            Self::directions(self.player, king).iter().any(|&dir| {
+                let step = NEIGHBOURS[0][square as usize][dir];
+                let jump = NEIGHBOURS[1][square as usize][dir];
+                step.is_some_and(|step| {
+                    empty & bit(step) != 0
+                        || opponent & bit(step) != 0
+                            && jump.is_some_and(|jump| empty & bit(jump) != 0)
+                })
+            })
+        })
+    }
+
+    /// Finds the legal move written as `notation`. Jumps can also be written with only their
+    /// first and last squares, as long as that doesn't match several of them.
+    fn parse_move(&self, notation: &str) -> Option<Move> {
+        let squares = notation
+            .trim()
+            .split(['-', 'x', 'X'])
+            .map(|square| match square.parse::<Square>() {
+                Ok(square @ 1..=32) => Some(square - 1),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut matching = self.actions().into_iter().filter(|move_| {
+            move_.path().eq(squares.iter().copied())
+                || squares.len() == 2 && move_.from == squares[0] && move_.to == squares[1]
+        });
+
+        match (matching.next(), matching.next()) {
+            (Some(move_), None) => Some(move_),
+            _ => None,
+        }
+    }
+}
+
+impl minimax::State<f32, Move> for CheckersState {
+    fn is_terminal(&self) -> bool {
+        self.quiet_plies >= DRAW_PLIES || !self.has_moves()
+    }
+
+    fn evaluation(&self) -> f32 {
+        if self.quiet_plies >= DRAW_PLIES {
+            return 0.0;
+        }
+        if !self.has_moves() {
+            // The player to move has lost
+            return match self.player {
+                Player::Max => f32::NEG_INFINITY,
+                Player::Min => f32::INFINITY,
+            };
+        }
+
+        [Player::Max, Player::Min]
+            .into_iter()
+            .map(|player| {
+                let pieces = self.pieces[player as usize];
+                let men = pieces & !self.kings;
+                let advancement: u32 = Self::squares(men)
+                    .map(|square| coordinates(square).0.abs_diff(7 - crowning_row(player)) as u32)
+                    .sum();
+
+                let value = men.count_ones() as f32 * MAN_VALUE
+                    + (pieces & self.kings).count_ones() as f32 * KING_VALUE
+                    + advancement as f32 * ADVANCEMENT_VALUE;
+
+                match player {
+                    Player::Max => value,
+                    Player::Min => -value,
+                }
+            })
+            .sum()
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let own = self.pieces[self.player as usize];
+
+        // Captures are compulsory, so steps are only allowed if there are none
+        let mut can_jump = false;
+        for square in Self::squares(own) {
+            can_jump |= self.add_jumps(square, actions);
+        }
+        if can_jump {
+            return;
+        }
+
+        let empty = !self.occupied();
+        for from in Self::squares(own) {
+            let king = self.kings & bit(from) != 0;
+            for &dir in Self::directions(self.player, king) {
+                if let Some(to) =
+                    NEIGHBOURS[0][from as usize][dir].filter(|&to| empty & bit(to) != 0)
+                {
+                    actions.push(Move {
+                        from,
+                        to,
+                        via: Vec::new(),
+                        captured: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let mut state = self.clone();
+        let player = self.player as usize;
+        let king = self.kings & bit(action.from) != 0;
+
+        state.pieces[player] = state.pieces[player] & !bit(action.from) | bit(action.to);
+        state.pieces[1 - player] &= !action.captured;
+        state.kings &= !action.captured & !bit(action.from);
+        if king || coordinates(action.to).0 == crowning_row(self.player) {
+            state.kings |= bit(action.to);
+        }
+
+        state.quiet_plies = if king && action.captured == 0 {
+            self.quiet_plies + 1
+        } else {
+            0
+        };
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        action.captured == 0
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        action.captured.count_ones() as f32 * MAN_VALUE
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // A bijective mix of the position, so that similar positions don't share buckets
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let pieces = self.pieces[0] as u64 | (self.pieces[1] as u64) << 32;
+        let kings = self.kings as u64 | (self.player as u64) << 32;
+        Some(mix(pieces) ^ mix(kings).rotate_left(17))
+    }
+}
+
+impl Display for CheckersState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.board(false, 0))
+    }
+}
+
+impl CheckersState {
+    /// The board with Black at the top, or at the bottom if it's `rotated`, and the squares in
+    /// the bitboard `moved` picked out.
+    fn board(&self, rotated: bool, moved: u32) -> Text<'static> {
+        let order = |i: u8| if rotated { 7 - i } else { i };
+        let mut lines = vec![Line::from("┌───┬───┬───┬───┬───┬───┬───┬───┐")];
+        for row in (0..8u8).map(order) {
+            let mut spans = vec![Span::raw("│")];
+            for column in (0..8u8).map(order) {
+                // Only dark squares are played on, and empty ones show their number
+                if (row + column) % 2 == 0 {
+                    spans.push(Span::styled("   ", Style::new().bg(LIGHT_SQUARE)));
+                    spans.push(Span::raw("│"));
+                    continue;
+                }
+
+                let square = row * 4 + column / 2;
+                let dark = if moved & bit(square) != 0 {
+                    Style::new().bg(MOVED_SQUARE)
+                } else {
+                    Style::new().bg(DARK_SQUARE)
+                };
+                spans.push(match self.piece_at(square) {
+                    Some((Player::Max, false)) => Span::styled(" ● ", dark.fg(Color::Black)),
+                    Some((Player::Max, true)) => Span::styled(" ◆ ", dark.fg(Color::Black)),
+                    Some((Player::Min, false)) => Span::styled(" ○ ", dark.fg(Color::White)),
+                    Some((Player::Min, true)) => Span::styled(" ◇ ", dark.fg(Color::White)),
+                    None => Span::styled(format!("{:>2} ", square + 1), dark.fg(Color::Gray)),
+                });
+                spans.push(Span::raw("│"));
+            }
+            lines.push(Line::from(spans));
+            if row != order(7) {
+                lines.push(Line::from("├───┼───┼───┼───┼───┼───┼───┼───┤"));
+            }
+        }
+        lines.push(Line::from("└───┴───┴───┴───┴───┴───┴───┴───┘"));
+        Text::from(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A position from lists of squares in standard notation, with Black to move.
+    fn position(black: &[Square], white: &[Square], kings: &[Square]) -> CheckersState {
+        let bitboard = |squares: &[Square]| squares.iter().map(|&square| bit(square - 1)).sum();
+        CheckersState {
+            pieces: [bitboard(black), bitboard(white)],
+            kings: bitboard(kings),
+            ..Default::default()
+        }
+    }
+
+    fn notation(state: &CheckersState) -> Vec<String> {
+        state.actions().iter().map(Move::to_string).collect()
+    }
+
+    #[test]
+    fn opening_moves() {
+        let state = CheckersState::default();
+        assert_eq!(
+            notation(&state),
+            ["9-13", "9-14", "10-14", "10-15", "11-15", "11-16", "12-16"]
+        );
+        assert!(state.parse_move("11-15").is_some());
+        assert!(state.parse_move("11-16").is_some());
+        assert!(state.parse_move("11-14").is_none());
+        assert!(state.parse_move("33-29").is_none());
+    }
+
+    #[test]
+    fn captures_are_compulsory() {
+        // Black can step with its man on 5, but has to jump with the one on 14
+        let state = position(&[5, 14], &[18], &[]);
+        assert_eq!(notation(&state), ["14x23"]);
+    }
+
+    #[test]
+    fn multiple_jumps() {
+        let state = position(&[1], &[6, 15, 7], &[]);
+        assert_eq!(notation(&state), ["1x10x19"]);
+
+        // The jumps can be written with just the first and last squares
+        let move_ = state.parse_move("1x19").unwrap();
+        let state = state.result(&move_);
+        assert_eq!(state.pieces, [bit(18), bit(6)]);
+    }
+
+    #[test]
+    fn crowning_ends_the_move() {
+        // The man is crowned on 30, and can't go on to jump 25 as a king in the same move
+        let state = position(&[23], &[25, 26], &[]);
+        assert_eq!(notation(&state), ["23x30"]);
+
+        let state = state.result(&state.parse_move("23x30").unwrap());
+        assert_eq!(state.kings, bit(29));
+        assert_eq!(state.pieces, [bit(29), bit(24)]);
+    }
+
+    #[test]
+    fn blocked_player_loses() {
+        // White's only man is blocked against the edge by two black men
+        let state = CheckersState {
+            player: Player::Min,
+            ..position(&[19, 24], &[28], &[])
+        };
+        assert!(state.actions().is_empty());
+        assert!(state.is_terminal());
+        assert_eq!(state.evaluation(), f32::INFINITY);
+    }
+}
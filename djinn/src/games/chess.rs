@@ -1,13 +1,189 @@
-use crate::games::{Game, WinState};
+pub mod polyglot;
+pub mod puzzle;
+pub mod uci;
+
+use crate::games::{
+    grid_cell, random_seed, scaled_advantage, search_move_with, Click, Game, GameError,
+    SearchLimits, WinState,
+};
 use crate::minimax;
-use crate::minimax::Player;
+use crate::minimax::{Engine, EngineBuilder, Evaluator, Player};
 use itertools::Itertools;
-use shakmaty::{san::San, ByColor, ByRole, Color, Move, Outcome, Piece, Position, Role, Square};
+use lazy_static::lazy_static;
+use polyglot::Book;
+use ratatui::style::{Color as TermColor, Style};
+use ratatui::text::{Line, Span, Text};
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::variant::{Antichess, Crazyhouse, KingOfTheHill, ThreeCheck, VariantPosition};
+use shakmaty::zobrist::{Zobrist64, ZobristHash, ZobristValue};
+use shakmaty::{
+    san::{San, SanPlus},
+    Bitboard, ByColor, ByRole, CastlingMode, CastlingSide, Color, EnPassantMode, Move, Outcome,
+    Piece, Position, Role, Square,
+};
+use std::cmp::Reverse;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::error;
+use uci::Client;
+
+/// The bonus in three-check for each check given, since three of them win the game.
+const CHECK_VALUE: f32 = 150.0;
+
+/// The colours of the squares, from the 256-colour palette since the basic colours have no browns.
+const LIGHT_SQUARE: TermColor = TermColor::Indexed(180);
+const DARK_SQUARE: TermColor = TermColor::Indexed(137);
+/// The colours of the squares the last move was played from and to.
+const LIGHT_MOVED_SQUARE: TermColor = TermColor::Indexed(186);
+const DARK_MOVED_SQUARE: TermColor = TermColor::Indexed(143);
+/// The colours of the squares the selected piece can move to.
+const LIGHT_TARGET_SQUARE: TermColor = TermColor::Indexed(151);
+const DARK_TARGET_SQUARE: TermColor = TermColor::Indexed(108);
+/// The colour of the square of a king in check.
+const CHECKED_SQUARE: TermColor = TermColor::Indexed(167);
+
+/// The longest line of moves in an exported game, as recommended by the PGN standard.
+const PGN_LINE_LENGTH: usize = 79;
+
+/// How many plies an external engine searches on the hardest difficulty. Engines search far more
+/// quickly than the built-in one, so this is much deeper.
+const UCI_DEPTH: u32 = 18;
+
+/// How many plies the built-in engine deepens its search to on the hardest difficulty, if it
+/// has the time.
+const SEARCH_DEPTH: u32 = 9;
+
+/// How long the built-in engine aims to think about a move when there's no clock. It never
+/// starts a deeper search after this, and abandons one that takes more than three times as long.
+const THINKING_TIME: Duration = Duration::from_secs(2);
+
+/// The penalty in king of the hill for each step a king is away from the four centre squares,
+/// which it wins by reaching.
+const HILL_DISTANCE_VALUE: f32 = 25.0;
+
+lazy_static! {
+    static ref DEFAULT_EVAL_PARAMS: EvalParams = EvalParams::default();
+}
+
+/// The opening book the computer plays from in standard games, if one has been loaded.
+static BOOK: OnceLock<Book> = OnceLock::new();
+
+/// Makes the computer play the moves of `book` in standard games for as long as it has any.
+pub fn use_book(book: Book) {
+    // Only the first book is kept, since the app loads it once at startup
+    let _ = BOOK.set(book);
+}
+
+/// Whether the squares a piece can move to are picked out once its square has been typed.
+static SHOW_MOVES: AtomicBool = AtomicBool::new(true);
+
+/// Sets whether the squares a selected piece can move to are picked out on the board.
+pub fn show_moves(show: bool) {
+    SHOW_MOVES.store(show, Ordering::Relaxed);
+}
+
+/// The external engine the computer plays with in standard games, if one has been started.
+static ENGINE: OnceLock<Mutex<Client>> = OnceLock::new();
+
+/// Makes the computer ask `client` for its moves in standard games instead of searching itself.
+pub fn use_engine(client: Client) {
+    let _ = ENGINE.set(Mutex::new(client));
+}
+
+/// The pairs of files, counting only the five left empty by the bishops and queen, that the
+/// knights take in each of the ten ways of placing them in a Chess960 back rank.
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The position, the moves played along with the position before each of them, the variant, and
+/// the hashes of the position and the earlier positions that it could still repeat.
+#[derive(Clone, Debug)]
+pub struct Chess(
+    VariantPosition,
+    Vec<(San, VariantPosition)>,
+    Variant,
+    Hashes,
+);
+
+/// The Zobrist hash of a position, linked to those of the earlier positions it could repeat.
+///
+/// The search keeps these for every position it reaches, so the earlier hashes are shared between
+/// the positions following on from each other instead of being copied.
+#[derive(Clone, Debug)]
+struct Hashes {
+    current: u64,
+    earlier: Option<Arc<Hashes>>,
+}
+
+/// The sets of rules chess can be played with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Fischer random chess, where the back rank is shuffled at the start of every game, keeping
+    /// the bishops on opposite colours and the king between the rooks.
+    Chess960,
+    /// Losing chess, where captures are compulsory, the king is an ordinary piece, and the aim is
+    /// to lose every piece or be left without a move.
+    Antichess,
+    /// Captured pieces change sides and go into the capturer's pocket, from where they can be
+    /// dropped back onto any empty square instead of making a move, such as `N@f3`.
+    Crazyhouse,
+    /// Giving check three times wins the game, as well as checkmate.
+    ThreeCheck,
+    /// Bringing the king to one of the four centre squares wins the game, as well as checkmate.
+    KingOfTheHill,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 6] = [
+        Variant::Standard,
+        Variant::Chess960,
+        Variant::Antichess,
+        Variant::Crazyhouse,
+        Variant::ThreeCheck,
+        Variant::KingOfTheHill,
+    ];
+
+    /// The rules of the variant in shakmaty, where Chess960 is standard chess from a different
+    /// starting position.
+    fn rules(self) -> shakmaty::variant::Variant {
+        match self {
+            Variant::Standard | Variant::Chess960 => shakmaty::variant::Variant::Chess,
+            Variant::Antichess => shakmaty::variant::Variant::Antichess,
+            Variant::Crazyhouse => shakmaty::variant::Variant::Crazyhouse,
+            Variant::ThreeCheck => shakmaty::variant::Variant::ThreeCheck,
+            Variant::KingOfTheHill => shakmaty::variant::Variant::KingOfTheHill,
+        }
+    }
+}
 
-#[derive(Debug, Default)]
-pub struct Chess(shakmaty::Chess, Vec<San>);
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "Standard"),
+            Variant::Chess960 => write!(f, "Chess960"),
+            Variant::Antichess => write!(f, "Antichess"),
+            Variant::Crazyhouse => write!(f, "Crazyhouse"),
+            Variant::ThreeCheck => write!(f, "Three-check"),
+            Variant::KingOfTheHill => write!(f, "King of the Hill"),
+        }
+    }
+}
 
 impl Game for Chess {
     fn name(&self) -> String {
@@ -27,47 +203,792 @@ impl Game for Chess {
         self.to_string()
     }
 
+    fn display_for(&self, side: usize) -> String {
+        self.board(Self::side_color(side), None).to_string()
+    }
+
+    fn display_styled(&self, side: usize, typed: &str) -> Text<'static> {
+        let selected = SHOW_MOVES
+            .load(Ordering::Relaxed)
+            .then(|| typed.parse::<Square>().ok())
+            .flatten();
+        self.board(Self::side_color(side), selected)
+    }
+
+    fn click(&self, typed: &str, column: u16, row: u16, side: usize) -> Option<Click> {
+        let bottom = Self::side_color(side);
+        let top = u16::from(self.status(!bottom).is_some());
+        let (file, rank) = grid_cell(column, row, (3, 1 + top), (8, 8), 2)?;
+        let (file, rank) = match bottom {
+            Color::White => (file, 7 - rank),
+            Color::Black => (7 - file, rank),
+        };
+        let square = Square::new((rank * 8 + file) as u32);
+
+        // Clicking a piece and then where it goes makes the move, leaving the piece a pawn
+        // promotes to for the player to choose, and castling can be clicked as the king moving
+        // either two squares or onto the rook
+        let moves: Vec<_> = self
+            .0
+            .legal_moves()
+            .iter()
+            .flat_map(|move_| {
+                [CastlingMode::Standard, CastlingMode::Chess960]
+                    .map(|mode| move_.to_uci(mode).to_string())
+            })
+            .collect();
+        let move_ = format!("{typed}{square}");
+        if moves.contains(&move_) || moves.contains(&format!("{move_}q")) {
+            Some(Click::Play(move_))
+        } else {
+            Some(Click::Select(square.to_string()))
+        }
+    }
+
     fn display_size(&self) -> (u16, u16) {
-        (36, 18)
+        // Crazyhouse pockets and the checks left in three-check go on their own lines above and
+        // below the board
+        match self.2 {
+            Variant::Crazyhouse | Variant::ThreeCheck => (36, 20),
+            _ => (36, 18),
+        }
     }
 
     fn move_history(&self) -> Vec<String> {
-        self.1.iter().map(San::to_string).collect()
+        self.1.iter().map(|(move_, _)| move_.to_string()).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        usize::from(self.0.turn() == Color::Black)
     }
 
     fn win_state(&self) -> Option<WinState> {
-        self.0.outcome().map(|outcome| match outcome {
-            Outcome::Decisive { .. } => WinState::Decisive,
-            Outcome::Draw => WinState::Draw,
-        })
+        match self.0.outcome() {
+            Some(Outcome::Decisive {
+                winner: Color::White,
+            }) => Some(WinState::Decisive(Player::Max)),
+            Some(Outcome::Decisive {
+                winner: Color::Black,
+            }) => Some(WinState::Decisive(Player::Min)),
+            Some(Outcome::Draw) => Some(WinState::Draw),
+            None => self.is_drawn_by_rule().then_some(WinState::Draw),
+        }
     }
 
     fn is_valid_move(&self, move_: &str) -> bool {
-        move_
-            .parse::<San>()
-            .is_ok_and(|s| s.to_move(&self.0).is_ok())
+        self.parse_move(move_).is_some()
     }
 
-    fn play_move(&mut self, move_: &str) {
-        let move_ = move_.parse::<San>().expect("invalid SAN move");
-        self.0 = self
-            .0
-            .clone()
-            .play(&move_.to_move(&self.0).expect("invalid move"))
-            .unwrap();
-        self.1.push(move_);
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = self
+            .parse_move(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        // Moves given in coordinates are kept in SAN like the rest, for the history and PGN
+        let san = San::from_move(&self.0, &move_);
+        let position = self.0.clone().play(&move_).unwrap();
+        self.1.push((san, std::mem::replace(&mut self.0, position)));
+        self.3 = Hashes::of_game(&self.0, &self.1);
+        Ok(())
+    }
+
+    /// Plays from the opening book if there is one, then asks the external engine, and otherwise
+    /// searches, paced to the time left on the clock.
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        if let (Some(book), VariantPosition::Chess(position)) = (BOOK.get(), &self.0) {
+            if let Some(move_) = book.pick(position, random_seed()) {
+                return Ok(San::from_move(&self.0, &move_).to_string());
+            }
+        }
+        if let Some(engine) = ENGINE.get().filter(|_| self.2 == Variant::Standard) {
+            match self.external_move(&mut engine.lock().unwrap(), &limits) {
+                Ok(move_) => return Ok(San::from_move(&self.0, &move_).to_string()),
+                // Fall back to searching, so that a broken engine doesn't stop the game
+                Err(err) => error!("Failed to get a move from the chess engine: {err}"),
+            }
+        }
+
+        let mut engine = Self::engine_builder(limits.depth(SEARCH_DEPTH));
+        match limits.time {
+            Some(time) => engine.time_control(time.remaining, time.increment),
+            None => engine
+                .soft_time_limit(THINKING_TIME)
+                .hard_time_limit(THINKING_TIME * 3),
+        };
+        let engine = engine.build().expect("failed to build chess engine");
+        let move_ = search_move_with(&engine, self, &*DEFAULT_EVAL_PARAMS, &limits)
+            .ok_or(GameError::NoMoves)?;
+        Ok(San::from_move(&self.0, &move_).to_string())
+    }
+
+    fn completions(&self, move_: &str) -> Vec<String> {
+        if self.parse_move(move_).is_some() {
+            return Vec::new();
+        }
+        // SAN also reads moves given in coordinates with a promotion written this way
+        let roles = [
+            Role::Queen,
+            Role::Rook,
+            Role::Bishop,
+            Role::Knight,
+            Role::King,
+        ];
+        roles
+            .into_iter()
+            .map(|role| format!("={}", role.upper_char()))
+            .filter(|promotion| self.parse_move(&format!("{move_}{promotion}")).is_some())
+            .collect()
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0
+            .legal_moves()
+            .iter()
+            .map(|move_| San::from_move(&self.0, move_).to_string())
+            .collect()
+    }
+
+    fn hints(&self, count: usize) -> Vec<String> {
+        Self::engine(4)
+            .multi_pv_with(self, count, &*DEFAULT_EVAL_PARAMS)
+            .into_iter()
+            .map(|line| San::from_move(&self.0, &line.pv[0]).to_string())
+            .collect()
+    }
+
+    fn advantage(&self) -> Option<f32> {
+        let value = Self::engine(2).value_with(self, &*DEFAULT_EVAL_PARAMS);
+        let player = minimax::State::current_player(self);
+        Some(scaled_advantage(value, player, 300.0))
     }
 
-    fn computer_move(&self) -> String {
-        San::from_move(&self.0, &minimax::best_move(self, 3)).to_string()
+    fn sides(&self) -> [String; 2] {
+        ["White", "Black"].map(String::from)
+    }
+
+    fn captures(&self) -> Option<[String; 2]> {
+        let material = |color| {
+            Role::ALL
+                .into_iter()
+                .map(|role| {
+                    let pieces = self.0.board().by_piece(Piece { color, role }).count();
+                    Self::points(role) * pieces as i32
+                })
+                .sum::<i32>()
+        };
+        let balance = material(Color::White) - material(Color::Black);
+
+        Some([Color::White, Color::Black].map(|color| {
+            let captured = self
+                .1
+                .iter()
+                .filter(|(_, position)| position.turn() == color)
+                .filter_map(|(move_, position)| move_.to_move(position).ok()?.capture())
+                .sorted_by_key(|&role| -Self::points(role))
+                .map(|role| Self::get_piece_char(role.of(!color)))
+                .collect::<String>();
+            // Promotions and pockets change the material too, so it's counted from the board
+            let lead = if color == Color::White {
+                balance
+            } else {
+                -balance
+            };
+            if lead > 0 {
+                format!("{captured} +{lead}")
+            } else {
+                captured
+            }
+        }))
     }
 
     fn reset(&mut self) {
-        *self = Self::default();
+        *self = Self::new(self.2);
+    }
+
+    fn variants(&self) -> Vec<String> {
+        Variant::ALL.iter().map(Variant::to_string).collect()
+    }
+
+    fn set_variant(&mut self, index: usize) {
+        *self = Self::new(Variant::ALL[index]);
+    }
+
+    fn pgn(&self, players: [&str; 2], result: Option<WinState>) -> Option<String> {
+        let start = self.1.first().map_or(&self.0, |(_, position)| position);
+        let result = match result.or_else(|| self.win_state()) {
+            Some(WinState::Decisive(Player::Max)) => "1-0",
+            Some(WinState::Decisive(Player::Min)) => "0-1",
+            Some(WinState::Draw) => "1/2-1/2",
+            None => "*",
+        };
+
+        let [white, black] = players;
+        let mut tags = vec![
+            ("Event", "Casual game".to_string()),
+            ("Site", "djinn".to_string()),
+            ("Date", pgn_date(SystemTime::now())),
+            ("Round", "-".to_string()),
+            ("White", white.to_string()),
+            ("Black", black.to_string()),
+            ("Result", result.to_string()),
+        ];
+        if self.2 != Variant::Standard {
+            tags.push(("Variant", self.2.to_string()));
+        }
+        // Only Chess960 starts from somewhere other than the variant's usual position, unless the
+        // game was set up from a FEN
+        let fen = Fen::from_position(start.clone(), EnPassantMode::Legal).to_string();
+        let usual = (self.2 != Variant::Chess960).then(|| Self::new(self.2).0);
+        if usual
+            .is_none_or(|usual| Fen::from_position(usual, EnPassantMode::Legal).to_string() != fen)
+        {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", fen));
+        }
+
+        let mut tokens = Vec::new();
+        for (i, (move_, position)) in self.1.iter().enumerate() {
+            match position.turn() {
+                Color::White => tokens.push(format!("{}.", position.fullmoves())),
+                Color::Black if i == 0 => tokens.push(format!("{}...", position.fullmoves())),
+                Color::Black => {}
+            }
+            let move_ = move_.to_move(position).expect("invalid move in history");
+            tokens.push(SanPlus::from_move(position.clone(), &move_).to_string());
+        }
+        tokens.push(result.to_string());
+
+        let mut pgn = tags
+            .into_iter()
+            .map(|(name, value)| {
+                let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+                format!("[{name} \"{value}\"]\n")
+            })
+            .collect::<String>();
+        pgn.push('\n');
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > PGN_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push('\n');
+        Some(pgn)
+    }
+
+    fn load_pgn(&mut self, pgn: &str) -> Result<(), String> {
+        *self = Self::from_pgn(pgn)?;
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<Option<String>, GameError> {
+        // Chess960 starts from a random position, so the position before the move is restored
+        // instead of replaying the game
+        let Some((move_, position)) = self.1.pop() else {
+            return Ok(None);
+        };
+        self.0 = position;
+        self.3 = Hashes::of_game(&self.0, &self.1);
+        Ok(Some(move_.to_string()))
+    }
+}
+
+fn position_hash(position: &VariantPosition) -> u64 {
+    let hash: Zobrist64 = position.zobrist_hash(EnPassantMode::Legal);
+    hash.0
+}
+
+impl Hashes {
+    /// The hashes of `position` at the end of a game with the positions in `history` before it.
+    /// The earlier positions it could repeat are those since the last capture or pawn move.
+    fn of_game(position: &VariantPosition, history: &[(San, VariantPosition)]) -> Self {
+        let repeatable = &history[history.len().saturating_sub(position.halfmoves() as usize)..];
+        let earlier = repeatable.iter().fold(None, |earlier, (_, position)| {
+            Some(Arc::new(Hashes {
+                current: position_hash(position),
+                earlier,
+            }))
+        });
+        Hashes {
+            current: position_hash(position),
+            earlier,
+        }
+    }
+
+    /// The hashes of `after`, which a move from `before`, the position with these hashes, leads
+    /// to.
+    fn after_move(&self, before: &VariantPosition, after: &VariantPosition) -> Self {
+        Hashes {
+            current: Self::updated_hash(self.current, before, after),
+            earlier: (after.halfmoves() > 0).then(|| Arc::new(self.clone())),
+        }
+    }
+
+    /// Updates `hash`, the hash of `before`, to be the hash of `after` by changing only what
+    /// differs between them, rather than hashing every piece again.
+    fn updated_hash(hash: u64, before: &VariantPosition, after: &VariantPosition) -> u64 {
+        // Pockets, promoted pieces and remaining checks only come up in a few variants, which are
+        // hashed from scratch
+        if after.pockets().is_some() || after.remaining_checks().is_some() {
+            return position_hash(after);
+        }
+
+        let mut hash = Zobrist64(hash);
+        for color in Color::ALL {
+            for role in Role::ALL {
+                let piece = Piece { color, role };
+                let moved = before.board().by_piece(piece) ^ after.board().by_piece(piece);
+                for square in moved {
+                    hash ^= Zobrist64::zobrist_for_piece(square, piece);
+                }
+            }
+        }
+        for color in Color::ALL {
+            for side in CastlingSide::ALL {
+                if before.castles().has(color, side) != after.castles().has(color, side) {
+                    hash ^= Zobrist64::zobrist_for_castling_right(color, side);
+                }
+            }
+        }
+        let en_passant = [before, after].map(|position| position.ep_square(EnPassantMode::Legal));
+        for square in en_passant.into_iter().flatten() {
+            hash ^= Zobrist64::zobrist_for_en_passant_file(square.file());
+        }
+        hash ^= Zobrist64::zobrist_for_white_turn();
+        hash.0
+    }
+
+    /// How many times the earlier positions were the same as this one.
+    fn repetitions(&self) -> usize {
+        std::iter::successors(self.earlier.as_deref(), |hashes| hashes.earlier.as_deref())
+            .filter(|hashes| hashes.current == self.current)
+            .count()
+    }
+}
+
+/// Splits PGN into its tags, as names and values, and the moves of its main line.
+fn pgn_sections(pgn: &str) -> (Vec<(String, String)>, Vec<String>) {
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+    for line in pgn.lines().map(str::trim) {
+        let tag = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+            .and_then(|line| line.split_once(' '));
+        match tag {
+            Some((name, value)) => {
+                let value = value.trim().trim_matches('"');
+                let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+                tags.push((name.to_string(), value));
+            }
+            // Everything after a semicolon is a comment
+            None => {
+                movetext.push_str(line.split(';').next().unwrap_or_default());
+                movetext.push('\n');
+            }
+        }
+    }
+
+    // Comments in braces and variations in brackets are dropped, and variations can be nested
+    let mut main_line = String::new();
+    let (mut in_comment, mut variation_depth) = (false, 0);
+    for char in movetext.chars() {
+        match char {
+            '{' => in_comment = true,
+            '}' => in_comment = false,
+            _ if in_comment => {}
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            _ if variation_depth > 0 => {}
+            _ => main_line.push(char),
+        }
+    }
+
+    let moves = main_line
+        .split_whitespace()
+        .filter(|token| !["1-0", "0-1", "1/2-1/2", "*"].contains(token))
+        .map(|token| match token {
+            "0-0" => "O-O",
+            "0-0-0" => "O-O-O",
+            // Move numbers can be written right before the move, as in `1.e4`
+            _ => token.trim_start_matches(|char: char| char.is_ascii_digit() || char == '.'),
+        })
+        .map(|token| token.trim_end_matches(['!', '?']))
+        .filter(|token| !token.is_empty() && !token.starts_with('$'))
+        .map(str::to_string)
+        .collect();
+    (tags, moves)
+}
+
+/// The date of `time` in the `YYYY.MM.DD` form used by PGN.
+fn pgn_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() / 86_400) as i64;
+
+    // Counting from the 1st of March 0000 puts leap days at the end of each year
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+impl Default for Chess {
+    fn default() -> Self {
+        shakmaty::Chess::default().into()
+    }
+}
+
+impl From<shakmaty::Chess> for Chess {
+    fn from(position: shakmaty::Chess) -> Self {
+        Self::from_position(position.into(), Variant::Standard)
     }
 }
 
 impl Chess {
+    /// A new game of `variant`, with a randomly chosen starting position for Chess960.
+    pub fn new(variant: Variant) -> Self {
+        let position = match variant {
+            Variant::Standard => shakmaty::Chess::default().into(),
+            Variant::Chess960 => Self::chess960_position((random_seed() % 960) as u32).into(),
+            Variant::Antichess => Antichess::default().into(),
+            Variant::Crazyhouse => Crazyhouse::default().into(),
+            Variant::ThreeCheck => ThreeCheck::default().into(),
+            Variant::KingOfTheHill => KingOfTheHill::default().into(),
+        };
+        Self::from_position(position, variant)
+    }
+
+    /// A game of `variant` starting from `position`, with no moves played yet.
+    fn from_position(position: VariantPosition, variant: Variant) -> Self {
+        let hashes = Hashes::of_game(&position, &[]);
+        Self(position, Vec::new(), variant, hashes)
+    }
+
+    /// A game read from Portable Game Notation, starting from its `FEN` tag if it has one and
+    /// with every move of its main line played. Comments, variations and annotations are skipped.
+    pub fn from_pgn(pgn: &str) -> Result<Self, String> {
+        let (tags, movetext) = pgn_sections(pgn);
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let variant = match tag("Variant") {
+            None => Variant::Standard,
+            Some(name) => *Variant::ALL
+                .iter()
+                .find(|variant| variant.to_string().eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("unsupported variant {name}"))?,
+        };
+        let mut chess = match tag("FEN") {
+            Some(fen) => {
+                let mode = match variant {
+                    Variant::Chess960 => CastlingMode::Chess960,
+                    _ => CastlingMode::Standard,
+                };
+                let setup = fen
+                    .parse::<Fen>()
+                    .map_err(|error| format!("invalid FEN: {error}"))?
+                    .into_setup();
+                let position = VariantPosition::from_setup(variant.rules(), setup, mode)
+                    .map_err(|error| format!("invalid position: {error}"))?;
+                Self::from_position(position, variant)
+            }
+            None if variant == Variant::Chess960 => {
+                return Err("Chess960 games need a FEN tag".to_string())
+            }
+            None => Self::new(variant),
+        };
+
+        for token in movetext {
+            let move_ = token
+                .parse::<SanPlus>()
+                .map_err(|_| format!("invalid move {token}"))?
+                .san
+                .to_string();
+            chess
+                .play_move(&move_)
+                .map_err(|_| format!("illegal move {token}"))?;
+        }
+        Ok(chess)
+    }
+
+    /// Whether the game is drawn by the fifty-move rule, or by the position having been reached
+    /// three times.
+    fn is_drawn_by_rule(&self) -> bool {
+        if self.0.halfmoves() >= 100 {
+            return true;
+        }
+        self.3.repetitions() >= 2
+    }
+
+    /// The Chess960 starting position with the given number from 0 to 959, using the standard
+    /// numbering where 518 is the usual starting position.
+    pub fn chess960_position(number: u32) -> shakmaty::Chess {
+        let back_rank: String = Self::chess960_back_rank(number)
+            .into_iter()
+            .map(Role::char)
+            .collect();
+        let fen = format!(
+            "{back_rank}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+            back_rank.to_ascii_uppercase()
+        );
+
+        // Castling has to follow the Chess960 rules, which allow for the king and rooks starting
+        // on any file
+        fen.parse::<Fen>()
+            .expect("invalid chess960 fen")
+            .into_position(CastlingMode::Chess960)
+            .expect("invalid chess960 position")
+    }
+
+    /// The pieces on the back rank of a Chess960 starting position, from the a-file to the h-file.
+    fn chess960_back_rank(number: u32) -> [Role; 8] {
+        assert!(number < 960, "{number} is not a chess960 position");
+
+        // One bishop goes on each of the light and the dark squares
+        let number = number as usize;
+        let mut back_rank = [None; 8];
+        back_rank[number % 4 * 2 + 1] = Some(Role::Bishop);
+        back_rank[number / 4 % 4 * 2] = Some(Role::Bishop);
+
+        // The rest go on whichever files are still empty
+        let mut place = |role: Role, index: usize| {
+            let file = (0..8)
+                .filter(|&file| back_rank[file].is_none())
+                .nth(index)
+                .expect("no empty file left");
+            back_rank[file] = Some(role);
+        };
+
+        place(Role::Queen, number / 16 % 6);
+
+        // The second knight goes first, so that placing it doesn't move the first one's file
+        let (first, second) = CHESS960_KNIGHTS[number / 96];
+        place(Role::Knight, second);
+        place(Role::Knight, first);
+
+        // The last three files are left for the king and the rooks on either side of it
+        place(Role::Rook, 0);
+        place(Role::King, 0);
+        place(Role::Rook, 0);
+
+        back_rank.map(|role| role.expect("every file is filled"))
+    }
+
+    /// The engine used to find computer moves, searching to `depth` plies.
+    /// Reads a legal move written in SAN, such as `Nf3`, or in the coordinates of its squares,
+    /// such as `g1f3` or `e7e8q`.
+    fn parse_move(&self, move_: &str) -> Option<Move> {
+        let san = move_
+            .parse::<San>()
+            .ok()
+            .and_then(|san| san.to_move(&self.0).ok());
+        san.or_else(|| {
+            let uci = move_.to_ascii_lowercase().parse::<UciMove>().ok()?;
+            uci.to_move(&self.0).ok()
+        })
+    }
+
+    /// Asks an external engine for the move to play within `limits`, including the time left on
+    /// its clock.
+    fn external_move(&self, client: &mut Client, limits: &SearchLimits) -> Result<Move, String> {
+        let start = self.1.first().map_or(&self.0, |(_, position)| position);
+        let fen = Fen::from_position(start.clone(), EnPassantMode::Legal).to_string();
+        let moves = self
+            .1
+            .iter()
+            .map(|(move_, position)| {
+                let move_ = move_.to_move(position).expect("invalid move in history");
+                move_.to_uci(CastlingMode::Standard).to_string()
+            })
+            .collect::<Vec<_>>();
+
+        let mut go = format!("depth {}", limits.depth(UCI_DEPTH));
+        if let Some(time) = limits.time {
+            let side = match self.0.turn() {
+                Color::White => 'w',
+                Color::Black => 'b',
+            };
+            go += &format!(
+                " {side}time {} {side}inc {}",
+                time.remaining.as_millis(),
+                time.increment.as_millis()
+            );
+        }
+        let move_ = client
+            .best_move(&fen, &moves, &go)
+            .map_err(|err| err.to_string())?;
+        move_
+            .parse::<UciMove>()
+            .ok()
+            .and_then(|uci| uci.to_move(&self.0).ok())
+            .ok_or_else(|| format!("the engine played an illegal move, {move_}"))
+    }
+
+    pub fn engine(depth: u32) -> Engine<f32> {
+        Self::engine_builder(depth)
+            .build()
+            .expect("failed to build chess engine")
+    }
+
+    fn engine_builder(depth: u32) -> EngineBuilder<f32> {
+        let mut builder = EngineBuilder::default();
+        builder
+            .depth(depth)
+            .futility_margins([250.0])
+            .quiescence_depth(8)
+            .transposition_table_size(1 << 20);
+        builder
+    }
+
+    /// The pieces `color` has in hand to drop in crazyhouse, such as `♘ ♙×2`, or `None` in
+    /// variants without pockets.
+    fn pocket(&self, color: Color) -> Option<String> {
+        let pocket = self.0.pockets()?.get(color);
+        let pieces = Role::ALL
+            .into_iter()
+            .filter(|&role| *pocket.get(role) > 0)
+            .map(|role| {
+                let piece = Self::get_piece_char(Piece { color, role });
+                match *pocket.get(role) {
+                    1 => piece.to_string(),
+                    count => format!("{piece}×{count}"),
+                }
+            });
+        Some(pieces.format(" ").to_string())
+    }
+
+    /// How many more checks `color` has to give to win in three-check, such as `♔ 2 checks left`,
+    /// or `None` in other variants.
+    fn checks_left(&self, color: Color) -> Option<String> {
+        let checks = u32::from(*self.0.remaining_checks()?.get(color));
+        let king = Self::get_piece_char(Piece {
+            color,
+            role: Role::King,
+        });
+        let plural = if checks == 1 { "" } else { "s" };
+        Some(format!("{king} {checks} check{plural} left"))
+    }
+
+    /// The line shown next to `color`'s side of the board, for variants that keep track of more
+    /// than the pieces on it.
+    fn status(&self, color: Color) -> Option<String> {
+        self.pocket(color).or_else(|| self.checks_left(color))
+    }
+
+    /// How many steps `square` is from the nearest of the four centre squares.
+    /// The conventional value of a piece in pawns, for showing the material balance.
+    fn points(role: Role) -> i32 {
+        match role {
+            Role::Pawn => 1,
+            Role::Knight | Role::Bishop => 3,
+            Role::Rook => 5,
+            Role::Queen => 9,
+            Role::King => 0,
+        }
+    }
+
+    /// The colour of the side at `side` in [`Game::sides`].
+    fn side_color(side: usize) -> Color {
+        if side == 0 {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
+    /// The board with `bottom`'s pieces starting at the bottom, and the moves of the piece on the
+    /// `selected` square picked out.
+    fn board(&self, bottom: Color, selected: Option<Square>) -> Text<'static> {
+        // Seen from Black's side, both the ranks and the files are reversed
+        let (ranks, files): (Vec<u32>, Vec<u32>) = match bottom {
+            Color::White => ((0..8).rev().collect(), (0..8).collect()),
+            Color::Black => ((0..8).collect(), (0..8).rev().collect()),
+        };
+
+        let last_move = self.1.last().and_then(|(move_, position)| {
+            let move_ = move_.to_move(position).ok()?;
+            Some([move_.from(), Some(move_.to())])
+        });
+        let moved = |square| last_move.is_some_and(|squares| squares.contains(&Some(square)));
+        let targets: Bitboard = self
+            .0
+            .legal_moves()
+            .iter()
+            .filter(|move_| selected.is_some() && move_.from() == selected)
+            .map(Move::to)
+            .collect();
+        let checked = self
+            .0
+            .is_check()
+            .then(|| self.0.board().king_of(self.0.turn()))
+            .flatten();
+
+        let mut lines = Vec::new();
+        if let Some(status) = self.status(!bottom) {
+            lines.push(Line::from(format!("  {status}")));
+        }
+        lines.push(Line::from("  ┌───┬───┬───┬───┬───┬───┬───┬───┐"));
+        for (i, &rank) in ranks.iter().enumerate() {
+            let mut spans = vec![Span::raw(format!("{} │", rank + 1))];
+            for &file in &files {
+                let square = Square::new(rank * 8 + file);
+                let piece = self
+                    .0
+                    .board()
+                    .piece_at(square)
+                    .map_or(' ', Self::get_piece_char);
+                let (light, dark) = if checked == Some(square) {
+                    (CHECKED_SQUARE, CHECKED_SQUARE)
+                } else if targets.contains(square) {
+                    (LIGHT_TARGET_SQUARE, DARK_TARGET_SQUARE)
+                } else if moved(square) {
+                    (LIGHT_MOVED_SQUARE, DARK_MOVED_SQUARE)
+                } else {
+                    (LIGHT_SQUARE, DARK_SQUARE)
+                };
+                let background = if square.is_light() { light } else { dark };
+                let style = Style::new().fg(TermColor::Black).bg(background);
+                spans.push(Span::styled(format!(" {piece} "), style));
+                spans.push(Span::raw("│"));
+            }
+            lines.push(Line::from(spans));
+            if i != 7 {
+                lines.push(Line::from("  ├───┼───┼───┼───┼───┼───┼───┼───┤"));
+            }
+        }
+        lines.push(Line::from("  └───┴───┴───┴───┴───┴───┴───┴───┘"));
+        let labels = files
+            .iter()
+            .map(|&file| char::from(b'a' + file as u8))
+            .join("   ");
+        lines.push(Line::from(format!("    {labels}")));
+        if let Some(status) = self.status(bottom) {
+            lines.push(Line::from(format!("  {status}")));
+        }
+        Text::from(lines)
+    }
+
+    fn hill_distance(square: Square) -> u32 {
+        let distance = |coord: u32| coord.saturating_sub(4).max(3u32.saturating_sub(coord));
+        distance(square.file() as u32).max(distance(square.rank() as u32))
+    }
+
     fn get_piece_char(piece: Piece) -> char {
         match piece {
             Piece {
@@ -183,49 +1104,12 @@ impl Chess {
         22, 23, 8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7,
     ];
 
-    // Evaluate a heuristic value for non-terminal positions
-    fn heuristic_value(position: &shakmaty::Chess) -> f32 {
-        let color_diff = |color: ByColor<f32>| color.white - color.black;
-
-        let material_count = position.board().material();
-        let piece_values = [100, 320, 330, 550, 900];
-        let count = |material: ByRole<u8>| {
-            material
-                .into_iter()
-                .map(u16::from)
-                .zip(piece_values)
-                .map(|(count, score)| count * score)
-                .sum::<u16>() as f32
-        };
-
-        let material = color_diff(material_count.map(count));
-
-        let tables = Self::piece_square_tables();
-
-        let (role_bitboards, color_bitboards) = position.board().clone().into_bitboards();
-
-        let pst = color_diff(ByColor::new_with(|color| {
-            let bitboards = role_bitboards.map(|board| board & *color_bitboards.get(color));
-            tables
-                .zip(bitboards)
-                .map(|(table, bitboard)| {
-                    bitboard
-                        .into_iter()
-                        .map(|square| {
-                            f32::from(
-                                table[match color {
-                                    Color::White => Self::FLIP[square as usize],
-                                    Color::Black => square as usize,
-                                }],
-                            )
-                        })
-                        .sum::<f32>()
-                })
-                .into_iter()
-                .sum()
-        }));
-
-        material + pst
+    // Index into a piece-square table for a piece of the given color
+    fn table_index(color: Color, square: Square) -> usize {
+        match color {
+            Color::White => Self::FLIP[square as usize],
+            Color::Black => square as usize,
+        }
     }
 
     const fn piece_square_tables() -> ByRole<[i8; 64]> {
@@ -273,36 +1157,159 @@ impl Chess {
     }
 }
 
-impl Display for Chess {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(f, "  ┌───┬───┬───┬───┬───┬───┬───┬───┐")?;
-        for rank in (0..8).rev() {
-            write!(f, "{} │", rank + 1)?;
-            for file in 0..8 {
-                write!(
-                    f,
-                    " {} │",
-                    self.0
-                        .board()
-                        .piece_at(Square::new(rank * 8 + file))
-                        .map(Self::get_piece_char)
-                        .unwrap_or(' ')
-                )?;
+/// The weights used by the chess heuristic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    /// Material values for every role except the king, in the order pawn, knight, bishop, rook,
+    /// queen.
+    pub piece_values: [f32; 5],
+    /// Piece-square tables from White's perspective, with a8 as the first square. Black's tables
+    /// are mirrored vertically.
+    pub piece_square_tables: ByRole<[f32; 64]>,
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self {
+            piece_values: [100.0, 320.0, 330.0, 550.0, 900.0],
+            piece_square_tables: Chess::piece_square_tables().map(|table| table.map(f32::from)),
+        }
+    }
+}
+
+impl EvalParams {
+    /// The number of parameters in the flattened representation used for tuning.
+    pub const LEN: usize = 5 + 6 * 64;
+
+    /// Flattens the parameters into the layout described by [`EvalParams::features`].
+    pub fn to_vec(&self) -> Vec<f64> {
+        self.piece_values
+            .into_iter()
+            .chain(self.piece_square_tables.into_iter().flatten())
+            .map(f64::from)
+            .collect()
+    }
+
+    pub fn from_slice(params: &[f64]) -> Self {
+        assert_eq!(params.len(), Self::LEN, "wrong number of parameters");
+
+        let param = |i: usize| params[i] as f32;
+        Self {
+            piece_values: std::array::from_fn(param),
+            piece_square_tables: ByRole::new_with(|role| {
+                let offset = 5 + (role as usize - 1) * 64;
+                std::array::from_fn(|square| param(offset + square))
+            }),
+        }
+    }
+
+    /// The material value of `role`, treating the king as more valuable than anything it could
+    /// capture.
+    pub fn role_value(&self, role: Role) -> f32 {
+        match role {
+            Role::King => 20000.0,
+            role => self.piece_values[role as usize - 1],
+        }
+    }
+
+    /// The difference between White's and Black's material, leaving out the kings. Pieces in a
+    /// crazyhouse pocket count the same as those on the board.
+    fn material(&self, position: &impl Position) -> f32 {
+        let count = |material: ByRole<u8>| {
+            material
+                .into_iter()
+                .zip(self.piece_values)
+                .map(|(count, value)| f32::from(count) * value)
+                .sum::<f32>()
+        };
+
+        let board = position.board().material().map(count);
+        let pockets = position
+            .pockets()
+            .map_or_else(ByColor::default, |pockets| pockets.map(count));
+        board.white + pockets.white - board.black - pockets.black
+    }
+
+    pub fn evaluate_position(&self, position: &impl Position) -> f32 {
+        let color_diff = |color: ByColor<f32>| color.white - color.black;
+
+        let material = self.material(position);
+
+        let (role_bitboards, color_bitboards) = position.board().clone().into_bitboards();
+
+        let pst = color_diff(ByColor::new_with(|color| {
+            let bitboards = role_bitboards.map(|board| board & *color_bitboards.get(color));
+            self.piece_square_tables
+                .zip(bitboards)
+                .map(|(table, bitboard)| {
+                    bitboard
+                        .into_iter()
+                        .map(|square| table[Chess::table_index(color, square)])
+                        .sum::<f32>()
+                })
+                .into_iter()
+                .sum()
+        }));
+
+        material + pst
+    }
+
+    /// The evaluation of a position as a sparse linear combination of the flattened parameters,
+    /// given as `(index, coefficient)` pairs.
+    pub fn features(position: &shakmaty::Chess) -> Vec<(usize, f64)> {
+        position
+            .board()
+            .clone()
+            .into_iter()
+            .flat_map(|(square, piece)| {
+                let sign = piece.color.fold_wb(1.0, -1.0);
+                let role_index = piece.role as usize - 1;
+                let pst_index = 5 + role_index * 64 + Chess::table_index(piece.color, square);
+
+                let material = (piece.role != Role::King).then_some((role_index, sign));
+                material.into_iter().chain([(pst_index, sign)])
+            })
+            .collect()
+    }
+}
+
+impl Evaluator<Chess, f32> for EvalParams {
+    fn evaluate(&self, state: &Chess) -> f32 {
+        match state.2 {
+            Variant::Standard | Variant::Chess960 | Variant::Crazyhouse => {
+                self.evaluate_position(&state.0)
+            }
+            // Losing material is the aim, and with captures forced, the piece-square tables say
+            // little about who is doing better
+            Variant::Antichess => -self.material(&state.0),
+            Variant::ThreeCheck => {
+                let checks = state.0.remaining_checks().map_or(0.0, |checks| {
+                    u32::from(checks.black) as f32 - u32::from(checks.white) as f32
+                });
+                self.evaluate_position(&state.0) + checks * CHECK_VALUE
             }
-            if rank != 0 {
-                writeln!(f, "\n  ├───┼───┼───┼───┼───┼───┼───┼───┤")?;
+            Variant::KingOfTheHill => {
+                let board = state.0.board();
+                let distance = |color: Color| {
+                    let king = board.king_of(color).expect("no king on the board");
+                    Chess::hill_distance(king) as f32
+                };
+                let kings = distance(Color::Black) - distance(Color::White);
+                self.evaluate_position(&state.0) + kings * HILL_DISTANCE_VALUE
             }
         }
-        write!(
-            f,
-            "\n  └───┴───┴───┴───┴───┴───┴───┴───┘\n    a   b   c   d   e   f   g   h"
-        )
+    }
+}
+
+impl Display for Chess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.board(Color::White, None))
     }
 }
 
 impl minimax::State<f32, Move> for Chess {
     fn is_terminal(&self) -> bool {
-        self.0.outcome().is_some()
+        self.0.outcome().is_some() || self.is_drawn_by_rule()
     }
 
     fn evaluation(&self) -> f32 {
@@ -314,7 +1321,8 @@ impl minimax::State<f32, Move> for Chess {
                 winner: Color::Black,
             }) => f32::NEG_INFINITY,
             Some(Outcome::Draw) => 0.0,
-            None => Self::heuristic_value(&self.0),
+            None if self.is_drawn_by_rule() => 0.0,
+            None => DEFAULT_EVAL_PARAMS.evaluate(self),
         }
     }
 
@@ -329,11 +1337,650 @@ impl minimax::State<f32, Move> for Chess {
         self.0.legal_moves().into_iter().collect_vec()
     }
 
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let start = actions.len();
+        actions.extend(self.0.legal_moves());
+        // The search orders captures by their exchange, keeping this order between those that
+        // are as good as each other, so the most valuable victims are tried first, taken by the
+        // least valuable attackers
+        actions[start..].sort_by_key(|action| match action.capture() {
+            Some(victim) => (Reverse(victim as u8), action.role() as u8),
+            None => (Reverse(0), 0),
+        });
+    }
+
     fn result(&self, action: &Move) -> Self {
-        let mut history = self.1.clone();
-        history.push(San::from_move(&self.0, action));
+        // Only the search uses the resulting states, so the move history isn't kept, only the
+        // hashes of the positions that could be repeated
         let position = self.0.clone().play(action).expect("expected valid move");
+        let hashes = self.3.after_move(&self.0, &position);
+        Chess(position, Vec::new(), self.2, hashes)
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        !action.is_capture() && !action.is_promotion()
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        // Winning an exchange is a bad thing in antichess, so there's nothing to gain from
+        // ordering or skipping captures by it
+        let (Some(from), false, false) = (
+            action.from(),
+            action.is_castle(),
+            self.2 == Variant::Antichess,
+        ) else {
+            return 0.0;
+        };
+
+        let board = self.0.board();
+        let to = action.to();
+        let value = |role: Role| DEFAULT_EVAL_PARAMS.role_value(role);
+
+        // The material gained by each capture in the exchange, assuming that every capture is made
+        let mut gains = Vec::with_capacity(32);
+        gains.push(action.capture().map_or(0.0, value));
+
+        let mut occupied = board.occupied().without(from);
+        if action.is_en_passant() {
+            occupied.discard(Square::from_coords(to.file(), from.rank()));
+        }
+
+        // The value of the piece standing on the target square, which the next capture would win
+        let mut target = value(action.promotion().unwrap_or(action.role()));
+        let mut color = self.0.turn().other();
+
+        loop {
+            let attackers = board.attacks_to(to, color, occupied) & occupied;
+            let least_valuable = Role::ALL.into_iter().find_map(|role| {
+                (attackers & board.by_role(role))
+                    .first()
+                    .map(|square| (square, role))
+            });
+            let Some((square, role)) = least_valuable else {
+                break;
+            };
+
+            let previous = gains.last().copied().unwrap_or_default();
+            gains.push(target - previous);
+
+            occupied.discard(square);
+            target = value(role);
+            color = color.other();
+        }
+
+        // Work backwards, as either side can choose to stop capturing
+        while gains.len() > 1 {
+            let gain = gains.pop().unwrap_or_default();
+            if let Some(previous) = gains.last_mut() {
+                *previous = -f32::max(-*previous, gain);
+            }
+        }
+
+        gains[0]
+    }
+
+    fn is_tactical(&self) -> bool {
+        self.0.is_check()
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        Some(self.3.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::Difficulty;
+    use crate::minimax::State;
+    use std::collections::HashSet;
+
+    #[test]
+    fn features_match_evaluation() {
+        let params = EvalParams::default();
+        let flattened = params.to_vec();
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            "8/5k2/8/3Q4/8/8/2K5/8 b - - 0 1",
+        ] {
+            let position: shakmaty::Chess = fen
+                .parse::<Fen>()
+                .unwrap()
+                .into_position(CastlingMode::Standard)
+                .unwrap();
+
+            let from_features: f64 = EvalParams::features(&position)
+                .into_iter()
+                .map(|(index, coefficient)| flattened[index] * coefficient)
+                .sum();
+
+            assert_eq!(from_features as f32, params.evaluate_position(&position));
+        }
+    }
+
+    fn position(fen: &str) -> Chess {
+        let position: shakmaty::Chess = fen
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        Chess::from(position)
+    }
+
+    fn antichess(fen: &str) -> Chess {
+        let position: Antichess = fen
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        Chess::from_position(position.into(), Variant::Antichess)
+    }
+
+    fn san_move(chess: &Chess, san: &str) -> Move {
+        san.parse::<San>().unwrap().to_move(&chess.0).unwrap()
+    }
+
+    #[test]
+    fn captures_are_ordered_by_victim_then_attacker() {
+        let chess = position("k7/7p/8/3r4/2P1Q3/8/8/4K3 w - - 0 1");
+        let captures = chess
+            .actions()
+            .into_iter()
+            .filter(Move::is_capture)
+            .map(|move_| San::from_move(&chess.0, &move_).to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(captures, ["cxd5", "Qxd5", "Qxh7"]);
+    }
+
+    #[test]
+    fn see_exchanges() {
+        // The pawn on e5 is defended by the pawn on d6, so the queen loses itself for a pawn
+        let chess = position("4k3/8/3p4/4p3/8/8/8/4Q1K1 w - - 0 1");
+        assert_eq!(chess.see(&san_move(&chess, "Qxe5+")), 100.0 - 900.0);
+
+        // An undefended knight is won outright
+        let chess = position("4k3/8/8/3n4/4P3/8/8/6K1 w - - 0 1");
+        assert_eq!(chess.see(&san_move(&chess, "exd5")), 320.0);
+
+        // Rook takes a pawn defended by a rook, but the second rook behind it recaptures
+        let chess = position("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1");
+        assert_eq!(chess.see(&san_move(&chess, "Rxd5")), 100.0);
+    }
+
+    #[test]
+    fn params_round_trip() {
+        let params = EvalParams::default();
+        assert_eq!(EvalParams::from_slice(&params.to_vec()), params);
+    }
+
+    #[test]
+    fn chess960_positions() {
+        assert_eq!(
+            Chess::chess960_position(518).board(),
+            shakmaty::Chess::default().board()
+        );
+
+        let back_ranks: HashSet<_> = (0..960).map(Chess::chess960_back_rank).collect();
+        assert_eq!(back_ranks.len(), 960);
+        for back_rank in back_ranks {
+            let files = |role: Role| (0..8).filter(move |&file| back_rank[file] == role);
+            let bishops: Vec<_> = files(Role::Bishop).collect();
+            let rooks: Vec<_> = files(Role::Rook).collect();
+            let king = files(Role::King).next().unwrap();
+            assert_eq!(bishops.len(), 2);
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+            assert!(rooks[0] < king && king < rooks[1]);
+        }
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // The king starts on b1, so castling queenside moves it to the right
+        let fen = "4k3/8/8/8/8/8/8/RK5R w KQ - 0 1";
+        let position: shakmaty::Chess = fen
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Chess960)
+            .unwrap();
+
+        let mut chess = Chess::from_position(position.clone().into(), Variant::Chess960);
+        assert!(chess.is_valid_move("O-O-O"));
+        chess.play_move("O-O-O").unwrap();
+        let board = chess.0.board();
+        assert_eq!(board.role_at(Square::C1), Some(Role::King));
+        assert_eq!(board.role_at(Square::D1), Some(Role::Rook));
+        assert_eq!(board.role_at(Square::A1), None);
+
+        let mut chess = Chess::from_position(position.into(), Variant::Chess960);
+        chess.play_move("O-O").unwrap();
+        let board = chess.0.board();
+        assert_eq!(board.role_at(Square::G1), Some(Role::King));
+        assert_eq!(board.role_at(Square::F1), Some(Role::Rook));
+        assert_eq!(chess.move_history(), ["O-O"]);
+    }
+
+    #[test]
+    fn reset_keeps_the_variant() {
+        let mut chess = Chess::default();
+        chess.set_variant(1);
+        chess.play_move("e4").unwrap();
+        chess.reset();
+        assert_eq!(chess.2, Variant::Chess960);
+        assert!(chess.move_history().is_empty());
+        assert_eq!(chess.variants().len(), Variant::ALL.len());
+    }
+
+    #[test]
+    fn hashes_are_updated_by_each_move() {
+        let mut en_passant = Chess::default();
+        for move_ in ["e4", "Nf6", "e5", "d5"] {
+            en_passant.play_move(move_).unwrap();
+        }
+        // Castling, taking a rook that could castle, and promoting
+        let castling = position("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        for chess in [en_passant, castling] {
+            for move_ in chess.actions() {
+                let result = chess.result(&move_);
+                assert_eq!(result.3.current, position_hash(&result.0), "{move_}");
+            }
+        }
+    }
+
+    #[test]
+    fn threefold_repetition_is_a_draw() {
+        let mut chess = Chess::default();
+        for move_ in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"] {
+            chess.play_move(move_).unwrap();
+        }
+        assert!(!chess.is_terminal());
+        // The search sees the repetition too, without the move history
+        let repeated = chess.result(&san_move(&chess, "Ng8"));
+        assert!(repeated.is_terminal());
+        assert_eq!(repeated.evaluation(), 0.0);
+
+        chess.play_move("Ng8").unwrap();
+        assert_eq!(chess.win_state(), Some(WinState::Draw));
+        chess.undo().unwrap();
+        assert_eq!(chess.win_state(), None);
+    }
+
+    #[test]
+    fn fifty_moves_without_progress_is_a_draw() {
+        let position: shakmaty::Chess = "4k3/8/8/8/8/8/8/4K2R w K - 99 80"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let mut chess = Chess::from(position);
+        assert!(!chess.is_terminal());
+        chess.play_move("Kd1").unwrap();
+        assert!(chess.is_terminal());
+        assert_eq!(chess.win_state(), Some(WinState::Draw));
+    }
+
+    #[test]
+    fn hints_are_distinct_legal_moves() {
+        let mut chess = Chess::default();
+        for move_ in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6"] {
+            chess.play_move(move_).unwrap();
+        }
+        let hints = chess.hints(3);
+        assert_eq!(hints[0], "Qxf7");
+        assert_eq!(hints.len(), 3);
+        assert!(hints.iter().all_unique());
+        assert!(hints.iter().all(|hint| chess.is_valid_move(hint)));
+    }
+
+    #[test]
+    fn advantage_is_for_white() {
+        let advantage = Chess::default().advantage().unwrap();
+        assert!(advantage.abs() < 0.2);
+
+        // White is a queen up whoever is to move
+        for fen in [
+            "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3QK3 b - - 0 1",
+        ] {
+            assert!(position(fen).advantage().unwrap() > 0.9);
+        }
+    }
+
+    #[test]
+    fn pgn_dates() {
+        assert_eq!(pgn_date(UNIX_EPOCH), "1970.01.01");
+        let leap_day = UNIX_EPOCH + std::time::Duration::from_secs(951_782_400);
+        assert_eq!(pgn_date(leap_day), "2000.02.29");
+    }
+
+    #[test]
+    fn exports_pgn() {
+        let mut chess = Chess::default();
+        for move_ in ["f3", "e5", "g4", "Qh4"] {
+            chess.play_move(move_).unwrap();
+        }
+        let pgn = chess.pgn(["Player", "Computer"], None).unwrap();
+        assert!(pgn.starts_with("[Event \"Casual game\"]\n"));
+        assert!(pgn.contains("[White \"Player\"]\n[Black \"Computer\"]\n[Result \"0-1\"]\n"));
+        assert!(!pgn.contains("FEN"));
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+
+        let mut chess = Chess::new(Variant::Chess960);
+        chess
+            .play_move(&chess.computer_move(Difficulty::Easy.into()).unwrap())
+            .unwrap();
+        let pgn = chess.pgn(["Player", "Computer"], None).unwrap();
+        assert!(pgn.contains("[Variant \"Chess960\"]\n[SetUp \"1\"]\n[FEN "));
+        assert!(pgn.ends_with(" *\n"));
+
+        // Games that end early, such as by resigning, have the result they were given
+        let pgn = chess
+            .pgn(
+                ["Player", "Computer"],
+                Some(WinState::Decisive(Player::Min)),
+            )
+            .unwrap();
+        assert!(pgn.contains("[Result \"0-1\"]\n"));
+        assert!(pgn.ends_with(" 0-1\n"));
+    }
+
+    #[test]
+    fn imports_pgn() {
+        let pgn = r#"[Event "Casual game"]
+[White "Player"]
+[Black "Computer"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2.Nf3 Nc6 (2... d6 3. d4 (3. Bc4)) 3. Bb5 a6?! $6
+4. 0-0 ; the Ruy Lopez
+Nf6 1-0"#;
+        let chess = Chess::from_pgn(pgn).unwrap();
+        assert_eq!(
+            chess.move_history(),
+            ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "O-O", "Nf6"]
+        );
+        assert_eq!(chess.2, Variant::Standard);
 
-        Chess(position, history)
+        // Exported games can be read back in, including their starting position
+        let mut chess = Chess::new(Variant::Chess960);
+        chess
+            .play_move(&chess.computer_move(Difficulty::Easy.into()).unwrap())
+            .unwrap();
+        let pgn = chess.pgn(["Player", "Computer"], None).unwrap();
+        let mut imported = Chess::default();
+        imported.load_pgn(&pgn).unwrap();
+        assert_eq!(imported.2, Variant::Chess960);
+        assert_eq!(imported.move_history(), chess.move_history());
+        assert_eq!(imported.0.board(), chess.0.board());
+
+        assert_eq!(
+            Chess::from_pgn("1. e4 e5 2. Ke3").unwrap_err(),
+            "illegal move Ke3"
+        );
+        assert!(Chess::from_pgn("[Variant \"Atomic\"]\n\n1. e4").is_err());
+    }
+
+    #[test]
+    fn undo_restores_the_position() {
+        let mut chess = Chess::new(Variant::Chess960);
+        let start = chess.0.board().clone();
+        let move_ = chess.computer_move(Difficulty::Easy.into()).unwrap();
+        chess.play_move(&move_).unwrap();
+        let reply = chess.computer_move(Difficulty::Easy.into()).unwrap();
+        chess.play_move(&reply).unwrap();
+
+        assert_eq!(chess.undo().unwrap(), Some(reply));
+        assert_eq!(chess.undo().unwrap(), Some(move_));
+        assert_eq!(chess.undo().unwrap(), None);
+        assert_eq!(chess.0.board(), &start);
+        assert!(chess.move_history().is_empty());
+    }
+
+    #[test]
+    fn lists_legal_moves_in_san() {
+        let mut chess = Chess::default();
+        let moves = chess.legal_moves();
+        assert_eq!(moves.len(), 20);
+        assert!(moves.iter().all(|move_| chess.is_valid_move(move_)));
+        assert!(moves.contains(&"Nf3".to_string()));
+
+        chess.play_move("e4").unwrap();
+        chess.play_move("d5").unwrap();
+        assert!(chess.legal_moves().contains(&"exd5".to_string()));
+    }
+
+    #[test]
+    fn clicks_make_moves() {
+        let chess = Chess::default();
+        // Each square is 4 characters wide after the rank, and 2 lines high below the border
+        assert_eq!(chess.click("", 19, 13, 0), Some(Click::Select("e2".into())));
+        assert_eq!(
+            chess.click("e2", 19, 9, 0),
+            Some(Click::Play("e2e4".into()))
+        );
+        assert_eq!(
+            chess.click("e2", 19, 5, 0),
+            Some(Click::Select("e6".into()))
+        );
+        assert_eq!(chess.click("", 15, 3, 1), Some(Click::Select("e2".into())));
+        assert_eq!(chess.click("", 1, 3, 0), None);
+
+        // Promotions are left for the player to choose the piece
+        let chess = position("8/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+        assert_eq!(
+            chess.click("e7", 19, 1, 0),
+            Some(Click::Play("e7e8".into()))
+        );
+    }
+
+    #[test]
+    fn promotions_are_completed() {
+        let chess = position("3r4/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+        assert_eq!(chess.completions("e8"), ["=Q", "=R", "=B", "=N"]);
+        assert_eq!(chess.completions("exd8"), ["=Q", "=R", "=B", "=N"]);
+        assert_eq!(chess.completions("e7e8"), ["=Q", "=R", "=B", "=N"]);
+        assert!(chess.is_valid_move("e7e8=Q"));
+        assert!(chess.is_valid_move("e8=N"));
+        assert!(chess.completions("e8=Q").is_empty());
+        assert!(chess.completions("Ke2").is_empty());
+        assert!(chess.completions("e6").is_empty());
+
+        // Kings can be promoted to in antichess
+        let chess = antichess("8/4P3/8/8/8/8/k7/8 w - - 0 1");
+        assert_eq!(chess.completions("e8").len(), 5);
+    }
+
+    #[test]
+    fn highlights_the_last_move() {
+        let mut chess = Chess::default();
+        chess.play_move("e4").unwrap();
+        let board = chess.display_styled(0, "");
+        let background = |line: usize, file: usize| board.lines[line].spans[1 + 2 * file].style.bg;
+        // The fourth rank is drawn on the ninth line, and the second on the thirteenth
+        assert_eq!(background(9, 4), Some(LIGHT_MOVED_SQUARE));
+        assert_eq!(background(13, 4), Some(LIGHT_MOVED_SQUARE));
+        assert_eq!(background(13, 3), Some(DARK_SQUARE));
+    }
+
+    #[test]
+    fn highlights_checks_and_moves() {
+        let mut chess = Chess::default();
+        let board = chess.display_styled(0, "g1");
+        let background = |line: usize, file: usize| board.lines[line].spans[1 + 2 * file].style.bg;
+        // The knight can go to f3 and h3, on the sixth rank from the top
+        assert_eq!(background(11, 5), Some(LIGHT_TARGET_SQUARE));
+        assert_eq!(background(11, 7), Some(LIGHT_TARGET_SQUARE));
+        assert_eq!(background(11, 6), Some(DARK_SQUARE));
+
+        for move_ in ["e4", "f5", "Qh5"] {
+            chess.play_move(move_).unwrap();
+        }
+        let board = chess.display_styled(0, "");
+        assert_eq!(
+            board.lines[1].spans[1 + 2 * 4].style.bg,
+            Some(CHECKED_SQUARE)
+        );
+    }
+
+    #[test]
+    fn counts_captures_and_material() {
+        let mut chess = Chess::default();
+        assert_eq!(chess.captures(), Some([String::new(), String::new()]));
+
+        for move_ in ["e4", "d5", "exd5", "Qxd5", "Nc3", "Qxg2", "Bxg2"] {
+            chess.play_move(move_).unwrap();
+        }
+        assert_eq!(
+            chess.captures(),
+            Some(["♛♟ +8".to_string(), "♙♙".to_string()])
+        );
+    }
+
+    #[test]
+    fn flips_the_board_for_black() {
+        let chess = Chess::default();
+        assert_eq!(chess.display_for(0), chess.display());
+
+        let board = chess.display_for(1);
+        let lines: Vec<_> = board.lines().collect();
+        assert!(lines[1].starts_with("1 │"));
+        assert!(lines[15].starts_with("8 │"));
+        assert_eq!(lines[17].trim(), "h   g   f   e   d   c   b   a");
+    }
+
+    #[test]
+    fn accepts_coordinate_moves() {
+        let mut chess = Chess::default();
+        assert!(chess.is_valid_move("g1f3"));
+        assert!(!chess.is_valid_move("g1g3"));
+        chess.play_move("g1f3").unwrap();
+        chess.play_move("Nc6").unwrap();
+        assert_eq!(chess.move_history(), ["Nf3", "Nc6"]);
+
+        let mut chess = Chess::from(
+            "4k3/P7/8/8/8/8/8/4K2R w K - 0 1"
+                .parse::<Fen>()
+                .unwrap()
+                .into_position::<shakmaty::Chess>(CastlingMode::Standard)
+                .unwrap(),
+        );
+        assert!(chess.is_valid_move("e1g1"));
+        chess.play_move("a7a8n").unwrap();
+        assert_eq!(chess.move_history(), ["a8=N"]);
+    }
+
+    #[test]
+    fn antichess_captures_are_compulsory() {
+        // The queen has to take the pawn, even though it's then lost to the other one
+        let chess = antichess("4k3/8/8/3p4/4p3/8/8/3QK3 w - - 0 1");
+        assert!(!chess.is_valid_move("Qd4"));
+        assert!(chess.is_valid_move("Qxd5"));
+        assert_eq!(chess.actions().len(), 1);
+
+        // Kings can be captured like any other piece
+        let chess = antichess("8/8/8/8/8/8/3k4/3QK3 b - - 0 1");
+        assert!(chess.is_valid_move("Kxd1"));
+        assert!(chess.is_valid_move("Kxe1"));
+    }
+
+    #[test]
+    fn antichess_is_won_by_losing_everything() {
+        // White's last piece is a pawn that Black is forced to take
+        let chess = antichess("8/8/8/8/8/3p4/4P3/7k b - - 0 1");
+        let chess = chess.result(&san_move(&chess, "dxe2"));
+        assert!(chess.is_terminal());
+        assert_eq!(chess.evaluation(), f32::INFINITY);
+        assert_eq!(chess.win_state(), Some(WinState::Decisive(Player::Max)));
+
+        // Being a queen down is good
+        let chess = antichess("4k3/8/8/8/3q4/8/8/4K3 w - - 0 1");
+        assert!(DEFAULT_EVAL_PARAMS.evaluate(&chess) > 0.0);
+    }
+
+    #[test]
+    fn engine_gives_away_pieces_in_antichess() {
+        // White can give the rook to the king, which then has to take it
+        let chess = antichess("8/8/8/8/8/8/k7/1R6 w - - 0 1");
+        let move_ = Chess::engine(3)
+            .best_move_with(&chess, &*DEFAULT_EVAL_PARAMS)
+            .unwrap();
+        let chess = chess.result(&move_);
+        let replies = chess.actions();
+        assert!(
+            replies.iter().all(Move::is_capture),
+            "{move_} gives nothing away"
+        );
+    }
+
+    #[test]
+    fn crazyhouse_drops_captured_pieces() {
+        let mut chess = Chess::new(Variant::Crazyhouse);
+        for move_ in ["e4", "d5", "exd5", "Qxd5", "Nc3", "Qe5+"] {
+            chess.play_move(move_).unwrap();
+        }
+        assert_eq!(chess.pocket(Color::White).unwrap(), "♙");
+        assert_eq!(chess.pocket(Color::Black).unwrap(), "♟");
+
+        // The check can be blocked by dropping the captured pawn
+        assert!(!chess.is_valid_move("N@e2"));
+        assert!(chess.is_valid_move("P@e2"));
+        for move_ in ["P@e2", "Qxc3", "dxc3"] {
+            chess.play_move(move_).unwrap();
+        }
+        assert_eq!(chess.pocket(Color::White).unwrap(), "♕");
+        assert_eq!(chess.pocket(Color::Black).unwrap(), "♟ ♞");
+        assert_eq!(
+            DEFAULT_EVAL_PARAMS.material(&chess.0),
+            2.0 * (900.0 - 320.0)
+        );
+
+        chess.play_move("N@f3").unwrap();
+        assert_eq!(chess.move_history().last().unwrap(), "N@f3");
+        let display = chess.to_string();
+        assert!(display.starts_with("  ♟\n"));
+        assert!(display.ends_with("\n  ♕"));
+        assert_eq!(display.lines().count(), 20);
+    }
+
+    #[test]
+    fn three_checks_win() {
+        let position: ThreeCheck = "4k3/8/8/8/8/8/8/4K2R w - - 1+3 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let chess = Chess::from_position(position.into(), Variant::ThreeCheck);
+        assert_eq!(chess.checks_left(Color::White).unwrap(), "♔ 1 check left");
+        assert_eq!(chess.checks_left(Color::Black).unwrap(), "♚ 3 checks left");
+
+        let move_ = Chess::engine(2)
+            .best_move_with(&chess, &*DEFAULT_EVAL_PARAMS)
+            .unwrap();
+        let chess = chess.result(&move_);
+        assert!(chess.0.is_check());
+        assert_eq!(chess.win_state(), Some(WinState::Decisive(Player::Max)));
+        assert_eq!(chess.evaluation(), f32::INFINITY);
+    }
+
+    #[test]
+    fn reaching_the_hill_wins() {
+        assert_eq!(Chess::hill_distance(Square::E1), 3);
+        assert_eq!(Chess::hill_distance(Square::C6), 1);
+        assert_eq!(Chess::hill_distance(Square::D5), 0);
+
+        let position: KingOfTheHill = "8/8/8/8/8/4K3/8/k6r w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let chess = Chess::from_position(position.into(), Variant::KingOfTheHill);
+        let move_ = Chess::engine(2)
+            .best_move_with(&chess, &*DEFAULT_EVAL_PARAMS)
+            .unwrap();
+        let chess = chess.result(&move_);
+        assert_eq!(chess.win_state(), Some(WinState::Decisive(Player::Max)));
+        assert_eq!(chess.evaluation(), f32::INFINITY);
     }
 }
@@ -0,0 +1,177 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use shakmaty::zobrist::{Zobrist64, ZobristHash};
+use shakmaty::{EnPassantMode, File, Move, Position, Rank, Role, Square};
+
+/// The size in bytes of each entry in a book file.
+const ENTRY_SIZE: usize = 16;
+
+/// An opening book in the Polyglot format, which lists moves for positions along with how often
+/// each should be played.
+#[derive(Debug, Default)]
+pub struct Book {
+    /// The entries of the book, sorted by the keys of their positions like they are in the file.
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Entry {
+    key: u64,
+    /// The move packed into the bits of the destination file and rank, then the origin file and
+    /// rank, then the promotion piece. Castling is written as the king moving to its rook.
+    move_: u16,
+    weight: u16,
+}
+
+impl Book {
+    /// Reads the book at `path`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Reads a book from the contents of its file, which is a sequence of big-endian entries.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "polyglot books are made of 16 byte entries",
+            ));
+        }
+
+        let mut entries: Vec<Entry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| Entry {
+                key: u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                move_: u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+            })
+            .collect();
+        // Books are meant to be sorted already, but searching one that isn't would miss moves
+        entries.sort_by_key(|entry| entry.key);
+        Ok(Self { entries })
+    }
+
+    /// The legal moves the book has for `position`, along with their weights.
+    pub fn moves<P: Position + Clone>(&self, position: &P) -> Vec<(Move, u16)> {
+        let key: Zobrist64 = position.zobrist_hash(EnPassantMode::Legal);
+        let start = self.entries.partition_point(|entry| entry.key < key.0);
+        let legal_moves = position.legal_moves();
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key.0)
+            .filter_map(|entry| {
+                let move_ = legal_moves
+                    .iter()
+                    .find(|move_| entry.matches(move_))?
+                    .clone();
+                Some((move_, entry.weight))
+            })
+            .collect()
+    }
+
+    /// Picks one of the book's moves for `position` with probability in proportion to its weight,
+    /// using `seed` as the source of randomness.
+    pub fn pick<P: Position + Clone>(&self, position: &P, seed: u64) -> Option<Move> {
+        let moves = self.moves(position);
+        let total: u64 = moves.iter().map(|&(_, weight)| u64::from(weight)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut target = seed % total;
+        moves.into_iter().find_map(|(move_, weight)| {
+            if target < u64::from(weight) {
+                Some(move_)
+            } else {
+                target -= u64::from(weight);
+                None
+            }
+        })
+    }
+}
+
+impl Entry {
+    fn matches(self, move_: &Move) -> bool {
+        let square = |bits: u16| {
+            Square::from_coords(
+                File::new(u32::from(bits & 7)),
+                Rank::new(u32::from(bits >> 3 & 7)),
+            )
+        };
+        let promotion = match self.move_ >> 12 & 7 {
+            1 => Some(Role::Knight),
+            2 => Some(Role::Bishop),
+            3 => Some(Role::Rook),
+            4 => Some(Role::Queen),
+            _ => None,
+        };
+
+        // Castling moves go to the rook's square in shakmaty too
+        move_.from() == Some(square(self.move_ >> 6))
+            && move_.to() == square(self.move_)
+            && move_.promotion() == promotion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::fen::Fen;
+    use shakmaty::{CastlingMode, Chess};
+
+    fn entry(key: u64, move_: &str, weight: u16) -> [u8; ENTRY_SIZE] {
+        let coordinates =
+            |square: &[u8]| u16::from(square[1] - b'1') << 3 | u16::from(square[0] - b'a');
+        let move_ = coordinates(&move_.as_bytes()[2..]) | coordinates(move_.as_bytes()) << 6;
+        let mut entry = [0; ENTRY_SIZE];
+        entry[0..8].copy_from_slice(&key.to_be_bytes());
+        entry[8..10].copy_from_slice(&move_.to_be_bytes());
+        entry[10..12].copy_from_slice(&weight.to_be_bytes());
+        entry
+    }
+
+    #[test]
+    fn finds_weighted_moves() {
+        // The key of the starting position, from the Polyglot documentation
+        let start = 0x463b_9618_1691_fc9c;
+        let bytes = [
+            entry(start - 1, "a2a3", 1),
+            entry(start, "e2e4", 3),
+            entry(start, "d2d4", 1),
+            entry(start + 1, "h2h4", 1),
+        ]
+        .concat();
+        let book = Book::from_bytes(&bytes).unwrap();
+
+        let position = Chess::default();
+        let moves: Vec<_> = book
+            .moves(&position)
+            .into_iter()
+            .map(|(move_, weight)| (move_.to_uci(CastlingMode::Standard).to_string(), weight))
+            .collect();
+        assert_eq!(moves, [("e2e4".to_string(), 3), ("d2d4".to_string(), 1)]);
+
+        let picks: Vec<_> = (0..4)
+            .map(|seed| book.pick(&position, seed).unwrap().to().to_string())
+            .collect();
+        assert_eq!(picks, ["e4", "e4", "e4", "d4"]);
+
+        assert!(Book::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn castling_goes_to_the_rook() {
+        let position: Chess = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let key: Zobrist64 = position.zobrist_hash(EnPassantMode::Legal);
+        let book = Book::from_bytes(&entry(key.0, "e1h1", 1)).unwrap();
+        let (move_, _) = book.moves(&position).pop().unwrap();
+        assert!(move_.is_castle());
+        assert_eq!(move_.to_uci(CastlingMode::Standard).to_string(), "e1g1");
+    }
+}
@@ -0,0 +1,416 @@
+use std::io;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ratatui::text::Text;
+use serde::Deserialize;
+use shakmaty::fen::Fen;
+use shakmaty::san::San;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Color, EnPassantMode, Move, Position};
+
+use crate::games::chess::{Chess, Variant};
+use crate::games::{Click, Game, GameError, GameOption, SearchLimits, WinState};
+use crate::minimax::Player;
+
+/// Where Lichess serves its puzzle of the day.
+const DAILY_URL: &str = "https://lichess.org/api/puzzle/daily";
+
+/// The puzzles played when the daily one can't be fetched, as their names, their starting
+/// positions, and their solutions in coordinate notation.
+const BUNDLED: [(&str, &str, &[&str]); 4] = [
+    (
+        "Back rank mate",
+        "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+        &["d1d8"],
+    ),
+    (
+        "Scholar's mate",
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        &["h5f7"],
+    ),
+    (
+        "Royal fork",
+        "4k3/8/8/1N1q4/8/8/8/4K3 w - - 0 1",
+        &["b5c7", "e8d7", "c7d5"],
+    ),
+    (
+        "Back rank mate for Black",
+        "3r2k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1",
+        &["d8d1"],
+    ),
+];
+
+/// Today's puzzle from Lichess, if it has been fetched.
+static DAILY: OnceLock<PuzzleData> = OnceLock::new();
+
+/// Offers `puzzle` as the puzzle of the day.
+pub fn use_daily(puzzle: PuzzleData) {
+    // Only the first puzzle is kept, since the app fetches it once at startup
+    let _ = DAILY.set(puzzle);
+}
+
+/// A chess position with a single line of moves that solves it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PuzzleData {
+    pub name: String,
+    pub fen: String,
+    /// The moves of both sides, starting with the solver's, in coordinate notation.
+    pub solution: Vec<String>,
+}
+
+impl PuzzleData {
+    fn bundled(index: usize) -> Self {
+        let (name, fen, solution) = BUNDLED[index % BUNDLED.len()];
+        Self {
+            name: name.to_string(),
+            fen: fen.to_string(),
+            solution: solution.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// The parts of the daily puzzle from the Lichess API that are needed to play it.
+#[derive(Deserialize)]
+struct Daily {
+    game: DailyGame,
+    puzzle: DailyPuzzle,
+}
+
+#[derive(Deserialize)]
+struct DailyGame {
+    /// The moves of the game the puzzle comes from in SAN, up to where the puzzle starts.
+    pgn: String,
+}
+
+#[derive(Deserialize)]
+struct DailyPuzzle {
+    id: String,
+    rating: u32,
+    solution: Vec<String>,
+}
+
+/// Fetches today's puzzle from Lichess. There's no HTTP client built in, so this runs `curl`,
+/// which fails if it isn't installed or the computer is offline.
+pub fn fetch_daily() -> Result<PuzzleData, String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+        .arg(DAILY_URL)
+        .output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => "it needs curl, which isn't installed".to_string(),
+            _ => format!("couldn't run curl: {err}"),
+        })?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    parse_daily(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Reads the daily puzzle from the JSON the Lichess API gives for it.
+fn parse_daily(json: &str) -> Result<PuzzleData, String> {
+    let daily: Daily = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let mut chess = Chess::default();
+    for move_ in daily.game.pgn.split_whitespace() {
+        chess
+            .play_move(move_)
+            .map_err(|_| format!("illegal move {move_} in the puzzle's game"))?;
+    }
+    Ok(PuzzleData {
+        name: format!(
+            "Lichess daily puzzle {}, rated {}",
+            daily.puzzle.id, daily.puzzle.rating
+        ),
+        fen: Fen::from_position(chess.0, EnPassantMode::Legal).to_string(),
+        solution: daily.puzzle.solution,
+    })
+}
+
+/// Chess puzzles, where one side has to find the moves of the solution while the computer plays
+/// the other side's replies. Any move that gives checkmate also solves the puzzle.
+///
+/// Only the puzzle's moves are in the history, so the solver's side moves first.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    puzzle: PuzzleData,
+    chess: Chess,
+    /// How the puzzle ended if it was by a move outside the solution: losing to a wrong move, or
+    /// winning with a different checkmate.
+    ending: Option<WinState>,
+}
+
+impl Default for Puzzle {
+    fn default() -> Self {
+        Self::new(PuzzleData::bundled(0))
+    }
+}
+
+impl Puzzle {
+    /// Starts solving `puzzle`.
+    ///
+    /// # Panics
+    ///
+    /// If the puzzle's position isn't a legal one.
+    pub fn new(puzzle: PuzzleData) -> Self {
+        let position = puzzle
+            .fen
+            .parse::<Fen>()
+            .ok()
+            .and_then(|fen| {
+                fen.into_position::<shakmaty::Chess>(CastlingMode::Standard)
+                    .ok()
+            })
+            .expect("invalid puzzle position");
+        let chess = Chess::from_position(position.into(), Variant::Standard);
+        Self {
+            puzzle,
+            chess,
+            ending: None,
+        }
+    }
+
+    /// The colour of the solver, who moves first.
+    fn solver(&self) -> Color {
+        let turn = self.chess.0.turn();
+        if self.is_solvers_turn() {
+            turn
+        } else {
+            !turn
+        }
+    }
+
+    /// The side the board is drawn for in [`Chess`], for whoever plays the puzzle's `side`.
+    fn chess_side(&self, side: usize) -> usize {
+        match self.solver() {
+            Color::White => side,
+            Color::Black => 1 - side,
+        }
+    }
+
+    /// The next move of the solution, unless the puzzle is over.
+    fn expected_move(&self) -> Option<Move> {
+        if self.ending.is_some() {
+            return None;
+        }
+        let uci = self.puzzle.solution.get(self.chess.1.len())?;
+        uci.parse::<UciMove>().ok()?.to_move(&self.chess.0).ok()
+    }
+
+    fn is_solvers_turn(&self) -> bool {
+        self.chess.1.len().is_multiple_of(2)
+    }
+}
+
+impl Game for Puzzle {
+    fn name(&self) -> String {
+        "Chess Puzzles".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " ♜ │   │ ♚
+───┼───┼───
+   │   │ ♟
+───┼───┼───
+ ♖ │ ? │ ♔ "
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.display_for(0)
+    }
+
+    fn display_for(&self, side: usize) -> String {
+        self.chess.display_for(self.chess_side(side))
+    }
+
+    fn display_styled(&self, side: usize, typed: &str) -> Text<'static> {
+        self.chess.display_styled(self.chess_side(side), typed)
+    }
+
+    fn click(&self, typed: &str, column: u16, row: u16, side: usize) -> Option<Click> {
+        self.chess.click(typed, column, row, self.chess_side(side))
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        self.chess.display_size()
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.chess.move_history()
+    }
+
+    fn side_to_move(&self) -> usize {
+        usize::from(!self.is_solvers_turn())
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        let solved = self.chess.1.len() >= self.puzzle.solution.len();
+        self.ending
+            .or(solved.then_some(WinState::Decisive(Player::Max)))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        match self.chess.parse_move(move_) {
+            Some(_) if self.is_solvers_turn() => self.win_state().is_none(),
+            Some(move_) => self.expected_move() == Some(move_),
+            None => false,
+        }
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        if !self.is_valid_move(move_) {
+            return Err(GameError::invalid_move(move_));
+        }
+        let expected = self.expected_move();
+        let played = self.chess.parse_move(move_);
+        self.chess.play_move(move_)?;
+        if played != expected {
+            self.ending = Some(if self.chess.0.is_checkmate() {
+                WinState::Decisive(Player::Max)
+            } else {
+                WinState::Decisive(Player::Min)
+            });
+        }
+        Ok(())
+    }
+
+    /// Plays the next move of the solution, for either side.
+    fn computer_move(&self, _limits: SearchLimits) -> Result<String, GameError> {
+        let move_ = self.expected_move().ok_or(GameError::NoMoves)?;
+        Ok(San::from_move(&self.chess.0, &move_).to_string())
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.puzzle.clone());
+    }
+
+    /// Today's puzzle, followed by each of the bundled ones.
+    fn variants(&self) -> Vec<String> {
+        let bundled = BUNDLED.iter().map(|(name, _, _)| name.to_string());
+        ["Puzzle of the day".to_string()]
+            .into_iter()
+            .chain(bundled)
+            .collect()
+    }
+
+    /// Starts the puzzle at `index` in [`Game::variants`]. Without a puzzle from Lichess, the
+    /// puzzle of the day is one of the bundled ones, changing each day.
+    fn set_variant(&mut self, index: usize) {
+        let puzzle = match (index, DAILY.get()) {
+            (0, Some(daily)) => daily.clone(),
+            (0, None) => {
+                let days = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_secs() / (24 * 60 * 60));
+                PuzzleData::bundled(days as usize)
+            }
+            (index, _) => PuzzleData::bundled(index - 1),
+        };
+        *self = Self::new(puzzle);
+    }
+
+    fn options(&self) -> Vec<GameOption> {
+        vec![GameOption::new("Puzzle", self.variants())]
+    }
+
+    fn sides(&self) -> [String; 2] {
+        let solver = self.solver();
+        [solver, !solver].map(|color| {
+            let name = match color {
+                Color::White => "White",
+                Color::Black => "Black",
+            };
+            name.to_string()
+        })
+    }
+
+    fn completions(&self, move_: &str) -> Vec<String> {
+        self.chess.completions(move_)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        if self.win_state().is_some() {
+            Vec::new()
+        } else if self.is_solvers_turn() {
+            self.chess.legal_moves()
+        } else {
+            self.computer_move(SearchLimits::default())
+                .into_iter()
+                .collect()
+        }
+    }
+
+    fn undo(&mut self) -> Result<Option<String>, GameError> {
+        let Some(move_) = self.chess.undo()? else {
+            return Ok(None);
+        };
+        self.ending = None;
+        Ok(Some(move_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_puzzles_are_solved_by_their_solutions() {
+        for index in 0..BUNDLED.len() {
+            let mut puzzle = Puzzle::new(PuzzleData::bundled(index));
+            while puzzle.win_state().is_none() {
+                let move_ = puzzle.computer_move(SearchLimits::default()).unwrap();
+                puzzle.play_move(&move_).unwrap();
+            }
+            assert_eq!(
+                puzzle.win_state(),
+                Some(WinState::Decisive(Player::Max)),
+                "{}",
+                puzzle.puzzle.name
+            );
+        }
+    }
+
+    #[test]
+    fn wrong_moves_fail_the_puzzle() {
+        let mut puzzle = Puzzle::new(PuzzleData::bundled(2));
+        assert_eq!(puzzle.sides()[0], "White");
+        // Only the solution's replies can be played for the opponent
+        puzzle.play_move("Nc7").unwrap();
+        assert!(!puzzle.is_valid_move("Kf8"));
+        assert_eq!(puzzle.legal_moves(), ["Kd7"]);
+
+        puzzle.undo().unwrap();
+        puzzle.play_move("Kf2").unwrap();
+        assert_eq!(puzzle.win_state(), Some(WinState::Decisive(Player::Min)));
+        puzzle.undo().unwrap();
+        assert_eq!(puzzle.win_state(), None);
+    }
+
+    #[test]
+    fn black_solvers_see_their_side() {
+        let puzzle = Puzzle::new(PuzzleData::bundled(3));
+        assert_eq!(puzzle.sides(), ["Black", "White"]);
+        assert!(puzzle
+            .display_for(0)
+            .starts_with(&puzzle.chess.display_for(1)));
+    }
+
+    #[test]
+    fn reads_the_daily_puzzle() {
+        let json = r#"{
+            "game": {"id": "abcd1234", "pgn": "e4 e5 Bc4 Nc6 Qh5 Nf6", "clock": "3+0"},
+            "puzzle": {"id": "K69di", "rating": 1500, "solution": ["h5f7"], "themes": ["mateIn1"]}
+        }"#;
+        let daily = parse_daily(json).unwrap();
+        assert_eq!(daily.name, "Lichess daily puzzle K69di, rated 1500");
+        assert_eq!(
+            daily,
+            PuzzleData {
+                name: daily.name.clone(),
+                ..PuzzleData::bundled(1)
+            }
+        );
+        assert!(parse_daily("{}").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// A connection to an external chess engine, such as Stockfish, that speaks the Universal Chess
+/// Interface over its standard input and output.
+#[derive(Debug)]
+pub struct Client {
+    process: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Client {
+    /// Starts the engine at `path` and waits for it to be ready.
+    pub fn start(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::spawn(Command::new(path.as_ref()))
+    }
+
+    /// Starts the engine run by `command` and waits for it to be ready.
+    pub fn spawn(mut command: Command) -> io::Result<Self> {
+        let mut process = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = process.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(process.stdout.take().expect("stdout is piped"));
+        let mut client = Self {
+            process,
+            stdin,
+            stdout,
+        };
+
+        client.send("uci")?;
+        client.wait_for("uciok")?;
+        client.send("isready")?;
+        client.wait_for("readyok")?;
+        Ok(client)
+    }
+
+    /// Asks the engine for its best move, in coordinate notation, after `moves` have been played
+    /// from the position given by `fen`, searching within `limits` such as `depth 10` or
+    /// `wtime 60000 winc 1000`.
+    pub fn best_move(&mut self, fen: &str, moves: &[String], limits: &str) -> io::Result<String> {
+        if moves.is_empty() {
+            self.send(&format!("position fen {fen}"))?;
+        } else {
+            self.send(&format!("position fen {fen} moves {}", moves.join(" ")))?;
+        }
+        self.send(&format!("go {limits}"))?;
+
+        let line = self.wait_for("bestmove")?;
+        match line.split_whitespace().nth(1) {
+            Some("(none)") | None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the engine has no move to play",
+            )),
+            Some(move_) => Ok(move_.to_string()),
+        }
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+
+    /// Reads lines from the engine until one starts with `command`, which is returned. Anything
+    /// else the engine says in the meantime, like its progress, is skipped.
+    fn wait_for(&mut self, command: &str) -> io::Result<String> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("the engine quit while waiting for {command}"),
+                ));
+            }
+            if line.split_whitespace().next() == Some(command) {
+                return Ok(line.trim().to_string());
+            }
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if self.send("quit").is_err() {
+            let _ = self.process.kill();
+        }
+        let _ = self.process.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// A stand-in for an engine that answers each command the way a real one would, and records
+    /// the position it was given as its move so that it can be checked.
+    const FAKE_ENGINE: &str = r#"
+        while read -r command rest; do
+            case "$command" in
+                uci) echo "id name Fake"; echo uciok ;;
+                isready) echo readyok ;;
+                position) position="$rest" ;;
+                go) echo "info depth 1 score cp 20"; echo "bestmove $(echo "$position" | tr ' ' _) ponder e7e5" ;;
+                quit) exit ;;
+            esac
+        done
+    "#;
+
+    #[test]
+    fn asks_for_the_best_move() {
+        let mut command = Command::new("sh");
+        command.args(["-c", FAKE_ENGINE]);
+        let mut client = Client::spawn(command).unwrap();
+
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(
+            client.best_move(fen, &[], "depth 5").unwrap(),
+            format!("fen_{}", fen.replace(' ', "_")),
+        );
+        let moves = ["e2e4".to_string(), "e7e5".to_string()];
+        assert!(client
+            .best_move(fen, &moves, "depth 5 wtime 60000")
+            .unwrap()
+            .ends_with("_moves_e2e4_e7e5"));
+    }
+
+    #[test]
+    fn fails_when_the_engine_quits() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "read -r command; echo uciok"]);
+        assert!(Client::spawn(command).is_err());
+    }
+}
@@ -0,0 +1,497 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const SIZE: u8 = 19;
+
+/// The row and column steps along a horizontal, vertical or diagonal line.
+const DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Stones are only searched this many squares or fewer from an existing stone.
+const CANDIDATE_DISTANCE: i8 = 2;
+
+/// How many of the most threatening points near the stones are paired up into moves. Every pair
+/// of points would be tens of thousands of moves, so most of them have to be left out.
+const CANDIDATE_COUNT: usize = 10;
+
+/// The value to a player of a line of six holding only their stones, for each number of them
+/// in it. Four or five are a threat to win on the next turn, which takes both stones to stop.
+const LINE_VALUES: [f32; 6] = [0.0, 1.0, 4.0, 16.0, 250.0, 400.0];
+
+/// The values of the line a stone would join or block, used to pick out which points to search.
+const THREAT_VALUES: [f32; 7] = [0.0, 0.0, 1.0, 10.0, 100.0, 1_000.0, 10_000.0];
+
+#[derive(Clone, Debug, Default)]
+pub struct Connect6(Connect6State, Vec<Move>);
+
+impl Game for Connect6 {
+    fn name(&self) -> String {
+        "Connect 6".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "· · · · · ·
+· ● ● ○ · ·
+· ○ ● ● ○ ·
+· · ○ ● · ·
+· · · ○ ● ·"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (2 * SIZE as u16 + 3, SIZE as u16 + 1)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if let Some(winner) = self.0.winner {
+            Some(WinState::Decisive(winner))
+        } else if self.0.is_terminal() {
+            Some(WinState::Draw)
+        } else {
+            None
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        self.0
+            .parse_move(move_)
+            .is_some_and(|move_| !self.0.is_terminal() && self.0.is_legal(move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = self
+            .0
+            .parse_move(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(2)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Connect6 {
+    /// The engine used to find computer moves, searching to `depth` turns of two stones each.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build connect 6 engine")
+    }
+}
+
+/// A point to place a stone on, written as a column letter and a row number, such as `j10` for
+/// the centre of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point {
+    row: u8,
+    column: u8,
+}
+
+impl Point {
+    fn index(self) -> usize {
+        self.row as usize * SIZE as usize + self.column as usize
+    }
+
+    /// The point `distance` steps along `direction` from this one, if it's on the board.
+    fn step(self, (dy, dx): (i8, i8), distance: i8) -> Option<Point> {
+        let row = self.row as i8 + dy * distance;
+        let column = self.column as i8 + dx * distance;
+        let size = SIZE as i8;
+        ((0..size).contains(&row) && (0..size).contains(&column)).then_some(Point {
+            row: row as u8,
+            column: column as u8,
+        })
+    }
+
+    fn all() -> impl Iterator<Item = Point> {
+        (0..SIZE).flat_map(|row| (0..SIZE).map(move |column| Point { row, column }))
+    }
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.column) as char, self.row + 1)
+    }
+}
+
+/// The stones placed in one turn, written as the points separated by a space, such as `j10 k11`.
+/// Every turn places two stones except for the very first one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    first: Point,
+    second: Option<Point>,
+}
+
+impl Move {
+    fn points(self) -> impl Iterator<Item = Point> {
+        [Some(self.first), self.second].into_iter().flatten()
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        if let Some(second) = self.second {
+            write!(f, " {second}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Connect6State {
+    /// The stones on each point, going along each row from the bottom left.
+    stones: Vec<Option<Player>>,
+    stone_count: u16,
+    /// Black places the first stone and is the maximising player.
+    player: Player,
+    winner: Option<Player>,
+    /// The Zobrist hash of the stones, updated as they're placed.
+    hash: u64,
+}
+
+impl Default for Connect6State {
+    fn default() -> Self {
+        Self {
+            stones: vec![None; SIZE as usize * SIZE as usize],
+            stone_count: 0,
+            player: Player::Max,
+            winner: None,
+            hash: 0,
+        }
+    }
+}
+
+impl Connect6State {
+    fn parse_point(s: &str) -> Option<Point> {
+        let s = s.trim().to_ascii_lowercase();
+        let column = s.bytes().next()?.checked_sub(b'a')?;
+        let row = s.get(1..)?.parse::<u8>().ok()?.checked_sub(1)?;
+        (column < SIZE && row < SIZE).then_some(Point { row, column })
+    }
+
+    fn parse_move(&self, s: &str) -> Option<Move> {
+        let mut points = s.split_whitespace().map(Self::parse_point);
+        let move_ = Move {
+            first: points.next()??,
+            second: points.next().map_or(Some(None), |point| point.map(Some))?,
+        };
+        points.next().is_none().then_some(move_)
+    }
+
+    /// How many stones the player to move places this turn, which is only one on the first turn
+    /// or if there's only one empty point left.
+    fn stones_to_place(&self) -> usize {
+        let empty = self.stones.len() - self.stone_count as usize;
+        if self.stone_count == 0 {
+            1
+        } else {
+            empty.min(2)
+        }
+    }
+
+    fn is_legal(&self, move_: Move) -> bool {
+        move_.points().count() == self.stones_to_place()
+            && Some(move_.first) != move_.second
+            && move_
+                .points()
+                .all(|point| self.stones[point.index()].is_none())
+    }
+
+    fn is_full(&self) -> bool {
+        self.stone_count as usize == self.stones.len()
+    }
+
+    /// The number of `player`'s stones in a row from `point` along `direction`, not counting
+    /// `point` itself.
+    fn run(&self, point: Point, direction: (i8, i8), player: Player) -> usize {
+        (1..)
+            .map_while(|distance| point.step(direction, distance))
+            .take_while(|near| self.stones[near.index()] == Some(player))
+            .count()
+    }
+
+    /// The length of the line `player` would have through `point` along `direction` by placing
+    /// a stone there.
+    fn line_through(&self, point: Point, (dy, dx): (i8, i8), player: Player) -> usize {
+        self.run(point, (dy, dx), player) + self.run(point, (-dy, -dx), player) + 1
+    }
+
+    /// The local value of placing a stone on `point`, from the lines it would make for the player
+    /// to move and the ones it would block for their opponent.
+    fn threat(&self, point: Point) -> f32 {
+        DIRECTIONS
+            .into_iter()
+            .flat_map(|direction| {
+                [self.player, self.player.opposite()]
+                    .map(|player| THREAT_VALUES[self.line_through(point, direction, player).min(6)])
+            })
+            .sum()
+    }
+
+    /// Every run of six points in a straight line, by its first point and direction.
+    fn lines() -> impl Iterator<Item = (Point, (i8, i8))> {
+        Point::all().flat_map(|point| {
+            DIRECTIONS
+                .into_iter()
+                .filter(move |&direction| point.step(direction, 5).is_some())
+                .map(move |direction| (point, direction))
+        })
+    }
+
+    /// The empty points near the stones that are worth searching, most threatening first.
+    fn candidates(&self) -> Vec<Point> {
+        let mut near = vec![false; self.stones.len()];
+        for point in Point::all().filter(|point| self.stones[point.index()].is_some()) {
+            for dy in -CANDIDATE_DISTANCE..=CANDIDATE_DISTANCE {
+                for dx in -CANDIDATE_DISTANCE..=CANDIDATE_DISTANCE {
+                    if let Some(point) = point.step((dy, dx), 1) {
+                        near[point.index()] = true;
+                    }
+                }
+            }
+        }
+
+        let mut candidates: Vec<_> = Point::all()
+            .filter(|point| near[point.index()] && self.stones[point.index()].is_none())
+            .map(|point| (self.threat(point), point))
+            .collect();
+        candidates.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        candidates.truncate(CANDIDATE_COUNT);
+        candidates.into_iter().map(|(_, point)| point).collect()
+    }
+
+    /// Places a stone for the player to move, without passing the turn.
+    fn place(&mut self, point: Point) {
+        let index = point.index();
+        assert!(self.stones[index].is_none(), "{point} is already taken");
+
+        self.stones[index] = Some(self.player);
+        self.stone_count += 1;
+        self.hash ^= zobrist_key(index, self.player);
+
+        // Lines longer than six count as well
+        if DIRECTIONS
+            .into_iter()
+            .any(|direction| self.line_through(point, direction, self.player) >= 6)
+        {
+            self.winner = Some(self.player);
+        }
+    }
+}
+
+impl minimax::State<f32, Move> for Connect6State {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some() || self.is_full()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        // Lines holding stones of both colours can't be completed by either player
+        let mut value = 0.0;
+        for (point, direction) in Self::lines() {
+            let (mut max, mut min) = (0, 0);
+            for distance in 0..6 {
+                match point
+                    .step(direction, distance)
+                    .and_then(|point| self.stones[point.index()])
+                {
+                    Some(Player::Max) => max += 1,
+                    Some(Player::Min) => min += 1,
+                    None => {}
+                }
+            }
+            match (max, min) {
+                (count, 0) => value += LINE_VALUES[count],
+                (0, count) => value -= LINE_VALUES[count],
+                _ => {}
+            }
+        }
+        value
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        if self.stone_count == 0 {
+            let centre = SIZE / 2;
+            actions.push(Move {
+                first: Point {
+                    row: centre,
+                    column: centre,
+                },
+                second: None,
+            });
+            return;
+        }
+
+        let candidates = self.candidates();
+        if self.stones_to_place() == 1 {
+            actions.extend(candidates.into_iter().map(|first| Move {
+                first,
+                second: None,
+            }));
+            return;
+        }
+
+        // Pairs of the most threatening points come first
+        for (i, &first) in candidates.iter().enumerate() {
+            actions.extend(candidates[i + 1..].iter().map(|&second| Move {
+                first,
+                second: Some(second),
+            }));
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        assert!(self.is_legal(*action), "{action} is not a legal move");
+
+        let mut state = self.clone();
+        for point in action.points() {
+            state.place(point);
+        }
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // The player to move follows from the number of stones
+        Some(self.hash)
+    }
+}
+
+impl Display for Connect6State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in (0..SIZE).rev() {
+            write!(f, "{:>2}", row + 1)?;
+            for column in 0..SIZE {
+                let stone = match self.stones[Point { row, column }.index()] {
+                    Some(Player::Max) => '●',
+                    Some(Player::Min) => '○',
+                    None => '·',
+                };
+                write!(f, " {stone}")?;
+            }
+            writeln!(f)?;
+        }
+
+        write!(f, "  ")?;
+        for column in 0..SIZE {
+            write!(f, " {}", (b'a' + column) as char)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> Connect6State {
+        moves.iter().fold(Connect6State::default(), |state, move_| {
+            state.result(&state.parse_move(move_).unwrap())
+        })
+    }
+
+    #[test]
+    fn parses_moves() {
+        let state = Connect6State::default();
+        let move_ = state.parse_move("j10").unwrap();
+        assert_eq!(move_.first, Point { row: 9, column: 9 });
+        assert_eq!(move_.second, None);
+        assert!(state.is_legal(move_));
+        assert!(!state.is_legal(state.parse_move("j10 k10").unwrap()));
+
+        let state = play(&["j10"]);
+        let move_ = state.parse_move("S19 a1").unwrap();
+        assert_eq!(move_.to_string(), "s19 a1");
+        assert!(state.is_legal(move_));
+        assert!(!state.is_legal(state.parse_move("k10").unwrap()));
+        assert!(!state.is_legal(state.parse_move("k10 k10").unwrap()));
+        assert!(!state.is_legal(state.parse_move("j10 k10").unwrap()));
+        assert!(state.parse_move("t1 a1").is_none());
+        assert!(state.parse_move("a1 a2 a3").is_none());
+    }
+
+    #[test]
+    fn six_or_more_in_a_row_wins() {
+        let moves = ["j10", "a1 a2", "k10 l10", "a4 s1", "n10 o10", "s3 s5"];
+        let state = play(&moves);
+        assert_eq!(state.winner, None);
+        let won = state.result(&state.parse_move("m10 a19").unwrap());
+        assert_eq!(won.winner, Some(Player::Max));
+        assert!(won.is_terminal());
+
+        // Filling the gap between a three and another three makes seven, which still wins
+        let state = play(&[&moves[..], &["p10 a19", "s7 s9"]].concat());
+        assert_eq!(state.winner, None);
+        let won = state.result(&state.parse_move("m10 a17").unwrap());
+        assert_eq!(won.winner, Some(Player::Max));
+    }
+
+    #[test]
+    fn moves_pair_up_the_best_candidates() {
+        let state = Connect6State::default();
+        assert_eq!(state.actions().len(), 1);
+
+        let state = play(&["j10"]);
+        let actions = state.actions();
+        assert_eq!(actions.len(), CANDIDATE_COUNT * (CANDIDATE_COUNT - 1) / 2);
+        assert!(actions.iter().all(|&action| state.is_legal(action)));
+    }
+
+    #[test]
+    fn finds_wins_and_blocks() {
+        let moves = ["d10", "a1 b1", "e10 f10", "s1 s2", "g10 a19"];
+
+        // White has to put a stone at each end of Black's open four
+        let state = play(&moves);
+        let block = state.result(&Connect6::engine(2).best_move(&state).unwrap());
+        let stone = |point: &str| block.stones[Connect6State::parse_point(point).unwrap().index()];
+        assert_eq!([stone("c10"), stone("h10")], [Some(Player::Min); 2]);
+
+        // Otherwise, Black completes six straight away
+        let state = play(&[&moves[..], &["s5 s6"]].concat());
+        let won = state.result(&Connect6::engine(1).best_move(&state).unwrap());
+        assert_eq!(won.winner, Some(Player::Max));
+    }
+}
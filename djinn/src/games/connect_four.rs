@@ -0,0 +1,437 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+
+use crate::games::{
+    grid_cell, scaled_advantage, search_move, Click, Game, GameError, SearchLimits, WinState,
+};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const WIDTH: usize = 7;
+const HEIGHT: usize = 6;
+
+/// Each column takes up `HEIGHT + 1` bits of a bitboard, with the top bit kept empty so that lines
+/// of discs can't wrap around from one column into the next.
+const COLUMN_BITS: usize = HEIGHT + 1;
+
+/// The bit of the bottom row in every column.
+const BOTTOM: u64 = {
+    let mut bottom = 0;
+    let mut column = 0;
+    while column < WIDTH {
+        bottom |= 1 << (column * COLUMN_BITS);
+        column += 1;
+    }
+    bottom
+};
+
+/// The shifts between neighbouring squares along a vertical, horizontal or diagonal line.
+const DIRECTIONS: [usize; 4] = [1, COLUMN_BITS, COLUMN_BITS - 1, COLUMN_BITS + 1];
+
+/// Every line of four squares a player could win with.
+const WINDOWS: [u64; 69] = {
+    let mut windows = [0; 69];
+    let mut count = 0;
+    let mut column = 0;
+    while column < WIDTH {
+        let mut row = 0;
+        while row < HEIGHT {
+            // The column and row offsets of each direction
+            let steps: [(isize, isize); 4] = [(0, 1), (1, 0), (1, -1), (1, 1)];
+            let mut i = 0;
+            while i < steps.len() {
+                let (dx, dy) = steps[i];
+                let end_column = column as isize + 3 * dx;
+                let end_row = row as isize + 3 * dy;
+                if end_column < WIDTH as isize && end_row >= 0 && end_row < HEIGHT as isize {
+                    let mut window = 0;
+                    let mut j = 0;
+                    while j < 4 {
+                        let x = column as isize + j * dx;
+                        let y = row as isize + j * dy;
+                        window |= 1 << (x as usize * COLUMN_BITS + y as usize);
+                        j += 1;
+                    }
+                    windows[count] = window;
+                    count += 1;
+                }
+                i += 1;
+            }
+            row += 1;
+        }
+        column += 1;
+    }
+    windows
+};
+
+/// The value of a line of four containing this many discs of one player and none of the other.
+const WINDOW_VALUES: [f32; 4] = [0.0, 1.0, 4.0, 16.0];
+
+/// The order columns are searched in, from the centre outwards, as central moves are usually
+/// stronger.
+const COLUMN_ORDER: [u8; WIDTH] = [3, 2, 4, 1, 5, 0, 6];
+
+#[derive(Clone, Debug, Default)]
+pub struct ConnectFour(ConnectFourState, Vec<Column>);
+
+impl Game for ConnectFour {
+    fn name(&self) -> String {
+        "Connect Four".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "│ │ │ │ │ │
+│ │ ● │ │ │
+│ ○ ● ○ │ │
+│ ● ○ ● ○ │
+└─┴─┴─┴─┴─┘"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_styled(&self, _side: usize, _typed: &str) -> Text<'static> {
+        self.0.board(self.1.last().copied())
+    }
+
+    fn click(&self, _typed: &str, column: u16, row: u16, _side: usize) -> Option<Click> {
+        let (column, _) = grid_cell(column, row, (1, 0), (WIDTH, HEIGHT), 1)?;
+        Some(Click::Play(Column(column as u8).to_string()))
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (30, 8)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Column::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if let Some(winner) = self.0.winner {
+            Some(WinState::Decisive(winner))
+        } else if self.0.is_full() {
+            Some(WinState::Draw)
+        } else {
+            None
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Column>()
+            .is_ok_and(|column| !self.0.is_terminal() && self.0.can_play(column))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let column = move_
+            .parse::<Column>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&column);
+        self.1.push(column);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(10)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn hints(&self, count: usize) -> Vec<String> {
+        Self::engine(10)
+            .multi_pv(&self.0, count)
+            .into_iter()
+            .map(|line| line.pv[0].to_string())
+            .collect()
+    }
+
+    fn advantage(&self) -> Option<f32> {
+        let value = Self::engine(6).value(&self.0);
+        Some(scaled_advantage(value, self.0.player, 20.0))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl ConnectFour {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build connect four engine")
+    }
+}
+
+/// A column to drop a disc into, written as a letter from `a` to `g`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Column(u8);
+
+impl FromStr for Column {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().chars();
+        let letter = chars.next().ok_or("expected a column")?;
+        if chars.next().is_some() {
+            Err("expected a single column")?;
+        }
+
+        match letter.to_ascii_lowercase() {
+            letter @ 'a'..='g' => Ok(Column(letter as u8 - b'a')),
+            _ => Err("invalid column"),
+        }
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", (b'a' + self.0) as char)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConnectFourState {
+    /// The discs of each player, indexed by `Player`, with each column taking up `COLUMN_BITS`
+    /// bits starting from the bottom row.
+    discs: [u64; 2],
+    heights: [u8; WIDTH],
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl ConnectFourState {
+    fn can_play(&self, column: Column) -> bool {
+        self.heights[column.0 as usize] < HEIGHT as u8
+    }
+
+    fn is_full(&self) -> bool {
+        self.heights.iter().all(|&height| height == HEIGHT as u8)
+    }
+
+    fn disc_at(&self, column: usize, row: usize) -> Option<Player> {
+        let bit = 1 << (column * COLUMN_BITS + row);
+        [Player::Max, Player::Min]
+            .into_iter()
+            .find(|&player| self.discs[player as usize] & bit != 0)
+    }
+
+    /// The discs in the lines of four that won the game, if it's been won.
+    fn winning_discs(&self) -> u64 {
+        let Some(winner) = self.winner else {
+            return 0;
+        };
+        let discs = self.discs[winner as usize];
+        WINDOWS
+            .into_iter()
+            .filter(|&window| discs & window == window)
+            .fold(0, |line, window| line | window)
+    }
+
+    /// The board, with red and yellow discs and the winning line picked out, along with the top
+    /// disc of the `last` column played in.
+    fn board(&self, last: Option<Column>) -> Text<'static> {
+        let winning = self.winning_discs();
+        let last = last.map(|column| {
+            let column = column.0 as usize;
+            (column, usize::from(self.heights[column]) - 1)
+        });
+        let mut lines: Vec<_> = (0..HEIGHT)
+            .rev()
+            .map(|row| {
+                let mut spans = vec![Span::raw("│")];
+                for column in 0..WIDTH {
+                    let (disc, color) = match self.disc_at(column, row) {
+                        Some(Player::Max) => ('●', Color::LightRed),
+                        Some(Player::Min) => ('○', Color::LightYellow),
+                        None => (' ', Color::Reset),
+                    };
+                    let mut style = Style::new().fg(color);
+                    if winning & 1 << (column * COLUMN_BITS + row) != 0 {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    } else if last == Some((column, row)) {
+                        style = style.bg(Color::DarkGray);
+                    }
+                    spans.push(Span::styled(format!(" {disc} "), style));
+                    spans.push(Span::raw("│"));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        lines.push(Line::from("└───┴───┴───┴───┴───┴───┴───┘"));
+        lines.push(Line::from("  a   b   c   d   e   f   g"));
+        Text::from(lines)
+    }
+
+    /// Whether `discs` contains four in a row in any direction.
+    fn has_four(discs: u64) -> bool {
+        DIRECTIONS.into_iter().any(|shift| {
+            let pairs = discs & (discs >> shift);
+            pairs & (pairs >> (2 * shift)) != 0
+        })
+    }
+}
+
+impl minimax::State<f32, Column> for ConnectFourState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some() || self.is_full()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        let [max, min] = self.discs;
+        WINDOWS
+            .into_iter()
+            .map(|window| {
+                let (max, min) = ((max & window).count_ones(), (min & window).count_ones());
+                match (max, min) {
+                    (count, 0) => WINDOW_VALUES[count as usize],
+                    (0, count) => -WINDOW_VALUES[count as usize],
+                    _ => 0.0,
+                }
+            })
+            .sum()
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Column> {
+        let mut actions = Vec::with_capacity(WIDTH);
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Column>) {
+        actions.extend(
+            COLUMN_ORDER
+                .into_iter()
+                .map(Column)
+                .filter(|&column| self.can_play(column)),
+        );
+    }
+
+    fn result(&self, action: &Column) -> Self {
+        assert!(self.can_play(*action), "column {action} is full");
+
+        let column = action.0 as usize;
+        let mut state = self.clone();
+        let discs = &mut state.discs[self.player as usize];
+        *discs |= 1 << (column * COLUMN_BITS + self.heights[column] as usize);
+
+        if Self::has_four(*discs) {
+            state.winner = Some(self.player);
+        }
+        state.heights[column] += 1;
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // Marking the lowest empty square of each column makes this unique, and the player to move
+        // follows from the number of discs
+        let occupied = self.discs[0] | self.discs[1];
+        Some(self.discs[Player::Max as usize] + occupied + BOTTOM)
+    }
+}
+
+impl Display for ConnectFourState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.board(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(columns: &str) -> ConnectFourState {
+        columns
+            .chars()
+            .map(|column| column.to_string().parse::<Column>().unwrap())
+            .fold(ConnectFourState::default(), |state, column| {
+                state.result(&column)
+            })
+    }
+
+    #[test]
+    fn wins_in_every_direction() {
+        // Vertical, horizontal, and both diagonals
+        for columns in ["abababa", "aabbccd", "abbccdcddgd", "gffeededadd"] {
+            let state = play(columns);
+            assert_eq!(state.winner, Some(Player::Max), "{columns}");
+            assert!(state.is_terminal());
+        }
+
+        // Lines don't wrap from the top of one column to the bottom of the next
+        assert!(ConnectFourState::has_four(0b1111));
+        assert!(!ConnectFourState::has_four(0b1011_1000));
+    }
+
+    #[test]
+    fn picks_out_the_winning_line() {
+        let state = play("abababa");
+        assert_eq!(state.winning_discs(), 0b1111);
+        assert_eq!(play("ababab").winning_discs(), 0);
+    }
+
+    #[test]
+    fn parses_columns() {
+        assert_eq!("d".parse::<Column>(), Ok(Column(3)));
+        assert_eq!("G".parse::<Column>(), Ok(Column(6)));
+        assert!("h".parse::<Column>().is_err());
+        assert!("ab".parse::<Column>().is_err());
+        assert_eq!(Column(2).to_string(), "c");
+    }
+
+    #[test]
+    fn full_columns_can_not_be_played() {
+        let state = play("aaaaaa");
+        assert!(!state.can_play(Column(0)));
+        assert!(!state.actions().contains(&Column(0)));
+    }
+
+    #[test]
+    fn finds_wins_and_blocks() {
+        let engine = ConnectFour::engine(4);
+
+        // Three in a row along the bottom, with the fourth square open
+        assert_eq!(engine.best_move(&play("aabbcc")), Some(Column(3)));
+        // The opponent threatens to complete the same line
+        assert_eq!(engine.best_move(&play("aabbc")), Some(Column(3)));
+    }
+
+    #[test]
+    fn undo_replays_the_earlier_moves() {
+        let mut game = ConnectFour::default();
+        for move_ in ["d", "d", "e"] {
+            game.play_move(move_).unwrap();
+        }
+        assert_eq!(game.undo().unwrap().as_deref(), Some("e"));
+        assert_eq!(game.move_history(), ["d", "d"]);
+        assert_eq!(game.0.to_string(), play("dd").to_string());
+    }
+}
@@ -0,0 +1,583 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const DEFAULT_SIZE: u8 = 3;
+/// Every line gets a bit of a `u64`.
+const MAX_LINES: usize = 64;
+
+/// How much of each box in a long chain goes to the player who's expected to control the chains,
+/// since their opponent will have to open them.
+const CHAIN_CONTROL_VALUE: f32 = 0.5;
+
+#[derive(Clone, Debug, Default)]
+pub struct DotsAndBoxes(DotsAndBoxesState, Vec<Line>);
+
+impl Game for DotsAndBoxes {
+    fn name(&self) -> String {
+        "Dots and Boxes".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " •───•───•
+ │ X │
+ •───•   •
+     │ O │
+ •   •───•"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        let (rows, columns) = (self.0.rows as u16, self.0.columns as u16);
+        // Wide enough for the board, and for the scores below it
+        ((4 * columns + 4).max(12) + 1, 2 * rows + 4)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Line::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        self.0
+            .parse_line(move_)
+            .is_some_and(|line| !self.0.is_drawn(line))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let line = self
+            .0
+            .parse_line(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&line);
+        self.1.push(line);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(8)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.0.rows, self.0.columns);
+    }
+}
+
+impl DotsAndBoxes {
+    /// A game on a grid of `rows` by `columns` boxes, which can have at most 64 lines between
+    /// its dots.
+    pub fn new(rows: u8, columns: u8) -> Self {
+        assert!(rows > 0 && columns > 0, "the grid needs at least one box");
+        assert!(
+            line_count(rows, columns) <= MAX_LINES,
+            "the grid can have at most {MAX_LINES} lines"
+        );
+        Self(DotsAndBoxesState::new(rows, columns), Vec::new())
+    }
+
+    /// The engine used to find computer moves, searching to `depth` plies. Completing a box is
+    /// never quiet, so whole chains are taken during quiescence search.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(8)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build dots and boxes engine")
+    }
+}
+
+/// The number of lines that can be drawn on a grid of `rows` by `columns` boxes.
+fn line_count(rows: u8, columns: u8) -> usize {
+    let (rows, columns) = (rows as usize, columns as usize);
+    (rows + 1) * columns + rows * (columns + 1)
+}
+
+/// A line between two neighbouring dots, written as the dots' column letters and row numbers,
+/// such as `a1-b1` for the top edge of the top left box. The dots can be given in either order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Line {
+    /// The dot at the top or left end of the line.
+    row: u8,
+    column: u8,
+    horizontal: bool,
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (row, column) = if self.horizontal {
+            (self.row, self.column + 1)
+        } else {
+            (self.row + 1, self.column)
+        };
+        write!(
+            f,
+            "{}{}-{}{}",
+            (b'a' + self.column) as char,
+            self.row + 1,
+            (b'a' + column) as char,
+            row + 1
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DotsAndBoxesState {
+    /// The size of the grid in boxes, which has one more row and column of dots.
+    rows: u8,
+    columns: u8,
+    /// The lines drawn, with the horizontal lines going along each row of dots from the top left,
+    /// followed by the vertical lines going along each row of boxes.
+    lines: u64,
+    /// The boxes completed by each player, going along each row from the top left. Max's boxes
+    /// are marked with an X and Min's with an O.
+    boxes: [u64; 2],
+    /// Completing a box gives the player another turn.
+    player: Player,
+}
+
+impl Default for DotsAndBoxesState {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIZE, DEFAULT_SIZE)
+    }
+}
+
+impl DotsAndBoxesState {
+    fn new(rows: u8, columns: u8) -> Self {
+        Self {
+            rows,
+            columns,
+            lines: 0,
+            boxes: [0; 2],
+            player: Player::Max,
+        }
+    }
+
+    fn parse_dot(&self, s: &str) -> Option<(u8, u8)> {
+        let s = s.trim().to_ascii_lowercase();
+        let column = s.bytes().next()?.checked_sub(b'a')?;
+        let row = s.get(1..)?.parse::<u8>().ok()?.checked_sub(1)?;
+        (column <= self.columns && row <= self.rows).then_some((row, column))
+    }
+
+    fn parse_line(&self, s: &str) -> Option<Line> {
+        let (first, second) = s.split_once('-')?;
+        let (first, second) = (self.parse_dot(first)?, self.parse_dot(second)?);
+        let ((row, column), end) = (first.min(second), first.max(second));
+        let horizontal = match (end.0 - row, end.1 - column) {
+            (0, 1) => true,
+            (1, 0) => false,
+            _ => return None,
+        };
+        Some(Line {
+            row,
+            column,
+            horizontal,
+        })
+    }
+
+    fn index(&self, line: Line) -> usize {
+        let (row, column) = (line.row as usize, line.column as usize);
+        let columns = self.columns as usize;
+        if line.horizontal {
+            row * columns + column
+        } else {
+            (self.rows as usize + 1) * columns + row * (columns + 1) + column
+        }
+    }
+
+    fn is_drawn(&self, line: Line) -> bool {
+        self.lines & 1 << self.index(line) != 0
+    }
+
+    fn all_lines(&self) -> u64 {
+        u64::MAX >> (MAX_LINES - line_count(self.rows, self.columns))
+    }
+
+    fn score(&self, player: Player) -> u32 {
+        self.boxes[player as usize].count_ones()
+    }
+
+    /// Every line, going along each row of dots and then each row of boxes.
+    fn lines(&self) -> impl Iterator<Item = Line> + '_ {
+        let horizontal = (0..=self.rows).flat_map(|row| {
+            (0..self.columns).map(move |column| Line {
+                row,
+                column,
+                horizontal: true,
+            })
+        });
+        let vertical = (0..self.rows).flat_map(|row| {
+            (0..=self.columns).map(move |column| Line {
+                row,
+                column,
+                horizontal: false,
+            })
+        });
+        horizontal.chain(vertical)
+    }
+
+    /// The lines around the box with `row` and `column` as its top left corner.
+    fn sides(&self, row: u8, column: u8) -> [Line; 4] {
+        let line = |row, column, horizontal| Line {
+            row,
+            column,
+            horizontal,
+        };
+        [
+            line(row, column, true),
+            line(row + 1, column, true),
+            line(row, column, false),
+            line(row, column + 1, false),
+        ]
+    }
+
+    fn box_lines(&self, row: u8, column: u8) -> u64 {
+        self.sides(row, column)
+            .into_iter()
+            .fold(0, |lines, line| lines | 1 << self.index(line))
+    }
+
+    /// The one or two boxes on either side of `line`.
+    fn beside(&self, line: Line) -> impl Iterator<Item = (u8, u8)> {
+        let (row, column) = (line.row, line.column);
+        let [before, after] = if line.horizontal {
+            [
+                row.checked_sub(1).map(|row| (row, column)),
+                (row < self.rows).then_some((row, column)),
+            ]
+        } else {
+            [
+                column.checked_sub(1).map(|column| (row, column)),
+                (column < self.columns).then_some((row, column)),
+            ]
+        };
+        before.into_iter().chain(after)
+    }
+
+    /// The number of boxes that drawing `line` completes.
+    fn completes(&self, line: Line) -> u32 {
+        let lines = self.lines | 1 << self.index(line);
+        self.beside(line)
+            .filter(|&(row, column)| {
+                let sides = self.box_lines(row, column);
+                lines & sides == sides
+            })
+            .count() as u32
+    }
+
+    /// Whether drawing `line` leaves a box with three sides for the opponent to complete.
+    fn is_sacrifice(&self, line: Line) -> bool {
+        let lines = self.lines | 1 << self.index(line);
+        self.beside(line)
+            .any(|(row, column)| (lines & self.box_lines(row, column)).count_ones() == 3)
+    }
+
+    /// The number of long chains, of three or more boxes, and the boxes in them.
+    ///
+    /// Boxes with two sides drawn are linked through their missing sides, so once one is given
+    /// away every box along the chain can be taken in turn.
+    fn long_chains(&self) -> (u32, u32) {
+        let links =
+            |row: u8, column: u8| (self.lines & self.box_lines(row, column)).count_ones() == 2;
+        let bit = |row: u8, column: u8| 1 << (row as u32 * self.columns as u32 + column as u32);
+
+        let mut visited = 0u64;
+        let mut stack = Vec::new();
+        let (mut chains, mut chain_boxes) = (0, 0);
+
+        for (row, column) in (0..self.rows).flat_map(|row| (0..self.columns).map(move |c| (row, c)))
+        {
+            if visited & bit(row, column) != 0 || !links(row, column) {
+                continue;
+            }
+
+            let mut size = 0;
+            visited |= bit(row, column);
+            stack.push((row, column));
+            while let Some((row, column)) = stack.pop() {
+                size += 1;
+                for side in self.sides(row, column) {
+                    if self.is_drawn(side) {
+                        continue;
+                    }
+                    for (row, column) in self.beside(side) {
+                        if visited & bit(row, column) == 0 && links(row, column) {
+                            visited |= bit(row, column);
+                            stack.push((row, column));
+                        }
+                    }
+                }
+            }
+
+            if size >= 3 {
+                chains += 1;
+                chain_boxes += size;
+            }
+        }
+
+        (chains, chain_boxes)
+    }
+}
+
+impl minimax::State<f32, Line> for DotsAndBoxesState {
+    fn is_terminal(&self) -> bool {
+        self.lines == self.all_lines()
+    }
+
+    fn evaluation(&self) -> f32 {
+        let [max, min] = [Player::Max, Player::Min].map(|player| self.score(player) as f32);
+        if self.is_terminal() {
+            return match max.total_cmp(&min) {
+                std::cmp::Ordering::Greater => f32::INFINITY,
+                std::cmp::Ordering::Less => f32::NEG_INFINITY,
+                std::cmp::Ordering::Equal => 0.0,
+            };
+        }
+
+        // By the long chain rule, the player who moves first wants the number of dots plus the
+        // number of long chains to be even, which forces their opponent to open the first one
+        let (chains, chain_boxes) = self.long_chains();
+        let dots = (self.rows as u32 + 1) * (self.columns as u32 + 1);
+        let control = if (dots + chains).is_multiple_of(2) {
+            1.0
+        } else {
+            -1.0
+        };
+        (max - min) + control * CHAIN_CONTROL_VALUE * chain_boxes as f32
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Line> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Line>) {
+        let start = actions.len();
+        actions.extend(self.lines().filter(|&line| !self.is_drawn(line)));
+
+        // Completing boxes comes first, then lines that don't give any away
+        actions[start..].sort_by_key(|&line| {
+            if self.completes(line) > 0 {
+                0
+            } else if !self.is_sacrifice(line) {
+                1
+            } else {
+                2
+            }
+        });
+    }
+
+    fn result(&self, action: &Line) -> Self {
+        assert!(!self.is_drawn(*action), "{action} is already drawn");
+
+        let mut state = self.clone();
+        state.lines |= 1 << self.index(*action);
+
+        let mut completed = false;
+        for (row, column) in self.beside(*action) {
+            let sides = self.box_lines(row, column);
+            if state.lines & sides == sides {
+                state.boxes[self.player as usize] |= 1 << (row * self.columns + column);
+                completed = true;
+            }
+        }
+
+        if !completed {
+            state.player = self.player.opposite();
+        }
+        state
+    }
+
+    fn is_quiet(&self, action: &Line) -> bool {
+        self.completes(*action) == 0
+    }
+
+    fn see(&self, action: &Line) -> f32 {
+        self.completes(*action) as f32
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // A bijective mix of the lines and Max's boxes, since the same lines can be drawn with
+        // different scores
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let boxes = self.boxes[Player::Max as usize] << 1 | self.player as u64;
+        Some(mix(self.lines) ^ mix(boxes).rotate_left(17))
+    }
+}
+
+impl Display for DotsAndBoxesState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "   a")?;
+        for column in 1..=self.columns {
+            write!(f, "   {}", (b'a' + column) as char)?;
+        }
+
+        for row in 0..=self.rows {
+            write!(f, "\n{:>2} •", row + 1)?;
+            for column in 0..self.columns {
+                let line = Line {
+                    row,
+                    column,
+                    horizontal: true,
+                };
+                let edge = if self.is_drawn(line) {
+                    "───"
+                } else {
+                    "   "
+                };
+                write!(f, "{edge}•")?;
+            }
+
+            if row == self.rows {
+                break;
+            }
+
+            write!(f, "\n   ")?;
+            for column in 0..=self.columns {
+                let line = Line {
+                    row,
+                    column,
+                    horizontal: false,
+                };
+                write!(f, "{}", if self.is_drawn(line) { '│' } else { ' ' })?;
+                if column == self.columns {
+                    break;
+                }
+
+                let bit = 1 << (row * self.columns + column);
+                let owner = if self.boxes[Player::Max as usize] & bit != 0 {
+                    'X'
+                } else if self.boxes[Player::Min as usize] & bit != 0 {
+                    'O'
+                } else {
+                    ' '
+                };
+                write!(f, " {owner} ")?;
+            }
+        }
+
+        write!(
+            f,
+            "\n\nX: {}  O: {}",
+            self.score(Player::Max),
+            self.score(Player::Min)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(lines: &[&str]) -> DotsAndBoxesState {
+        lines
+            .iter()
+            .fold(DotsAndBoxesState::default(), |state, line| {
+                state.result(&state.parse_line(line).unwrap())
+            })
+    }
+
+    #[test]
+    fn parses_lines() {
+        let state = DotsAndBoxesState::default();
+        let line = state.parse_line("b1-a1").unwrap();
+        assert_eq!(line.to_string(), "a1-b1");
+        assert_eq!(state.parse_line("D3 - D4").unwrap().to_string(), "d3-d4");
+        assert_eq!(state.index(state.parse_line("d3-d4").unwrap()), 23);
+
+        assert!(state.parse_line("a1-b2").is_none());
+        assert!(state.parse_line("a1-a3").is_none());
+        assert!(state.parse_line("d4-e4").is_none());
+        assert!(state.parse_line("a1").is_none());
+    }
+
+    #[test]
+    fn completing_a_box_gives_another_turn() {
+        let state = play(&["a1-b1", "a2-b2", "a1-a2"]);
+        assert_eq!(state.player, Player::Min);
+
+        let state = state.result(&state.parse_line("b1-b2").unwrap());
+        assert_eq!(state.score(Player::Min), 1);
+        assert_eq!(state.player, Player::Min);
+
+        // A line between two boxes can complete both at once
+        let state = play(&[
+            "a1-b1", "b1-c1", "a2-b2", "b2-c2", "a1-a2", "c1-c2", "b1-b2",
+        ]);
+        assert_eq!(state.score(Player::Max), 2);
+        assert_eq!(state.player, Player::Max);
+    }
+
+    #[test]
+    fn side_to_move_follows_extra_turns() {
+        let mut game = DotsAndBoxes::default();
+        for line in ["a1-b1", "a2-b2", "a1-a2", "b1-b2"] {
+            game.play_move(line).unwrap();
+        }
+        // The second player completed the box, so it's their turn again after an even number
+        // of moves
+        assert_eq!(game.move_history().len(), 4);
+        assert_eq!(game.side_to_move(), 1);
+
+        game.play_move("c1-c2").unwrap();
+        assert_eq!(game.side_to_move(), 0);
+    }
+
+    #[test]
+    fn finds_long_chains() {
+        // The top row of boxes is a chain of three, once each has two sides
+        let mut state = play(&["a1-b1", "b1-c1", "c1-d1", "a2-b2", "b2-c2", "c2-d2"]);
+        assert_eq!(state.long_chains(), (1, 3));
+
+        // Giving away the box at one end leaves only two linked boxes
+        state.lines |= 1 << state.index(state.parse_line("a1-a2").unwrap());
+        assert_eq!(state.long_chains(), (0, 0));
+    }
+
+    #[test]
+    fn engine_takes_a_chain() {
+        // Only the chain along the top row is left, and taking every box of it is the only way
+        // to win
+        let mut state = DotsAndBoxesState::default();
+        state.lines = state.all_lines();
+        for line in ["b1-b2", "c1-c2", "d1-d2"] {
+            state.lines &= !(1 << state.index(state.parse_line(line).unwrap()));
+        }
+
+        let result = DotsAndBoxes::engine(4).search(&state);
+        assert_eq!(result.score(), Some(f32::INFINITY));
+        let pv = result.pv().iter().map(Line::to_string).collect::<Vec<_>>();
+        assert_eq!(pv, ["b1-b2", "c1-c2", "d1-d2"]);
+    }
+}
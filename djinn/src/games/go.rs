@@ -0,0 +1,491 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const SIZE: u8 = 9;
+/// Files are lettered without `i`, which is too easily mistaken for `j`.
+const FILES: &[u8; 9] = b"abcdefghj";
+const STAR_POINTS: [u8; 5] = [20, 24, 40, 56, 60];
+
+const BOARD: u128 = (1 << 81) - 1;
+const FILE_A: u128 = file_mask(0);
+const FILE_J: u128 = file_mask(SIZE - 1);
+
+/// The points White is given for moving second, with a half point so games can't be drawn.
+const KOMI: f32 = 7.5;
+/// How many points out from its stones each player's influence is spread when guessing who
+/// will own the empty points before the game is over.
+const INFLUENCE_DISTANCE: usize = 3;
+/// The value of each stone on top of the point it's on, since influence alone is cheaper to gain
+/// by playing somewhere new than by capturing.
+const STONE_VALUE: f32 = 4.0;
+
+const fn file_mask(file: u8) -> u128 {
+    let mut mask = 0;
+    let mut rank = 0;
+    while rank < SIZE {
+        mask |= 1 << (rank * SIZE + file);
+        rank += 1;
+    }
+    mask
+}
+
+/// The points next to any of the points in `points`.
+fn neighbours(points: u128) -> u128 {
+    (points << SIZE | points >> SIZE | (points & !FILE_J) << 1 | (points & !FILE_A) >> 1)
+        & BOARD
+        & !points
+}
+
+/// The points in `within` connected to `start` through other points in `within`.
+fn flood(start: u128, within: u128) -> u128 {
+    let mut region = start & within;
+    loop {
+        let grown = (region | neighbours(region)) & within;
+        if grown == region {
+            return region;
+        }
+        region = grown;
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Go(GoState, Vec<Move>);
+
+impl Game for Go {
+    fn name(&self) -> String {
+        "Go".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "┌─┬─┬─┬─┐
+├─●─○─┼─┤
+├─○─●─╋─┤
+├─┼─●─○─┤
+└─┴─┴─┴─┘"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (28, 11)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // Komi has a half point, so there's always a winner
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.is_legal(move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(3)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["Black", "White"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Go {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(2)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build go engine")
+    }
+}
+
+/// Placing a stone on a point, written like `e5`, or passing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    Play(u8),
+    Pass,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected a point from a1 to j9, or `pass`";
+
+        let s = s.trim().to_ascii_lowercase();
+        if s == "pass" {
+            return Ok(Move::Pass);
+        }
+        let mut chars = s.chars();
+        let file = chars
+            .next()
+            .and_then(|file| FILES.iter().position(|&letter| letter as char == file))
+            .ok_or(EXPECTED)?;
+        match chars.as_str().parse::<u8>() {
+            Ok(rank @ 1..=SIZE) => Ok(Move::Play((rank - 1) * SIZE + file as u8)),
+            _ => Err(EXPECTED),
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Move::Play(point) => write!(
+                f,
+                "{}{}",
+                FILES[(point % SIZE) as usize] as char,
+                point / SIZE + 1
+            ),
+            Move::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GoState {
+    /// Each player's stones, as bitmasks of the points going along each rank from a1. Max plays
+    /// black and moves first.
+    stones: [u128; 2],
+    /// The point that can't be played because it would retake a ko straight away.
+    ko: Option<u8>,
+    /// The number of passes in a row, where the game ends after two.
+    passes: u8,
+    player: Player,
+}
+
+impl Default for GoState {
+    fn default() -> Self {
+        Self {
+            stones: [0; 2],
+            ko: None,
+            passes: 0,
+            player: Player::Max,
+        }
+    }
+}
+
+impl GoState {
+    fn empty(&self) -> u128 {
+        BOARD & !(self.stones[0] | self.stones[1])
+    }
+
+    /// The opponent's stones that playing on `point` would capture.
+    fn captures(&self, point: u8) -> u128 {
+        let opponent = self.stones[self.player.opposite() as usize];
+        let empty = self.empty() & !(1 << point);
+        let mut captured = 0;
+        let mut adjacent = neighbours(1 << point) & opponent;
+        while adjacent != 0 {
+            let group = flood(adjacent & adjacent.wrapping_neg(), opponent);
+            if neighbours(group) & empty == 0 {
+                captured |= group;
+            }
+            adjacent &= !group;
+        }
+        captured
+    }
+
+    /// Whether `point` is empty, isn't a ko, and wouldn't leave the stone played without
+    /// liberties.
+    fn is_legal_play(&self, point: u8) -> bool {
+        let stone = 1 << point;
+        if self.empty() & stone == 0 || self.ko == Some(point) {
+            return false;
+        }
+        if neighbours(stone) & self.empty() != 0 {
+            return true;
+        }
+        let own = self.stones[self.player as usize] | stone;
+        let empty = self.empty() & !stone | self.captures(point);
+        neighbours(flood(stone, own)) & empty != 0
+    }
+
+    fn is_legal(&self, move_: Move) -> bool {
+        match move_ {
+            Move::Play(point) => self.is_legal_play(point),
+            Move::Pass => true,
+        }
+    }
+
+    /// Each player's stones and the empty points only they surround, with every stone on the
+    /// board counted as alive.
+    fn area(&self) -> [u32; 2] {
+        let empty = self.empty();
+        let mut area = self.stones.map(u128::count_ones);
+        let mut remaining = empty;
+        while remaining != 0 {
+            let region = flood(remaining & remaining.wrapping_neg(), empty);
+            let border = neighbours(region);
+            match (border & self.stones[0] != 0, border & self.stones[1] != 0) {
+                (true, false) => area[0] += region.count_ones(),
+                (false, true) => area[1] += region.count_ones(),
+                _ => {}
+            }
+            remaining &= !region;
+        }
+        area
+    }
+
+    /// Black's area less White's, including komi.
+    fn score(&self) -> f32 {
+        let [black, white] = self.area();
+        black as f32 - white as f32 - KOMI
+    }
+
+    /// Each player's stones and the empty points closer to their stones than their opponent's,
+    /// up to a few points away.
+    fn influence(&self) -> [u32; 2] {
+        let mut claimed = self.stones;
+        let mut free = self.empty();
+        for _ in 0..INFLUENCE_DISTANCE {
+            let [black, white] = claimed.map(|stones| neighbours(stones) & free);
+            claimed[0] |= black & !white;
+            claimed[1] |= white & !black;
+            free &= !(black | white);
+        }
+        claimed.map(u128::count_ones)
+    }
+}
+
+impl minimax::State<f32, Move> for GoState {
+    fn is_terminal(&self) -> bool {
+        self.passes >= 2
+    }
+
+    fn evaluation(&self) -> f32 {
+        if self.is_terminal() {
+            return if self.score() > 0.0 {
+                f32::INFINITY
+            } else {
+                f32::NEG_INFINITY
+            };
+        }
+
+        let [black, white] = self.influence();
+        let [black_stones, white_stones] = self.stones.map(u128::count_ones);
+        black as f32 - white as f32 - KOMI
+            + (black_stones as f32 - white_stones as f32) * STONE_VALUE
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        // Filling in an eye of your own is legal but never worth searching
+        let own = self.stones[self.player as usize];
+        actions.extend(
+            (0..SIZE * SIZE)
+                .filter(|&point| neighbours(1 << point) & !own != 0 && self.is_legal_play(point))
+                .map(Move::Play),
+        );
+        actions.push(Move::Pass);
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let mut state = self.clone();
+        state.player = self.player.opposite();
+        state.ko = None;
+
+        let Move::Play(point) = *action else {
+            state.passes += 1;
+            return state;
+        };
+        assert!(self.is_legal_play(point), "{action} is not a legal move");
+
+        let (own, opponent) = (self.player as usize, self.player.opposite() as usize);
+        let captured = self.captures(point);
+        state.stones[own] |= 1 << point;
+        state.stones[opponent] &= !captured;
+        state.passes = 0;
+
+        // Taking a single stone with a single stone that could be taken straight back is a ko
+        if captured.count_ones() == 1 && neighbours(1 << point) & !captured & state.empty() == 0 {
+            let group = flood(1 << point, state.stones[own]);
+            if group.count_ones() == 1 {
+                state.ko = Some(captured.trailing_zeros() as u8);
+            }
+        }
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        match *action {
+            Move::Play(point) => self.captures(point) == 0,
+            Move::Pass => true,
+        }
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        match *action {
+            Move::Play(point) => self.captures(point).count_ones() as f32,
+            Move::Pass => 0.0,
+        }
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mut hash = 0;
+        for player in [Player::Max, Player::Min] {
+            let mut stones = self.stones[player as usize];
+            while stones != 0 {
+                hash ^= zobrist_key(stones.trailing_zeros() as usize, player);
+                stones &= stones - 1;
+            }
+        }
+        if let Some(ko) = self.ko {
+            hash ^= zobrist_key(81 + ko as usize, Player::Max);
+        }
+        hash ^= zobrist_key(162 + self.passes as usize, self.player);
+        Some(hash)
+    }
+}
+
+impl Display for GoState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for rank in (0..SIZE).rev() {
+            write!(f, "{} ", rank + 1)?;
+            for file in 0..SIZE {
+                let point = rank * SIZE + file;
+                let symbol = if self.stones[Player::Max as usize] & 1 << point != 0 {
+                    '●'
+                } else if self.stones[Player::Min as usize] & 1 << point != 0 {
+                    '○'
+                } else if STAR_POINTS.contains(&point) {
+                    '╋'
+                } else {
+                    match (rank, file) {
+                        (8, 0) => '┌',
+                        (8, 8) => '┐',
+                        (0, 0) => '└',
+                        (0, 8) => '┘',
+                        (8, _) => '┬',
+                        (0, _) => '┴',
+                        (_, 0) => '├',
+                        (_, 8) => '┤',
+                        _ => '┼',
+                    }
+                };
+                write!(f, "{symbol}")?;
+                if file < SIZE - 1 {
+                    write!(f, "─")?;
+                }
+            }
+            match rank {
+                8 => write!(f, "   ● {}", self.area()[0])?,
+                7 => write!(f, "   ○ {}", self.area()[1] as f32 + KOMI)?,
+                _ => {}
+            }
+            writeln!(f)?;
+        }
+        write!(f, "  a b c d e f g h j")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> GoState {
+        moves.iter().fold(GoState::default(), |state, move_| {
+            state.result(&move_.parse().unwrap())
+        })
+    }
+
+    #[test]
+    fn parses_moves() {
+        assert_eq!("a1".parse::<Move>(), Ok(Move::Play(0)));
+        assert_eq!("J9".parse::<Move>(), Ok(Move::Play(80)));
+        assert_eq!("h1".parse::<Move>(), Ok(Move::Play(7)));
+        assert_eq!(" Pass ".parse::<Move>(), Ok(Move::Pass));
+        assert!("i5".parse::<Move>().is_err());
+        assert!("e10".parse::<Move>().is_err());
+        assert_eq!(Move::Play(8).to_string(), "j1");
+    }
+
+    #[test]
+    fn surrounded_stones_are_captured() {
+        let state = play(&["e4", "e5", "d5", "pass", "f5", "pass", "e6"]);
+        assert_eq!(state.stones[Player::Min as usize], 0);
+        assert_eq!(state.stones[Player::Max as usize].count_ones(), 4);
+
+        // Playing into the middle of them would have no liberties
+        assert!(!state.is_legal("e5".parse().unwrap()));
+        assert!(state.is_legal("e7".parse().unwrap()));
+    }
+
+    #[test]
+    fn ko_cannot_be_retaken_straight_away() {
+        let state = play(&["d5", "e5", "e4", "f4", "e6", "f6", "a1", "g5", "f5"]);
+        assert_eq!(state.ko, Some(40));
+        assert!(!state.is_legal("e5".parse().unwrap()));
+
+        // Once White has played elsewhere, the ko can be taken back
+        let state = state.result(&"a9".parse().unwrap());
+        let state = state.result(&"b9".parse().unwrap());
+        assert!(state.is_legal("e5".parse().unwrap()));
+    }
+
+    #[test]
+    fn area_scoring_counts_stones_and_surrounded_points() {
+        let mut state = play(&["e1", "f1"]);
+        for rank in 2..=9 {
+            state = state
+                .result(&format!("e{rank}").parse().unwrap())
+                .result(&format!("f{rank}").parse().unwrap());
+        }
+        assert_eq!(state.area(), [45, 36]);
+        let state = state.result(&Move::Pass).result(&Move::Pass);
+        assert!(state.is_terminal());
+        assert_eq!(state.score(), 45.0 - 36.0 - KOMI);
+        assert_eq!(state.evaluation(), f32::INFINITY);
+    }
+
+    #[test]
+    fn engine_captures_a_stone_in_atari() {
+        let state = play(&["e4", "e5", "d5", "pass", "f5", "pass"]);
+        let move_ = Go::engine(3).best_move(&state).unwrap();
+        assert_eq!(move_.to_string(), "e6");
+    }
+}
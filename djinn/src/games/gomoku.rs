@@ -0,0 +1,444 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const DEFAULT_SIZE: u8 = 15;
+/// The largest board, which is the size of a Go board.
+const MAX_SIZE: u8 = 19;
+
+/// The row and column steps along a horizontal, vertical or diagonal line.
+const DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Moves are only searched this many squares or fewer from an existing stone, as ones further
+/// away are almost never better and the search couldn't cope with every empty square.
+const CANDIDATE_DISTANCE: i8 = 2;
+
+/// The values of a run of stones of each length, when one or both ends of it are open.
+const CLOSED_RUN_VALUES: [f32; 5] = [0.0, 0.0, 10.0, 100.0, 1_000.0];
+const OPEN_RUN_VALUES: [f32; 5] = [0.0, 1.0, 100.0, 1_000.0, 10_000.0];
+
+/// The values of the run a stone would join or block, used to search threatening moves first.
+const THREAT_VALUES: [f32; 6] = [0.0, 0.0, 1.0, 10.0, 100.0, 1_000.0];
+
+#[derive(Clone, Debug, Default)]
+pub struct Gomoku(GomokuState, Vec<Point>);
+
+impl Game for Gomoku {
+    fn name(&self) -> String {
+        "Gomoku".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "· · · · · ·
+· ○ ● · ○ ·
+· · ● ● · ·
+· · ● ○ · ·
+· ○ · · · ·"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        let size = self.0.size as u16;
+        (2 * size + 3, size + 1)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Point::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if let Some(winner) = self.0.winner {
+            Some(WinState::Decisive(winner))
+        } else if self.0.is_full() {
+            Some(WinState::Draw)
+        } else {
+            None
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        self.0
+            .parse_point(move_)
+            .is_some_and(|point| !self.0.is_terminal() && self.0.stone_at(point).is_none())
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let point = self
+            .0
+            .parse_point(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&point);
+        self.1.push(point);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(4)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.0.size);
+    }
+}
+
+impl Gomoku {
+    /// A game on a `size` by `size` board, which must be from 5 to 19 squares wide.
+    pub fn new(size: u8) -> Self {
+        assert!(
+            (5..=MAX_SIZE).contains(&size),
+            "gomoku boards must be from 5 to {MAX_SIZE} squares wide"
+        );
+        Self(GomokuState::new(size), Vec::new())
+    }
+
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build gomoku engine")
+    }
+}
+
+/// A point to place a stone on, written as a column letter and a row number, such as `h8` for
+/// the centre of a 15 by 15 board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point {
+    row: u8,
+    column: u8,
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.column) as char, self.row + 1)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GomokuState {
+    size: u8,
+    /// The stones on each point, going along each row from the bottom left.
+    stones: Vec<Option<Player>>,
+    stone_count: u16,
+    player: Player,
+    winner: Option<Player>,
+    /// The Zobrist hash of the stones, updated as they're placed.
+    hash: u64,
+}
+
+impl Default for GomokuState {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIZE)
+    }
+}
+
+impl GomokuState {
+    fn new(size: u8) -> Self {
+        Self {
+            size,
+            stones: vec![None; size as usize * size as usize],
+            stone_count: 0,
+            player: Player::Max,
+            winner: None,
+            hash: 0,
+        }
+    }
+
+    fn parse_point(&self, s: &str) -> Option<Point> {
+        let s = s.trim().to_ascii_lowercase();
+        let column = s.bytes().next()?.checked_sub(b'a')?;
+        let row = s.get(1..)?.parse::<u8>().ok()?.checked_sub(1)?;
+        (column < self.size && row < self.size).then_some(Point { row, column })
+    }
+
+    fn index(&self, point: Point) -> usize {
+        point.row as usize * self.size as usize + point.column as usize
+    }
+
+    fn stone_at(&self, point: Point) -> Option<Player> {
+        self.stones[self.index(point)]
+    }
+
+    fn is_full(&self) -> bool {
+        self.stone_count as usize == self.stones.len()
+    }
+
+    /// The point `distance` steps along `direction` from `point`, if it's on the board.
+    fn step(&self, point: Point, (dy, dx): (i8, i8), distance: i8) -> Option<Point> {
+        let row = point.row as i8 + dy * distance;
+        let column = point.column as i8 + dx * distance;
+        let size = self.size as i8;
+        ((0..size).contains(&row) && (0..size).contains(&column)).then_some(Point {
+            row: row as u8,
+            column: column as u8,
+        })
+    }
+
+    /// The number of `player`'s stones in a row from `point` along `direction`, not counting
+    /// `point` itself, and whether the square after them is empty.
+    fn run(&self, point: Point, direction: (i8, i8), player: Player) -> (usize, bool) {
+        let mut length = 0;
+        loop {
+            match self
+                .step(point, direction, length as i8 + 1)
+                .map(|point| self.stone_at(point))
+            {
+                Some(Some(stone)) if stone == player => length += 1,
+                Some(None) => return (length, true),
+                _ => return (length, false),
+            }
+        }
+    }
+
+    /// The length of the line `player` would have through `point` along `direction` by placing
+    /// a stone there.
+    fn line_through(&self, point: Point, (dy, dx): (i8, i8), player: Player) -> usize {
+        let (forwards, _) = self.run(point, (dy, dx), player);
+        let (backwards, _) = self.run(point, (-dy, -dx), player);
+        forwards + backwards + 1
+    }
+
+    /// The local value of placing a stone on `point`, from the lines it would make for the player
+    /// to move and the ones it would block for their opponent.
+    fn threat(&self, point: Point) -> f32 {
+        DIRECTIONS
+            .into_iter()
+            .flat_map(|direction| {
+                [self.player, self.player.opposite()]
+                    .map(|player| THREAT_VALUES[self.line_through(point, direction, player).min(5)])
+            })
+            .sum()
+    }
+
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.size).flat_map(|row| (0..self.size).map(move |column| Point { row, column }))
+    }
+}
+
+impl minimax::State<f32, Point> for GomokuState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some() || self.is_full()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        // Score every maximal run of stones by its length and how many of its ends are open
+        let mut value = 0.0;
+        for point in self.points() {
+            let Some(player) = self.stone_at(point) else {
+                continue;
+            };
+
+            for direction in DIRECTIONS {
+                let (dy, dx) = direction;
+                let (length, open_before) = self.run(point, (-dy, -dx), player);
+                if length > 0 {
+                    // Only count each run from its first stone
+                    continue;
+                }
+
+                let (length, open_after) = self.run(point, direction, player);
+                let values = match (open_before, open_after) {
+                    (true, true) => &OPEN_RUN_VALUES,
+                    (false, false) => continue,
+                    _ => &CLOSED_RUN_VALUES,
+                };
+                let run_value = values[(length + 1).min(4)];
+                value += match player {
+                    Player::Max => run_value,
+                    Player::Min => -run_value,
+                };
+            }
+        }
+        value
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Point> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Point>) {
+        if self.stone_count == 0 {
+            let centre = self.size / 2;
+            actions.push(Point {
+                row: centre,
+                column: centre,
+            });
+            return;
+        }
+
+        let mut candidates = vec![false; self.stones.len()];
+        for point in self
+            .points()
+            .filter(|&point| self.stone_at(point).is_some())
+        {
+            for dy in -CANDIDATE_DISTANCE..=CANDIDATE_DISTANCE {
+                for dx in -CANDIDATE_DISTANCE..=CANDIDATE_DISTANCE {
+                    if let Some(near) = self.step(point, (dy, dx), 1) {
+                        candidates[self.index(near)] = true;
+                    }
+                }
+            }
+        }
+
+        actions.extend(
+            self.points()
+                .filter(|&point| candidates[self.index(point)] && self.stone_at(point).is_none()),
+        );
+    }
+
+    fn result(&self, action: &Point) -> Self {
+        let index = self.index(*action);
+        assert!(self.stones[index].is_none(), "{action} is already taken");
+
+        let mut state = self.clone();
+        state.stones[index] = Some(self.player);
+        state.stone_count += 1;
+        state.hash ^= zobrist_key(index, self.player);
+
+        if DIRECTIONS
+            .into_iter()
+            .any(|direction| self.line_through(*action, direction, self.player) >= 5)
+        {
+            state.winner = Some(self.player);
+        }
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn is_quiet(&self, action: &Point) -> bool {
+        // Moves that make or block three in a row are searched first
+        DIRECTIONS.into_iter().all(|direction| {
+            [self.player, self.player.opposite()]
+                .into_iter()
+                .all(|player| self.line_through(*action, direction, player) < 3)
+        })
+    }
+
+    fn see(&self, action: &Point) -> f32 {
+        self.threat(*action)
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // The player to move follows from the number of stones
+        Some(self.hash)
+    }
+}
+
+impl Display for GomokuState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in (0..self.size).rev() {
+            write!(f, "{:>2}", row + 1)?;
+            for column in 0..self.size {
+                let stone = match self.stone_at(Point { row, column }) {
+                    Some(Player::Max) => '●',
+                    Some(Player::Min) => '○',
+                    None => '·',
+                };
+                write!(f, " {stone}")?;
+            }
+            writeln!(f)?;
+        }
+
+        write!(f, "  ")?;
+        for column in 0..self.size {
+            write!(f, " {}", (b'a' + column) as char)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(points: &[&str]) -> GomokuState {
+        points.iter().fold(GomokuState::default(), |state, point| {
+            state.result(&state.parse_point(point).unwrap())
+        })
+    }
+
+    #[test]
+    fn parses_points() {
+        let state = GomokuState::default();
+        assert_eq!(state.parse_point("h8"), Some(Point { row: 7, column: 7 }));
+        assert_eq!(
+            state.parse_point("O15"),
+            Some(Point {
+                row: 14,
+                column: 14
+            })
+        );
+        assert_eq!(state.parse_point("p1"), None);
+        assert_eq!(state.parse_point("a16"), None);
+        assert_eq!(state.parse_point("a0"), None);
+        assert_eq!(Point { row: 9, column: 2 }.to_string(), "c10");
+
+        assert_eq!(GomokuState::new(9).parse_point("j1"), None);
+    }
+
+    #[test]
+    fn five_in_a_row_wins() {
+        let state = play(&["a1", "a2", "b2", "b3", "c3", "c4", "d4", "d5"]);
+        assert_eq!(state.winner, None);
+        let state = state.result(&Point { row: 4, column: 4 });
+        assert_eq!(state.winner, Some(Player::Max));
+        assert!(state.is_terminal());
+    }
+
+    #[test]
+    fn candidates_are_near_stones() {
+        let state = GomokuState::default();
+        assert_eq!(state.actions(), [Point { row: 7, column: 7 }]);
+
+        // Every empty point within two of the stone in the corner
+        let state = play(&["a1"]);
+        assert_eq!(state.actions().len(), 8);
+    }
+
+    #[test]
+    fn open_lines_are_worth_more() {
+        let open = play(&["g8", "a1", "h8", "a15", "i8"]);
+        let closed = play(&["g8", "f8", "h8", "a15", "i8"]);
+        assert!(open.evaluation() > closed.evaluation());
+    }
+
+    #[test]
+    fn finds_wins_and_blocks() {
+        let engine = Gomoku::engine(2);
+
+        // Black completes an open four
+        let state = play(&["e8", "e1", "f8", "f1", "g8", "g1", "h8", "o15"]);
+        let best = engine.best_move(&state).unwrap();
+        assert!(["d8", "i8"].contains(&best.to_string().as_str()), "{best}");
+
+        // White has to block the open end of Black's four
+        let state = play(&["e8", "d8", "f8", "f1", "g8", "g1", "h8"]);
+        let best = engine.best_move(&state).unwrap();
+        assert_eq!(best.to_string(), "i8");
+    }
+}
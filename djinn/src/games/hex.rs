@@ -0,0 +1,413 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const DEFAULT_SIZE: u8 = 11;
+const MAX_SIZE: u8 = 19;
+
+/// The row and column steps to the six neighbours of a cell, on a board where each row is shifted
+/// half a cell to the right of the one above it.
+const NEIGHBOURS: [(i8, i8); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0)];
+
+#[derive(Clone, Debug, Default)]
+pub struct Hex(HexState, Vec<Cell>);
+
+impl Game for Hex {
+    fn name(&self) -> String {
+        "Hex".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "· ● · ○
+ ○ ● · ·
+  · ● ○ ·
+   · ● · ·
+    ○ ● · ·"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        let size = self.0.size as u16;
+        (3 * size + 2, size + 1)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Cell::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // Someone always wins once the board is full
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        self.0
+            .parse_cell(move_)
+            .is_some_and(|cell| !self.0.is_terminal() && self.0.stone_at(cell).is_none())
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let cell = self
+            .0
+            .parse_cell(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&cell);
+        self.1.push(cell);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(3)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.0.size);
+    }
+}
+
+impl Hex {
+    /// A game on a `size` by `size` board, which must be from 2 to 19 cells wide.
+    pub fn new(size: u8) -> Self {
+        assert!(
+            (2..=MAX_SIZE).contains(&size),
+            "hex boards must be from 2 to {MAX_SIZE} cells wide"
+        );
+        Self(HexState::new(size), Vec::new())
+    }
+
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build hex engine")
+    }
+}
+
+/// A cell to place a stone on, written as a column letter and a row number, such as `f6` for the
+/// centre of an 11 by 11 board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    row: u8,
+    column: u8,
+}
+
+impl Display for Cell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.column) as char, self.row + 1)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HexState {
+    size: u8,
+    /// The stones on each cell, going along each row from the top left.
+    stones: Vec<Option<Player>>,
+    /// A union-find forest of the cells, with a node for each edge of the board after them, in
+    /// which each group of connected stones is one tree. Stones on an edge are joined to its node.
+    parents: Vec<u16>,
+    /// Max moves first, and connects the top and bottom edges, while Min connects the left and
+    /// right edges.
+    player: Player,
+    winner: Option<Player>,
+    /// The Zobrist hash of the stones, updated as they're placed.
+    hash: u64,
+}
+
+impl Default for HexState {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIZE)
+    }
+}
+
+impl HexState {
+    fn new(size: u8) -> Self {
+        let cells = size as usize * size as usize;
+        Self {
+            size,
+            stones: vec![None; cells],
+            parents: (0..cells as u16 + 4).collect(),
+            player: Player::Max,
+            winner: None,
+            hash: 0,
+        }
+    }
+
+    fn parse_cell(&self, s: &str) -> Option<Cell> {
+        let s = s.trim().to_ascii_lowercase();
+        let column = s.bytes().next()?.checked_sub(b'a')?;
+        let row = s.get(1..)?.parse::<u8>().ok()?.checked_sub(1)?;
+        (column < self.size && row < self.size).then_some(Cell { row, column })
+    }
+
+    fn index(&self, cell: Cell) -> usize {
+        cell.row as usize * self.size as usize + cell.column as usize
+    }
+
+    fn stone_at(&self, cell: Cell) -> Option<Player> {
+        self.stones[self.index(cell)]
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        (0..self.size).flat_map(|row| (0..self.size).map(move |column| Cell { row, column }))
+    }
+
+    fn neighbours(&self, cell: Cell) -> impl Iterator<Item = Cell> + '_ {
+        NEIGHBOURS.into_iter().filter_map(move |(dy, dx)| {
+            let row = cell.row as i8 + dy;
+            let column = cell.column as i8 + dx;
+            let size = self.size as i8;
+            ((0..size).contains(&row) && (0..size).contains(&column)).then_some(Cell {
+                row: row as u8,
+                column: column as u8,
+            })
+        })
+    }
+
+    /// The union-find nodes of the two edges `player` has to connect, and whether `cell` is on
+    /// each of them.
+    fn edges(&self, player: Player, cell: Cell) -> [(usize, bool); 2] {
+        let first = self.stones.len() + 2 * player as usize;
+        let last = self.size - 1;
+        match player {
+            Player::Max => [(first, cell.row == 0), (first + 1, cell.row == last)],
+            Player::Min => [(first, cell.column == 0), (first + 1, cell.column == last)],
+        }
+    }
+
+    fn find(&mut self, mut node: usize) -> usize {
+        while self.parents[node] as usize != node {
+            // Path halving keeps the trees shallow
+            let grandparent = self.parents[self.parents[node] as usize];
+            self.parents[node] = grandparent;
+            node = grandparent as usize;
+        }
+        node
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b as u16;
+    }
+
+    /// The number of empty cells `player` still has to fill to connect their edges, found by a
+    /// breadth-first search where their own stones cost nothing and their opponent's block.
+    fn distance(&self, player: Player) -> u32 {
+        let cost = |cell: Cell| match self.stone_at(cell) {
+            Some(stone) if stone == player => Some(0),
+            Some(_) => None,
+            None => Some(1),
+        };
+
+        let mut distances = vec![u32::MAX; self.stones.len()];
+        let mut queue = VecDeque::new();
+        let visit =
+            |queue: &mut VecDeque<Cell>, distances: &mut [u32], cell: Cell, distance: u32| {
+                let Some(cost) = cost(cell) else {
+                    return;
+                };
+                let index = self.index(cell);
+                if distance + cost < distances[index] {
+                    distances[index] = distance + cost;
+                    // Cells reached for free go to the front, which keeps the queue in order
+                    if cost == 0 {
+                        queue.push_front(cell);
+                    } else {
+                        queue.push_back(cell);
+                    }
+                }
+            };
+
+        for cell in self.cells().filter(|&cell| self.edges(player, cell)[0].1) {
+            visit(&mut queue, &mut distances, cell, 0);
+        }
+
+        while let Some(cell) = queue.pop_front() {
+            let distance = distances[self.index(cell)];
+            if self.edges(player, cell)[1].1 {
+                return distance;
+            }
+            for neighbour in self.neighbours(cell) {
+                visit(&mut queue, &mut distances, neighbour, distance);
+            }
+        }
+
+        // The opponent has already connected their edges
+        u32::MAX
+    }
+}
+
+impl minimax::State<f32, Cell> for HexState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => f32::INFINITY,
+            Some(Player::Min) => f32::NEG_INFINITY,
+            None => self.distance(Player::Min) as f32 - self.distance(Player::Max) as f32,
+        }
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Cell> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Cell>) {
+        let start = actions.len();
+        actions.extend(self.cells().filter(|&cell| self.stone_at(cell).is_none()));
+
+        // Search from the centre outwards, as central cells are usually stronger and the
+        // evaluation often can't tell moves apart
+        let centre = (self.size as i16 - 1) / 2;
+        actions[start..].sort_by_key(|cell| {
+            let (dy, dx) = (cell.row as i16 - centre, cell.column as i16 - centre);
+            dy.abs() + dx.abs() + (dy + dx).abs()
+        });
+    }
+
+    fn result(&self, action: &Cell) -> Self {
+        let index = self.index(*action);
+        assert!(self.stones[index].is_none(), "{action} is already taken");
+
+        let mut state = self.clone();
+        state.stones[index] = Some(self.player);
+        state.hash ^= zobrist_key(index, self.player);
+
+        for neighbour in self.neighbours(*action) {
+            if self.stone_at(neighbour) == Some(self.player) {
+                state.union(index, self.index(neighbour));
+            }
+        }
+        let edges = self.edges(self.player, *action);
+        for (edge, touches) in edges {
+            if touches {
+                state.union(index, edge);
+            }
+        }
+
+        let [(first, _), (last, _)] = edges;
+        if state.find(first) == state.find(last) {
+            state.winner = Some(self.player);
+        }
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // The player to move follows from the number of stones
+        Some(self.hash)
+    }
+}
+
+impl Display for HexState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "  ")?;
+        for column in 0..self.size {
+            write!(f, " {}", (b'a' + column) as char)?;
+        }
+
+        for row in 0..self.size {
+            write!(f, "\n{}{:>2}", " ".repeat(row as usize), row + 1)?;
+            for column in 0..self.size {
+                let stone = match self.stone_at(Cell { row, column }) {
+                    Some(Player::Max) => '●',
+                    Some(Player::Min) => '○',
+                    None => '·',
+                };
+                write!(f, " {stone}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(size: u8, cells: &[&str]) -> HexState {
+        cells.iter().fold(HexState::new(size), |state, cell| {
+            state.result(&state.parse_cell(cell).unwrap())
+        })
+    }
+
+    #[test]
+    fn parses_cells() {
+        let state = HexState::default();
+        assert_eq!(state.parse_cell("f6"), Some(Cell { row: 5, column: 5 }));
+        assert_eq!(
+            state.parse_cell("K11"),
+            Some(Cell {
+                row: 10,
+                column: 10
+            })
+        );
+        assert_eq!(state.parse_cell("l1"), None);
+        assert_eq!(state.parse_cell("a12"), None);
+        assert_eq!(Cell { row: 0, column: 2 }.to_string(), "c1");
+    }
+
+    #[test]
+    fn connecting_edges_wins() {
+        // Max joins the top and bottom along a winding path, with Min's stones in the way
+        let state = play(4, &["b1", "a1", "b2", "c2", "a3", "b3"]);
+        assert_eq!(state.winner, None);
+        assert!(!state.is_terminal());
+
+        let state = state.result(&Cell { row: 3, column: 0 });
+        assert_eq!(state.winner, Some(Player::Max));
+
+        // A line across the board from left to right only wins for Min
+        let state = play(3, &["a1", "a2", "c3", "b2", "a3", "c2"]);
+        assert_eq!(state.winner, Some(Player::Min));
+        let state = play(3, &["a2", "a1", "b2", "a3", "c2"]);
+        assert_eq!(state.winner, None);
+    }
+
+    #[test]
+    fn distance_counts_missing_stones() {
+        let state = HexState::new(5);
+        assert_eq!(state.distance(Player::Max), 5);
+
+        // Min's stones block the direct route, while Max's own count for nothing
+        let state = play(5, &["c1", "c2", "b3", "a5"]);
+        assert_eq!(state.distance(Player::Max), 3);
+        assert_eq!(state.distance(Player::Min), 4);
+    }
+
+    #[test]
+    fn finds_winning_moves() {
+        // Max only needs b3 to finish the connection, and has to take it before Min blocks it
+        let state = play(3, &["b1", "a2", "a3", "c1"]);
+        assert_eq!(
+            Hex::engine(2).best_move(&state),
+            Some(Cell { row: 1, column: 1 })
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const FILE_A: u16 = 0b001_001_001;
+const FILE_C: u16 = FILE_A << 2;
+const RANK_1: u16 = 0b111;
+const RANK_3: u16 = RANK_1 << 6;
+/// No game lasts longer than this, since each pawn can move at most twice.
+const MAX_PLIES: u32 = 12;
+
+#[derive(Clone, Debug, Default)]
+pub struct Hexapawn(HexapawnState, Vec<Move>);
+
+impl Game for Hexapawn {
+    fn name(&self) -> String {
+        "Hexapawn".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " ♟ │ ♟ │ ♟
+───┼───┼───
+   │ ♙ │
+───┼───┼───
+ ♙ │   │ ♙"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (16, 8)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // A player who can't move loses, so every game has a winner
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.find_move(move_).is_some())
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .ok()
+            .and_then(|move_| self.0.find_move(move_))
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(MAX_PLIES)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["White", "Black"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Hexapawn {
+    /// An engine searching to `depth` plies. The game is small enough that every line can be
+    /// searched to the end, so there's no heuristic at all and positions are solved exactly when
+    /// `depth` is [`MAX_PLIES`].
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 10)
+            .build()
+            .expect("failed to build hexapawn engine")
+    }
+}
+
+/// A pawn moving one square forwards, written as the squares it moves between, such as `a1-a2`,
+/// or `a2xb3` for a capture. Pawns only move straight ahead and only capture diagonally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    from: u8,
+    to: u8,
+    capture: bool,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected two squares, such as `a1-a2`";
+        let s = s.trim().to_ascii_lowercase();
+        let square = |name: &[u8]| match *name {
+            [file @ b'a'..=b'c', rank @ b'1'..=b'3'] => Ok((rank - b'1') * 3 + file - b'a'),
+            _ => Err(EXPECTED),
+        };
+
+        let bytes = s.as_bytes();
+        let (from, to, capture) = match bytes.len() {
+            4 => (&bytes[..2], &bytes[2..], false),
+            5 if matches!(bytes[2], b'-' | b'x') => (&bytes[..2], &bytes[3..], bytes[2] == b'x'),
+            _ => Err(EXPECTED)?,
+        };
+        Ok(Move {
+            from: square(from)?,
+            to: square(to)?,
+            capture,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let square = |square: u8| format!("{}{}", (b'a' + square % 3) as char, square / 3 + 1);
+        let separator = if self.capture { 'x' } else { '-' };
+        write!(f, "{}{separator}{}", square(self.from), square(self.to))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HexapawnState {
+    /// The pawns of each player, indexed by `Player`. White moves first from the first rank and
+    /// is the maximising player, while Black starts on the last.
+    pawns: [u16; 2],
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl Default for HexapawnState {
+    fn default() -> Self {
+        Self {
+            pawns: [RANK_1, RANK_3],
+            player: Player::Max,
+            winner: None,
+        }
+    }
+}
+
+impl HexapawnState {
+    /// The squares `player`'s pawns can move to from `pawns`, straight ahead and to the left and
+    /// right diagonally, along with the shift back to the square moved from.
+    fn targets(player: Player, pawns: u16) -> [(u16, i8); 3] {
+        let targets = match player {
+            Player::Max => [
+                (pawns << 3, 3),
+                ((pawns & !FILE_A) << 2, 2),
+                ((pawns & !FILE_C) << 4, 4),
+            ],
+            Player::Min => [
+                (pawns >> 3, -3),
+                ((pawns & !FILE_A) >> 4, -4),
+                ((pawns & !FILE_C) >> 2, -2),
+            ],
+        };
+        targets.map(|(targets, step)| (targets & (RANK_1 | RANK_1 << 3 | RANK_3), step))
+    }
+
+    /// The rank `player` has to reach to win.
+    fn goal(player: Player) -> u16 {
+        match player {
+            Player::Max => RANK_3,
+            Player::Min => RANK_1,
+        }
+    }
+
+    fn has_moves(&self, player: Player) -> bool {
+        let own = self.pawns[player as usize];
+        let opponent = self.pawns[player.opposite() as usize];
+        let [(ahead, _), (left, _), (right, _)] = Self::targets(player, own);
+        ahead & !(own | opponent) != 0 || (left | right) & opponent != 0
+    }
+
+    /// The legal move matching the squares of `move_`, whether or not it was written as a capture.
+    fn find_move(&self, move_: Move) -> Option<Move> {
+        self.actions()
+            .into_iter()
+            .find(|action| action.from == move_.from && action.to == move_.to)
+    }
+}
+
+impl minimax::State<f32, Move> for HexapawnState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => f32::INFINITY,
+            Some(Player::Min) => f32::NEG_INFINITY,
+            None => 0.0,
+        }
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let own = self.pawns[self.player as usize];
+        let opponent = self.pawns[self.player.opposite() as usize];
+        let [ahead, left, right] = Self::targets(self.player, own);
+
+        for (mut targets, step, capture) in [
+            (left.0 & opponent, left.1, true),
+            (right.0 & opponent, right.1, true),
+            (ahead.0 & !(own | opponent), ahead.1, false),
+        ] {
+            while targets != 0 {
+                let to = targets.trailing_zeros() as u8;
+                actions.push(Move {
+                    from: (to as i8 - step) as u8,
+                    to,
+                    capture,
+                });
+                targets &= targets - 1;
+            }
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let own = self.player as usize;
+        let opponent = self.player.opposite() as usize;
+        assert!(
+            self.pawns[own] & 1 << action.from != 0 && self.pawns[own] & 1 << action.to == 0,
+            "{action} is not a legal move"
+        );
+
+        let mut state = self.clone();
+        state.pawns[own] ^= 1 << action.from | 1 << action.to;
+        state.pawns[opponent] &= !(1 << action.to);
+        state.player = self.player.opposite();
+
+        // Reaching the far side, capturing every pawn and leaving the opponent stuck all win
+        if 1 << action.to & Self::goal(self.player) != 0
+            || state.pawns[opponent] == 0
+            || !state.has_moves(state.player)
+        {
+            state.winner = Some(self.player);
+        }
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let [max, min] = self.pawns.map(u64::from);
+        Some(max | min << 9 | (self.player as u64) << 18)
+    }
+}
+
+impl Display for HexapawnState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ┌───┬───┬───┐")?;
+        for rank in (0..3).rev() {
+            write!(f, "{} │", rank + 1)?;
+            for file in 0..3 {
+                let square = 1 << (rank * 3 + file);
+                let pawn = if self.pawns[Player::Max as usize] & square != 0 {
+                    '♙'
+                } else if self.pawns[Player::Min as usize] & square != 0 {
+                    '♟'
+                } else {
+                    ' '
+                };
+                write!(f, " {pawn} │")?;
+            }
+            if rank != 0 {
+                writeln!(f, "\n  ├───┼───┼───┤")?;
+            }
+        }
+        write!(f, "\n  └───┴───┴───┘\n    a   b   c")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> HexapawnState {
+        moves.iter().fold(HexapawnState::default(), |state, move_| {
+            let move_ = state.find_move(move_.parse().unwrap()).unwrap();
+            state.result(&move_)
+        })
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "a1-a2".parse::<Move>().unwrap();
+        assert_eq!((move_.from, move_.to, move_.capture), (0, 3, false));
+        assert_eq!("B2xC3".parse::<Move>().unwrap().to_string(), "b2xc3");
+        assert!("a1-a4".parse::<Move>().is_err());
+        assert!("d1-d2".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn pawns_only_capture_diagonally() {
+        let state = play(&["b1-b2"]);
+        assert!(state.find_move("b3-b2".parse().unwrap()).is_none());
+        assert!(state.find_move("a3xb2".parse().unwrap()).is_some());
+        assert!(state.find_move("a3-a2".parse().unwrap()).is_some());
+        assert_eq!(state.actions().len(), 4);
+    }
+
+    #[test]
+    fn reaching_the_far_side_or_blocking_every_pawn_wins() {
+        let state = play(&["b1-b2", "c3-c2"]);
+        assert!(!state.is_terminal());
+        let state = state.result(&state.find_move("b2xa3".parse().unwrap()).unwrap());
+        assert_eq!(state.winner, Some(Player::Max));
+
+        // White's last two pawns are both blocked, with nothing to capture
+        let state = play(&["a1-a2", "b3xa2", "b1xa2", "c3-c2"]);
+        assert_eq!(state.winner, Some(Player::Min));
+    }
+
+    #[test]
+    fn solver_finds_the_second_player_wins() {
+        let engine = Hexapawn::engine(MAX_PLIES);
+        assert_eq!(engine.value(&HexapawnState::default()), f32::NEG_INFINITY);
+
+        let state = play(&["b1-b2"]);
+        let move_ = engine.best_move(&state).unwrap();
+        assert_eq!(engine.value(&state.result(&move_)), f32::NEG_INFINITY);
+    }
+}
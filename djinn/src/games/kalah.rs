@@ -0,0 +1,373 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const PITS: usize = 6;
+const SEEDS: u8 = 4;
+
+/// The board is a loop sown around anticlockwise: Max's pits along the bottom from left to right,
+/// then Max's store on the right, then Min's pits along the top from right to left, and finally
+/// Min's store on the left.
+const HOLES: usize = 2 * (PITS + 1);
+
+/// The value of each seed still in a player's pits, which they're likelier to end up with.
+const PIT_SEED_VALUE: f32 = 0.25;
+
+/// The index of `player`'s store.
+fn store(player: Player) -> usize {
+    match player {
+        Player::Max => PITS,
+        Player::Min => HOLES - 1,
+    }
+}
+
+/// The index of `player`'s first pit.
+fn first_pit(player: Player) -> usize {
+    match player {
+        Player::Max => 0,
+        Player::Min => PITS + 1,
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Kalah(KalahState, Vec<Pit>);
+
+impl Game for Kalah {
+    fn name(&self) -> String {
+        "Kalah".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "┌──┬─┬─┬──┐
+│  │3│5│  │
+│ 7├─┼─┤ 4│
+│  │4│2│  │
+└──┴─┴─┴──┘"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (42, 7)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Pit::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Pit>()
+            .is_ok_and(|pit| !self.0.is_terminal() && self.0.seeds(pit) > 0)
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let pit = move_
+            .parse::<Pit>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&pit);
+        self.1.push(pit);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(14)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Kalah {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build kalah engine")
+    }
+}
+
+/// One of the pits of the player to move, numbered from 1 to 6 in the order seeds are sown in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pit(u8);
+
+impl FromStr for Pit {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse::<u8>() {
+            Ok(pit @ 1..=6) => Ok(Pit(pit - 1)),
+            _ => Err("expected a pit from 1 to 6"),
+        }
+    }
+}
+
+impl Display for Pit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0 + 1)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KalahState {
+    /// The seeds in each pit and store, in the order they're sown in.
+    holes: [u8; HOLES],
+    player: Player,
+}
+
+impl Default for KalahState {
+    fn default() -> Self {
+        let mut holes = [SEEDS; HOLES];
+        holes[store(Player::Max)] = 0;
+        holes[store(Player::Min)] = 0;
+        Self {
+            holes,
+            player: Player::Max,
+        }
+    }
+}
+
+impl KalahState {
+    fn pits(&self, player: Player) -> &[u8] {
+        let first = first_pit(player);
+        &self.holes[first..first + PITS]
+    }
+
+    fn seeds(&self, pit: Pit) -> u8 {
+        self.holes[first_pit(self.player) + pit.0 as usize]
+    }
+}
+
+impl minimax::State<f32, Pit> for KalahState {
+    fn is_terminal(&self) -> bool {
+        // Once either side runs out, the remaining seeds are moved to the stores straight away
+        self.pits(Player::Max).iter().all(|&seeds| seeds == 0)
+    }
+
+    fn evaluation(&self) -> f32 {
+        let [max, min] = [Player::Max, Player::Min].map(|player| self.holes[store(player)] as f32);
+        if self.is_terminal() {
+            return match max.total_cmp(&min) {
+                std::cmp::Ordering::Greater => f32::INFINITY,
+                std::cmp::Ordering::Less => f32::NEG_INFINITY,
+                std::cmp::Ordering::Equal => 0.0,
+            };
+        }
+
+        let [max_pits, min_pits] = [Player::Max, Player::Min].map(|player| {
+            self.pits(player)
+                .iter()
+                .map(|&seeds| seeds as f32)
+                .sum::<f32>()
+        });
+        (max - min) + (max_pits - min_pits) * PIT_SEED_VALUE
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Pit> {
+        let mut actions = Vec::with_capacity(PITS);
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Pit>) {
+        actions.extend((0..PITS as u8).map(Pit).filter(|&pit| self.seeds(pit) > 0));
+    }
+
+    fn result(&self, action: &Pit) -> Self {
+        let mut state = self.clone();
+        let own_store = store(self.player);
+        let start = first_pit(self.player) + action.0 as usize;
+
+        let mut seeds = std::mem::take(&mut state.holes[start]);
+        assert!(seeds > 0, "pit {action} is empty");
+
+        // Sow one seed into each following hole, skipping the opponent's store
+        let mut hole = start;
+        while seeds > 0 {
+            hole = (hole + 1) % HOLES;
+            if hole != store(self.player.opposite()) {
+                state.holes[hole] += 1;
+                seeds -= 1;
+            }
+        }
+
+        // Ending in an empty pit of their own captures the seeds in the pit opposite, which is the
+        // same distance from the other side's store
+        let own_pits = first_pit(self.player)..first_pit(self.player) + PITS;
+        if own_pits.contains(&hole) && state.holes[hole] == 1 {
+            let opposite = HOLES - 2 - hole;
+            if state.holes[opposite] > 0 {
+                state.holes[own_store] += 1 + std::mem::take(&mut state.holes[opposite]);
+                state.holes[hole] = 0;
+            }
+        }
+
+        // Ending in their own store gives the player another turn
+        if hole != own_store {
+            state.player = self.player.opposite();
+        }
+
+        // The game ends once either side is empty, with each player keeping what's left on theirs
+        if [Player::Max, Player::Min]
+            .into_iter()
+            .any(|player| state.pits(player).iter().all(|&seeds| seeds == 0))
+        {
+            for player in [Player::Max, Player::Min] {
+                let first = first_pit(player);
+                let remaining: u8 = state.holes[first..first + PITS].iter().sum();
+                state.holes[first..first + PITS].fill(0);
+                state.holes[store(player)] += remaining;
+            }
+        }
+
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let key = self
+            .holes
+            .iter()
+            .fold(self.player as u64 + 1, |key, &seeds| {
+                (key ^ seeds as u64).wrapping_mul(0x0000_0100_0000_01b3)
+            });
+        // Mix the high bits, which depend on every hole, down into the low ones
+        Some(key ^ key >> 29)
+    }
+}
+
+impl Display for KalahState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let row = |f: &mut Formatter<'_>, pits: &mut dyn Iterator<Item = &u8>| {
+            write!(f, "│    │")?;
+            for seeds in pits {
+                write!(f, " {seeds:>2} │")?;
+            }
+            writeln!(f, "    │")
+        };
+
+        writeln!(f, "        6    5    4    3    2    1")?;
+        writeln!(f, "┌────┬────┬────┬────┬────┬────┬────┬────┐")?;
+        row(f, &mut self.pits(Player::Min).iter().rev())?;
+        writeln!(
+            f,
+            "│ {:>2} ├────┼────┼────┼────┼────┼────┤ {:>2} │",
+            self.holes[store(Player::Min)],
+            self.holes[store(Player::Max)]
+        )?;
+        row(f, &mut self.pits(Player::Max).iter())?;
+        writeln!(f, "└────┴────┴────┴────┴────┴────┴────┴────┘")?;
+        write!(f, "        1    2    3    4    5    6")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(pits: &str) -> KalahState {
+        pits.chars()
+            .map(|pit| pit.to_string().parse::<Pit>().unwrap())
+            .fold(KalahState::default(), |state, pit| state.result(&pit))
+    }
+
+    #[test]
+    fn ending_in_the_store_gives_another_turn() {
+        // Pit 3 has four seeds, the last of which lands in the store
+        let state = play("3");
+        assert_eq!(state.holes[store(Player::Max)], 1);
+        assert_eq!(state.player, Player::Max);
+
+        let state = state.result(&Pit(0));
+        assert_eq!(state.player, Player::Min);
+        assert_eq!(state.pits(Player::Max), [0, 5, 1, 6, 6, 5]);
+    }
+
+    #[test]
+    fn sowing_skips_the_opponents_store() {
+        let mut state = KalahState::default();
+        state.holes[5] = 10;
+        let state = state.result(&Pit(5));
+        assert_eq!(state.holes[store(Player::Min)], 0);
+        assert_eq!(state.pits(Player::Min), [5, 5, 5, 5, 5, 5]);
+        assert_eq!(state.pits(Player::Max), [5, 5, 5, 4, 4, 0]);
+    }
+
+    #[test]
+    fn landing_in_an_empty_pit_captures() {
+        // Max's pit 1 holds one seed, which lands in the empty pit 2 opposite Min's pit 5
+        let mut state = KalahState::default();
+        state.holes[0] = 1;
+        state.holes[1] = 0;
+        let state = state.result(&Pit(0));
+        assert_eq!(state.holes[store(Player::Max)], 5);
+        assert_eq!(state.pits(Player::Max), [0, 0, 4, 4, 4, 4]);
+        assert_eq!(state.pits(Player::Min), [4, 4, 4, 4, 0, 4]);
+    }
+
+    #[test]
+    fn emptying_a_side_ends_the_game() {
+        let mut state = KalahState {
+            holes: [0; HOLES],
+            player: Player::Max,
+        };
+        state.holes[5] = 1;
+        state.holes[8] = 3;
+        state.holes[store(Player::Min)] = 20;
+        state.holes[store(Player::Max)] = 24;
+
+        // Max's last seed goes to its store, and Min keeps the three on its side
+        let state = state.result(&Pit(5));
+        assert!(state.is_terminal());
+        assert_eq!(state.holes[store(Player::Max)], 25);
+        assert_eq!(state.holes[store(Player::Min)], 23);
+        assert_eq!(state.evaluation(), f32::INFINITY);
+    }
+
+    #[test]
+    fn engine_takes_extra_turns() {
+        // Playing pit 6 and then pit 5 keeps every seed on Max's side, while playing pit 5 first
+        // sows the last one across to Min and only draws
+        let mut state = KalahState {
+            holes: [0; HOLES],
+            player: Player::Max,
+        };
+        state.holes[4] = 2;
+        state.holes[5] = 1;
+        state.holes[10] = 2;
+        state.holes[store(Player::Max)] = 22;
+        state.holes[store(Player::Min)] = 21;
+
+        let result = Kalah::engine(4).search(&state);
+        assert_eq!(result.score(), Some(f32::INFINITY));
+        assert_eq!(result.pv(), [Pit(5), Pit(4), Pit(5)]);
+    }
+}
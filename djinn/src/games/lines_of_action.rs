@@ -0,0 +1,467 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+const RANK_1: u64 = 0xff;
+const RANK_8: u64 = RANK_1 << 56;
+
+/// The file and rank steps of the four lines through a square, which pieces can move along in
+/// either direction.
+const LINES: [(i8, i8); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// The value of each group of pieces a player has more than their opponent, since connecting
+/// every piece into one group wins.
+const GROUP_VALUE: f32 = 4.0;
+/// The value of each square pieces are further from the centre of their group on average.
+const SPREAD_VALUE: f32 = 6.0;
+/// The value of each ring of squares closer to the centre of the board a piece is, where pieces
+/// have the most room to move and meet.
+const CENTRE_VALUE: f32 = 0.5;
+
+#[derive(Clone, Debug, Default)]
+pub struct LinesOfAction(LinesOfActionState, Vec<Move>);
+
+impl Game for LinesOfAction {
+    fn name(&self) -> String {
+        "Lines of Action".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "   │ ● │ ●
+───┼───┼───
+ ○ │ ● │
+───┼───┼───
+ ○ │   │ ○"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (36, 18)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // Pieces can always be captured or connected, so there are no draws
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.find_move(move_).is_some())
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .ok()
+            .and_then(|move_| self.0.find_move(move_))
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(4)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl LinesOfAction {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build lines of action engine")
+    }
+}
+
+/// A piece moving along a line, written as the squares it moves between, such as `b1-b3`, or
+/// `b1xd3` for a capture. A piece moves exactly as many squares as there are pieces on the line
+/// it moves along.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    from: u8,
+    to: u8,
+    capture: bool,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected two squares, such as `b1-b3`";
+        let s = s.trim().to_ascii_lowercase();
+        let square = |name: &[u8]| match *name {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Ok((rank - b'1') * 8 + file - b'a'),
+            _ => Err(EXPECTED),
+        };
+
+        let bytes = s.as_bytes();
+        let (from, to, capture) = match bytes.len() {
+            4 => (&bytes[..2], &bytes[2..], false),
+            5 if matches!(bytes[2], b'-' | b'x') => (&bytes[..2], &bytes[3..], bytes[2] == b'x'),
+            _ => Err(EXPECTED)?,
+        };
+        Ok(Move {
+            from: square(from)?,
+            to: square(to)?,
+            capture,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let square = |square: u8| format!("{}{}", (b'a' + square % 8) as char, square / 8 + 1);
+        let separator = if self.capture { 'x' } else { '-' };
+        write!(f, "{}{separator}{}", square(self.from), square(self.to))
+    }
+}
+
+/// The squares `pieces` are next to in any direction, including diagonally.
+fn neighbours(pieces: u64) -> u64 {
+    let sideways = (pieces & !FILE_A) >> 1 | (pieces & !FILE_H) << 1;
+    let row = pieces | sideways;
+    sideways | row << 8 | row >> 8
+}
+
+/// The index of each square in `bitboard`, from lowest to highest.
+fn squares(mut bitboard: u64) -> impl Iterator<Item = u32> {
+    std::iter::from_fn(move || {
+        let square = (bitboard != 0).then(|| bitboard.trailing_zeros())?;
+        bitboard &= bitboard - 1;
+        Some(square)
+    })
+}
+
+/// The number of groups `pieces` form, where pieces next to each other are in the same group.
+fn groups(mut pieces: u64) -> u32 {
+    let mut groups = 0;
+    while pieces != 0 {
+        // Grow the group from its lowest piece until no more pieces join it
+        let mut group = pieces & pieces.wrapping_neg();
+        loop {
+            let grown = (group | neighbours(group)) & pieces;
+            if grown == group {
+                break;
+            }
+            group = grown;
+        }
+        pieces &= !group;
+        groups += 1;
+    }
+    groups
+}
+
+#[derive(Clone, Debug)]
+pub struct LinesOfActionState {
+    /// The pieces of each player, indexed by `Player`. Black starts along the first and last
+    /// ranks and is the maximising player, while White starts along the a and h files.
+    pieces: [u64; 2],
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl Default for LinesOfActionState {
+    fn default() -> Self {
+        let (ranks, files) = (RANK_1 | RANK_8, FILE_A | FILE_H);
+        Self {
+            pieces: [ranks & !files, files & !ranks],
+            player: Player::Max,
+            winner: None,
+        }
+    }
+}
+
+impl LinesOfActionState {
+    /// The legal move matching the squares of `move_`, whether or not it was written as a capture.
+    fn find_move(&self, move_: Move) -> Option<Move> {
+        self.actions()
+            .into_iter()
+            .find(|action| action.from == move_.from && action.to == move_.to)
+    }
+
+    /// The square `distance` steps from `square` along `(file, rank)`, if it's on the board.
+    fn step(square: u8, (file, rank): (i8, i8), distance: i8) -> Option<u8> {
+        let file = (square % 8) as i8 + file * distance;
+        let rank = (square / 8) as i8 + rank * distance;
+        ((0..8).contains(&file) && (0..8).contains(&rank)).then_some((rank * 8 + file) as u8)
+    }
+
+    /// The number of pieces of either player on the line through `square` along `direction`.
+    fn pieces_on_line(&self, square: u8, direction: (i8, i8)) -> i8 {
+        let occupied = self.pieces[0] | self.pieces[1];
+        let (forwards, backwards) = (direction, (-direction.0, -direction.1));
+        let count = |direction| {
+            (1..8)
+                .map_while(|distance| Self::step(square, direction, distance))
+                .filter(|&square| occupied & 1 << square != 0)
+                .count() as i8
+        };
+        1 + count(forwards) + count(backwards)
+    }
+
+    /// The number of rings of squares between `square` and the edge of the board.
+    fn centrality(square: u32) -> u32 {
+        let (file, rank) = (square % 8, square / 8);
+        file.min(7 - file).min(rank).min(7 - rank)
+    }
+
+    /// The average distance of `pieces` from their centre of mass, counting diagonal steps as one.
+    fn spread(pieces: u64) -> f32 {
+        let count = pieces.count_ones() as f32;
+        let coordinates =
+            || squares(pieces).map(|square| ((square % 8) as f32, (square / 8) as f32));
+        let (files, ranks) = coordinates().fold((0.0, 0.0), |(files, ranks), (file, rank)| {
+            (files + file, ranks + rank)
+        });
+        let (file, rank) = (files / count, ranks / count);
+        coordinates()
+            .map(|(x, y)| (x - file).abs().max((y - rank).abs()))
+            .sum::<f32>()
+            / count
+    }
+}
+
+impl minimax::State<f32, Move> for LinesOfActionState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        let [max, min] = self.pieces.map(|pieces| {
+            let centre = squares(pieces).map(Self::centrality).sum::<u32>();
+            -(groups(pieces) as f32) * GROUP_VALUE - Self::spread(pieces) * SPREAD_VALUE
+                + centre as f32 / pieces.count_ones() as f32 * CENTRE_VALUE
+        });
+        max - min
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let start = actions.len();
+        let own = self.pieces[self.player as usize];
+        let opponent = self.pieces[self.player.opposite() as usize];
+
+        for from in squares(own).map(|square| square as u8) {
+            for line in LINES {
+                let distance = self.pieces_on_line(from, line);
+                for direction in [line, (-line.0, -line.1)] {
+                    let Some(to) = Self::step(from, direction, distance) else {
+                        continue;
+                    };
+                    // Pieces can jump over their own pieces but not their opponent's, and can't
+                    // land on their own
+                    let blocked = (1..distance)
+                        .filter_map(|distance| Self::step(from, direction, distance))
+                        .any(|square| opponent & 1 << square != 0);
+                    if !blocked && own & 1 << to == 0 {
+                        actions.push(Move {
+                            from,
+                            to,
+                            capture: opponent & 1 << to != 0,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Search moves towards the centre first, which tend to bring pieces together
+        actions[start..]
+            .sort_by_key(|action| std::cmp::Reverse(Self::centrality(action.to as u32)));
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let own = self.player as usize;
+        let opponent = self.player.opposite() as usize;
+        assert!(
+            self.pieces[own] & 1 << action.from != 0 && self.pieces[own] & 1 << action.to == 0,
+            "{action} is not a legal move"
+        );
+
+        let mut state = self.clone();
+        state.pieces[own] ^= 1 << action.from | 1 << action.to;
+        state.pieces[opponent] &= !(1 << action.to);
+
+        // Connecting every piece wins, even if a capture connects the opponent's at the same
+        // time, and a player left without a move loses
+        state.player = self.player.opposite();
+        state.winner = if groups(state.pieces[own]) == 1 {
+            Some(self.player)
+        } else if groups(state.pieces[opponent]) == 1 {
+            Some(state.player)
+        } else if state.actions().is_empty() {
+            Some(self.player)
+        } else {
+            None
+        };
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let [max, min] = self.pieces;
+        Some(mix(max) ^ mix(min ^ self.player as u64).rotate_left(32))
+    }
+}
+
+impl Display for LinesOfActionState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ┌───┬───┬───┬───┬───┬───┬───┬───┐")?;
+        for rank in (0..8).rev() {
+            write!(f, "{} │", rank + 1)?;
+            for file in 0..8 {
+                let square = 1 << (rank * 8 + file);
+                let piece = if self.pieces[Player::Max as usize] & square != 0 {
+                    '●'
+                } else if self.pieces[Player::Min as usize] & square != 0 {
+                    '○'
+                } else {
+                    ' '
+                };
+                write!(f, " {piece} │")?;
+            }
+            if rank != 0 {
+                writeln!(f, "\n  ├───┼───┼───┼───┼───┼───┼───┼───┤")?;
+            }
+        }
+        write!(
+            f,
+            "\n  └───┴───┴───┴───┴───┴───┴───┴───┘\n    a   b   c   d   e   f   g   h"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn squares(names: &[&str]) -> u64 {
+        names.iter().fold(0, |squares, name| {
+            squares | 1 << format!("{name}-{name}").parse::<Move>().unwrap().from
+        })
+    }
+
+    fn moves(state: &LinesOfActionState, from: &str) -> Vec<String> {
+        let mut moves = state
+            .actions()
+            .iter()
+            .map(Move::to_string)
+            .filter(|move_| move_.starts_with(from))
+            .collect::<Vec<_>>();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn moves_as_far_as_the_pieces_on_the_line() {
+        // b1 shares its file with b8, its rank with five others, and one diagonal with h7, but
+        // moving two along the other diagonal would leave the board
+        let state = LinesOfActionState::default();
+        assert_eq!(moves(&state, "b1"), ["b1-b3", "b1-d3", "b1-h1"]);
+        assert!(state.find_move("b1-b3".parse().unwrap()).is_some());
+        assert!(state.find_move("b1-b2".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn jumps_only_over_own_pieces() {
+        let state = LinesOfActionState {
+            pieces: [squares(&["a1", "b1", "h8"]), squares(&["c1", "a4"])],
+            player: Player::Max,
+            winner: None,
+        };
+        // There are three pieces along the first rank, but moving along it means jumping over
+        // White's piece on c1
+        assert_eq!(moves(&state, "a1"), ["a1-a3", "a1-c3"]);
+        assert_eq!(moves(&state, "b1"), ["b1-a2", "b1-b2", "b1-c2"]);
+    }
+
+    #[test]
+    fn counts_groups() {
+        assert_eq!(groups(squares(&["a1", "b2", "c3"])), 1);
+        assert_eq!(groups(squares(&["a1", "h1", "a8", "h8"])), 4);
+        assert_eq!(groups(squares(&["h1", "a2"])), 2);
+        assert_eq!(groups(LinesOfActionState::default().pieces[0]), 2);
+    }
+
+    #[test]
+    fn connecting_every_piece_wins() {
+        let state = LinesOfActionState {
+            pieces: [squares(&["a1", "b2", "d2"]), squares(&["h8", "h6"])],
+            player: Player::Max,
+            winner: None,
+        };
+        let state = state.result(&state.find_move("d2-c3".parse().unwrap()).unwrap());
+        assert_eq!(state.winner, Some(Player::Max));
+
+        // Capturing the opponent's only stray piece connects them instead
+        let state = LinesOfActionState {
+            pieces: [squares(&["b1", "e1", "h4"]), squares(&["b3", "f8", "g8"])],
+            player: Player::Max,
+            winner: None,
+        };
+        let move_ = state.find_move("b1-b3".parse().unwrap()).unwrap();
+        assert!(move_.capture);
+        assert_eq!(state.result(&move_).winner, Some(Player::Min));
+    }
+
+    #[test]
+    fn engine_connects_its_pieces() {
+        let state = LinesOfActionState {
+            pieces: [squares(&["a1", "b2", "d2"]), squares(&["h8", "h6", "e8"])],
+            player: Player::Max,
+            winner: None,
+        };
+        let result = LinesOfAction::engine(2).search(&state);
+        assert_eq!(result.score(), Some(f32::INFINITY));
+        assert_eq!(result.pv().len(), 1);
+    }
+}
@@ -0,0 +1,808 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const SIZE: i8 = 5;
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const GOLD_STEPS: [(i8, i8); 6] = [(0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0), (0, -1)];
+const SILVER_STEPS: [(i8, i8); 5] = [(0, 1), (1, 1), (-1, 1), (1, -1), (-1, -1)];
+const PAWN_STEPS: [(i8, i8); 1] = [(0, 1)];
+const ORTHOGONALS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+type Directions = &'static [(i8, i8)];
+
+/// Pieces in hand are worth a little more than on the board, since they can be dropped anywhere.
+const HAND_BONUS: f32 = 1.1;
+
+/// The square one step from `square` along `(file, rank)`, if it's on the board.
+fn step(square: u8, (file, rank): (i8, i8)) -> Option<u8> {
+    let file = (square as i8 % SIZE) + file;
+    let rank = (square as i8 / SIZE) + rank;
+    ((0..SIZE).contains(&file) && (0..SIZE).contains(&rank)).then_some((rank * SIZE + file) as u8)
+}
+
+/// Whether `square` is on the far rank from `player`, where their pieces promote.
+fn in_promotion_zone(square: u8, player: Player) -> bool {
+    let rank = square as i8 / SIZE;
+    match player {
+        Player::Max => rank == SIZE - 1,
+        Player::Min => rank == 0,
+    }
+}
+
+fn square_name(square: u8) -> String {
+    format!("{}{}", (b'a' + square % 5) as char, square / 5 + 1)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Minishogi(MinishogiState, Vec<Move>);
+
+impl Game for Minishogi {
+    fn name(&self) -> String {
+        "Minishogi".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " r │ b │ s
+───┼───┼───
+   │ P │ k
+───┼───┼───
++S │   │ K "
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        // Each player's hand goes on its own line above or below the board
+        (30, 14)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.actions().contains(&move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(4)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["Sente", "Gote"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Minishogi {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(2)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build minishogi engine")
+    }
+}
+
+/// The kinds of piece, with the ones that can be held in hand first so they can index the hands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Rook,
+    Bishop,
+    Gold,
+    Silver,
+    Pawn,
+    King,
+}
+
+impl Kind {
+    const IN_HAND: [Kind; 5] = [
+        Kind::Rook,
+        Kind::Bishop,
+        Kind::Gold,
+        Kind::Silver,
+        Kind::Pawn,
+    ];
+
+    fn letter(self) -> char {
+        match self {
+            Kind::Rook => 'R',
+            Kind::Bishop => 'B',
+            Kind::Gold => 'G',
+            Kind::Silver => 'S',
+            Kind::Pawn => 'P',
+            Kind::King => 'K',
+        }
+    }
+
+    fn from_letter(letter: char) -> Option<Kind> {
+        [Kind::King]
+            .into_iter()
+            .chain(Kind::IN_HAND)
+            .find(|kind| kind.letter() == letter.to_ascii_uppercase())
+    }
+
+    fn can_promote(self) -> bool {
+        !matches!(self, Kind::Gold | Kind::King)
+    }
+
+    /// The material value of the piece, with promoted rooks and bishops being worth the most.
+    fn value(self, promoted: bool) -> f32 {
+        match (self, promoted) {
+            (Kind::Rook, false) => 10.0,
+            (Kind::Rook, true) => 12.0,
+            (Kind::Bishop, false) => 8.0,
+            (Kind::Bishop, true) => 10.0,
+            (Kind::Gold, _) | (Kind::Silver, true) => 6.0,
+            (Kind::Silver, false) => 5.0,
+            (Kind::Pawn, false) => 1.0,
+            (Kind::Pawn, true) => 7.0,
+            (Kind::King, _) => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Piece {
+    kind: Kind,
+    owner: Player,
+    promoted: bool,
+}
+
+impl Piece {
+    /// The single steps the piece can take and the directions it can slide along, as seen from
+    /// the player it belongs to.
+    fn movement(self) -> (Directions, Directions) {
+        match (self.kind, self.promoted) {
+            (Kind::King, _) => (&KING_STEPS, &[]),
+            (Kind::Gold, _) | (Kind::Silver | Kind::Pawn, true) => (&GOLD_STEPS, &[]),
+            (Kind::Silver, false) => (&SILVER_STEPS, &[]),
+            (Kind::Pawn, false) => (&PAWN_STEPS, &[]),
+            (Kind::Bishop, false) => (&[], &DIAGONALS),
+            (Kind::Bishop, true) => (&ORTHOGONALS, &DIAGONALS),
+            (Kind::Rook, false) => (&[], &ORTHOGONALS),
+            (Kind::Rook, true) => (&DIAGONALS, &ORTHOGONALS),
+        }
+    }
+}
+
+impl Display for Piece {
+    /// The piece's letter, in upper case for Sente and lower case for Gote, after a `+` if it's
+    /// promoted.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let letter = match self.owner {
+            Player::Max => self.kind.letter(),
+            Player::Min => self.kind.letter().to_ascii_lowercase(),
+        };
+        if self.promoted {
+            write!(f, "+{letter}")
+        } else {
+            write!(f, "{letter}")
+        }
+    }
+}
+
+/// Either a piece moving on the board, written as the squares it moves between and a `+` if it
+/// promotes, such as `c4-c5+`, or a piece from hand being dropped, written as its letter and the
+/// square, such as `P*c3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    Board { from: u8, to: u8, promote: bool },
+    Drop { kind: Kind, to: u8 },
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected a move such as `c1-c2` or `c4-c5+`, or a drop like `P*c3`";
+        let s = s.trim();
+        let square = |name: &str| match *name.to_ascii_lowercase().as_bytes() {
+            [file @ b'a'..=b'e', rank @ b'1'..=b'5'] => Ok((rank - b'1') * 5 + file - b'a'),
+            _ => Err(EXPECTED),
+        };
+
+        if let Some((kind, to)) = s.split_once('*') {
+            let mut letters = kind.chars();
+            let kind = letters
+                .next()
+                .and_then(Kind::from_letter)
+                .filter(|&kind| kind != Kind::King && letters.next().is_none())
+                .ok_or(EXPECTED)?;
+            return Ok(Move::Drop {
+                kind,
+                to: square(to)?,
+            });
+        }
+
+        let (s, promote) = match s.strip_suffix('+') {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let (from, to) = s.split_once('-').ok_or(EXPECTED)?;
+        Ok(Move::Board {
+            from: square(from)?,
+            to: square(to)?,
+            promote,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Move::Board { from, to, promote } => {
+                let promote = if promote { "+" } else { "" };
+                write!(f, "{}-{}{promote}", square_name(from), square_name(to))
+            }
+            Move::Drop { kind, to } => write!(f, "{}*{}", kind.letter(), square_name(to)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MinishogiState {
+    /// The piece on each square, going along each rank from a1.
+    board: [Option<Piece>; 25],
+    /// How many of each kind of piece each player holds, indexed by player and then by kind.
+    hands: [[u8; 5]; 2],
+    /// Sente moves first from the bottom of the board and is the maximising player.
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl Default for MinishogiState {
+    fn default() -> Self {
+        let mut board = [None; 25];
+        let back_rank = [
+            Kind::King,
+            Kind::Gold,
+            Kind::Silver,
+            Kind::Bishop,
+            Kind::Rook,
+        ];
+        for (file, kind) in back_rank.into_iter().enumerate() {
+            let piece = |owner| {
+                Some(Piece {
+                    kind,
+                    owner,
+                    promoted: false,
+                })
+            };
+            // Gote's pieces are set up the same way, turned round to face Sente
+            board[file] = piece(Player::Max);
+            board[24 - file] = piece(Player::Min);
+        }
+        let pawn = |owner| {
+            Some(Piece {
+                kind: Kind::Pawn,
+                owner,
+                promoted: false,
+            })
+        };
+        board[5] = pawn(Player::Max);
+        board[19] = pawn(Player::Min);
+
+        Self {
+            board,
+            hands: [[0; 5]; 2],
+            player: Player::Max,
+            winner: None,
+        }
+    }
+}
+
+impl MinishogiState {
+    /// The squares the piece on `from` can move to, including ones where it captures.
+    fn targets(&self, from: u8) -> Vec<u8> {
+        let Some(piece) = self.board[from as usize] else {
+            return Vec::new();
+        };
+        // Gote faces down the board
+        let facing = |(file, rank): (i8, i8)| match piece.owner {
+            Player::Max => (file, rank),
+            Player::Min => (file, -rank),
+        };
+        let is_open =
+            |square: u8| self.board[square as usize].is_none_or(|other| other.owner != piece.owner);
+
+        let (steps, slides) = piece.movement();
+        let mut targets: Vec<u8> = steps
+            .iter()
+            .filter_map(|&direction| step(from, facing(direction)))
+            .filter(|&square| is_open(square))
+            .collect();
+        for &direction in slides {
+            let mut square = from;
+            while let Some(next) = step(square, facing(direction)) {
+                if !is_open(next) {
+                    break;
+                }
+                targets.push(next);
+                if self.board[next as usize].is_some() {
+                    break;
+                }
+                square = next;
+            }
+        }
+        targets
+    }
+
+    fn king(&self, player: Player) -> Option<u8> {
+        (0..25).find(|&square| {
+            self.board[square as usize]
+                == Some(Piece {
+                    kind: Kind::King,
+                    owner: player,
+                    promoted: false,
+                })
+        })
+    }
+
+    fn is_in_check(&self, player: Player) -> bool {
+        let Some(king) = self.king(player) else {
+            return false;
+        };
+        (0..25).any(|square| {
+            self.board[square as usize].is_some_and(|piece| piece.owner != player)
+                && self.targets(square).contains(&king)
+        })
+    }
+
+    /// The moves the player to move could make if it didn't matter whether they left their king
+    /// in check.
+    fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for from in 0..25 {
+            let Some(piece) = self.board[from as usize].filter(|piece| piece.owner == self.player)
+            else {
+                continue;
+            };
+            for to in self.targets(from) {
+                let may_promote = piece.kind.can_promote()
+                    && !piece.promoted
+                    && (in_promotion_zone(from, self.player) || in_promotion_zone(to, self.player));
+                // A pawn on the far rank would have nowhere to go, so it has to promote
+                let must_promote = piece.kind == Kind::Pawn && in_promotion_zone(to, self.player);
+                if !must_promote {
+                    moves.push(Move::Board {
+                        from,
+                        to,
+                        promote: false,
+                    });
+                }
+                if may_promote {
+                    moves.push(Move::Board {
+                        from,
+                        to,
+                        promote: true,
+                    });
+                }
+            }
+        }
+
+        let hand = self.hands[self.player as usize];
+        for kind in Kind::IN_HAND
+            .into_iter()
+            .filter(|&kind| hand[kind as usize] > 0)
+        {
+            for to in (0..25).filter(|&square| self.board[square as usize].is_none()) {
+                // Pawns can't be dropped where they couldn't move, or onto a file that already
+                // has an unpromoted pawn of the same player's
+                if kind == Kind::Pawn
+                    && (in_promotion_zone(to, self.player)
+                        || (0..SIZE as u8).any(|rank| {
+                            self.board[(rank * 5 + to % 5) as usize]
+                                == Some(Piece {
+                                    kind: Kind::Pawn,
+                                    owner: self.player,
+                                    promoted: false,
+                                })
+                        }))
+                {
+                    continue;
+                }
+                moves.push(Move::Drop { kind, to });
+            }
+        }
+        moves
+    }
+
+    /// Whether `move_` checkmates by dropping a pawn, which isn't allowed.
+    fn is_pawn_drop_mate(&self, move_: Move) -> bool {
+        if !matches!(
+            move_,
+            Move::Drop {
+                kind: Kind::Pawn,
+                ..
+            }
+        ) {
+            return false;
+        }
+        let state = self.play(move_);
+        state.is_in_check(state.player)
+            && state
+                .pseudo_legal_moves()
+                .into_iter()
+                .all(|reply| state.play(reply).is_in_check(state.player))
+    }
+
+    /// Makes `move_` and passes the turn, without checking whether the game is over.
+    fn play(&self, move_: Move) -> Self {
+        let mut state = self.clone();
+        match move_ {
+            Move::Board { from, to, promote } => {
+                let mut piece = state.board[from as usize]
+                    .take()
+                    .expect("there should be a piece to move");
+                piece.promoted |= promote;
+                // Captured pieces go to the capturing player's hand, unpromoted
+                if let Some(captured) = state.board[to as usize].replace(piece) {
+                    state.hands[self.player as usize][captured.kind as usize] += 1;
+                }
+            }
+            Move::Drop { kind, to } => {
+                state.hands[self.player as usize][kind as usize] -= 1;
+                state.board[to as usize] = Some(Piece {
+                    kind,
+                    owner: self.player,
+                    promoted: false,
+                });
+            }
+        }
+        state.player = self.player.opposite();
+        state
+    }
+
+    /// The pieces in `player`'s hand, such as `P×2 S`.
+    fn hand(&self, player: Player) -> String {
+        Kind::IN_HAND
+            .into_iter()
+            .filter_map(|kind| {
+                let piece = Piece {
+                    kind,
+                    owner: player,
+                    promoted: false,
+                };
+                match self.hands[player as usize][kind as usize] {
+                    0 => None,
+                    1 => Some(piece.to_string()),
+                    count => Some(format!("{piece}×{count}")),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl minimax::State<f32, Move> for MinishogiState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        let sign = |player| match player {
+            Player::Max => 1.0,
+            Player::Min => -1.0,
+        };
+        let board: f32 = self
+            .board
+            .iter()
+            .flatten()
+            .map(|piece| sign(piece.owner) * piece.kind.value(piece.promoted))
+            .sum();
+        let hands: f32 = [Player::Max, Player::Min]
+            .into_iter()
+            .flat_map(|player| {
+                Kind::IN_HAND.map(|kind| {
+                    sign(player)
+                        * self.hands[player as usize][kind as usize] as f32
+                        * kind.value(false)
+                        * HAND_BONUS
+                })
+            })
+            .sum();
+        board + hands
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        actions.extend(self.pseudo_legal_moves().into_iter().filter(|&move_| {
+            !self.play(move_).is_in_check(self.player) && !self.is_pawn_drop_mate(move_)
+        }));
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let is_own = match *action {
+            Move::Board { from, .. } => {
+                self.board[from as usize].is_some_and(|piece| piece.owner == self.player)
+            }
+            Move::Drop { kind, to } => {
+                self.hands[self.player as usize][kind as usize] > 0
+                    && self.board[to as usize].is_none()
+            }
+        };
+        assert!(is_own, "{action} is not a legal move");
+
+        // Leaving the opponent without a move wins, whether or not they're in check
+        let mut state = self.play(*action);
+        if state.actions().is_empty() {
+            state.winner = Some(self.player);
+        }
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        match *action {
+            Move::Board { to, promote, .. } => !promote && self.board[to as usize].is_none(),
+            Move::Drop { .. } => true,
+        }
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        let Move::Board { from, to, promote } = *action else {
+            return 0.0;
+        };
+        let captured =
+            self.board[to as usize].map_or(0.0, |piece| piece.kind.value(piece.promoted));
+        let promotion = self.board[from as usize].map_or(0.0, |piece| {
+            if promote {
+                piece.kind.value(true) - piece.kind.value(false)
+            } else {
+                0.0
+            }
+        });
+        captured + promotion
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mut hash = 0;
+        for (square, piece) in self.board.iter().enumerate() {
+            if let Some(piece) = piece {
+                let code = 2 * piece.kind as usize + piece.promoted as usize;
+                hash ^= zobrist_key(code * 25 + square, piece.owner);
+            }
+        }
+        for player in [Player::Max, Player::Min] {
+            for kind in Kind::IN_HAND {
+                let count = self.hands[player as usize][kind as usize] as usize;
+                hash ^= zobrist_key(300 + 4 * kind as usize + count, player);
+            }
+        }
+        Some(match self.player {
+            Player::Max => hash,
+            Player::Min => !hash,
+        })
+    }
+}
+
+impl Display for MinishogiState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ☖ {}", self.hand(Player::Min))?;
+        writeln!(f, "  ┌───┬───┬───┬───┬───┐")?;
+        for rank in (0..5).rev() {
+            write!(f, "{} │", rank + 1)?;
+            for file in 0..5 {
+                match self.board[rank * 5 + file] {
+                    Some(piece) => write!(f, "{:>2} │", piece.to_string())?,
+                    None => write!(f, "   │")?,
+                }
+            }
+            if rank != 0 {
+                writeln!(f, "\n  ├───┼───┼───┼───┼───┤")?;
+            }
+        }
+        writeln!(f, "\n  └───┴───┴───┴───┴───┘\n    a   b   c   d   e")?;
+        write!(f, "  ☗ {}", self.hand(Player::Max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> u8 {
+        match format!("P*{name}").parse() {
+            Ok(Move::Drop { to, .. }) => to,
+            _ => panic!("invalid square {name}"),
+        }
+    }
+
+    /// A position with pieces written as their letter and square, such as `+Pc4`, in upper case
+    /// for Sente and lower case for Gote.
+    fn position(pieces: &[&str], player: Player) -> MinishogiState {
+        let mut board = [None; 25];
+        for name in pieces {
+            let (promoted, name) = match name.strip_prefix('+') {
+                Some(name) => (true, name),
+                None => (false, *name),
+            };
+            let letter = name.chars().next().unwrap();
+            let owner = if letter.is_ascii_uppercase() {
+                Player::Max
+            } else {
+                Player::Min
+            };
+            board[square(&name[1..]) as usize] = Some(Piece {
+                kind: Kind::from_letter(letter).unwrap(),
+                owner,
+                promoted,
+            });
+        }
+        MinishogiState {
+            board,
+            hands: [[0; 5]; 2],
+            player,
+            winner: None,
+        }
+    }
+
+    fn actions(state: &MinishogiState) -> Vec<String> {
+        let mut actions: Vec<_> = state.actions().iter().map(Move::to_string).collect();
+        actions.sort();
+        actions
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "c4-c5+".parse::<Move>().unwrap();
+        assert_eq!(
+            move_,
+            Move::Board {
+                from: 17,
+                to: 22,
+                promote: true
+            }
+        );
+        assert_eq!(move_.to_string(), "c4-c5+");
+        let drop = "s*B3".parse::<Move>().unwrap();
+        assert_eq!(
+            drop,
+            Move::Drop {
+                kind: Kind::Silver,
+                to: 11
+            }
+        );
+        assert_eq!(drop.to_string(), "S*b3");
+        assert!("K*b3".parse::<Move>().is_err());
+        assert!("c5-c6".parse::<Move>().is_err());
+        assert!("c4c5".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn starting_position_has_fourteen_moves() {
+        assert_eq!(MinishogiState::default().actions().len(), 14);
+    }
+
+    #[test]
+    fn pieces_promote_in_the_far_rank() {
+        let state = position(&["Ka1", "Pc4", "Sb4", "ke5"], Player::Max);
+        let actions = actions(&state);
+        // Pawns have to promote, but silvers can choose not to
+        assert!(actions.contains(&"c4-c5+".to_string()));
+        assert!(!actions.contains(&"c4-c5".to_string()));
+        assert!(actions.contains(&"b4-b5".to_string()));
+        assert!(actions.contains(&"b4-b5+".to_string()));
+
+        let promoted = state.result(&"c4-c5+".parse().unwrap());
+        assert_eq!(
+            promoted.board[square("c5") as usize].unwrap().to_string(),
+            "+P"
+        );
+    }
+
+    #[test]
+    fn captured_pieces_can_be_dropped() {
+        let state = position(&["Ka1", "Rb2", "+sb4", "pe3", "ke5"], Player::Max);
+        let state = state.result(&"b2-b4".parse().unwrap());
+        assert_eq!(state.hands[Player::Max as usize][Kind::Silver as usize], 1);
+        assert_eq!(state.hand(Player::Max), "S");
+
+        let state = state.result(&"e3-e2".parse().unwrap());
+        let actions = actions(&state);
+        assert!(actions.contains(&"S*c3".to_string()));
+        let dropped = state.result(&"S*c3".parse().unwrap());
+        assert_eq!(
+            dropped.board[square("c3") as usize].unwrap().to_string(),
+            "S"
+        );
+        assert_eq!(dropped.hand(Player::Max), "");
+    }
+
+    #[test]
+    fn pawns_cant_be_dropped_onto_a_file_with_a_pawn_or_to_mate() {
+        let mut state = position(&["Ka1", "Pb2", "ke5"], Player::Max);
+        state.hands[Player::Max as usize][Kind::Pawn as usize] = 1;
+        let drops = actions(&state);
+        assert!(!drops.contains(&"P*b3".to_string()));
+        assert!(!drops.contains(&"P*c5".to_string()));
+        assert!(drops.contains(&"P*c3".to_string()));
+
+        // Dropping a pawn in front of the cornered king would be mate
+        let mut state = position(&["Ka1", "Rd1", "Se3", "pa4", "ke5"], Player::Max);
+        state.hands[Player::Max as usize][Kind::Pawn as usize] = 1;
+        assert!(state.is_pawn_drop_mate("P*e4".parse().unwrap()));
+        assert!(!actions(&state).contains(&"P*e4".to_string()));
+    }
+
+    #[test]
+    fn checkmate_wins() {
+        // Dropping the gold next to the king instead of the pawn is mate
+        let mut state = position(&["Ka1", "Rd1", "Se3", "pa4", "ke5"], Player::Max);
+        state.hands[Player::Max as usize][Kind::Pawn as usize] = 1;
+        state.hands[Player::Max as usize][Kind::Gold as usize] = 1;
+        let move_ = Minishogi::engine(2).best_move(&state).unwrap();
+        assert!(matches!(
+            move_,
+            Move::Drop {
+                kind: Kind::Gold,
+                ..
+            }
+        ));
+        let mated = state.result(&move_);
+        assert_eq!(mated.winner, Some(Player::Max));
+    }
+}
@@ -0,0 +1,621 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+/// A point on the board, indexing `POINT_NAMES`.
+type Point = u8;
+
+/// The points in algebraic notation, which places the board on a 7 by 7 grid.
+const POINT_NAMES: [&str; 24] = [
+    "a1", "a4", "a7", "b2", "b4", "b6", "c3", "c4", "c5", "d1", "d2", "d3", "d5", "d6", "d7", "e3",
+    "e4", "e5", "f2", "f4", "f6", "g1", "g4", "g7",
+];
+
+/// Every line of three points, which is a mill when one player has a piece on each of them.
+const MILLS: [[Point; 3]; 16] = [
+    [0, 9, 21],
+    [3, 10, 18],
+    [6, 11, 15],
+    [1, 4, 7],
+    [16, 19, 22],
+    [8, 12, 17],
+    [5, 13, 20],
+    [2, 14, 23],
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [9, 10, 11],
+    [12, 13, 14],
+    [15, 16, 17],
+    [18, 19, 20],
+    [21, 22, 23],
+];
+
+/// The points joined to each point by a line.
+const ADJACENT: [u32; 24] = {
+    let mut adjacent = [0; 24];
+    let mut i = 0;
+    while i < MILLS.len() {
+        let [a, b, c] = MILLS[i];
+        adjacent[a as usize] |= 1 << b;
+        adjacent[b as usize] |= 1 << a | 1 << c;
+        adjacent[c as usize] |= 1 << b;
+        i += 1;
+    }
+    adjacent
+};
+
+const ALL_POINTS: u32 = (1 << 24) - 1;
+
+const PIECES: u8 = 9;
+
+/// Without a mill for this many plies, the game is drawn.
+const DRAW_PLIES: u8 = 100;
+
+const PIECE_VALUE: f32 = 100.0;
+
+/// The values of a player's mills, of lines where they could complete one, and of each move they
+/// could make, which matter differently in each phase of the game.
+#[derive(Clone, Copy)]
+struct PhaseValues {
+    mill: f32,
+    open_mill: f32,
+    mobility: f32,
+}
+
+const PLACING: PhaseValues = PhaseValues {
+    mill: 20.0,
+    open_mill: 10.0,
+    mobility: 1.0,
+};
+const MOVING: PhaseValues = PhaseValues {
+    mill: 10.0,
+    open_mill: 5.0,
+    mobility: 5.0,
+};
+/// A player with three pieces left can complete any open mill, but can't be blocked.
+const FLYING: PhaseValues = PhaseValues {
+    mill: 10.0,
+    open_mill: 20.0,
+    mobility: 0.0,
+};
+
+fn points(bitboard: u32) -> impl Iterator<Item = Point> {
+    (0..24).filter(move |&point| bitboard & 1 << point != 0)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Morris(MorrisState, Vec<Move>);
+
+impl Game for Morris {
+    fn name(&self) -> String {
+        "Nine Men's Morris".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "●────○────●
+│ ●──┼──○ │
+├─┤     ├─┤
+│ ○──┼──● │
+○────┴────●"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (28, 16)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if !self.0.is_terminal() {
+            None
+        } else if self.0.quiet_plies >= DRAW_PLIES {
+            Some(WinState::Draw)
+        } else {
+            // The player to move has lost
+            Some(WinState::Decisive(self.0.player.opposite()))
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        !self.0.is_terminal() && self.0.parse_move(move_).is_some()
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = self
+            .0
+            .parse_move(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(6)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["White", "Black"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Morris {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build morris engine")
+    }
+}
+
+/// A piece placed on `to`, or moved there from `from`, and the opponent's piece removed if that
+/// completed a mill.
+///
+/// Moves are written as the points involved, such as `d2` for a placement, `d2-d3` for a move,
+/// and `d2-d3xa1` for a move that removes the piece on `a1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    from: Option<Point>,
+    to: Point,
+    remove: Option<Point>,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(from) = self.from {
+            write!(f, "{}-", POINT_NAMES[from as usize])?;
+        }
+        write!(f, "{}", POINT_NAMES[self.to as usize])?;
+        if let Some(remove) = self.remove {
+            write!(f, "x{}", POINT_NAMES[remove as usize])?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MorrisState {
+    /// The pieces of each player on the board, indexed by `Player`.
+    pieces: [u32; 2],
+    /// The number of pieces each player has left to place.
+    in_hand: [u8; 2],
+    /// White moves first, and is the maximising player.
+    player: Player,
+    /// The number of plies since the last mill.
+    quiet_plies: u8,
+}
+
+impl Default for MorrisState {
+    fn default() -> Self {
+        Self {
+            pieces: [0; 2],
+            in_hand: [PIECES; 2],
+            player: Player::Max,
+            quiet_plies: 0,
+        }
+    }
+}
+
+impl MorrisState {
+    fn piece_count(&self, player: Player) -> u32 {
+        self.pieces[player as usize].count_ones() + self.in_hand[player as usize] as u32
+    }
+
+    fn is_flying(&self, player: Player) -> bool {
+        self.in_hand[player as usize] == 0 && self.pieces[player as usize].count_ones() == 3
+    }
+
+    /// The points the player to move can move a piece on `from` to.
+    fn destinations(&self, from: Point) -> u32 {
+        let empty = ALL_POINTS & !(self.pieces[0] | self.pieces[1]);
+        if self.is_flying(self.player) {
+            empty
+        } else {
+            ADJACENT[from as usize] & empty
+        }
+    }
+
+    fn in_mill(pieces: u32, point: Point) -> bool {
+        MILLS
+            .iter()
+            .any(|mill| mill.contains(&point) && mill.iter().all(|&point| pieces & 1 << point != 0))
+    }
+
+    /// The opponent's pieces that can be removed after completing a mill, which are the ones that
+    /// aren't in mills themselves unless they all are.
+    fn removable(&self) -> u32 {
+        let opponent = self.pieces[self.player.opposite() as usize];
+        let outside_mills = points(opponent)
+            .filter(|&point| !Self::in_mill(opponent, point))
+            .fold(0, |outside, point| outside | 1 << point);
+
+        if outside_mills != 0 {
+            outside_mills
+        } else {
+            opponent
+        }
+    }
+
+    fn has_moves(&self) -> bool {
+        let own = self.pieces[self.player as usize];
+        self.in_hand[self.player as usize] > 0
+            || points(own).any(|from| self.destinations(from) != 0)
+    }
+
+    /// The number of mills `player` has, and the number of lines they could complete one on by
+    /// adding a piece.
+    fn mills(&self, player: Player) -> (u32, u32) {
+        let own = self.pieces[player as usize];
+        let empty = ALL_POINTS & !(self.pieces[0] | self.pieces[1]);
+        let mut mills = 0;
+        let mut open_mills = 0;
+        for mill in MILLS {
+            let line = mill.iter().fold(0u32, |line, &point| line | 1 << point);
+            match ((own & line).count_ones(), (empty & line).count_ones()) {
+                (3, _) => mills += 1,
+                (2, 1) => open_mills += 1,
+                _ => {}
+            }
+        }
+        (mills, open_mills)
+    }
+
+    /// Finds the legal move written as `notation`.
+    fn parse_move(&self, notation: &str) -> Option<Move> {
+        let notation = notation.trim().to_ascii_lowercase();
+        let (points, remove) = match notation.split_once('x') {
+            Some((points, remove)) => (points, Some(remove)),
+            None => (notation.as_str(), None),
+        };
+        let (from, to) = match points.split_once('-') {
+            Some((from, to)) => (Some(from), to),
+            None => (None, points),
+        };
+
+        let parse = |name: &str| {
+            POINT_NAMES
+                .iter()
+                .position(|&point| point == name)
+                .map(|point| point as Point)
+        };
+        let move_ = Move {
+            from: match from {
+                Some(from) => Some(parse(from)?),
+                None => None,
+            },
+            to: parse(to)?,
+            remove: match remove {
+                Some(remove) => Some(parse(remove)?),
+                None => None,
+            },
+        };
+        self.actions().contains(&move_).then_some(move_)
+    }
+}
+
+impl minimax::State<f32, Move> for MorrisState {
+    fn is_terminal(&self) -> bool {
+        self.quiet_plies >= DRAW_PLIES || self.piece_count(self.player) < 3 || !self.has_moves()
+    }
+
+    fn evaluation(&self) -> f32 {
+        if self.piece_count(self.player) < 3 || !self.has_moves() {
+            // The player to move has lost
+            return match self.player {
+                Player::Max => f32::NEG_INFINITY,
+                Player::Min => f32::INFINITY,
+            };
+        }
+        if self.quiet_plies >= DRAW_PLIES {
+            return 0.0;
+        }
+
+        [Player::Max, Player::Min]
+            .into_iter()
+            .map(|player| {
+                let values = if self.in_hand[player as usize] > 0 {
+                    PLACING
+                } else if self.is_flying(player) {
+                    FLYING
+                } else {
+                    MOVING
+                };
+
+                let empty = ALL_POINTS & !(self.pieces[0] | self.pieces[1]);
+                let mobility: u32 = points(self.pieces[player as usize])
+                    .map(|point| (ADJACENT[point as usize] & empty).count_ones())
+                    .sum();
+                let (mills, open_mills) = self.mills(player);
+
+                let value = self.piece_count(player) as f32 * PIECE_VALUE
+                    + mills as f32 * values.mill
+                    + open_mills as f32 * values.open_mill
+                    + mobility as f32 * values.mobility;
+
+                match player {
+                    Player::Max => value,
+                    Player::Min => -value,
+                }
+            })
+            .sum()
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let own = self.pieces[self.player as usize];
+        let empty = ALL_POINTS & !(self.pieces[0] | self.pieces[1]);
+
+        let steps: Vec<(Option<Point>, u32)> = if self.in_hand[self.player as usize] > 0 {
+            vec![(None, empty)]
+        } else {
+            points(own)
+                .map(|from| (Some(from), self.destinations(from)))
+                .collect()
+        };
+
+        // There's nothing to remove if the opponent hasn't got any pieces on the board yet
+        let removable = self.removable();
+        for (from, destinations) in steps {
+            for to in points(destinations) {
+                let pieces = own & !from.map_or(0, |from| 1 << from) | 1 << to;
+                if removable != 0 && Self::in_mill(pieces, to) {
+                    actions.extend(points(removable).map(|remove| Move {
+                        from,
+                        to,
+                        remove: Some(remove),
+                    }));
+                } else {
+                    actions.push(Move {
+                        from,
+                        to,
+                        remove: None,
+                    });
+                }
+            }
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let mut state = self.clone();
+        let player = self.player as usize;
+
+        match action.from {
+            Some(from) => state.pieces[player] &= !(1 << from),
+            None => state.in_hand[player] -= 1,
+        }
+        state.pieces[player] |= 1 << action.to;
+
+        if let Some(remove) = action.remove {
+            state.pieces[1 - player] &= !(1 << remove);
+            state.quiet_plies = 0;
+        } else {
+            state.quiet_plies = self.quiet_plies + 1;
+        }
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        action.remove.is_none()
+    }
+
+    fn see(&self, _action: &Move) -> f32 {
+        PIECE_VALUE
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // Every part of the state except the draw counter fits in the key, and multiplying and
+        // rotating spreads it over the low bits that pick its bucket
+        let key = self.pieces[0] as u64
+            | (self.pieces[1] as u64) << 24
+            | (self.in_hand[0] as u64) << 48
+            | (self.in_hand[1] as u64) << 52
+            | (self.player as u64) << 56;
+        Some(key.wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(32))
+    }
+}
+
+impl Display for MorrisState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Points are marked with `*`, and are found from their position in the grid
+        const BOARD: [&str; 13] = [
+            "*───────────*───────────*",
+            "│           │           │",
+            "│   *───────*───────*   │",
+            "│   │       │       │   │",
+            "│   │   *───*───*   │   │",
+            "│   │   │       │   │   │",
+            "*───*───*       *───*───*",
+            "│   │   │       │   │   │",
+            "│   │   *───*───*   │   │",
+            "│   │       │       │   │",
+            "│   *───────*───────*   │",
+            "│           │           │",
+            "*───────────*───────────*",
+        ];
+
+        for (line_index, line) in BOARD.into_iter().enumerate() {
+            if line_index % 2 == 0 {
+                write!(f, "{} ", 7 - line_index / 2)?;
+            } else {
+                write!(f, "  ")?;
+            }
+
+            for (column, char) in line.chars().enumerate() {
+                if char != '*' {
+                    write!(f, "{char}")?;
+                    continue;
+                }
+
+                let name = format!(
+                    "{}{}",
+                    (b'a' + column as u8 / 4) as char,
+                    7 - line_index / 2
+                );
+                let point = POINT_NAMES.iter().position(|&point| point == name).unwrap();
+                let piece = if self.pieces[Player::Max as usize] & 1 << point != 0 {
+                    '●'
+                } else if self.pieces[Player::Min as usize] & 1 << point != 0 {
+                    '○'
+                } else {
+                    '·'
+                };
+                write!(f, "{piece}")?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "  a   b   c   d   e   f   g")?;
+        write!(
+            f,
+            "  In hand: ● {}  ○ {}",
+            self.in_hand[Player::Max as usize],
+            self.in_hand[Player::Min as usize]
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> MorrisState {
+        moves.iter().fold(MorrisState::default(), |state, move_| {
+            let move_ = state.parse_move(move_).unwrap_or_else(|| panic!("{move_}"));
+            state.result(&move_)
+        })
+    }
+
+    fn point(name: &str) -> Point {
+        POINT_NAMES.iter().position(|&point| point == name).unwrap() as Point
+    }
+
+    #[test]
+    fn board_is_connected() {
+        assert_eq!(
+            ADJACENT
+                .iter()
+                .map(|points| points.count_ones())
+                .sum::<u32>(),
+            64
+        );
+        assert_eq!(ADJACENT[point("d2") as usize].count_ones(), 4);
+        assert_eq!(
+            ADJACENT[point("a1") as usize],
+            1 << point("a4") | 1 << point("d1")
+        );
+    }
+
+    #[test]
+    fn mills_remove_pieces() {
+        let state = play(&["a1", "b2", "a4", "b4"]);
+        // Completing the mill has to remove a piece
+        assert!(state.parse_move("a7").is_none());
+        assert!(state.parse_move("a7xb2").is_some());
+
+        let state = state.result(&state.parse_move("a7xb4").unwrap());
+        assert_eq!(state.pieces[Player::Min as usize], 1 << point("b2"));
+        assert_eq!(state.piece_count(Player::Min), 8);
+    }
+
+    #[test]
+    fn pieces_in_mills_are_protected() {
+        // White can't remove Black's pieces in the mill on the b file while it has others
+        let state = MorrisState {
+            pieces: [
+                1 << point("a1") | 1 << point("a4"),
+                1 << point("b2") | 1 << point("b4") | 1 << point("b6") | 1 << point("g7"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(state.removable(), 1 << point("g7"));
+
+        let state = MorrisState {
+            pieces: [state.pieces[0], state.pieces[1] & !(1 << point("g7"))],
+            ..state
+        };
+        assert_eq!(state.removable(), state.pieces[Player::Min as usize]);
+    }
+
+    #[test]
+    fn moving_and_flying() {
+        let pieces = [
+            1 << point("a1") | 1 << point("d1") | 1 << point("g4") | 1 << point("f6"),
+            1 << point("a4") | 1 << point("d2") | 1 << point("g7"),
+        ];
+        let state = MorrisState {
+            pieces,
+            in_hand: [0, 0],
+            ..Default::default()
+        };
+        // White's piece on a1 is blocked, so it can only move along lines from the others
+        assert!(state.parse_move("a1-a7").is_none());
+        assert!(state.parse_move("d1-g1").is_some());
+
+        // Black only has three pieces left, so it can fly to any empty point
+        let state = MorrisState {
+            player: Player::Min,
+            ..state
+        };
+        assert!(state.parse_move("a4-e5").is_some());
+        assert_eq!(state.actions().len(), 3 * 17);
+    }
+
+    #[test]
+    fn players_lose_with_two_pieces_or_no_moves() {
+        let state = MorrisState {
+            pieces: [1 << point("a1") | 1 << point("d1"), 0b111 << 3],
+            in_hand: [0, 0],
+            ..Default::default()
+        };
+        assert!(state.is_terminal());
+        assert_eq!(state.evaluation(), f32::NEG_INFINITY);
+
+        // All of White's pieces are surrounded
+        let state = MorrisState {
+            pieces: [
+                1 << point("a1") | 1 << point("d1") | 1 << point("g1") | 1 << point("a7"),
+                1 << point("a4") | 1 << point("d2") | 1 << point("g4") | 1 << point("d7"),
+            ],
+            in_hand: [0, 0],
+            ..Default::default()
+        };
+        assert!(state.is_terminal());
+        assert_eq!(state.evaluation(), f32::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,342 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const DEFAULT_HEAPS: [u8; 3] = [3, 4, 5];
+/// Every heap gets a byte of the hash key, next to the player to move.
+const MAX_HEAPS: usize = 7;
+
+#[derive(Clone, Debug, Default)]
+pub struct Nim(NimState, Vec<Take>);
+
+impl Game for Nim {
+    fn name(&self) -> String {
+        if self.0.misere {
+            "Misère Nim".to_string()
+        } else {
+            "Nim".to_string()
+        }
+    }
+
+    fn thumbnail(&self) -> String {
+        "  ●
+  ● ●
+  ● ● ●
+  ● ● ● ●
+ ─────────"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        let largest = self.initial_heaps().into_iter().max().unwrap_or(0) as u16;
+        // Wide enough for the longest heap, and for the line explaining who wins
+        ((2 * largest + 4).max(35) + 1, self.0.heaps.len() as u16 + 2)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Take::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // The last object is always taken by someone, so there are no draws
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Take>()
+            .is_ok_and(|take| !self.0.is_terminal() && self.0.is_legal(take))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let take = move_
+            .parse::<Take>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&take);
+        self.1.push(take);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        // No game lasts longer than there are objects left to take
+        let objects = self.0.heaps.iter().map(|&heap| u32::from(heap)).sum();
+        search_move(&Self::engine(limits.depth(objects)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(&self.initial_heaps(), self.0.misere);
+    }
+}
+
+impl Nim {
+    /// A game starting with heaps of the given sizes. In misère play, whoever takes the last
+    /// object loses instead of winning.
+    pub fn new(heaps: &[u8], misere: bool) -> Self {
+        assert!(
+            (1..=MAX_HEAPS).contains(&heaps.len()),
+            "nim is played with 1 to {MAX_HEAPS} heaps"
+        );
+        Self(
+            NimState {
+                heaps: heaps.to_vec(),
+                misere,
+                player: Player::Max,
+            },
+            Vec::new(),
+        )
+    }
+
+    /// An engine searching to `depth` plies. The heuristic knows nothing about the game, so
+    /// positions are only solved exactly when every line is searched until the last object is
+    /// taken.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build nim engine")
+    }
+
+    /// The heaps the game started with, which are the current heaps plus everything taken.
+    fn initial_heaps(&self) -> Vec<u8> {
+        let mut heaps = self.0.heaps.clone();
+        for take in &self.1 {
+            heaps[take.heap as usize] += take.count;
+        }
+        heaps
+    }
+}
+
+/// Taking `count` objects from a heap, written as the heap's number, counting from 1, and the
+/// count, such as `2/3` for taking three objects from the second heap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Take {
+    heap: u8,
+    count: u8,
+}
+
+impl FromStr for Take {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (heap, count) = s.trim().split_once('/').ok_or("expected `heap/count`")?;
+        let heap = heap.trim().parse::<u8>().map_err(|_| "invalid heap")?;
+        let count = count.trim().parse::<u8>().map_err(|_| "invalid count")?;
+        if heap == 0 || count == 0 {
+            Err("heaps and counts start from 1")?;
+        }
+
+        Ok(Take {
+            heap: heap - 1,
+            count,
+        })
+    }
+}
+
+impl Display for Take {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.heap + 1, self.count)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NimState {
+    heaps: Vec<u8>,
+    misere: bool,
+    player: Player,
+}
+
+impl Default for NimState {
+    fn default() -> Self {
+        Self {
+            heaps: DEFAULT_HEAPS.to_vec(),
+            misere: false,
+            player: Player::Max,
+        }
+    }
+}
+
+impl NimState {
+    fn is_legal(&self, take: Take) -> bool {
+        self.heaps
+            .get(take.heap as usize)
+            .is_some_and(|&heap| take.count <= heap)
+    }
+}
+
+impl minimax::State<f32, Take> for NimState {
+    fn is_terminal(&self) -> bool {
+        self.heaps.iter().all(|&heap| heap == 0)
+    }
+
+    fn evaluation(&self) -> f32 {
+        if !self.is_terminal() {
+            return 0.0;
+        }
+
+        // The player to move didn't take the last object, which wins in normal play
+        let winner = if self.misere {
+            self.player
+        } else {
+            self.player.opposite()
+        };
+        match winner {
+            Player::Max => f32::INFINITY,
+            Player::Min => f32::NEG_INFINITY,
+        }
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Take> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Take>) {
+        for (heap, &size) in self.heaps.iter().enumerate() {
+            actions.extend((1..=size).map(|count| Take {
+                heap: heap as u8,
+                count,
+            }));
+        }
+    }
+
+    fn result(&self, action: &Take) -> Self {
+        assert!(self.is_legal(*action), "can't take {action}");
+
+        let mut state = self.clone();
+        state.heaps[action.heap as usize] -= action.count;
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // Every heap fits in the key, and multiplying and rotating spreads it over the low bits
+        // that pick its bucket
+        let key = self
+            .heaps
+            .iter()
+            .fold(self.player as u64, |key, &heap| key << 8 | heap as u64);
+        Some(key.wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(32))
+    }
+}
+
+impl Display for NimState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (heap, &size) in self.heaps.iter().enumerate() {
+            write!(f, "{:>2} │", heap + 1)?;
+            for _ in 0..size {
+                write!(f, " ●")?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f)?;
+        if self.misere {
+            write!(f, "Whoever takes the last object loses")
+        } else {
+            write!(f, "Whoever takes the last object wins")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(heaps: &[u8], misere: bool) -> NimState {
+        NimState {
+            heaps: heaps.to_vec(),
+            misere,
+            player: Player::Max,
+        }
+    }
+
+    #[test]
+    fn parses_moves() {
+        assert_eq!("2/3".parse(), Ok(Take { heap: 1, count: 3 }));
+        assert_eq!(" 1 / 1 ".parse(), Ok(Take { heap: 0, count: 1 }));
+        assert!("0/1".parse::<Take>().is_err());
+        assert!("1/0".parse::<Take>().is_err());
+        assert!("1-1".parse::<Take>().is_err());
+
+        let state = NimState::default();
+        assert!(state.is_legal("3/5".parse().unwrap()));
+        assert!(!state.is_legal("3/6".parse().unwrap()));
+        assert!(!state.is_legal("4/1".parse().unwrap()));
+    }
+
+    #[test]
+    fn taking_the_last_object() {
+        let state = position(&[0, 1], false).result(&Take { heap: 1, count: 1 });
+        assert!(state.is_terminal());
+        assert_eq!(state.evaluation(), f32::INFINITY);
+
+        let state = position(&[0, 1], true).result(&Take { heap: 1, count: 1 });
+        assert_eq!(state.evaluation(), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn solver_agrees_with_theory() {
+        let engine = Nim::engine(u32::MAX);
+
+        for heaps in (0..64u8).map(|i| [i & 3, i >> 2 & 3, i >> 4 & 3]) {
+            // The player to move wins normal play unless the heaps' sizes XOR to zero. Misère
+            // play is the same, except when no heap has more than one object left
+            let nim_sum = heaps.iter().fold(0, |sum, heap| sum ^ heap);
+            let won = nim_sum != 0;
+            let misere_won = if heaps.iter().all(|&heap| heap <= 1) {
+                nim_sum == 0
+            } else {
+                won
+            };
+
+            for (misere, won) in [(false, won), (true, misere_won)] {
+                let value = engine.value(&position(&heaps, misere));
+                let expected = if won {
+                    f32::INFINITY
+                } else {
+                    f32::NEG_INFINITY
+                };
+                assert_eq!(value, expected, "{heaps:?}, misère: {misere}");
+            }
+        }
+    }
+
+    #[test]
+    fn solver_plays_winning_moves() {
+        // Taking two from the heap of three leaves heaps that XOR to zero
+        let best = Nim::engine(u32::MAX)
+            .best_move(&NimState::default())
+            .unwrap();
+        assert_eq!(best, Take { heap: 0, count: 2 });
+        // In misère play, the right move leaves an odd number of heaps with one object
+        let best = Nim::engine(u32::MAX)
+            .best_move(&position(&[1, 1, 4], true))
+            .unwrap();
+        assert_eq!(best, Take { heap: 2, count: 3 });
+    }
+}
@@ -0,0 +1,607 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{
+    random_seed, search_move, zobrist_key, Game, GameError, SearchLimits, WinState,
+};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const SIZE: i8 = 5;
+/// The squares each player's master starts on, which the other player's master wins by reaching.
+const TEMPLES: [u8; 2] = [2, 22];
+
+const STUDENT_VALUE: f32 = 10.0;
+/// The value of each move a player could make with their cards more than their opponent.
+const MOBILITY_VALUE: f32 = 0.5;
+
+/// The sixteen movement cards, five of which are dealt for each game.
+const CARDS: [Card; 16] = [
+    Card::Tiger,
+    Card::Dragon,
+    Card::Frog,
+    Card::Rabbit,
+    Card::Crab,
+    Card::Elephant,
+    Card::Goose,
+    Card::Rooster,
+    Card::Monkey,
+    Card::Mantis,
+    Card::Horse,
+    Card::Ox,
+    Card::Crane,
+    Card::Boar,
+    Card::Eel,
+    Card::Cobra,
+];
+
+/// The state, and the moves played along with the state before each of them.
+#[derive(Clone)]
+pub struct Onitama(OnitamaState, Vec<(Move, OnitamaState)>);
+
+impl Default for Onitama {
+    fn default() -> Self {
+        // Each game is dealt different cards
+        Self::new(deal(random_seed()))
+    }
+}
+
+impl fmt::Debug for Onitama {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Onitama")
+            .field(&self.0)
+            .field(&self.1)
+            .finish()
+    }
+}
+
+impl Game for Onitama {
+    fn name(&self) -> String {
+        "Onitama".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " ♟ │ ♚ │ ♟
+───┼───┼───
+   │ ♙ │
+───┼───┼───
+ ♙ │ ♔ │ ♙"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (49, 20)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(|(move_, _)| move_.to_string()).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // Both masters can never be stuck, so the game only ends with a win
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.is_legal(move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        let state = self.0.result(&move_);
+        self.1.push((move_, std::mem::replace(&mut self.0, state)));
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(7)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn undo(&mut self) -> Result<Option<String>, GameError> {
+        // Each game is dealt different cards, so the state before the move is restored instead of replaying the game
+        let Some((move_, state)) = self.1.pop() else {
+            return Ok(None);
+        };
+        self.0 = state;
+        Ok(Some(move_.to_string()))
+    }
+}
+
+impl Onitama {
+    /// A game with the given five cards, where Max is dealt the first two, Min the next two, and
+    /// the last is set aside.
+    pub fn new(cards: [Card; 5]) -> Self {
+        Self(OnitamaState::new(cards), Vec::new())
+    }
+
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(4)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build onitama engine")
+    }
+}
+
+/// Five different cards picked from all sixteen by `seed`.
+fn deal(seed: u64) -> [Card; 5] {
+    let mut deck = CARDS;
+    for i in 0..5 {
+        let j = i + (zobrist_key(i, Player::Max) ^ seed) as usize % (deck.len() - i);
+        deck.swap(i, j);
+    }
+    [deck[0], deck[1], deck[2], deck[3], deck[4]]
+}
+
+/// A movement card, which shows the steps a piece can take from its player's side of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Card {
+    Tiger,
+    Dragon,
+    Frog,
+    Rabbit,
+    Crab,
+    Elephant,
+    Goose,
+    Rooster,
+    Monkey,
+    Mantis,
+    Horse,
+    Ox,
+    Crane,
+    Boar,
+    Eel,
+    Cobra,
+}
+
+impl Card {
+    /// The file and rank steps the card allows, forwards and to the right being positive for the
+    /// player holding it.
+    fn steps(self) -> &'static [(i8, i8)] {
+        match self {
+            Card::Tiger => &[(0, 2), (0, -1)],
+            Card::Dragon => &[(-2, 1), (2, 1), (-1, -1), (1, -1)],
+            Card::Frog => &[(-2, 0), (-1, 1), (1, -1)],
+            Card::Rabbit => &[(2, 0), (1, 1), (-1, -1)],
+            Card::Crab => &[(0, 1), (-2, 0), (2, 0)],
+            Card::Elephant => &[(-1, 1), (1, 1), (-1, 0), (1, 0)],
+            Card::Goose => &[(-1, 1), (-1, 0), (1, 0), (1, -1)],
+            Card::Rooster => &[(1, 1), (-1, 0), (1, 0), (-1, -1)],
+            Card::Monkey => &[(-1, 1), (1, 1), (-1, -1), (1, -1)],
+            Card::Mantis => &[(-1, 1), (1, 1), (0, -1)],
+            Card::Horse => &[(0, 1), (-1, 0), (0, -1)],
+            Card::Ox => &[(0, 1), (1, 0), (0, -1)],
+            Card::Crane => &[(0, 1), (-1, -1), (1, -1)],
+            Card::Boar => &[(0, 1), (-1, 0), (1, 0)],
+            Card::Eel => &[(-1, 1), (-1, -1), (1, 0)],
+            Card::Cobra => &[(1, 1), (1, -1), (-1, 0)],
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CARDS
+            .into_iter()
+            .find(|card| card.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or("unknown card")
+    }
+}
+
+impl Display for Card {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("{self:?}"))
+    }
+}
+
+/// Moving a piece with one of the player's cards, written as the card followed by the squares
+/// the piece moves between, such as `tiger c1-c3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    card: Card,
+    from: u8,
+    to: u8,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (card, squares) = s
+            .trim()
+            .split_once(' ')
+            .ok_or("expected a card and two squares, such as `tiger c1-c3`")?;
+        let square = |name: &str| match name.trim().to_ascii_lowercase().as_bytes() {
+            &[file @ b'a'..=b'e', rank @ b'1'..=b'5'] => Ok((rank - b'1') * 5 + file - b'a'),
+            _ => Err("expected squares from a1 to e5"),
+        };
+        let (from, to) = squares
+            .split_once('-')
+            .ok_or("expected two squares, such as `c1-c3`")?;
+
+        Ok(Move {
+            card: card.parse()?,
+            from: square(from)?,
+            to: square(to)?,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let square = |square: u8| format!("{}{}", (b'a' + square % 5) as char, square / 5 + 1);
+        write!(f, "{} {}-{}", self.card, square(self.from), square(self.to))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OnitamaState {
+    /// Each player's students, as bitmasks of the squares going along each rank from a1. Max
+    /// starts along the first rank and moves first.
+    students: [u32; 2],
+    /// The square of each player's master.
+    masters: [u8; 2],
+    /// The two cards each player holds, in order, so that the same hands hash the same.
+    hands: [[Card; 2]; 2],
+    /// The card set aside, which the player to move takes in place of the card they play.
+    side: Card,
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl OnitamaState {
+    fn new([first, second, third, fourth, side]: [Card; 5]) -> Self {
+        let sorted = |mut hand: [Card; 2]| {
+            hand.sort();
+            hand
+        };
+        Self {
+            students: [0b11011, 0b11011 << 20],
+            masters: TEMPLES,
+            hands: [sorted([first, second]), sorted([third, fourth])],
+            side,
+            player: Player::Max,
+            winner: None,
+        }
+    }
+
+    fn pieces(&self, player: Player) -> u32 {
+        self.students[player as usize] | 1 << self.masters[player as usize]
+    }
+
+    /// The square `step` takes a piece of `player` to from `square`, if it's on the board.
+    fn step(player: Player, square: u8, (file, rank): (i8, i8)) -> Option<u8> {
+        // Cards are read from the side of the board of the player holding them
+        let (file, rank) = match player {
+            Player::Max => (file, rank),
+            Player::Min => (-file, -rank),
+        };
+        let file = (square % 5) as i8 + file;
+        let rank = (square / 5) as i8 + rank;
+        ((0..SIZE).contains(&file) && (0..SIZE).contains(&rank))
+            .then_some((rank * SIZE + file) as u8)
+    }
+
+    /// Every move `player` could make with their cards, ignoring whose turn it is.
+    fn moves(&self, player: Player) -> impl Iterator<Item = Move> + '_ {
+        let own = self.pieces(player);
+        self.hands[player as usize]
+            .into_iter()
+            .flat_map(move |card| {
+                (0..25u8)
+                    .filter(move |&from| own & 1 << from != 0)
+                    .flat_map(move |from| {
+                        card.steps().iter().filter_map(move |&step| {
+                            let to = Self::step(player, from, step)?;
+                            (own & 1 << to == 0).then_some(Move { card, from, to })
+                        })
+                    })
+            })
+    }
+
+    fn is_legal(&self, move_: Move) -> bool {
+        self.moves(self.player).any(|legal| legal == move_)
+    }
+
+    fn is_capture(&self, move_: &Move) -> bool {
+        self.pieces(self.player.opposite()) & 1 << move_.to != 0
+    }
+
+    /// Whether `move_` captures the opponent's master or moves the player's master onto the
+    /// opponent's temple.
+    fn is_winning(&self, move_: &Move) -> bool {
+        let (own, opponent) = (self.player as usize, self.player.opposite() as usize);
+        self.masters[opponent] == move_.to
+            || self.masters[own] == move_.from && move_.to == TEMPLES[opponent]
+    }
+}
+
+impl minimax::State<f32, Move> for OnitamaState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        let [max, min] = [Player::Max, Player::Min].map(|player| {
+            self.students[player as usize].count_ones() as f32 * STUDENT_VALUE
+                + self.moves(player).count() as f32 * MOBILITY_VALUE
+        });
+        max - min
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        actions.extend(self.moves(self.player));
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let (own, opponent) = (self.player as usize, self.player.opposite() as usize);
+        let pieces = self.pieces(self.player);
+        assert!(
+            pieces & 1 << action.from != 0
+                && pieces & 1 << action.to == 0
+                && self.hands[own].contains(&action.card),
+            "{action} is not a legal move"
+        );
+
+        let mut state = self.clone();
+
+        if self.masters[own] == action.from {
+            state.masters[own] = action.to;
+        } else {
+            state.students[own] ^= 1 << action.from | 1 << action.to;
+        }
+        state.students[opponent] &= !(1 << action.to);
+
+        if self.is_winning(action) {
+            state.winner = Some(self.player);
+        }
+
+        // The card played is set aside for the opponent, and the one set aside is taken
+        let hand = &mut state.hands[own];
+        let played = hand.iter().position(|&card| card == action.card).unwrap();
+        hand[played] = self.side;
+        hand.sort();
+        state.side = action.card;
+
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        !(self.is_capture(action) || self.is_winning(action))
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        if self.is_winning(action) {
+            f32::INFINITY
+        } else {
+            STUDENT_VALUE
+        }
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mut hash = 0;
+        for player in [Player::Max, Player::Min] {
+            let index = player as usize;
+            for square in (0..25).filter(|square| self.students[index] & 1 << square != 0) {
+                hash ^= zobrist_key(square, player);
+            }
+            hash ^= zobrist_key(25 + self.masters[index] as usize, player);
+            for card in self.hands[index] {
+                hash ^= zobrist_key(50 + card as usize, player);
+            }
+        }
+        hash ^= zobrist_key(66 + self.side as usize, self.player);
+        Some(hash)
+    }
+}
+
+impl Display for OnitamaState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut board = vec!["  ┌───┬───┬───┬───┬───┐".to_string()];
+        for rank in (0..5u8).rev() {
+            let mut line = format!("{} │", rank + 1);
+            for file in 0..5u8 {
+                let square = rank * 5 + file;
+                let piece = if self.masters[Player::Max as usize] == square {
+                    '♔'
+                } else if self.masters[Player::Min as usize] == square {
+                    '♚'
+                } else if self.students[Player::Max as usize] & 1 << square != 0 {
+                    '♙'
+                } else if self.students[Player::Min as usize] & 1 << square != 0 {
+                    '♟'
+                } else {
+                    ' '
+                };
+                line += &format!(" {piece} │");
+            }
+            board.push(line);
+            board.push(if rank == 0 {
+                "  └───┴───┴───┴───┴───┘".to_string()
+            } else {
+                "  ├───┼───┼───┼───┼───┤".to_string()
+            });
+        }
+        board.push("    a   b   c   d   e".to_string());
+
+        // Each card is drawn as it moves pieces on the board, for the player who holds it or is
+        // about to take it
+        let grid = |card: Card, player: Player, row: i8| {
+            (0..SIZE)
+                .map(|column| {
+                    let (file, rank) = (column - 2, 2 - row);
+                    let step = match player {
+                        Player::Max => (file, rank),
+                        Player::Min => (-file, -rank),
+                    };
+                    if (file, rank) == (0, 0) {
+                        '○'
+                    } else if card.steps().contains(&step) {
+                        '●'
+                    } else {
+                        '·'
+                    }
+                })
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut cards = Vec::new();
+        for (player, label) in [(Player::Min, "♚"), (Player::Max, "♔")] {
+            let [first, second] = self.hands[player as usize];
+            if player == Player::Max {
+                cards.push(format!("Set aside: {}", self.side));
+                cards.extend((0..SIZE).map(|row| grid(self.side, self.player, row)));
+                cards.push(String::new());
+            }
+            cards.push(format!("{label} {first:<10}{label} {second}"));
+            cards.extend((0..SIZE).map(|row| {
+                format!(
+                    "{}   {}",
+                    grid(first, player, row),
+                    grid(second, player, row)
+                )
+            }));
+            if player == Player::Min {
+                cards.push(String::new());
+            }
+        }
+
+        let lines = (0..board.len().max(cards.len()))
+            .map(|line| {
+                let board = board.get(line).map_or("", String::as_str);
+                let cards = cards.get(line).map_or("", String::as_str);
+                format!("{board:<23}    {cards}").trim_end().to_string()
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> u8 {
+        format!("tiger {name}-{name}").parse::<Move>().unwrap().from
+    }
+
+    fn play(cards: [Card; 5], moves: &[&str]) -> OnitamaState {
+        moves.iter().fold(OnitamaState::new(cards), |state, move_| {
+            state.result(&move_.parse().unwrap())
+        })
+    }
+
+    const CARDS: [Card; 5] = [Card::Tiger, Card::Crab, Card::Monkey, Card::Ox, Card::Boar];
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "Tiger c1-c3".parse::<Move>().unwrap();
+        assert_eq!(move_.card, Card::Tiger);
+        assert_eq!((move_.from, move_.to), (2, 12));
+        assert_eq!(move_.to_string(), "Tiger c1-c3");
+        assert_eq!(
+            "crab A1-a2".parse::<Move>().unwrap().to_string(),
+            "Crab a1-a2"
+        );
+        assert!("lion c1-c3".parse::<Move>().is_err());
+        assert!("tiger c1-c6".parse::<Move>().is_err());
+        assert!("c1-c3".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn playing_a_card_swaps_it_with_the_one_set_aside() {
+        let state = OnitamaState::new(CARDS);
+        assert!(state.is_legal("tiger c1-c3".parse().unwrap()));
+        assert!(!state.is_legal("monkey c1-d2".parse().unwrap()));
+
+        let state = state.result(&"tiger c1-c3".parse().unwrap());
+        assert_eq!(state.masters[Player::Max as usize], square("c3"));
+        assert_eq!(state.hands[Player::Max as usize], [Card::Crab, Card::Boar]);
+        assert_eq!(state.side, Card::Tiger);
+
+        // Min reads its cards from the other side, so the ox's forward step goes down
+        assert!(state.is_legal("ox b5-b4".parse().unwrap()));
+        assert_eq!(
+            OnitamaState::step(Player::Min, square("b5"), (0, 1)),
+            Some(square("b4"))
+        );
+        assert_eq!(OnitamaState::step(Player::Min, square("b5"), (0, -1)), None);
+        let state = state.result(&"ox a5-a4".parse().unwrap());
+        assert_eq!(
+            state.hands[Player::Min as usize],
+            [Card::Tiger, Card::Monkey]
+        );
+        assert_eq!(state.side, Card::Ox);
+    }
+
+    #[test]
+    fn capturing_the_master_wins() {
+        let state = play(CARDS, &["tiger c1-c3", "ox a5-a4", "crab c3-c4"]);
+        assert!(state.winner.is_none());
+        let state = state.result(&"monkey d5-c4".parse().unwrap());
+        assert_eq!(state.winner, Some(Player::Min));
+        assert!(state.is_terminal());
+    }
+
+    #[test]
+    fn reaching_the_temple_wins() {
+        let mut state = play(CARDS, &["tiger c1-c3"]);
+        state.masters[Player::Min as usize] = square("e3");
+        state.player = Player::Max;
+        state.hands[Player::Max as usize] = [Card::Tiger, Card::Crab];
+        let state = state.result(&"tiger c3-c5".parse().unwrap());
+        assert_eq!(state.winner, Some(Player::Max));
+    }
+
+    #[test]
+    fn engine_captures_the_master() {
+        let state = play(CARDS, &["tiger c1-c3", "ox a5-a4", "crab c3-c4"]);
+        let move_ = Onitama::engine(4).best_move(&state).unwrap();
+        assert_eq!(move_.to, square("c4"));
+        assert_eq!(state.result(&move_).winner, Some(Player::Min));
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::pentago::LINES;
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const BOARD: u64 = (1 << 36) - 1;
+
+/// The value to Order of a line holding only one symbol for each number of them in it, with an
+/// empty line still worth a little, since Chaos only has to put the other symbol in a line to
+/// stop it forever.
+const LINE_VALUES: [f32; 5] = [0.5, 1.0, 4.0, 16.0, 64.0];
+
+#[derive(Clone, Debug, Default)]
+pub struct OrderAndChaos(OrderAndChaosState, Vec<Move>);
+
+impl Game for OrderAndChaos {
+    fn name(&self) -> String {
+        "Order and Chaos".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " X │ O │ X
+───┼───┼───
+ O │ X │ O
+───┼───┼───
+   │ O │ X"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (28, 14)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        // Either Order makes five or Chaos fills the board first
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.empty() & 1 << move_.square != 0)
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(4)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["Order", "Chaos"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl OrderAndChaos {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build order and chaos engine")
+    }
+}
+
+/// The two symbols, which either player can place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symbol {
+    X,
+    O,
+}
+
+/// Placing a symbol on an empty square, written as the symbol followed by the square, such as
+/// `x c3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    symbol: Symbol,
+    square: u8,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected a symbol and a square, such as `x c3`";
+
+        let s = s.trim().to_ascii_lowercase();
+        let (symbol, square) = s.split_once(' ').ok_or(EXPECTED)?;
+        let symbol = match symbol {
+            "x" => Symbol::X,
+            "o" => Symbol::O,
+            _ => Err(EXPECTED)?,
+        };
+        let square = match *square.trim().as_bytes() {
+            [file @ b'a'..=b'f', rank @ b'1'..=b'6'] => (rank - b'1') * 6 + file - b'a',
+            _ => Err(EXPECTED)?,
+        };
+        Ok(Move { symbol, square })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let symbol = match self.symbol {
+            Symbol::X => 'X',
+            Symbol::O => 'O',
+        };
+        let (file, rank) = ((b'a' + self.square % 6) as char, self.square / 6 + 1);
+        write!(f, "{symbol} {file}{rank}")
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OrderAndChaosState {
+    /// The squares holding each symbol, indexed by `Symbol`, as bitmasks going along each rank
+    /// from a1.
+    symbols: [u64; 2],
+    /// Max plays Order, who wants five of the same symbol in a row and moves first, and Min
+    /// plays Chaos, who wants to fill the board without that happening.
+    player: Player,
+}
+
+impl OrderAndChaosState {
+    fn empty(&self) -> u64 {
+        BOARD & !(self.symbols[0] | self.symbols[1])
+    }
+
+    fn has_five(&self) -> bool {
+        self.symbols
+            .iter()
+            .any(|&symbols| LINES.iter().any(|&line| line & !symbols == 0))
+    }
+}
+
+impl minimax::State<f32, Move> for OrderAndChaosState {
+    fn is_terminal(&self) -> bool {
+        self.empty() == 0 || self.has_five()
+    }
+
+    fn evaluation(&self) -> f32 {
+        if self.has_five() {
+            return f32::INFINITY;
+        } else if self.empty() == 0 {
+            return f32::NEG_INFINITY;
+        }
+
+        // Lines holding both symbols can't be completed any more, so only the rest count
+        let [x, o] = self.symbols;
+        let value = |line: u64| match ((x & line).count_ones(), (o & line).count_ones()) {
+            (count, 0) | (0, count) => LINE_VALUES[count as usize],
+            _ => 0.0,
+        };
+        LINES.iter().map(|&line| value(line)).sum()
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let mut empty = self.empty();
+        while empty != 0 {
+            let square = empty.trailing_zeros() as u8;
+            actions.extend([Symbol::X, Symbol::O].map(|symbol| Move { symbol, square }));
+            empty &= empty - 1;
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        assert!(
+            self.empty() & 1 << action.square != 0,
+            "{action} is not a legal move"
+        );
+
+        let mut state = self.clone();
+        state.symbols[action.symbol as usize] |= 1 << action.square;
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let [x, o] = self.symbols;
+        Some(mix(x) ^ mix(o ^ (self.player as u64) << 40).rotate_left(32))
+    }
+}
+
+impl Display for OrderAndChaosState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ┌───┬───┬───┬───┬───┬───┐")?;
+        for rank in (0..6).rev() {
+            write!(f, "{} │", rank + 1)?;
+            for file in 0..6 {
+                let square = 1 << (rank * 6 + file);
+                let symbol = if self.symbols[Symbol::X as usize] & square != 0 {
+                    'X'
+                } else if self.symbols[Symbol::O as usize] & square != 0 {
+                    'O'
+                } else {
+                    ' '
+                };
+                write!(f, " {symbol} │")?;
+            }
+            if rank != 0 {
+                writeln!(f, "\n  ├───┼───┼───┼───┼───┼───┤")?;
+            }
+        }
+        write!(
+            f,
+            "\n  └───┴───┴───┴───┴───┴───┘\n    a   b   c   d   e   f"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> OrderAndChaosState {
+        moves
+            .iter()
+            .fold(OrderAndChaosState::default(), |state, move_| {
+                state.result(&move_.parse().unwrap())
+            })
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "X c3".parse::<Move>().unwrap();
+        assert_eq!((move_.symbol, move_.square), (Symbol::X, 14));
+        assert_eq!(move_.to_string(), "X c3");
+        assert_eq!("o F6".parse::<Move>().unwrap().to_string(), "O f6");
+        assert!("c3".parse::<Move>().is_err());
+        assert!("y c3".parse::<Move>().is_err());
+        assert!("x g1".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn five_of_a_symbol_wins_for_order() {
+        // Chaos is forced to help by placing the same symbol
+        let state = play(&["o a1", "o b2", "o c3", "o d4"]);
+        assert!(!state.is_terminal());
+        let mixed = state.result(&"x e5".parse().unwrap());
+        assert!(!mixed.is_terminal());
+        let five = state.result(&"o e5".parse().unwrap());
+        assert!(five.is_terminal());
+        assert_eq!(five.evaluation(), f32::INFINITY);
+    }
+
+    #[test]
+    fn filling_the_board_wins_for_chaos() {
+        // Alternating pairs of symbols along every rank never line up five
+        let mut state = OrderAndChaosState::default();
+        for square in 0..36u8 {
+            let symbol = if (square % 6 / 2 + square / 6) % 2 == 0 {
+                "x"
+            } else {
+                "o"
+            };
+            let name = format!("{symbol} {}{}", (b'a' + square % 6) as char, square / 6 + 1);
+            state = state.result(&name.parse().unwrap());
+            assert!(!state.has_five(), "{name} made five");
+        }
+        assert!(state.is_terminal());
+        assert_eq!(state.evaluation(), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn engine_completes_and_blocks_lines() {
+        let state = play(&["x a1", "x a2", "x a3", "o f6"]);
+        let order = state.result(&"x a4".parse().unwrap());
+
+        // With four in a row open at one end, Chaos has to put an O on a5
+        let move_ = OrderAndChaos::engine(2).best_move(&order).unwrap();
+        assert_eq!(move_.to_string(), "O a5");
+        let state = order.result(&move_);
+        assert!(!state.is_terminal());
+
+        // Order completes five straight away when it can
+        let state = play(&["x b1", "x b2", "x b3", "o f6", "x b4", "o f5"]);
+        let move_ = OrderAndChaos::engine(2).best_move(&state).unwrap();
+        assert_eq!(move_.to_string(), "X b5");
+    }
+}
@@ -0,0 +1,418 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{scaled_advantage, search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+const CORNERS: u64 = 0x8100_0000_0000_0081;
+
+/// The shifts between neighbouring squares in each direction, along with the file that squares
+/// can't reach by wrapping around from the other side of the board.
+const DIRECTIONS: [(i8, u64); 8] = [
+    (1, FILE_A),
+    (-1, FILE_H),
+    (8, 0),
+    (-8, 0),
+    (9, FILE_A),
+    (7, FILE_H),
+    (-7, FILE_A),
+    (-9, FILE_H),
+];
+
+const DISC_VALUE: f32 = 1.0;
+/// The value of each move a player has more than their opponent.
+const MOBILITY_VALUE: f32 = 5.0;
+/// Corners can never be flipped, and anchor stable discs along the edges.
+const CORNER_VALUE: f32 = 25.0;
+
+fn shift(bitboard: u64, (shift, wrapped): (i8, u64)) -> u64 {
+    let shifted = if shift > 0 {
+        bitboard << shift
+    } else {
+        bitboard >> -shift
+    };
+    shifted & !wrapped
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Othello(OthelloState, Vec<Move>);
+
+impl Game for Othello {
+    fn name(&self) -> String {
+        "Othello".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "   │   │
+───┼───┼───
+ ○ │ ● │ ●
+───┼───┼───
+ ● │ ○ │  "
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (36, 18)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.is_legal(move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(8)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn advantage(&self) -> Option<f32> {
+        let value = Self::engine(4).value(&self.0);
+        Some(scaled_advantage(value, self.0.player, 30.0))
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["Black", "White"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Othello {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build othello engine")
+    }
+}
+
+/// A disc placed on a square, written in algebraic notation such as `d3`, or a pass, which is
+/// only allowed when there are no squares to place a disc on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    /// The index of the square, from 0 for `a1` to 63 for `h8`, going along each row first.
+    Place(u8),
+    Pass,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        if s == "pass" {
+            return Ok(Move::Pass);
+        }
+
+        match s.as_bytes() {
+            &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Ok(Move::Place((rank - b'1') * 8 + file - b'a'))
+            }
+            _ => Err("expected a square or `pass`"),
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Place(square) => {
+                write!(f, "{}{}", (b'a' + square % 8) as char, square / 8 + 1)
+            }
+            Move::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OthelloState {
+    /// The discs of each player, indexed by `Player`. Black moves first, and is the maximising
+    /// player.
+    discs: [u64; 2],
+    player: Player,
+}
+
+impl Default for OthelloState {
+    fn default() -> Self {
+        // Black on d5 and e4, and White on d4 and e5
+        Self {
+            discs: [0x0000_0008_1000_0000, 0x0000_0010_0800_0000],
+            player: Player::Max,
+        }
+    }
+}
+
+impl OthelloState {
+    /// The squares `player` can place a disc on.
+    fn placements(&self, player: Player) -> u64 {
+        let own = self.discs[player as usize];
+        let opponent = self.discs[player.opposite() as usize];
+        let empty = !(own | opponent);
+
+        DIRECTIONS
+            .into_iter()
+            .map(|direction| {
+                // Extend runs of the opponent's discs from each of the player's discs, which can
+                // be at most six long
+                let mut run = shift(own, direction) & opponent;
+                for _ in 0..5 {
+                    run |= shift(run, direction) & opponent;
+                }
+                shift(run, direction) & empty
+            })
+            .fold(0, |placements, squares| placements | squares)
+    }
+
+    /// The opponent's discs flipped by the player to move placing a disc on `square`.
+    fn flips(&self, square: u8) -> u64 {
+        let own = self.discs[self.player as usize];
+        let opponent = self.discs[self.player.opposite() as usize];
+
+        DIRECTIONS
+            .into_iter()
+            .map(|direction| {
+                let mut run = 0;
+                let mut next = shift(1 << square, direction);
+                while next & opponent != 0 {
+                    run |= next;
+                    next = shift(next, direction);
+                }
+                if next & own != 0 {
+                    run
+                } else {
+                    0
+                }
+            })
+            .fold(0, |flips, run| flips | run)
+    }
+
+    fn is_legal(&self, move_: Move) -> bool {
+        let placements = self.placements(self.player);
+        match move_ {
+            Move::Place(square) => placements & (1 << square) != 0,
+            Move::Pass => placements == 0,
+        }
+    }
+}
+
+impl minimax::State<f32, Move> for OthelloState {
+    fn is_terminal(&self) -> bool {
+        self.placements(Player::Max) == 0 && self.placements(Player::Min) == 0
+    }
+
+    fn evaluation(&self) -> f32 {
+        let [max, min] = self.discs.map(|discs| discs.count_ones() as f32);
+        let [max_moves, min_moves] =
+            [Player::Max, Player::Min].map(|player| self.placements(player).count_ones() as f32);
+
+        if max_moves == 0.0 && min_moves == 0.0 {
+            return match max.total_cmp(&min) {
+                std::cmp::Ordering::Greater => f32::INFINITY,
+                std::cmp::Ordering::Less => f32::NEG_INFINITY,
+                std::cmp::Ordering::Equal => 0.0,
+            };
+        }
+
+        let [max_corners, min_corners] = self
+            .discs
+            .map(|discs| (discs & CORNERS).count_ones() as f32);
+
+        (max - min) * DISC_VALUE
+            + (max_moves - min_moves) * MOBILITY_VALUE
+            + (max_corners - min_corners) * CORNER_VALUE
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let placements = self.placements(self.player);
+        if placements == 0 {
+            actions.push(Move::Pass);
+        }
+
+        // Try corners first, as they're usually the strongest moves
+        for mut squares in [placements & CORNERS, placements & !CORNERS] {
+            while squares != 0 {
+                actions.push(Move::Place(squares.trailing_zeros() as u8));
+                squares &= squares - 1;
+            }
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        let mut state = self.clone();
+        if let &Move::Place(square) = action {
+            let flips = self.flips(square);
+            assert!(flips != 0, "{action} is not a legal move");
+
+            state.discs[self.player as usize] |= flips | 1 << square;
+            state.discs[self.player.opposite() as usize] &= !flips;
+        }
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // Each square can be empty or hold either disc, so mixing the two boards keeps positions
+        // that differ in one disc apart
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let [max, min] = self.discs;
+        Some(mix(max) ^ mix(min ^ self.player as u64).rotate_left(32))
+    }
+}
+
+impl Display for OthelloState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let placements = self.placements(self.player);
+
+        writeln!(f, "  ┌───┬───┬───┬───┬───┬───┬───┬───┐")?;
+        for rank in 0..8 {
+            write!(f, "{} │", rank + 1)?;
+            for file in 0..8 {
+                let square = 1 << (rank * 8 + file);
+                // Show where the player to move can place a disc
+                let disc = if self.discs[Player::Max as usize] & square != 0 {
+                    '●'
+                } else if self.discs[Player::Min as usize] & square != 0 {
+                    '○'
+                } else if placements & square != 0 {
+                    '·'
+                } else {
+                    ' '
+                };
+                write!(f, " {disc} │")?;
+            }
+            if rank != 7 {
+                writeln!(f, "\n  ├───┼───┼───┼───┼───┼───┼───┼───┤")?;
+            }
+        }
+        write!(
+            f,
+            "\n  └───┴───┴───┴───┴───┴───┴───┴───┘\n    a   b   c   d   e   f   g   h"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> OthelloState {
+        moves.iter().fold(OthelloState::default(), |state, move_| {
+            state.result(&move_.parse().unwrap())
+        })
+    }
+
+    fn notation(state: &OthelloState) -> Vec<String> {
+        state.actions().iter().map(Move::to_string).collect()
+    }
+
+    #[test]
+    fn parses_moves() {
+        assert_eq!("d3".parse(), Ok(Move::Place(19)));
+        assert_eq!("H8".parse(), Ok(Move::Place(63)));
+        assert_eq!("pass".parse(), Ok(Move::Pass));
+        assert!("i1".parse::<Move>().is_err());
+        assert!("d9".parse::<Move>().is_err());
+        assert_eq!(Move::Place(0).to_string(), "a1");
+    }
+
+    #[test]
+    fn opening_moves_flip_discs() {
+        assert_eq!(notation(&OthelloState::default()), ["d3", "c4", "f5", "e6"]);
+
+        let state = play(&["f5"]);
+        assert_eq!(state.discs[Player::Max as usize].count_ones(), 4);
+        assert_eq!(state.discs[Player::Min as usize].count_ones(), 1);
+        assert_eq!(notation(&state), ["f4", "d6", "f6"]);
+    }
+
+    #[test]
+    fn flips_in_several_directions() {
+        // Black on c1 outflanks b1 along the rank, c2 along the file and d2 diagonally, but not
+        // d1 as there's no black disc beyond it
+        let state = OthelloState {
+            discs: [
+                1 << 0 | 1 << 18 | 1 << 20,
+                1 << 1 | 1 << 3 | 1 << 10 | 1 << 11,
+            ],
+            player: Player::Max,
+        };
+        let state = state.result(&"c1".parse().unwrap());
+        assert_eq!(state.discs[Player::Max as usize].count_ones(), 7);
+        assert_eq!(state.discs[Player::Min as usize], 1 << 3);
+    }
+
+    #[test]
+    fn passes_only_without_moves() {
+        // White has no discs next to an empty square it could flip from
+        let state = OthelloState {
+            discs: [0x0000_0000_0000_0003, 0x0000_0000_0000_0100],
+            player: Player::Min,
+        };
+        assert!(!state.is_terminal());
+        assert_eq!(state.actions(), [Move::Pass]);
+        assert!(state.is_legal(Move::Pass));
+        assert!(!OthelloState::default().is_legal(Move::Pass));
+
+        // Once neither player can move the game is over, and the player with more discs wins
+        let state = OthelloState {
+            discs: [0x0000_0000_0000_0003, 0],
+            player: Player::Min,
+        };
+        assert!(state.is_terminal());
+        assert_eq!(state.evaluation(), f32::INFINITY);
+    }
+}
@@ -0,0 +1,422 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const SIZE: usize = 6;
+const BOARD: u64 = (1 << 36) - 1;
+/// Every line of five squares on the board, which Order and Chaos shares.
+pub(crate) const LINES: [u64; 32] = lines();
+
+/// The value of a line with only one player's marbles in it for each number of marbles, since
+/// lines closer to five are worth much more than several lines that have barely started.
+const LINE_VALUES: [f32; 5] = [0.0, 1.0, 4.0, 16.0, 64.0];
+
+const fn lines() -> [u64; 32] {
+    let mut lines = [0; 32];
+    let mut count = 0;
+    let directions = [(1, 0), (0, 1), (1, 1), (1, -1)];
+    let mut direction = 0;
+    while direction < directions.len() {
+        let (file_step, rank_step) = directions[direction];
+        let mut start = 0;
+        while start < SIZE * SIZE {
+            let (file, rank) = ((start % SIZE) as i32, (start / SIZE) as i32);
+            let (end_file, end_rank) = (file + 4 * file_step, rank + 4 * rank_step);
+            if end_file < SIZE as i32 && end_rank >= 0 && end_rank < SIZE as i32 {
+                let mut line = 0;
+                let mut i = 0;
+                while i < 5 {
+                    line |= 1 << ((rank + i * rank_step) * SIZE as i32 + file + i * file_step);
+                    i += 1;
+                }
+                lines[count] = line;
+                count += 1;
+            }
+            start += 1;
+        }
+        direction += 1;
+    }
+    lines
+}
+
+/// The squares in `marbles` after turning `quadrant` a quarter turn, numbering the quadrants
+/// from 1 in the top left to 4 in the bottom right.
+fn rotate(marbles: u64, quadrant: u8, clockwise: bool) -> u64 {
+    let file = 3 * (quadrant as usize - 1) % 6;
+    let rank = if quadrant <= 2 { 3 } else { 0 };
+    let square = |x: usize, y: usize| (rank + y) * SIZE + file + x;
+
+    let mut rotated = marbles;
+    for y in 0..3 {
+        for x in 0..3 {
+            let (to_x, to_y) = if clockwise { (y, 2 - x) } else { (2 - y, x) };
+            rotated &= !(1 << square(to_x, to_y));
+            rotated |= (marbles >> square(x, y) & 1) << square(to_x, to_y);
+        }
+    }
+    rotated
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Pentago(PentagoState, Vec<Move>);
+
+impl Game for Pentago {
+    fn name(&self) -> String {
+        "Pentago".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        " ○ · ● ┃ · ·
+ · ● · ┃ ○ ·
+━━━━━━━╋━━━━
+ ● · · ┃ · ○
+ · ○ · ┃ ● ·"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (28, 10)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        self.0
+            .is_terminal()
+            .then(|| WinState::from_evaluation(self.0.evaluation()))
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.empty() & 1 << move_.square != 0)
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(3)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Pentago {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build pentago engine")
+    }
+}
+
+/// Placing a marble and then turning a quadrant a quarter turn, written as the square followed
+/// by the quadrant and `r` or `l` for turning it right (clockwise) or left, such as `b5 2r`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    square: u8,
+    quadrant: u8,
+    clockwise: bool,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected a square and a quadrant to turn, such as `b5 2r`";
+
+        let s = s.trim().to_ascii_lowercase();
+        let (square, rotation) = s.split_once(' ').ok_or(EXPECTED)?;
+        let square = match *square.as_bytes() {
+            [file @ b'a'..=b'f', rank @ b'1'..=b'6'] => (rank - b'1') * 6 + file - b'a',
+            _ => Err(EXPECTED)?,
+        };
+        let (quadrant, clockwise) = match *rotation.trim().as_bytes() {
+            [quadrant @ b'1'..=b'4', direction @ (b'r' | b'l')] => {
+                (quadrant - b'0', direction == b'r')
+            }
+            _ => Err(EXPECTED)?,
+        };
+        Ok(Move {
+            square,
+            quadrant,
+            clockwise,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {}{}",
+            (b'a' + self.square % 6) as char,
+            self.square / 6 + 1,
+            self.quadrant,
+            if self.clockwise { 'r' } else { 'l' }
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PentagoState {
+    /// Each player's marbles, as bitmasks of the squares going along each rank from a1. Max
+    /// moves first.
+    marbles: [u64; 2],
+    player: Player,
+}
+
+impl PentagoState {
+    fn empty(&self) -> u64 {
+        BOARD & !(self.marbles[0] | self.marbles[1])
+    }
+
+    fn has_five(&self, player: Player) -> bool {
+        let marbles = self.marbles[player as usize];
+        LINES.iter().any(|&line| line & !marbles == 0)
+    }
+}
+
+impl minimax::State<f32, Move> for PentagoState {
+    fn is_terminal(&self) -> bool {
+        self.empty() == 0 || self.has_five(Player::Max) || self.has_five(Player::Min)
+    }
+
+    fn evaluation(&self) -> f32 {
+        // A turn can complete lines for both players at once, which is a draw
+        match (self.has_five(Player::Max), self.has_five(Player::Min)) {
+            (true, false) => return f32::INFINITY,
+            (false, true) => return f32::NEG_INFINITY,
+            (true, true) => return 0.0,
+            (false, false) if self.empty() == 0 => return 0.0,
+            (false, false) => {}
+        }
+
+        let [max, min] = self.marbles;
+        let value = |line: u64| match ((max & line).count_ones(), (min & line).count_ones()) {
+            (count, 0) => LINE_VALUES[count as usize],
+            (0, count) => -LINE_VALUES[count as usize],
+            _ => 0.0,
+        };
+        LINES.iter().map(|&line| value(line)).sum()
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let own = self.marbles[self.player as usize];
+        let mut empty = self.empty();
+        while empty != 0 {
+            let square = empty.trailing_zeros() as u8;
+            empty &= empty - 1;
+
+            // Turning a quadrant that looks the same afterwards, like an empty one, is the same
+            // as not turning anything, so only one of those turns is worth searching
+            let placed = [
+                own | 1 << square,
+                self.marbles[self.player.opposite() as usize],
+            ];
+            let mut unchanged = false;
+            for quadrant in 1..=4 {
+                for clockwise in [true, false] {
+                    let same = placed
+                        .iter()
+                        .all(|&marbles| rotate(marbles, quadrant, clockwise) == marbles);
+                    if !same || !unchanged {
+                        unchanged |= same;
+                        actions.push(Move {
+                            square,
+                            quadrant,
+                            clockwise,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        assert!(
+            self.empty() & 1 << action.square != 0,
+            "{action} is not a legal move"
+        );
+
+        let mut state = self.clone();
+        state.marbles[self.player as usize] |= 1 << action.square;
+        state.marbles = state
+            .marbles
+            .map(|marbles| rotate(marbles, action.quadrant, action.clockwise));
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let [max, min] = self.marbles;
+        Some(mix(max) ^ mix(min ^ (self.player as u64) << 40).rotate_left(32))
+    }
+}
+
+impl Display for PentagoState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ┏━━━━ 1 ━━━━┳━━━━ 2 ━━━━┓")?;
+        for rank in (0..SIZE).rev() {
+            write!(f, "{} ┃", rank + 1)?;
+            for file in 0..SIZE {
+                let square = 1 << (rank * SIZE + file);
+                let marble = if self.marbles[Player::Max as usize] & square != 0 {
+                    '●'
+                } else if self.marbles[Player::Min as usize] & square != 0 {
+                    '○'
+                } else {
+                    '·'
+                };
+                let separator = if file % 3 == 2 { " ┃" } else { "  " };
+                write!(f, " {marble}{separator}")?;
+            }
+            writeln!(f)?;
+            if rank == 3 {
+                writeln!(f, "  ┣━━━━━━━━━━━╋━━━━━━━━━━━┫")?;
+            }
+        }
+        writeln!(f, "  ┗━━━━ 3 ━━━━┻━━━━ 4 ━━━━┛")?;
+        write!(f, "    a   b   c   d   e   f")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> u8 {
+        format!("{name} 1r").parse::<Move>().unwrap().square
+    }
+
+    fn marbles(squares: &[&str]) -> u64 {
+        squares.iter().map(|name| 1 << square(name)).sum()
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "B5 2R".parse::<Move>().unwrap();
+        assert_eq!(
+            (move_.square, move_.quadrant, move_.clockwise),
+            (25, 2, true)
+        );
+        assert_eq!(move_.to_string(), "b5 2r");
+        assert_eq!("f1 4l".parse::<Move>().unwrap().to_string(), "f1 4l");
+        assert!("b5".parse::<Move>().is_err());
+        assert!("b5 5r".parse::<Move>().is_err());
+        assert!("g1 1r".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn quadrants_turn_a_quarter_turn() {
+        // The top left corner of each quadrant moves to its top right corner when turned right
+        for (quadrant, from, to) in [
+            (1, "a6", "c6"),
+            (2, "d6", "f6"),
+            (3, "a3", "c3"),
+            (4, "d3", "f3"),
+        ] {
+            let turned = rotate(1 << square(from), quadrant, true);
+            assert_eq!(turned, 1 << square(to));
+            assert_eq!(rotate(turned, quadrant, false), 1 << square(from));
+        }
+        // Squares in other quadrants stay where they are
+        assert_eq!(rotate(1 << square("d4"), 1, true), 1 << square("d4"));
+        assert_eq!(rotate(1 << square("b5"), 1, true), 1 << square("b5"));
+    }
+
+    #[test]
+    fn five_in_a_row_counts_after_turning() {
+        let state = PentagoState {
+            marbles: [
+                marbles(&["a3", "b3", "c3", "d1"]),
+                marbles(&["a6", "b6", "c6", "f6"]),
+            ],
+            player: Player::Max,
+        };
+
+        // Turning the bottom right quadrant right brings d1 and d2 round to d3 and e3
+        let five = state.result(&"d2 4r".parse().unwrap());
+        assert!(five.has_five(Player::Max));
+        assert!(five.is_terminal());
+        assert_eq!(five.evaluation(), f32::INFINITY);
+
+        let turned_away = state.result(&"d2 4l".parse().unwrap());
+        assert!(!turned_away.is_terminal());
+    }
+
+    #[test]
+    fn engine_completes_five() {
+        let state = PentagoState {
+            marbles: [
+                marbles(&["a1", "a2", "a3", "a4"]),
+                marbles(&["f1", "f2", "f3", "f4"]),
+            ],
+            player: Player::Max,
+        };
+        let move_ = Pentago::engine(2).best_move(&state).unwrap();
+        assert_eq!(state.result(&move_).evaluation(), f32::INFINITY);
+    }
+
+    #[test]
+    fn unchanged_turns_are_only_searched_once() {
+        // Every quadrant but the one played in is empty, and turning the centre square changes
+        // nothing either
+        let actions = PentagoState::default().actions();
+        let centre = actions
+            .iter()
+            .filter(|move_| move_.square == square("b5"))
+            .count();
+        assert_eq!(centre, 1);
+        let corner = actions
+            .iter()
+            .filter(|move_| move_.square == square("a6"))
+            .count();
+        assert_eq!(corner, 3);
+    }
+}
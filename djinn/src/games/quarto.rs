@@ -0,0 +1,561 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+/// The rows, columns and diagonals of the board, as bitmasks of its squares going along each row
+/// from the top left.
+const LINES: [u16; 10] = [
+    0x000f, 0x00f0, 0x0f00, 0xf000, 0x1111, 0x2222, 0x4444, 0x8888, 0x8421, 0x1248,
+];
+const PIECES: u8 = 16;
+
+/// The letters for each of a piece's four traits, with the letter for the trait's bit being set
+/// second.
+const TRAITS: [[char; 2]; 4] = [['l', 'd'], ['s', 't'], ['s', 'r'], ['s', 'h']];
+
+/// The value of each line with three pieces sharing a trait, for the player who isn't about to
+/// hand over a piece.
+const THREAT_VALUE: f32 = 1.0;
+
+#[derive(Clone, Debug, Default)]
+pub struct Quarto(QuartoState, Vec<Move>);
+
+impl Game for Quarto {
+    fn name(&self) -> String {
+        "Quarto".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "dtrh ·  ltss
+ ·  dsrs ·
+ltrh ·  dtsh
+ ·  lsss ·
+  ·  dssh · "
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (46, 7)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if let Some(winner) = self.0.winner {
+            Some(WinState::Decisive(winner))
+        } else if self.0.is_terminal() {
+            Some(WinState::Draw)
+        } else {
+            None
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        self.parse_move(move_)
+            .is_some_and(|move_| !self.0.is_terminal() && self.0.is_legal(move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = self
+            .parse_move(move_)
+            .ok_or_else(|| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(4)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Quarto {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build quarto engine")
+    }
+
+    /// Parses a move, leaving out the piece to hand over if placing the piece wins, since the
+    /// player may not have noticed.
+    fn parse_move(&self, s: &str) -> Option<Move> {
+        let mut move_ = s.parse::<Move>().ok()?;
+        if let (Some(square), Some(piece)) = (move_.square, self.0.in_hand) {
+            if self.0.wins(square, piece) {
+                move_.give = None;
+            }
+        }
+        Some(move_)
+    }
+}
+
+/// One of the sixteen pieces, whose bits are its four traits: dark or light, tall or short, round
+/// or square, and hollow or solid. Pieces are written as a letter for each trait in that order,
+/// such as `dtrh` for the dark, tall, round and hollow piece, or `lsss` for its opposite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Piece(u8);
+
+impl FromStr for Piece {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let letters = s.trim().to_ascii_lowercase().chars().collect::<Vec<_>>();
+        if letters.len() != TRAITS.len() {
+            Err("expected a letter for each of the four traits")?;
+        }
+
+        let mut piece = 0;
+        for (letter, [unset, set]) in letters.into_iter().zip(TRAITS) {
+            piece <<= 1;
+            if letter == set {
+                piece |= 1;
+            } else if letter != unset {
+                Err("expected d or l, t or s, r or s, and h or s")?;
+            }
+        }
+        Ok(Piece(piece))
+    }
+}
+
+impl Display for Piece {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (bit, [unset, set]) in TRAITS.into_iter().enumerate() {
+            let set_bit = self.0 & 1 << (TRAITS.len() - 1 - bit) != 0;
+            write!(f, "{}", if set_bit { set } else { unset })?;
+        }
+        Ok(())
+    }
+}
+
+/// A move of placing the piece the player was handed, then handing a piece to their opponent,
+/// written as the square followed by the piece, such as `c2 dtrh`.
+///
+/// The first move only hands over a piece, and a move that wins or fills the board only places
+/// one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    /// The square to place the piece on, going along each row from the top left.
+    square: Option<u8>,
+    give: Option<Piece>,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut move_ = Move {
+            square: None,
+            give: None,
+        };
+
+        let mut parts = s.split_whitespace().peekable();
+        if let Some(square) = parts.next_if(|part| part.len() == 2) {
+            let square = square.to_ascii_lowercase();
+            let column = match square.as_bytes()[0] {
+                letter @ b'a'..=b'd' => letter - b'a',
+                _ => Err("expected a column from a to d")?,
+            };
+            let row = match square.as_bytes()[1] {
+                digit @ b'1'..=b'4' => digit - b'1',
+                _ => Err("expected a row from 1 to 4")?,
+            };
+            move_.square = Some(row * 4 + column);
+        }
+        if let Some(piece) = parts.next() {
+            move_.give = Some(piece.parse()?);
+        }
+
+        if parts.next().is_some() || (move_.square.is_none() && move_.give.is_none()) {
+            Err("expected a square, a piece, or both")?;
+        }
+        Ok(move_)
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(square) = self.square {
+            write!(f, "{}{}", (b'a' + square % 4) as char, square / 4 + 1)?;
+        }
+        match (self.square, self.give) {
+            (Some(_), Some(piece)) => write!(f, " {piece}"),
+            (None, Some(piece)) => write!(f, "{piece}"),
+            (_, None) => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QuartoState {
+    /// The piece on each square, going along each row from the top left.
+    board: [Option<Piece>; 16],
+    /// The pieces that haven't been placed or handed over yet, as a bitmask.
+    available: u16,
+    /// The piece the player to move was handed and has to place, which is only `None` before the
+    /// first move.
+    in_hand: Option<Piece>,
+    player: Player,
+    winner: Option<Player>,
+    /// The Zobrist hash of the board and the piece in hand, updated as moves are made.
+    hash: u64,
+}
+
+impl Default for QuartoState {
+    fn default() -> Self {
+        Self {
+            board: [None; 16],
+            available: u16::MAX,
+            in_hand: None,
+            player: Player::Max,
+            winner: None,
+            hash: 0,
+        }
+    }
+}
+
+impl QuartoState {
+    fn empty_squares(&self) -> u16 {
+        (0..16u8)
+            .filter(|&square| self.board[square as usize].is_none())
+            .fold(0, |empty, square| empty | 1 << square)
+    }
+
+    /// The traits shared by every piece on the squares of `line`, as the bits common to all of
+    /// them that are set and the ones that are unset, or `None` if one of them is empty.
+    fn shared_traits(&self, line: u16) -> Option<(u8, u8)> {
+        (0..16)
+            .filter(|square| line & 1 << square != 0)
+            .map(|square| self.board[square].map(|Piece(piece)| (piece, !piece & 0xf)))
+            .try_fold((0xf, 0xf), |(set, unset), traits| {
+                traits.map(|(piece_set, piece_unset)| (set & piece_set, unset & piece_unset))
+            })
+    }
+
+    /// Whether placing `piece` on `square` completes a line of four pieces sharing a trait.
+    fn wins(&self, square: u8, piece: Piece) -> bool {
+        let mut board = self.clone();
+        board.board[square as usize] = Some(piece);
+        LINES
+            .into_iter()
+            .filter(|line| line & 1 << square != 0)
+            .any(|line| {
+                board
+                    .shared_traits(line)
+                    .is_some_and(|shared| shared != (0, 0))
+            })
+    }
+
+    /// The traits shared by the three pieces of each line with one empty square, as the bits set
+    /// in all of them and the bits unset in all of them.
+    fn threats(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let empty = self.empty_squares();
+        LINES.into_iter().filter_map(move |line| {
+            let gap = line & empty;
+            if gap.count_ones() != 1 {
+                return None;
+            }
+
+            let (set, unset) = (0..16)
+                .filter(|square| line & !gap & 1 << square != 0)
+                .map(|square| self.board[square].unwrap().0)
+                .fold((0xf, 0xf), |(set, unset), piece| {
+                    (set & piece, unset & !piece & 0xf)
+                });
+            ((set, unset) != (0, 0)).then_some((set, unset))
+        })
+    }
+
+    /// Whether `piece` could be placed to win straight away.
+    fn is_deadly(&self, piece: Piece) -> bool {
+        self.threats()
+            .any(|(set, unset)| piece.0 & set != 0 || !piece.0 & unset != 0)
+    }
+
+    fn is_legal(&self, move_: Move) -> bool {
+        if self.is_terminal() {
+            return false;
+        }
+
+        let placed = match (self.in_hand, move_.square) {
+            (Some(piece), Some(square)) => {
+                if self.board[square as usize].is_some() {
+                    return false;
+                }
+                Some((square, piece))
+            }
+            (None, None) => None,
+            _ => return false,
+        };
+
+        // A piece has to be handed over unless the game has just ended
+        let ended =
+            self.available == 0 || placed.is_some_and(|(square, piece)| self.wins(square, piece));
+        match move_.give {
+            Some(piece) => !ended && self.available & 1 << piece.0 != 0,
+            None => ended,
+        }
+    }
+
+    fn result_of(&self, move_: Move) -> Self {
+        let mut state = self.clone();
+        if let (Some(square), Some(piece)) = (move_.square, self.in_hand) {
+            if self.wins(square, piece) {
+                state.winner = Some(self.player);
+            }
+            state.board[square as usize] = Some(piece);
+            state.hash ^= zobrist_key(square as usize * 16 + piece.0 as usize, Player::Max);
+        }
+
+        if let Some(piece) = self.in_hand {
+            state.hash ^= zobrist_key(256 + piece.0 as usize, Player::Max);
+        }
+        state.in_hand = move_.give;
+        if let Some(piece) = move_.give {
+            state.available &= !(1 << piece.0);
+            state.hash ^= zobrist_key(256 + piece.0 as usize, Player::Max);
+        }
+
+        state.player = self.player.opposite();
+        state
+    }
+}
+
+impl minimax::State<f32, Move> for QuartoState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some() || self.empty_squares() == 0
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None if self.is_terminal() => return 0.0,
+            None => {}
+        }
+
+        // Every line that's one piece from winning leaves fewer safe pieces for the player to
+        // move to hand over once they've placed theirs
+        let threats = self.threats().count() as f32 * THREAT_VALUE;
+        match self.player {
+            Player::Max => -threats,
+            Player::Min => threats,
+        }
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let start = actions.len();
+        let gives = (0..PIECES)
+            .filter(|&piece| self.available & 1 << piece != 0)
+            .map(|piece| Some(Piece(piece)))
+            .collect::<Vec<_>>();
+
+        let Some(piece) = self.in_hand else {
+            actions.extend(gives.into_iter().map(|give| Move { square: None, give }));
+            return;
+        };
+
+        // Winning straight away is always best, so nothing else needs searching
+        let empty = self.empty_squares();
+        let squares = (0..16u8).filter(|&square| empty & 1 << square != 0);
+        if let Some(square) = squares.clone().find(|&square| self.wins(square, piece)) {
+            actions.push(Move {
+                square: Some(square),
+                give: None,
+            });
+            return;
+        }
+
+        if gives.is_empty() {
+            actions.extend(squares.map(|square| Move {
+                square: Some(square),
+                give: None,
+            }));
+            return;
+        }
+
+        // Handing over a piece that can win straight away loses, so those moves are only
+        // searched if there's no other choice
+        for square in squares {
+            let placed = self.result_of(Move {
+                square: Some(square),
+                give: None,
+            });
+            actions.extend(
+                gives
+                    .iter()
+                    .filter(|give| !placed.is_deadly(give.unwrap()))
+                    .map(|&give| Move {
+                        square: Some(square),
+                        give,
+                    }),
+            );
+        }
+        if actions.len() == start {
+            let square = empty.trailing_zeros() as u8;
+            actions.extend(gives.into_iter().map(|give| Move {
+                square: Some(square),
+                give,
+            }));
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        assert!(self.is_legal(*action), "{action} can't be played");
+        self.result_of(*action)
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // The player to move follows from the number of pieces placed
+        Some(self.hash)
+    }
+}
+
+impl Display for QuartoState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "   a    b    c    d         Left to hand over")?;
+        for row in 0..4 {
+            write!(f, "\n{}  ", row + 1)?;
+            for column in 0..4 {
+                if column > 0 {
+                    write!(f, " ")?;
+                }
+                match self.board[row * 4 + column] {
+                    Some(piece) => write!(f, "{piece}")?,
+                    None => write!(f, " ·  ")?,
+                }
+            }
+
+            write!(f, "    ")?;
+            for piece in row as u8 * 4..row as u8 * 4 + 4 {
+                if self.available & 1 << piece != 0 {
+                    write!(f, " {}", Piece(piece))?;
+                } else {
+                    write!(f, "     ")?;
+                }
+            }
+        }
+
+        match (self.in_hand, self.is_terminal()) {
+            (_, true) => Ok(()),
+            (Some(piece), false) => write!(f, "\n\nTo place: {piece}"),
+            (None, false) => write!(f, "\n\nChoose a piece to hand over"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> QuartoState {
+        moves.iter().fold(QuartoState::default(), |state, move_| {
+            state.result(&move_.parse().unwrap())
+        })
+    }
+
+    #[test]
+    fn parses_moves() {
+        assert_eq!("dtrh".parse(), Ok(Piece(0b1111)));
+        assert_eq!("LSSS".parse(), Ok(Piece(0)));
+        assert_eq!("dssh".parse::<Piece>().unwrap().to_string(), "dssh");
+        assert!("xtrh".parse::<Piece>().is_err());
+        assert!("dtr".parse::<Piece>().is_err());
+
+        let move_ = "c2 ltrs".parse::<Move>().unwrap();
+        assert_eq!(move_.square, Some(6));
+        assert_eq!(move_.give, Some(Piece(0b0110)));
+        assert_eq!(move_.to_string(), "c2 ltrs");
+        assert_eq!("ltrs".parse::<Move>().unwrap().to_string(), "ltrs");
+        assert_eq!("d4".parse::<Move>().unwrap().to_string(), "d4");
+        assert!("e1 ltrs".parse::<Move>().is_err());
+        assert!("".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn places_then_hands_over() {
+        let state = QuartoState::default();
+        assert!(!state.is_legal("a1 dtrh".parse().unwrap()));
+        assert_eq!(state.actions().len(), 16);
+
+        let state = play(&["dtrh", "a1 lsss"]);
+        assert_eq!(state.board[0], Some(Piece(0b1111)));
+        assert_eq!(state.in_hand, Some(Piece(0)));
+        assert_eq!(state.player, Player::Max);
+        assert!(!state.is_legal("a1 dsss".parse().unwrap()));
+        assert!(!state.is_legal("b1 lsss".parse().unwrap()));
+        assert!(!state.is_legal("b1".parse().unwrap()));
+        assert!(state.is_legal("b1 dsss".parse().unwrap()));
+    }
+
+    #[test]
+    fn four_sharing_a_trait_wins() {
+        // Every piece along the top row is short, although they have nothing else in common
+        let state = play(&["dsrh", "a1 lssh", "b1 dsss", "c1 lsrs"]);
+        assert!(state.is_legal("c2 dtrh".parse().unwrap()));
+        assert!(!state.is_legal("d1 dtrh".parse().unwrap()));
+        assert!(state.is_legal("d1".parse().unwrap()));
+        let state = state.result(&"d1".parse().unwrap());
+        assert_eq!(state.winner, Some(Player::Max));
+        assert!(state.is_terminal());
+
+        // Sharing a trait whose bit is set, like being dark, wins just the same
+        let state = play(&["dtrh", "a1 dsrs", "a2 dtsh", "a3 dssh"]);
+        assert!(state.wins(12, Piece(0b1001)));
+        assert!(!state.wins(12, Piece(0b0110)));
+    }
+
+    #[test]
+    fn avoids_handing_over_winning_pieces() {
+        // Three short pieces are along the top row, so placing anywhere else leaves only tall
+        // pieces safe to hand over
+        let state = play(&["dsrh", "a1 lssh", "b1 dsss", "c1 dtsh"]);
+        for action in state.actions() {
+            let give = action.give.unwrap();
+            assert!(action.square == Some(3) || give.0 & 0b0100 != 0, "{action}");
+        }
+    }
+
+    #[test]
+    fn engine_wins_with_the_piece_in_hand() {
+        let state = play(&["dsrh", "a1 lssh", "b1 dsss", "c1 lsrs"]);
+        let best = Quarto::engine(2).best_move(&state).unwrap();
+        assert_eq!(best.square, Some(3));
+        assert_eq!(best.give, None);
+    }
+}
@@ -0,0 +1,386 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const SIZE: usize = 4;
+const BOARD: u64 = u64::MAX;
+/// Every line of four cells through the cube: along the rows, columns and pillars, the diagonals
+/// of each layer and of each vertical slice, and the four space diagonals between opposite
+/// corners.
+const LINES: [u64; 76] = lines();
+
+/// The value of a line with only one player's marks in it for each number of marks, since a line
+/// of three is a threat to win straight away.
+const LINE_VALUES: [f32; 4] = [0.0, 1.0, 4.0, 32.0];
+
+const fn lines() -> [u64; 76] {
+    let mut lines = [0; 76];
+    let mut count = 0;
+    let mut direction: i32 = 0;
+    while direction < 27 {
+        let step = [direction % 3 - 1, direction / 3 % 3 - 1, direction / 9 - 1];
+        // Each line is only counted in the direction where its first step that isn't zero is
+        // positive, so that it isn't counted again backwards
+        let first = if step[0] != 0 {
+            step[0]
+        } else if step[1] != 0 {
+            step[1]
+        } else {
+            step[2]
+        };
+        let mut start = 0;
+        while first > 0 && start < SIZE * SIZE * SIZE {
+            let cell = [
+                (start % SIZE) as i32,
+                (start / SIZE % SIZE) as i32,
+                (start / (SIZE * SIZE)) as i32,
+            ];
+            let mut fits = true;
+            let mut axis = 0;
+            while axis < 3 {
+                let end = cell[axis] + (SIZE as i32 - 1) * step[axis];
+                fits &= end >= 0 && end < SIZE as i32;
+                axis += 1;
+            }
+            if fits {
+                let mut line = 0;
+                let mut i = 0;
+                while i < SIZE as i32 {
+                    let (file, rank, layer) = (
+                        cell[0] + i * step[0],
+                        cell[1] + i * step[1],
+                        cell[2] + i * step[2],
+                    );
+                    line |= 1 << ((layer * SIZE as i32 + rank) * SIZE as i32 + file);
+                    i += 1;
+                }
+                lines[count] = line;
+                count += 1;
+            }
+            start += 1;
+        }
+        direction += 1;
+    }
+    lines
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Qubic(QubicState, Vec<Move>);
+
+impl Game for Qubic {
+    fn name(&self) -> String {
+        "Qubic".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "   ╱ X · O ╱
+  ╱ · X · ╱
+   ╱ O · · ╱
+  ╱ · X O ╱
+   ╱ · · X ╱"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (41, 6)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if let Some(winner) = self.0.winner() {
+            Some(WinState::Decisive(winner))
+        } else if self.0.is_terminal() {
+            Some(WinState::Draw)
+        } else {
+            None
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.empty() & 1 << move_.cell != 0)
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(4)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Qubic {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build qubic engine")
+    }
+}
+
+/// A cell to mark, written as the layer number followed by the file letter and rank number
+/// within the layer, such as `2b3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    cell: u8,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected a layer and a square, such as `2b3`";
+
+        match *s.trim().to_ascii_lowercase().as_bytes() {
+            [layer @ b'1'..=b'4', file @ b'a'..=b'd', rank @ b'1'..=b'4'] => Ok(Move {
+                cell: ((layer - b'1') * 4 + rank - b'1') * 4 + file - b'a',
+            }),
+            _ => Err(EXPECTED),
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.cell / 16 + 1,
+            (b'a' + self.cell % 4) as char,
+            self.cell / 4 % 4 + 1
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct QubicState {
+    /// Each player's marks, as bitmasks of the cells going along each rank from a1 of the bottom
+    /// layer and then up through the layers. Max moves first.
+    marks: [u64; 2],
+    player: Player,
+}
+
+impl QubicState {
+    fn empty(&self) -> u64 {
+        BOARD & !(self.marks[0] | self.marks[1])
+    }
+
+    fn winner(&self) -> Option<Player> {
+        [Player::Max, Player::Min].into_iter().find(|&player| {
+            let marks = self.marks[player as usize];
+            LINES.iter().any(|&line| line & !marks == 0)
+        })
+    }
+}
+
+impl minimax::State<f32, Move> for QubicState {
+    fn is_terminal(&self) -> bool {
+        self.empty() == 0 || self.winner().is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner() {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None if self.empty() == 0 => return 0.0,
+            None => {}
+        }
+
+        let [max, min] = self.marks;
+        let value = |line: u64| match ((max & line).count_ones(), (min & line).count_ones()) {
+            (count, 0) => LINE_VALUES[count as usize],
+            (0, count) => -LINE_VALUES[count as usize],
+            _ => 0.0,
+        };
+        LINES.iter().map(|&line| value(line)).sum()
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        let mut empty = self.empty();
+        while empty != 0 {
+            actions.push(Move {
+                cell: empty.trailing_zeros() as u8,
+            });
+            empty &= empty - 1;
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        assert!(
+            self.empty() & 1 << action.cell != 0,
+            "{action} is not a legal move"
+        );
+
+        let mut state = self.clone();
+        state.marks[self.player as usize] |= 1 << action.cell;
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // The player to move follows from the number of marks
+        let mix = |mut x: u64| {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            x ^ (x >> 31)
+        };
+        let [max, min] = self.marks;
+        Some(mix(max) ^ mix(min).rotate_left(32))
+    }
+}
+
+impl Display for QubicState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // The layers are drawn side by side, from the bottom one on the left to the top one on
+        // the right
+        write!(f, " ")?;
+        for layer in 1..=SIZE {
+            write!(f, "   layer {layer}")?;
+        }
+        writeln!(f)?;
+        for rank in (0..SIZE).rev() {
+            write!(f, "{}", rank + 1)?;
+            for layer in 0..SIZE {
+                write!(f, "  ")?;
+                for file in 0..SIZE {
+                    let cell = 1 << ((layer * SIZE + rank) * SIZE + file);
+                    let mark = if self.marks[Player::Max as usize] & cell != 0 {
+                        'X'
+                    } else if self.marks[Player::Min as usize] & cell != 0 {
+                        'O'
+                    } else {
+                        '·'
+                    };
+                    write!(f, " {mark}")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, " {}", "   a b c d".repeat(SIZE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marks(cells: &[&str]) -> u64 {
+        cells
+            .iter()
+            .map(|name| 1 << name.parse::<Move>().unwrap().cell)
+            .sum()
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "2B3".parse::<Move>().unwrap();
+        assert_eq!(move_.cell, 16 + 2 * 4 + 1);
+        assert_eq!(move_.to_string(), "2b3");
+        assert_eq!("4d4".parse::<Move>().unwrap().cell, 63);
+        assert!("b3".parse::<Move>().is_err());
+        assert!("5a1".parse::<Move>().is_err());
+        assert!("1e1".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn there_are_76_lines_of_four() {
+        assert!(LINES.iter().all(|line| line.count_ones() == 4));
+        for (i, line) in LINES.iter().enumerate() {
+            assert!(!LINES[i + 1..].contains(line));
+        }
+        // Every cell on a space diagonal is on seven lines, and every other cell on four
+        let corner = marks(&["1a1"]);
+        assert_eq!(LINES.iter().filter(|&&line| line & corner != 0).count(), 7);
+        let edge = marks(&["1b1"]);
+        assert_eq!(LINES.iter().filter(|&&line| line & edge != 0).count(), 4);
+    }
+
+    #[test]
+    fn lines_through_the_layers_win() {
+        for cells in [
+            ["1a1", "2a1", "3a1", "4a1"],
+            ["1a1", "2b1", "3c1", "4d1"],
+            ["1d1", "2c2", "3b3", "4a4"],
+        ] {
+            let state = QubicState {
+                marks: [marks(&cells), 0],
+                player: Player::Min,
+            };
+            assert_eq!(state.winner(), Some(Player::Max));
+            assert!(state.is_terminal());
+            assert_eq!(state.evaluation(), f32::INFINITY);
+        }
+
+        // A bent line isn't a win
+        let state = QubicState {
+            marks: [marks(&["1a1", "2b1", "3c1", "4c1"]), 0],
+            player: Player::Min,
+        };
+        assert!(!state.is_terminal());
+    }
+
+    #[test]
+    fn engine_wins_or_blocks() {
+        // Max can finish the space diagonal straight away rather than block Min's pillar
+        let state = QubicState {
+            marks: [marks(&["1a1", "2b2", "3c3"]), marks(&["1d1", "2d1", "3d1"])],
+            player: Player::Max,
+        };
+        let move_ = Qubic::engine(2).best_move(&state).unwrap();
+        assert_eq!(move_.to_string(), "4d4");
+
+        // Min has to block it
+        let state = QubicState {
+            marks: [
+                marks(&["1a1", "2b2", "3c3", "1b4"]),
+                marks(&["1d1", "2a4", "3b4"]),
+            ],
+            player: Player::Min,
+        };
+        let move_ = Qubic::engine(2).best_move(&state).unwrap();
+        assert_eq!(move_.to_string(), "4d4");
+    }
+}
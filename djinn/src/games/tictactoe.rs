@@ -1,11 +1,25 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-use crate::games::{Game, WinState};
-use crate::minimax::{self, Player, State};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
 
-#[derive(Debug, Default)]
+use crate::games::{
+    grid_cell, search_move, Click, Game, GameError, GameOption, SearchLimits, WinState,
+};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+/// The board sizes and the number in a row needed to win on each, which the player picks from
+/// when opening the game.
+const SIZES: [(usize, usize); 3] = [(3, 3), (4, 4), (5, 4)];
+
+/// How much a winnable line is worth on boards too big to search to the end, indexed by how many
+/// of one player's marks it holds.
+const LINE_VALUES: [f32; 5] = [0.0, 1.0, 4.0, 16.0, 64.0];
+
+#[derive(Clone, Debug, Default)]
 pub struct TicTacToe(TicTacToeState);
 
 impl Game for TicTacToe {
@@ -26,21 +40,48 @@ impl Game for TicTacToe {
         self.0.board.to_string()
     }
 
+    fn display_styled(&self, _side: usize, _typed: &str) -> Text<'static> {
+        let mut text = Text::raw(self.display());
+        // The last square played on is shaded, which means splitting its line around it
+        if let Some(move_) = self.0.move_history.last() {
+            let line = &mut text.lines[1 + 2 * move_.y];
+            let chars: Vec<char> = line.to_string().chars().collect();
+            let (start, end) = (3 + 4 * move_.x, 6 + 4 * move_.x);
+            *line = Line::from(vec![
+                Span::raw(chars[..start].iter().collect::<String>()),
+                Span::styled(
+                    chars[start..end].iter().collect::<String>(),
+                    Style::new().bg(Color::DarkGray),
+                ),
+                Span::raw(chars[end..].iter().collect::<String>()),
+            ]);
+        }
+        text
+    }
+
+    fn click(&self, _typed: &str, column: u16, row: u16, _side: usize) -> Option<Click> {
+        let size = self.0.board.size();
+        let (x, y) = grid_cell(column, row, (3, 1), (size, size), 2)?;
+        let move_ = format!("{}{}", char::from(b'a' + x as u8), y + 1);
+        Some(Click::Play(move_))
+    }
+
     fn display_size(&self) -> (u16, u16) {
-        (16, 8)
+        let size = self.0.board.size() as u16;
+        (4 * size + 4, 2 * size + 2)
     }
 
     fn move_history(&self) -> Vec<String> {
         self.0.move_history.iter().map(Move::to_string).collect()
     }
 
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
     fn win_state(&self) -> Option<WinState> {
         if self.0.is_terminal() {
-            if self.0.winner.is_some() {
-                Some(WinState::Decisive)
-            } else {
-                Some(WinState::Draw)
-            }
+            Some(self.0.winner.map_or(WinState::Draw, WinState::Decisive))
         } else {
             None
         }
@@ -48,23 +89,93 @@ impl Game for TicTacToe {
 
     fn is_valid_move(&self, move_: &str) -> bool {
         if let Ok(move_) = Move::from_str(move_) {
-            self.0.board.0[move_.y][move_.x] == Tile::Empty
+            self.0.board.tile(move_.x, move_.y) == Some(Tile::Empty)
         } else {
             false
         }
     }
 
-    fn play_move(&mut self, move_: &str) {
-        self.0
-            .place(Move::from_str(move_).expect("expected valid move"))
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = Move::from_str(move_).map_err(|_| GameError::invalid_move(move_))?;
+        self.0.place(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        if self.0.board.size() == 3 {
+            // The board fills up within nine plies, so the hardest level plays perfectly from the
+            // solved game, while easier ones only search part of the way
+            let depth = limits.depth(9);
+            if depth < 9 {
+                return Ok(minimax::best_move(&self.0, depth).to_string());
+            }
+            self.0
+                .perfect_move()
+                .map(|move_| move_.to_string())
+                .ok_or(GameError::NoMoves)
+        } else {
+            search_move(&Self::engine(limits.depth(4)), &self.0, &limits)
+                .map(|move_| move_.to_string())
+                .ok_or(GameError::NoMoves)
+        }
     }
 
-    fn computer_move(&self) -> String {
-        minimax::best_move(&self.0, u32::MAX).to_string()
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["X", "O"].map(String::from)
     }
 
     fn reset(&mut self) {
-        self.0 = TicTacToeState::default();
+        let board = &self.0.board;
+        self.0 = TicTacToeState::with_rules(board.size(), board.win_length, self.0.misere);
+    }
+
+    /// Every board size, first with the usual rules and then misère.
+    fn variants(&self) -> Vec<String> {
+        let sizes = SIZES
+            .iter()
+            .map(|(size, win_length)| format!("{size}×{size}, {win_length} in a row"));
+        let misere = sizes.clone().map(|size| format!("{size}, misère"));
+        sizes.chain(misere).collect()
+    }
+
+    fn set_variant(&mut self, index: usize) {
+        let (size, win_length) = SIZES[index % SIZES.len()];
+        let misere = index >= SIZES.len();
+        self.0 = TicTacToeState::with_rules(size, win_length, misere);
+    }
+
+    /// The board size and whether it's misère are chosen separately, rather than as one of the
+    /// variants.
+    fn options(&self) -> Vec<GameOption> {
+        let sizes = SIZES
+            .iter()
+            .map(|(size, win_length)| format!("{size}×{size}, {win_length} in a row"))
+            .collect();
+        let rules = ["Normal", "Misère"].map(String::from).to_vec();
+        vec![
+            GameOption::new("Board size", sizes),
+            GameOption::new("Rules", rules),
+        ]
+    }
+
+    fn set_options(&mut self, choices: &[usize]) {
+        let [size, rules] = [0, 1].map(|i| choices.get(i).copied().unwrap_or_default());
+        self.set_variant(rules * SIZES.len() + size);
+    }
+}
+
+impl TicTacToe {
+    /// The engine used on boards bigger than 3×3, which are too big to search to the end,
+    /// searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .build()
+            .expect("failed to build tic tac toe engine")
     }
 }
 
@@ -110,18 +221,16 @@ impl FromStr for Move {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Whether the square is actually on the board depends on its size, so that's left to
+        // the state to check
         let mut coords = s.chars();
         let x = match coords.next().ok_or("expected x-coordinate")? {
-            'a' => 0,
-            'b' => 1,
-            'c' => 2,
+            x @ 'a'..='i' => x as usize - 'a' as usize,
             _ => Err("invalid x-coordinate")?,
         };
 
         let y = match coords.next().ok_or("expected y-coordinate")? {
-            '1' => 0,
-            '2' => 1,
-            '3' => 2,
+            y @ '1'..='9' => y as usize - '1' as usize,
             _ => Err("invalid y-coordinate")?,
         };
 
@@ -139,117 +248,166 @@ impl FromStr for Move {
 
 impl Display for Move {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let x = match self.x {
-            0 => 'a',
-            1 => 'b',
-            2 => 'c',
-            _ => unreachable!("invalid x-coordinate"),
-        };
-
-        let y = match self.y {
-            0 => '1',
-            1 => '2',
-            2 => '3',
-            _ => unreachable!("invalid y-coordinate"),
-        };
+        let x = (b'a' + self.x as u8) as char;
+        let y = self.y + 1;
 
         write!(f, "{}{}", x, y)
     }
 }
 
-#[derive(Clone, Default)]
-pub struct Board([[Tile; 3]; 3]);
+/// The tiles of the board, indexed by row and then column, along with how many in a row win on it.
+#[derive(Clone)]
+pub struct Board {
+    tiles: Vec<Vec<Tile>>,
+    win_length: usize,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(3, 3)
+    }
+}
+
+impl<const N: usize> From<[[Tile; N]; N]> for Board {
+    fn from(tiles: [[Tile; N]; N]) -> Self {
+        Self {
+            tiles: tiles.map(Vec::from).into(),
+            win_length: N,
+        }
+    }
+}
 
 impl Board {
-    #[allow(dead_code)]
-    fn new() -> Self {
-        Self::default()
+    fn new(size: usize, win_length: usize) -> Self {
+        Self {
+            tiles: vec![vec![Tile::Empty; size]; size],
+            win_length,
+        }
     }
 
-    fn check_win(&self, action: &Move) -> bool {
-        let Move { x, y, tile } = *action;
-        let board = self.0;
+    fn size(&self) -> usize {
+        self.tiles.len()
+    }
 
-        let n = 3;
+    /// The tile at column `x` and row `y`, or `None` if it's off the board.
+    fn tile(&self, x: usize, y: usize) -> Option<Tile> {
+        self.tiles.get(y).and_then(|row| row.get(x)).copied()
+    }
 
-        // Check row
-        let row = (0..n).all(|i| board[i][x] == tile);
+    fn check_win(&self, action: &Move) -> bool {
+        let Move { x, y, tile } = *action;
 
-        // Check column
-        let column = (0..n).all(|i| board[y][i] == tile);
+        // Count the matching tiles stretching out either way from the move along each direction,
+        // which covers rows, columns and both diagonals
+        let count = |dx: isize, dy: isize| {
+            (1..)
+                .map(|i| (x as isize + i * dx, y as isize + i * dy))
+                .take_while(|&(x, y)| {
+                    x >= 0 && y >= 0 && self.tile(x as usize, y as usize) == Some(tile)
+                })
+                .count()
+        };
 
-        // Check main diagonal
-        let main_diag = x == y && (0..n).all(|i| board[i][i] == tile);
+        [(1, 0), (0, 1), (1, 1), (1, -1)]
+            .into_iter()
+            .any(|(dx, dy)| 1 + count(dx, dy) + count(-dx, -dy) >= self.win_length)
+    }
 
-        // Check anti-diagonal
-        let anti_diag = x + y == n - 1 && (0..n).all(|i| board[i][n - 1 - i] == tile);
+    /// Every run of `win_length` squares in a straight line, as the squares' coordinates.
+    fn lines(&self) -> impl Iterator<Item = impl Iterator<Item = (usize, usize)>> + '_ {
+        let (n, k) = (self.size() as isize, self.win_length as isize);
+        [(1, 0), (0, 1), (1, 1), (1, -1)]
+            .into_iter()
+            .flat_map(move |(dx, dy)| (0..n).flat_map(move |x| (0..n).map(move |y| (x, y, dx, dy))))
+            .filter(move |&(x, y, dx, dy)| {
+                let (end_x, end_y) = (x + (k - 1) * dx, y + (k - 1) * dy);
+                (0..n).contains(&end_x) && (0..n).contains(&end_y)
+            })
+            .map(move |(x, y, dx, dy)| {
+                (0..k).map(move |i| ((x + i * dx) as usize, (y + i * dy) as usize))
+            })
+    }
 
-        row || column || main_diag || anti_diag
+    /// A heuristic value for boards too big to search to the end, adding up the lines that only
+    /// Crosses could still complete and taking away those only Noughts could.
+    fn heuristic_value(&self) -> f32 {
+        self.lines()
+            .map(|line| {
+                let (mut crosses, mut noughts) = (0, 0);
+                for (x, y) in line {
+                    match self.tiles[y][x] {
+                        Tile::Cross => crosses += 1,
+                        Tile::Nought => noughts += 1,
+                        Tile::Empty => {}
+                    }
+                }
+                match (crosses, noughts) {
+                    (count, 0) => LINE_VALUES[count.min(4)],
+                    (0, count) => -LINE_VALUES[count.min(4)],
+                    _ => 0.0,
+                }
+            })
+            .sum()
     }
 }
 
 impl fmt::Debug for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         if f.alternate() {
-            let board = self.0;
-            write!(
-                f,
-                "Board(
-     {} | {} | {}
-    ---+---+---
-     {} | {} | {}
-    ---+---+---
-     {} | {} | {}
-)",
-                board[0][0],
-                board[0][1],
-                board[0][2],
-                board[1][0],
-                board[1][1],
-                board[1][2],
-                board[2][0],
-                board[2][1],
-                board[2][2],
-            )
+            let separator = vec!["---"; self.size()].join("+");
+            writeln!(f, "Board(")?;
+            for (y, row) in self.tiles.iter().enumerate() {
+                if y != 0 {
+                    writeln!(f, "    {separator}")?;
+                }
+                let row = row
+                    .iter()
+                    .map(|tile| format!(" {tile} "))
+                    .collect::<Vec<_>>();
+                writeln!(f, "    {}", row.join("|"))?;
+            }
+            write!(f, ")")
         } else {
-            f.debug_tuple("Board").field(&self.0).finish()
+            f.debug_tuple("Board").field(&self.tiles).finish()
         }
     }
 }
 
 impl Display for Board {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let board = self.0;
-        write!(
-            f,
-            "  ┌───┬───┬───┐
-1 │ {} │ {} │ {} │
-  ├───┼───┼───┤
-2 │ {} │ {} │ {} │
-  ├───┼───┼───┤
-3 │ {} │ {} │ {} │
-  └───┴───┴───┘
-    a   b   c",
-            board[0][0],
-            board[0][1],
-            board[0][2],
-            board[1][0],
-            board[1][1],
-            board[1][2],
-            board[2][0],
-            board[2][1],
-            board[2][2]
-        )
+        let n = self.size();
+        let border = |left: &str, middle: &str, right: &str| {
+            format!("  {left}{}{right}", vec!["───"; n].join(middle))
+        };
+
+        writeln!(f, "{}", border("┌", "┬", "┐"))?;
+        for (y, row) in self.tiles.iter().enumerate() {
+            write!(f, "{} │", y + 1)?;
+            for tile in row {
+                write!(f, " {} │", tile)?;
+            }
+            if y != n - 1 {
+                writeln!(f, "\n{}", border("├", "┼", "┤"))?;
+            }
+        }
+        writeln!(f, "\n{}", border("└", "┴", "┘"))?;
+        let files: Vec<_> = (0..n)
+            .map(|x| ((b'a' + x as u8) as char).to_string())
+            .collect();
+        write!(f, "    {}", files.join("   "))
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct TicTacToeState {
+    /// The board, which knows how many in a row it takes to win.
     board: Board,
     player: Player,
     winner: Option<Player>,
     draw: bool,
     move_history: Vec<Move>,
+    /// Whether the game is misère, where whoever completes a line loses instead of winning.
+    misere: bool,
 }
 
 impl TicTacToeState {
@@ -258,6 +416,16 @@ impl TicTacToeState {
         Self::default()
     }
 
+    /// A new game on a `size`×`size` board, won by getting `win_length` in a row, or lost by it
+    /// if the game is `misere`.
+    pub fn with_rules(size: usize, win_length: usize, misere: bool) -> Self {
+        Self {
+            board: Board::new(size, win_length),
+            misere,
+            ..Default::default()
+        }
+    }
+
     // Create a new Tic-Tac-Toe state with a given board where Crosses are to move
     #[cfg(test)]
     fn with_board(board: Board) -> Self {
@@ -285,6 +453,71 @@ impl TicTacToeState {
     fn play_move(&mut self, action: Move) {
         *self = self.result(&action);
     }
+
+    /// The best move on a 3×3 board with perfect play from both sides, winning as soon as
+    /// possible or losing as late as possible.
+    fn perfect_move(&self) -> Option<Move> {
+        let mut solved = HashMap::new();
+        let value = |action: &Move| self.result(action).solve(&mut solved);
+        match self.player {
+            Player::Max => self.actions().into_iter().max_by_key(value),
+            Player::Min => self.actions().into_iter().min_by_key(value),
+        }
+    }
+
+    /// The value of a 3×3 position with perfect play from both sides: positive if Crosses win
+    /// and negative if Noughts do, further from zero the sooner it happens. The values of the
+    /// positions searched are kept in `solved`, which rotations and reflections of the board share.
+    fn solve(&self, solved: &mut HashMap<u32, i8>) -> i8 {
+        let tiles = self.board.tiles.iter().flatten();
+        let remaining = 1 + tiles.filter(|&&tile| tile == Tile::Empty).count() as i8;
+        match self.winner {
+            Some(Player::Max) => return remaining,
+            Some(Player::Min) => return -remaining,
+            None if self.draw => return 0,
+            None => {}
+        }
+
+        let key = self.symmetric_key();
+        if let Some(&value) = solved.get(&key) {
+            return value;
+        }
+        let values = self
+            .actions()
+            .into_iter()
+            .map(|action| self.result(&action).solve(solved));
+        let value = match self.player {
+            Player::Max => values.max(),
+            Player::Min => values.min(),
+        }
+        .expect("a position that isn't over has moves");
+        solved.insert(key, value);
+        value
+    }
+
+    /// A key for a 3×3 position that's the same for each of the eight ways of rotating and
+    /// reflecting the board, which all have the same value.
+    fn symmetric_key(&self) -> u32 {
+        let board = (0..8)
+            .map(|symmetry| {
+                (0..9).fold(0, |key, square| {
+                    let (mut x, mut y) = (square % 3, square / 3);
+                    if symmetry & 1 != 0 {
+                        (x, y) = (y, x);
+                    }
+                    if symmetry & 2 != 0 {
+                        x = 2 - x;
+                    }
+                    if symmetry & 4 != 0 {
+                        y = 2 - y;
+                    }
+                    key * 3 + self.board.tiles[y][x] as u32
+                })
+            })
+            .min()
+            .unwrap_or_default();
+        board << 2 | (self.player as u32) << 1 | u32::from(self.misere)
+    }
 }
 
 impl minimax::State<f32, Move> for TicTacToeState {
@@ -296,7 +529,9 @@ impl minimax::State<f32, Move> for TicTacToeState {
         match self.winner {
             Some(Player::Min) => f32::NEG_INFINITY,
             Some(Player::Max) => f32::INFINITY,
-            None => 0.0,
+            None if self.draw || self.board.size() == 3 => 0.0,
+            None if self.misere => -self.board.heuristic_value(),
+            None => self.board.heuristic_value(),
         }
     }
 
@@ -309,9 +544,9 @@ impl minimax::State<f32, Move> for TicTacToeState {
 
         let mut actions = vec![];
 
-        for x in 0..3 {
-            for y in 0..3 {
-                if self.board.0[y][x] == Tile::Empty {
+        for x in 0..self.board.size() {
+            for y in 0..self.board.size() {
+                if self.board.tiles[y][x] == Tile::Empty {
                     actions.push(Move { x, y, tile })
                 }
             }
@@ -322,16 +557,16 @@ impl minimax::State<f32, Move> for TicTacToeState {
 
     // TODO: don't produce a whole new State for each minimax node
     fn result(&self, action: &Move) -> Self {
-        assert_eq!(self.board.0[action.y][action.x], Tile::Empty);
+        assert_eq!(self.board.tiles[action.y][action.x], Tile::Empty);
 
         let mut board = self.board.clone();
-        board.0[action.y][action.x] = action.tile;
+        board.tiles[action.y][action.x] = action.tile;
 
         let win = board.check_win(action);
         let full = !board
-            .0
-            .as_flattened()
+            .tiles
             .iter()
+            .flatten()
             .any(|tile| matches!(tile, Tile::Empty));
         let draw = full && !win;
 
@@ -339,12 +574,20 @@ impl minimax::State<f32, Move> for TicTacToeState {
         let mut move_history = self.move_history.clone();
         move_history.push(action.clone());
 
+        // Completing a line hands the win to the opponent in misère
+        let winner = match (win, self.misere) {
+            (false, _) => None,
+            (true, false) => Some(self.player),
+            (true, true) => Some(self.player.opposite()),
+        };
+
         Self {
             board,
             player: self.player.opposite(),
-            winner: if win { Some(self.player) } else { None },
+            winner,
             draw,
             move_history,
+            misere: self.misere,
         }
     }
 }
@@ -352,11 +595,12 @@ impl minimax::State<f32, Move> for TicTacToeState {
 #[allow(unused_imports)]
 mod tests {
     use super::*;
+    use crate::games::Difficulty;
     use crate::minimax::State;
 
     #[test]
     fn win_diagonal() {
-        let initial_state = TicTacToeState::with_board(Board([
+        let initial_state = TicTacToeState::with_board(Board::from([
             [Tile::Empty, Tile::Nought, Tile::Cross],
             [Tile::Nought, Tile::Cross, Tile::Empty],
             [Tile::Empty, Tile::Empty, Tile::Empty],
@@ -375,7 +619,7 @@ mod tests {
 
     #[test]
     fn win_horizontal() {
-        let initial_state = TicTacToeState::with_board(Board([
+        let initial_state = TicTacToeState::with_board(Board::from([
             [Tile::Empty, Tile::Empty, Tile::Nought],
             [Tile::Nought, Tile::Cross, Tile::Nought],
             [Tile::Cross, Tile::Cross, Tile::Empty],
@@ -394,7 +638,7 @@ mod tests {
 
     #[test]
     fn ensure_draw() {
-        let state = TicTacToeState::with_board_and_player(Board([
+        let state = TicTacToeState::with_board_and_player(Board::from([
             [Tile::Cross, Tile::Empty, Tile::Empty],
             [Tile::Empty, Tile::Empty, Tile::Empty],
             [Tile::Empty, Tile::Empty, Tile::Empty],
@@ -402,4 +646,142 @@ mod tests {
         let Move { x, y, .. } = minimax::best_move(&state, u32::MAX);
         assert_eq!((x, y), (1, 1));
     }
+
+    #[cfg(test)]
+    fn play(size: usize, win_length: usize, moves: &[&str]) -> TicTacToeState {
+        let mut state = TicTacToeState::with_rules(size, win_length, false);
+        for move_ in moves {
+            state.place(Move::from_str(move_).unwrap());
+        }
+        state
+    }
+
+    #[test]
+    fn parses_moves_beyond_c3() {
+        let mut game = TicTacToe::default();
+        game.set_variant(2);
+        assert!(game.is_valid_move("e5"));
+        assert!(!game.is_valid_move("f1"));
+        assert!(!game.is_valid_move("a6"));
+        game.play_move("e5").unwrap();
+        assert!(!game.is_valid_move("e5"));
+        assert_eq!(game.move_history(), ["e5"]);
+
+        game.set_variant(0);
+        assert!(!game.is_valid_move("d1"));
+    }
+
+    #[test]
+    fn wins_need_the_full_length() {
+        // Three in a row isn't enough on a 4×4 board
+        let state = play(4, 4, &["a1", "a2", "b1", "b2", "c1"]);
+        assert!(!state.is_terminal());
+        let state = play(4, 4, &["a1", "a2", "b1", "b2", "c1", "d3", "d1"]);
+        assert_eq!(state.winner, Some(Player::Max));
+
+        // And four in a row is enough on a 5×5 one, along a diagonal off the middle
+        let mut board = Board::new(5, 4);
+        for i in 0..3 {
+            board.tiles[i + 1][i] = Tile::Nought;
+        }
+        let mut state = TicTacToeState::with_board_and_player(board, Player::Min);
+        state.place(Move::from_str("d5").unwrap());
+        assert_eq!(state.winner, Some(Player::Min));
+    }
+
+    #[test]
+    fn displays_bigger_boards() {
+        let mut game = TicTacToe::default();
+        game.play_move("b2").unwrap();
+        assert_eq!(
+            game.display(),
+            "  ┌───┬───┬───┐
+1 │   │   │   │
+  ├───┼───┼───┤
+2 │   │ X │   │
+  ├───┼───┼───┤
+3 │   │   │   │
+  └───┴───┴───┘
+    a   b   c"
+        );
+
+        game.set_variant(1);
+        game.play_move("d4").unwrap();
+        let display = game.display();
+        let (width, height) = game.display_size();
+        assert_eq!(display.lines().count(), height as usize);
+        assert!(display
+            .lines()
+            .all(|line| line.chars().count() <= width as usize));
+        assert!(display.contains("4 │   │   │   │ X │"));
+    }
+
+    #[test]
+    fn engine_blocks_on_bigger_boards() {
+        let state = play(4, 4, &["a1", "b1", "a2", "b2", "a3"]);
+        let Move { x, y, .. } = TicTacToe::engine(2).best_move(&state).unwrap();
+        assert_eq!((x, y), (0, 3));
+    }
+
+    #[test]
+    fn misere_lines_lose() {
+        let mut game = TicTacToe::default();
+        game.set_variant(3);
+        assert_eq!(game.variants()[3], "3×3, 3 in a row, misère");
+        for move_ in ["a1", "a2", "b1", "b2"] {
+            game.play_move(move_).unwrap();
+        }
+        game.play_move("c1").unwrap();
+        assert_eq!(game.0.winner, Some(Player::Min));
+        assert_eq!(game.0.evaluation(), f32::NEG_INFINITY);
+
+        game.reset();
+        assert!(game.0.misere);
+        assert!(game.0.move_history.is_empty());
+    }
+
+    #[test]
+    fn options_choose_the_size_and_rules() {
+        let mut game = TicTacToe::default();
+        let options = game.options();
+        assert_eq!(options[0].values[2], "5×5, 4 in a row");
+        assert_eq!(options[1].values, ["Normal", "Misère"]);
+
+        game.set_options(&[2, 1]);
+        assert_eq!((game.0.board.size(), game.0.misere), (5, true));
+        game.set_options(&[1, 0]);
+        assert_eq!((game.0.board.size(), game.0.misere), (4, false));
+    }
+
+    #[test]
+    fn perfect_play_wins_soonest_and_draws_otherwise() {
+        // Crosses complete the column on a3 rather than blocking Noughts on b3
+        let state = play(3, 3, &["a1", "b1", "a2", "b2"]);
+        let Move { x, y, .. } = state.perfect_move().unwrap();
+        assert_eq!((x, y), (0, 2));
+
+        let mut game = TicTacToe::default();
+        while !game.0.is_terminal() {
+            let move_ = game.computer_move(Difficulty::Hard.into()).unwrap();
+            game.play_move(&move_).unwrap();
+        }
+        assert_eq!(game.win_state(), Some(WinState::Draw));
+    }
+
+    #[test]
+    fn misere_engine_takes_the_centre() {
+        // Taking the centre and then mirroring the opponent is the only way for Crosses not to
+        // lose, so any other first move is worse
+        let mut game = TicTacToe::default();
+        game.set_variant(3);
+        assert_eq!(game.computer_move(Difficulty::Hard.into()).unwrap(), "b2");
+
+        // Noughts avoid completing a line of their own when they can
+        for move_ in ["b2", "a1", "c3", "a3", "b1"] {
+            game.play_move(move_).unwrap();
+        }
+        assert!(!game.0.is_terminal());
+        let move_ = game.computer_move(Difficulty::Hard.into()).unwrap();
+        assert_ne!(move_, "a2");
+    }
 }
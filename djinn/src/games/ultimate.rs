@@ -0,0 +1,432 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+/// The eight lines through a three by three board, as bitmasks of its squares going along each
+/// row from the top left. The same lines are used for the small boards and the large one.
+const LINES: [u16; 8] = [
+    0b000_000_111,
+    0b000_111_000,
+    0b111_000_000,
+    0b001_001_001,
+    0b010_010_010,
+    0b100_100_100,
+    0b100_010_001,
+    0b001_010_100,
+];
+const FULL: u16 = 0b111_111_111;
+
+/// How much each square of a three by three board is worth, which is the number of lines through
+/// it plus one.
+const SQUARE_WEIGHTS: [f32; 9] = [4.0, 3.0, 4.0, 3.0, 5.0, 3.0, 4.0, 3.0, 4.0];
+/// The value of winning a small board, on top of its square's weight on the large board.
+const BOARD_VALUE: f32 = 10.0;
+/// The value of two won boards in a line of the large board that can still be completed.
+const BOARD_THREAT_VALUE: f32 = 15.0;
+/// The value of two marks in a line of a small board that can still be completed.
+const SQUARE_THREAT_VALUE: f32 = 2.0;
+/// The value of each square's weight for a mark on a small board that's still being played.
+const MARK_VALUE: f32 = 0.1;
+
+#[derive(Clone, Debug, Default)]
+pub struct Ultimate(UltimateState, Vec<Square>);
+
+impl Game for Ultimate {
+    fn name(&self) -> String {
+        "Ultimate Tic Tac Toe".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "X·O│···│·X·
+···│·X·│···
+───┼───┼───
+·O·│X··│·O·
+···│···│O··"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (25, 12)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Square::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        if let Some(winner) = self.0.winner {
+            Some(WinState::Decisive(winner))
+        } else if self.0.is_terminal() {
+            Some(WinState::Draw)
+        } else {
+            None
+        }
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Square>()
+            .is_ok_and(|square| !self.0.is_terminal() && self.0.is_legal(square))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let square = move_
+            .parse::<Square>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&square);
+        self.1.push(square);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(8)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Ultimate {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build ultimate tic tac toe engine")
+    }
+}
+
+/// A square of one of the small boards, written as a column letter and a row number across the
+/// whole nine by nine grid, such as `e5` for the centre of the centre board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Square {
+    /// The small board, going along each row of the large board from the top left.
+    board: u8,
+    /// The square within the small board, going along each of its rows from the top left.
+    square: u8,
+}
+
+impl FromStr for Square {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let column = match s.bytes().next() {
+            Some(letter @ b'a'..=b'i') => letter - b'a',
+            _ => Err("expected a column from a to i")?,
+        };
+        let row = match s.get(1..).and_then(|row| row.parse::<u8>().ok()) {
+            Some(row @ 1..=9) => row - 1,
+            _ => Err("expected a row from 1 to 9")?,
+        };
+
+        Ok(Square {
+            board: row / 3 * 3 + column / 3,
+            square: row % 3 * 3 + column % 3,
+        })
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let row = self.board / 3 * 3 + self.square / 3;
+        let column = self.board % 3 * 3 + self.square % 3;
+        write!(f, "{}{}", (b'a' + column) as char, row + 1)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UltimateState {
+    /// Each player's marks on each small board, as bitmasks of its squares.
+    marks: [[u16; 9]; 2],
+    /// The small boards each player has won, as a bitmask of the squares of the large board.
+    won: [u16; 2],
+    /// The small board the player to move has to play on, which is the one matching the square
+    /// their opponent just played on, or `None` if they can play on any board still open.
+    next: Option<u8>,
+    player: Player,
+    winner: Option<Player>,
+    /// The Zobrist hash of the marks and the board to play on, updated as moves are made.
+    hash: u64,
+}
+
+impl UltimateState {
+    /// The squares taken on `board` by either player.
+    fn taken(&self, board: u8) -> u16 {
+        self.marks[0][board as usize] | self.marks[1][board as usize]
+    }
+
+    /// Whether `board` can still be played on, which is until it's won or full.
+    fn is_open(&self, board: u8) -> bool {
+        (self.won[0] | self.won[1]) & 1 << board == 0 && self.taken(board) != FULL
+    }
+
+    fn is_legal(&self, square: Square) -> bool {
+        self.next.is_none_or(|next| next == square.board)
+            && self.is_open(square.board)
+            && self.taken(square.board) & 1 << square.square == 0
+    }
+
+    fn next_key(next: Option<u8>) -> u64 {
+        zobrist_key(81 + next.unwrap_or(9) as usize, Player::Max)
+    }
+
+    /// The value of the three by three board given by each player's marks to Max: the squares
+    /// they hold, and the lines where they have two and could still complete a third.
+    fn board_value(marks: [u16; 2], open: u16, square_value: f32, threat_value: f32) -> f32 {
+        let mut value = 0.0;
+        for (player, sign) in [(0, 1.0), (1, -1.0)] {
+            let [own, other] = [marks[player], marks[1 - player]];
+            for (square, weight) in SQUARE_WEIGHTS.iter().enumerate() {
+                if own & 1 << square != 0 {
+                    value += sign * square_value * weight;
+                }
+            }
+            for line in LINES {
+                if (own & line).count_ones() == 2 && other & line == 0 && open & line != 0 {
+                    value += sign * threat_value;
+                }
+            }
+        }
+        value
+    }
+}
+
+impl minimax::State<f32, Square> for UltimateState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some() || (0..9).all(|board| !self.is_open(board))
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None if self.is_terminal() => return 0.0,
+            None => {}
+        }
+
+        // Boards on the large board can be completed if they're still open
+        let open = (0..9u8)
+            .filter(|&board| self.is_open(board))
+            .fold(0, |open, board| open | 1 << board);
+        let mut value = Self::board_value(self.won, open, 1.0, BOARD_THREAT_VALUE)
+            + (self.won[0].count_ones() as f32 - self.won[1].count_ones() as f32) * BOARD_VALUE;
+
+        // Control of the boards still being played counts for as much as they're worth
+        for board in (0..9u8).filter(|&board| open & 1 << board != 0) {
+            let marks = [self.marks[0][board as usize], self.marks[1][board as usize]];
+            let local =
+                Self::board_value(marks, !self.taken(board), MARK_VALUE, SQUARE_THREAT_VALUE);
+            value += local * SQUARE_WEIGHTS[board as usize] / SQUARE_WEIGHTS[4];
+        }
+        value
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Square> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Square>) {
+        let boards = match self.next {
+            Some(next) => next..next + 1,
+            None => 0..9,
+        };
+        for board in boards.filter(|&board| self.is_open(board)) {
+            let taken = self.taken(board);
+            actions.extend(
+                (0..9)
+                    .filter(|square| taken & 1 << square == 0)
+                    .map(|square| Square { board, square }),
+            );
+        }
+    }
+
+    fn result(&self, action: &Square) -> Self {
+        assert!(self.is_legal(*action), "{action} can't be played");
+
+        let mut state = self.clone();
+        let player = self.player as usize;
+        let (board, square) = (action.board as usize, action.square as usize);
+
+        state.marks[player][board] |= 1 << square;
+        state.hash ^= zobrist_key(board * 9 + square, self.player);
+
+        let marks = state.marks[player][board];
+        if LINES.iter().any(|&line| line & !marks == 0) {
+            state.won[player] |= 1 << board;
+            if LINES.iter().any(|&line| line & !state.won[player] == 0) {
+                state.winner = Some(self.player);
+            }
+        }
+
+        // The opponent is sent to the board matching the square, unless it's already closed
+        state.next = state.is_open(action.square).then_some(action.square);
+        state.hash ^= Self::next_key(self.next) ^ Self::next_key(state.next);
+        state.player = self.player.opposite();
+        state
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        // The player to move follows from the number of marks
+        Some(self.hash ^ Self::next_key(self.next))
+    }
+}
+
+impl Display for UltimateState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "   a b c   d e f   g h i")?;
+        for row in 0..9u8 {
+            if row > 0 && row % 3 == 0 {
+                write!(f, "\n   ──────┼───────┼──────")?;
+            }
+
+            write!(f, "\n{:>2} ", row + 1)?;
+            for column in 0..9u8 {
+                if column > 0 {
+                    write!(f, "{}", if column % 3 == 0 { " │ " } else { " " })?;
+                }
+
+                let Square { board, square } = Square {
+                    board: row / 3 * 3 + column / 3,
+                    square: row % 3 * 3 + column % 3,
+                };
+                let (board, square) = (board as usize, square);
+
+                // Won boards show a large mark, and empty squares are only shown where the player
+                // to move can play
+                let mark = if self.won[0] & 1 << board != 0 {
+                    if 0b101_010_101 & 1 << square != 0 {
+                        'X'
+                    } else {
+                        ' '
+                    }
+                } else if self.won[1] & 1 << board != 0 {
+                    if 0b111_101_111 & 1 << square != 0 {
+                        'O'
+                    } else {
+                        ' '
+                    }
+                } else if self.marks[0][board] & 1 << square != 0 {
+                    'X'
+                } else if self.marks[1][board] & 1 << square != 0 {
+                    'O'
+                } else if !self.is_terminal() && self.next.is_none_or(|next| next as usize == board)
+                {
+                    '·'
+                } else {
+                    ' '
+                };
+                write!(f, "{mark}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(squares: &str) -> UltimateState {
+        squares
+            .split_whitespace()
+            .fold(UltimateState::default(), |state, square| {
+                state.result(&square.parse().unwrap())
+            })
+    }
+
+    #[test]
+    fn parses_squares() {
+        assert_eq!(
+            "e5".parse(),
+            Ok(Square {
+                board: 4,
+                square: 4
+            })
+        );
+        assert_eq!(
+            "G2".parse(),
+            Ok(Square {
+                board: 2,
+                square: 3
+            })
+        );
+        assert_eq!("i9".parse::<Square>().unwrap().to_string(), "i9");
+        assert!("j1".parse::<Square>().is_err());
+        assert!("a0".parse::<Square>().is_err());
+    }
+
+    #[test]
+    fn sends_the_opponent_to_a_board() {
+        // The top right square of the centre board sends the opponent to the top right board
+        let state = play("f4");
+        assert_eq!(state.next, Some(2));
+        assert!(state.is_legal("g1".parse().unwrap()));
+        assert!(!state.is_legal("e5".parse().unwrap()));
+        assert_eq!(state.actions().len(), 9);
+
+        // Being sent to a board that's been won lets the opponent play on any other board
+        let state = UltimateState {
+            won: [0, 0b000_000_001],
+            ..Default::default()
+        };
+        let state = state.result(&"d1".parse().unwrap());
+        assert_eq!(state.next, None);
+        assert!(!state.is_legal("b2".parse().unwrap()));
+        assert!(state.is_legal("i9".parse().unwrap()));
+    }
+
+    #[test]
+    fn winning_three_boards_in_a_row() {
+        let mut state = UltimateState {
+            won: [0b000_000_011, 0],
+            ..Default::default()
+        };
+        state.marks[0][2] = 0b000_000_011;
+
+        let state = state.result(&"i1".parse().unwrap());
+        assert_eq!(state.won[0], 0b000_000_111);
+        assert_eq!(state.winner, Some(Player::Max));
+        assert!(state.is_terminal());
+    }
+
+    #[test]
+    fn engine_wins_the_last_board() {
+        // Max has won two boards along the top and only needs one more square on the third
+        let mut state = UltimateState {
+            won: [0b000_000_011, 0b000_110_000],
+            next: Some(2),
+            ..Default::default()
+        };
+        state.marks[0][2] = 0b001_000_001;
+        state.marks[1][2] = 0b000_010_000;
+
+        let best = Ultimate::engine(3).best_move(&state).unwrap();
+        assert_eq!(best.to_string(), "g2");
+    }
+}
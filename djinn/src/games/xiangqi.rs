@@ -0,0 +1,670 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::games::{search_move, zobrist_key, Game, GameError, SearchLimits, WinState};
+use crate::minimax::{self, Engine, EngineBuilder, Player, State};
+
+const FILES: i8 = 9;
+const RANKS: i8 = 10;
+
+const ORTHOGONALS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+/// The step a horse takes along a file or rank, which has to be empty, and where it lands after
+/// going on diagonally outwards.
+const HORSE_MOVES: [((i8, i8), (i8, i8)); 8] = [
+    ((0, 1), (1, 2)),
+    ((0, 1), (-1, 2)),
+    ((1, 0), (2, 1)),
+    ((1, 0), (2, -1)),
+    ((0, -1), (1, -2)),
+    ((0, -1), (-1, -2)),
+    ((-1, 0), (-2, 1)),
+    ((-1, 0), (-2, -1)),
+];
+
+/// The value of each square a chariot, horse or cannon can move to, since pieces with more room
+/// are better placed.
+const MOBILITY_VALUE: f32 = 0.05;
+/// The value of each rank a soldier has advanced past the river.
+const ADVANCE_VALUE: f32 = 0.2;
+
+/// The square one step from `square` along `(file, rank)`, if it's on the board.
+fn step(square: u8, (file, rank): (i8, i8)) -> Option<u8> {
+    let file = (square as i8 % FILES) + file;
+    let rank = (square as i8 / FILES) + rank;
+    ((0..FILES).contains(&file) && (0..RANKS).contains(&rank))
+        .then_some((rank * FILES + file) as u8)
+}
+
+/// How many ranks `square` is from `player`'s back rank.
+fn ranks_forward(square: u8, player: Player) -> i8 {
+    let rank = square as i8 / FILES;
+    match player {
+        Player::Max => rank,
+        Player::Min => RANKS - 1 - rank,
+    }
+}
+
+/// Whether `square` is on `player`'s side of the river.
+fn is_own_side(square: u8, player: Player) -> bool {
+    ranks_forward(square, player) < RANKS / 2
+}
+
+/// Whether `square` is in `player`'s palace, the three by three squares the general and
+/// advisors have to stay in.
+fn is_in_palace(square: u8, player: Player) -> bool {
+    (3..=5).contains(&(square as i8 % FILES)) && ranks_forward(square, player) < 3
+}
+
+fn square_name(square: u8) -> String {
+    format!("{}{}", (b'a' + square % 9) as char, square / 9 + 1)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Xiangqi(XiangqiState, Vec<Move>);
+
+impl Game for Xiangqi {
+    fn name(&self) -> String {
+        "Xiangqi".to_string()
+    }
+
+    fn thumbnail(&self) -> String {
+        "車─馬─象─士─將
+│  │  │  │  │
+┼──炮─┼──┼──┼
+│  │  │  │  │
+兵─┼──兵─┼──帥"
+            .to_string()
+    }
+
+    fn display(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn display_size(&self) -> (u16, u16) {
+        (28, 20)
+    }
+
+    fn move_history(&self) -> Vec<String> {
+        self.1.iter().map(Move::to_string).collect()
+    }
+
+    fn side_to_move(&self) -> usize {
+        self.0.player as usize
+    }
+
+    fn win_state(&self) -> Option<WinState> {
+        self.0.winner.map(WinState::Decisive)
+    }
+
+    fn is_valid_move(&self, move_: &str) -> bool {
+        move_
+            .parse::<Move>()
+            .is_ok_and(|move_| !self.0.is_terminal() && self.0.actions().contains(&move_))
+    }
+
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
+        let move_ = move_
+            .parse::<Move>()
+            .map_err(|_| GameError::invalid_move(move_))?;
+        self.0 = self.0.result(&move_);
+        self.1.push(move_);
+        Ok(())
+    }
+
+    fn computer_move(&self, limits: SearchLimits) -> Result<String, GameError> {
+        search_move(&Self::engine(limits.depth(3)), &self.0, &limits)
+            .map(|move_| move_.to_string())
+            .ok_or(GameError::NoMoves)
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.0.actions().iter().map(ToString::to_string).collect()
+    }
+
+    fn sides(&self) -> [String; 2] {
+        ["Red", "Black"].map(String::from)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Xiangqi {
+    /// The engine used to find computer moves, searching to `depth` plies.
+    pub fn engine(depth: u32) -> Engine<f32> {
+        EngineBuilder::default()
+            .depth(depth)
+            .quiescence_depth(2)
+            .transposition_table_size(1 << 16)
+            .build()
+            .expect("failed to build xiangqi engine")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    General,
+    Advisor,
+    Elephant,
+    Horse,
+    Chariot,
+    Cannon,
+    Soldier,
+}
+
+impl Kind {
+    fn value(self) -> f32 {
+        match self {
+            Kind::General => 0.0,
+            Kind::Advisor | Kind::Elephant => 2.0,
+            Kind::Horse => 4.0,
+            Kind::Chariot => 9.0,
+            Kind::Cannon => 4.5,
+            Kind::Soldier => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Piece {
+    kind: Kind,
+    owner: Player,
+}
+
+impl Piece {
+    /// The traditional character for the piece, which differs between Red and Black.
+    fn character(self) -> char {
+        match (self.kind, self.owner) {
+            (Kind::General, Player::Max) => '帥',
+            (Kind::General, Player::Min) => '將',
+            (Kind::Advisor, Player::Max) => '仕',
+            (Kind::Advisor, Player::Min) => '士',
+            (Kind::Elephant, Player::Max) => '相',
+            (Kind::Elephant, Player::Min) => '象',
+            (Kind::Horse, Player::Max) => '傌',
+            (Kind::Horse, Player::Min) => '馬',
+            (Kind::Chariot, Player::Max) => '俥',
+            (Kind::Chariot, Player::Min) => '車',
+            (Kind::Cannon, Player::Max) => '炮',
+            (Kind::Cannon, Player::Min) => '砲',
+            (Kind::Soldier, Player::Max) => '兵',
+            (Kind::Soldier, Player::Min) => '卒',
+        }
+    }
+
+    /// The value of the piece, with soldiers being worth more once they've crossed the river
+    /// and the further they've gone.
+    fn value(self, square: u8) -> f32 {
+        if self.kind == Kind::Soldier && !is_own_side(square, self.owner) {
+            let advance = ranks_forward(square, self.owner) - RANKS / 2;
+            2.0 + advance as f32 * ADVANCE_VALUE
+        } else {
+            self.kind.value()
+        }
+    }
+}
+
+/// A piece moving between two points, written as the squares such as `h3-e3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    from: u8,
+    to: u8,
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "expected two squares, such as `h3-e3`";
+        let s = s.trim().to_ascii_lowercase();
+        let square = |name: &str| -> Result<u8, Self::Err> {
+            let file = match name.bytes().next() {
+                Some(file @ b'a'..=b'i') => file - b'a',
+                _ => Err(EXPECTED)?,
+            };
+            let rank = name[1..]
+                .parse::<u8>()
+                .ok()
+                .filter(|rank| (1..=10).contains(rank))
+                .ok_or(EXPECTED)?;
+            Ok((rank - 1) * 9 + file)
+        };
+
+        let (from, to) = s.split_once('-').ok_or(EXPECTED)?;
+        Ok(Move {
+            from: square(from)?,
+            to: square(to)?,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", square_name(self.from), square_name(self.to))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct XiangqiState {
+    /// The piece on each point, going along each rank from a1.
+    board: [Option<Piece>; 90],
+    /// Red moves first from the bottom of the board and is the maximising player.
+    player: Player,
+    winner: Option<Player>,
+}
+
+impl Default for XiangqiState {
+    fn default() -> Self {
+        let mut board = [None; 90];
+        let back_rank = [
+            Kind::Chariot,
+            Kind::Horse,
+            Kind::Elephant,
+            Kind::Advisor,
+            Kind::General,
+            Kind::Advisor,
+            Kind::Elephant,
+            Kind::Horse,
+            Kind::Chariot,
+        ];
+        let mut place = |square: usize, kind| {
+            // Black's pieces mirror Red's across the river
+            let rank = square / 9;
+            let mirrored = (9 - rank) * 9 + square % 9;
+            board[square] = Some(Piece {
+                kind,
+                owner: Player::Max,
+            });
+            board[mirrored] = Some(Piece {
+                kind,
+                owner: Player::Min,
+            });
+        };
+        for (file, kind) in back_rank.into_iter().enumerate() {
+            place(file, kind);
+        }
+        place(19, Kind::Cannon);
+        place(25, Kind::Cannon);
+        for file in [0, 2, 4, 6, 8] {
+            place(27 + file, Kind::Soldier);
+        }
+
+        Self {
+            board,
+            player: Player::Max,
+            winner: None,
+        }
+    }
+}
+
+impl XiangqiState {
+    /// The points the piece on `from` can move to, including ones where it captures.
+    fn targets(&self, from: u8) -> Vec<u8> {
+        let Some(piece) = self.board[from as usize] else {
+            return Vec::new();
+        };
+        let is_empty = |square: u8| self.board[square as usize].is_none();
+        let is_open =
+            |square: u8| self.board[square as usize].is_none_or(|other| other.owner != piece.owner);
+
+        let mut targets = Vec::new();
+        match piece.kind {
+            Kind::General => targets.extend(
+                ORTHOGONALS
+                    .into_iter()
+                    .filter_map(|direction| step(from, direction))
+                    .filter(|&square| is_in_palace(square, piece.owner)),
+            ),
+            Kind::Advisor => targets.extend(
+                DIAGONALS
+                    .into_iter()
+                    .filter_map(|direction| step(from, direction))
+                    .filter(|&square| is_in_palace(square, piece.owner)),
+            ),
+            // Elephants can't cross the river, or jump over a piece in the middle of their move
+            Kind::Elephant => targets.extend(DIAGONALS.into_iter().filter_map(|direction| {
+                step(from, direction)
+                    .filter(|&eye| is_empty(eye))
+                    .and_then(|eye| step(eye, direction))
+                    .filter(|&square| is_own_side(square, piece.owner))
+            })),
+            Kind::Horse => targets.extend(HORSE_MOVES.into_iter().filter_map(|(leg, jump)| {
+                step(from, leg)
+                    .filter(|&leg| is_empty(leg))
+                    .and(step(from, jump))
+            })),
+            Kind::Chariot => {
+                for direction in ORTHOGONALS {
+                    let mut square = from;
+                    while let Some(next) = step(square, direction) {
+                        targets.push(next);
+                        if !is_empty(next) {
+                            break;
+                        }
+                        square = next;
+                    }
+                }
+            }
+            // Cannons move like chariots, but capture by jumping over exactly one piece
+            Kind::Cannon => {
+                for direction in ORTHOGONALS {
+                    let mut square = from;
+                    let mut screen = false;
+                    while let Some(next) = step(square, direction) {
+                        square = next;
+                        match (is_empty(next), screen) {
+                            (true, false) => targets.push(next),
+                            (true, true) => {}
+                            (false, false) => screen = true,
+                            (false, true) => {
+                                targets.push(next);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            // Soldiers can also move sideways once they've crossed the river
+            Kind::Soldier => {
+                let forward = match piece.owner {
+                    Player::Max => 1,
+                    Player::Min => -1,
+                };
+                targets.extend(step(from, (0, forward)));
+                if !is_own_side(from, piece.owner) {
+                    targets.extend(step(from, (1, 0)));
+                    targets.extend(step(from, (-1, 0)));
+                }
+            }
+        }
+        targets.retain(|&square| is_open(square));
+        targets
+    }
+
+    fn general(&self, player: Player) -> Option<u8> {
+        (0..90).find(|&square| {
+            self.board[square as usize]
+                == Some(Piece {
+                    kind: Kind::General,
+                    owner: player,
+                })
+        })
+    }
+
+    /// Whether `player`'s general is attacked, or faces the other general along an open file.
+    fn is_in_check(&self, player: Player) -> bool {
+        let (Some(general), Some(opposing)) =
+            (self.general(player), self.general(player.opposite()))
+        else {
+            return false;
+        };
+
+        let (low, high) = (general.min(opposing), general.max(opposing));
+        let flying = general % 9 == opposing % 9
+            && (low + 9..high)
+                .step_by(9)
+                .all(|square| self.board[square as usize].is_none());
+
+        flying
+            || (0..90).any(|square| {
+                self.board[square as usize].is_some_and(|piece| piece.owner != player)
+                    && self.targets(square).contains(&general)
+            })
+    }
+
+    fn play(&self, move_: Move) -> Self {
+        let mut state = self.clone();
+        state.board[move_.to as usize] = state.board[move_.from as usize].take();
+        state.player = self.player.opposite();
+        state
+    }
+}
+
+impl minimax::State<f32, Move> for XiangqiState {
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn evaluation(&self) -> f32 {
+        match self.winner {
+            Some(Player::Max) => return f32::INFINITY,
+            Some(Player::Min) => return f32::NEG_INFINITY,
+            None => {}
+        }
+
+        let mut value = 0.0;
+        for square in 0..90 {
+            let Some(piece) = self.board[square as usize] else {
+                continue;
+            };
+            let mut piece_value = piece.value(square);
+            if matches!(piece.kind, Kind::Chariot | Kind::Horse | Kind::Cannon) {
+                piece_value += self.targets(square).len() as f32 * MOBILITY_VALUE;
+            }
+            match piece.owner {
+                Player::Max => value += piece_value,
+                Player::Min => value -= piece_value,
+            }
+        }
+        value
+    }
+
+    fn current_player(&self) -> Player {
+        self.player
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        let mut actions = Vec::new();
+        self.actions_into(&mut actions);
+        actions
+    }
+
+    fn actions_into(&self, actions: &mut Vec<Move>) {
+        for from in 0..90 {
+            if self.board[from as usize].is_some_and(|piece| piece.owner == self.player) {
+                actions.extend(
+                    self.targets(from)
+                        .into_iter()
+                        .map(|to| Move { from, to })
+                        .filter(|&move_| !self.play(move_).is_in_check(self.player)),
+                );
+            }
+        }
+    }
+
+    fn result(&self, action: &Move) -> Self {
+        assert!(
+            self.board[action.from as usize].is_some_and(|piece| piece.owner == self.player),
+            "{action} is not a legal move"
+        );
+
+        // Leaving the opponent without a move wins, whether or not they're in check
+        let mut state = self.play(*action);
+        if state.actions().is_empty() {
+            state.winner = Some(self.player);
+        }
+        state
+    }
+
+    fn is_quiet(&self, action: &Move) -> bool {
+        self.board[action.to as usize].is_none()
+    }
+
+    fn see(&self, action: &Move) -> f32 {
+        self.board[action.to as usize].map_or(0.0, |piece| piece.value(action.to))
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        let mut hash = 0;
+        for (square, piece) in self.board.iter().enumerate() {
+            if let Some(piece) = piece {
+                hash ^= zobrist_key(piece.kind as usize * 90 + square, piece.owner);
+            }
+        }
+        Some(match self.player {
+            Player::Max => hash,
+            Player::Min => !hash,
+        })
+    }
+}
+
+impl Display for XiangqiState {
+    /// The board drawn with the pieces on the points where the lines cross, the river across the
+    /// middle, and the diagonals through each palace.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for rank in (0..10).rev() {
+            write!(f, "{:>2} ", rank + 1)?;
+            for file in 0..9 {
+                let last = file == 8;
+                match self.board[rank * 9 + file] {
+                    Some(piece) => {
+                        write!(f, "{}{}", piece.character(), if last { "" } else { "─" })?
+                    }
+                    None => write!(f, "┼{}", if last { "" } else { "──" })?,
+                }
+            }
+            writeln!(f)?;
+
+            match rank {
+                0 => {}
+                5 => writeln!(f, "   │  楚河           漢界  │")?,
+                _ => {
+                    // The palace diagonals cross in the middle of each palace
+                    let diagonals = match rank {
+                        2 | 9 => ["╲ ", "╱ "],
+                        1 | 8 => ["╱ ", "╲ "],
+                        _ => ["  ", "  "],
+                    };
+                    writeln!(
+                        f,
+                        "   │  │  │  │{}│{}│  │  │  │",
+                        diagonals[0], diagonals[1]
+                    )?;
+                }
+            }
+        }
+        write!(f, "   a  b  c  d  e  f  g  h  i")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> u8 {
+        format!("{name}-{name}").parse::<Move>().unwrap().from
+    }
+
+    /// A position with pieces written as their character and square, such as `俥a1`.
+    fn position(pieces: &[&str], player: Player) -> XiangqiState {
+        let mut board = [None; 90];
+        for name in pieces {
+            let mut chars = name.chars();
+            let character = chars.next().unwrap();
+            let piece = [
+                Kind::General,
+                Kind::Advisor,
+                Kind::Elephant,
+                Kind::Horse,
+                Kind::Chariot,
+                Kind::Cannon,
+                Kind::Soldier,
+            ]
+            .into_iter()
+            .flat_map(|kind| [Player::Max, Player::Min].map(|owner| Piece { kind, owner }))
+            .find(|piece| piece.character() == character)
+            .unwrap();
+            board[square(chars.as_str()) as usize] = Some(piece);
+        }
+        XiangqiState {
+            board,
+            player,
+            winner: None,
+        }
+    }
+
+    fn targets(state: &XiangqiState, from: &str) -> Vec<String> {
+        let mut targets: Vec<_> = state
+            .targets(square(from))
+            .into_iter()
+            .map(square_name)
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    #[test]
+    fn parses_moves() {
+        let move_ = "h3-e3".parse::<Move>().unwrap();
+        assert_eq!((move_.from, move_.to), (25, 22));
+        assert_eq!(move_.to_string(), "h3-e3");
+        assert_eq!("A10-a9".parse::<Move>().unwrap().to_string(), "a10-a9");
+        assert!("a11-a10".parse::<Move>().is_err());
+        assert!("j1-j2".parse::<Move>().is_err());
+        assert!("a1a2".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn starting_position_has_44_moves() {
+        let state = XiangqiState::default();
+        assert_eq!(state.actions().len(), 44);
+        assert!(state.evaluation().abs() < 1e-4);
+    }
+
+    #[test]
+    fn horses_and_elephants_can_be_blocked() {
+        let state = position(&["帥e1", "將e10", "傌b1", "相g1"], Player::Max);
+        assert_eq!(targets(&state, "b1"), ["a3", "c3", "d2"]);
+        assert_eq!(targets(&state, "g1"), ["e3", "i3"]);
+
+        // A piece next to a horse or diagonally next to an elephant is in its way
+        let state = position(
+            &["帥e1", "將e10", "傌b1", "兵b2", "相g1", "仕f2"],
+            Player::Max,
+        );
+        assert_eq!(targets(&state, "b1"), ["d2"]);
+        assert_eq!(targets(&state, "g1"), ["i3"]);
+
+        // Elephants stay on their own side of the river
+        let state = position(&["帥e1", "將e10", "相c5"], Player::Max);
+        assert_eq!(targets(&state, "c5"), ["a3", "e3"]);
+    }
+
+    #[test]
+    fn cannons_capture_by_jumping_a_screen() {
+        let state = position(&["帥e1", "將e10", "炮b3", "卒b7", "馬b9"], Player::Max);
+        let targets = targets(&state, "b3");
+        assert!(targets.contains(&"b6".to_string()));
+        assert!(!targets.contains(&"b7".to_string()));
+        assert!(targets.contains(&"b9".to_string()));
+        assert!(!targets.contains(&"b10".to_string()));
+    }
+
+    #[test]
+    fn generals_cant_face_each_other() {
+        let state = position(&["帥d1", "將e10", "兵c5"], Player::Max);
+        let actions: Vec<_> = state.actions().iter().map(Move::to_string).collect();
+        assert!(!actions.contains(&"d1-e1".to_string()));
+        assert!(actions.contains(&"d1-d2".to_string()));
+
+        // A piece between them can't step aside
+        let state = position(&["帥e1", "將e10", "兵e6"], Player::Max);
+        assert!(!state.is_in_check(Player::Max));
+        let actions: Vec<_> = state.actions().iter().map(Move::to_string).collect();
+        assert!(!actions.contains(&"e6-d6".to_string()));
+        assert!(actions.contains(&"e6-e7".to_string()));
+    }
+
+    #[test]
+    fn checkmate_wins() {
+        let state = position(&["帥d1", "俥a9", "俥i1", "將e10"], Player::Max);
+        let mated = state.result(&"i1-i10".parse().unwrap());
+        assert_eq!(mated.winner, Some(Player::Max));
+
+        let move_ = Xiangqi::engine(2).best_move(&state).unwrap();
+        assert_eq!(state.result(&move_).winner, Some(Player::Max));
+    }
+}
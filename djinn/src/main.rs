@@ -2,21 +2,30 @@ use clap::error::ErrorKind;
 use clap::{CommandFactory, Parser};
 
 use app::App;
-use cli::Cli;
+use cli::{Cli, Command};
 use color_eyre::Result;
+use network::Connection;
 
 pub use djinn_minimax as minimax;
 
 mod action;
+mod analysis;
 mod app;
+mod bench;
 mod cli;
+mod clipboard;
+mod clock;
 mod components;
 mod config;
 mod errors;
 mod games;
 mod logging;
+mod network;
 mod plugins;
+mod session;
+mod stats;
 mod tui;
+mod tune;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -25,9 +34,18 @@ async fn main() -> Result<()> {
 
     let args = Cli::parse();
 
+    match args.command {
+        Some(Command::Tune {
+            positions,
+            iterations,
+        }) => return tune::run(&positions, iterations),
+        Some(Command::Bench { depth }) => return bench::run(depth),
+        None => {}
+    }
+
     let mut app = App::new()?;
-    if let Some(game) = args.game {
-        app.open_game_from_name(&game).unwrap_or_else(|_| {
+    if let Some(game) = &args.game {
+        app.open_game_from_name(game).unwrap_or_else(|_| {
             let mut cmd = Cli::command();
             cmd.error(
                 ErrorKind::InvalidValue,
@@ -35,6 +53,27 @@ async fn main() -> Result<()> {
             )
             .exit();
         });
+
+        let connection = match (args.host, args.join) {
+            (Some(port), _) => Some(Connection::host(port, game)?),
+            (None, Some(address)) => Some(Connection::join(address, game)),
+            (None, None) => None,
+        };
+        if let Some(connection) = connection {
+            app.play_online(game, connection)?;
+        }
+    }
+
+    if let Some(path) = args.pgn {
+        let pgn = std::fs::read_to_string(&path)?;
+        app.open_game_from_pgn("chess", &pgn).unwrap_or_else(|error| {
+            let mut cmd = Cli::command();
+            cmd.error(
+                ErrorKind::InvalidValue,
+                format!("Can't open \"{}\": {error}", path.display()),
+            )
+            .exit();
+        });
     }
 
     app.run().await?;
@@ -0,0 +1,299 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+/// The version of the protocol, which both ends have to be speaking to play each other.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What the two ends of a game send each other, one message per line as JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// The first message from each end, which has to match the other's.
+    Hello {
+        version: u32,
+        game: String,
+    },
+    Move {
+        #[serde(rename = "move")]
+        move_: String,
+    },
+    Resign,
+}
+
+/// How the connection to the other player is going.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    /// Hosting the game at this address until someone joins it.
+    Waiting(SocketAddr),
+    Connecting(String),
+    Connected(SocketAddr),
+    /// The connection failed or was closed, for this reason.
+    Disconnected(String),
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Waiting(address) => {
+                write!(f, "Waiting for an opponent on port {}", address.port())
+            }
+            Status::Connecting(address) => write!(f, "Connecting to {address}"),
+            Status::Connected(address) => write!(f, "Playing against {address}"),
+            Status::Disconnected(reason) => write!(f, "Disconnected: {reason}"),
+        }
+    }
+}
+
+/// What the thread looking after the connection tells the game.
+enum Event {
+    Connected(TcpStream),
+    Received(Message),
+    Closed(String),
+}
+
+/// A game against another djinn over TCP. The host plays the side that moves first, and whoever
+/// joins plays the other.
+#[derive(Debug)]
+pub struct Connection {
+    hosting: bool,
+    status: Status,
+    /// The stream to send messages on, once connected.
+    stream: Option<TcpStream>,
+    events: Receiver<Event>,
+}
+
+impl Connection {
+    /// Hosts `game` on `port`, waiting in the background for someone to join it.
+    pub fn host(port: u16, game: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let address = listener.local_addr()?;
+        let (sender, events) = mpsc::channel();
+        let game = game.to_lowercase();
+        thread::spawn(move || match listener.accept() {
+            Ok((stream, _)) => run(stream, &game, &sender),
+            Err(err) => {
+                let _ = sender.send(Event::Closed(err.to_string()));
+            }
+        });
+        Ok(Self::new(true, Status::Waiting(address), events))
+    }
+
+    /// Joins the game of `game` hosted at `address`, connecting in the background.
+    pub fn join(address: String, game: &str) -> Self {
+        let (sender, events) = mpsc::channel();
+        let game = game.to_lowercase();
+        thread::spawn({
+            let address = address.clone();
+            move || match TcpStream::connect(&address) {
+                Ok(stream) => run(stream, &game, &sender),
+                Err(err) => {
+                    let _ = sender.send(Event::Closed(err.to_string()));
+                }
+            }
+        });
+        Self::new(false, Status::Connecting(address), events)
+    }
+
+    fn new(hosting: bool, status: Status, events: Receiver<Event>) -> Self {
+        Self {
+            hosting,
+            status,
+            stream: None,
+            events,
+        }
+    }
+
+    /// Whether this end is hosting the game, and so moves first.
+    pub fn is_hosting(&self) -> bool {
+        self.hosting
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    pub fn is_connected(&self) -> bool {
+        matches!(self.status, Status::Connected(_))
+    }
+
+    /// The messages received from the other player since the last call, updating the status
+    /// along the way.
+    pub fn receive(&mut self) -> Vec<Message> {
+        let mut messages = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Event::Connected(stream) => {
+                    if let Ok(address) = stream.peer_addr() {
+                        self.status = Status::Connected(address);
+                    }
+                    self.stream = Some(stream);
+                }
+                Event::Received(message) => messages.push(message),
+                Event::Closed(reason) => self.disconnect(reason),
+            }
+        }
+        messages
+    }
+
+    /// Sends `message` to the other player, disconnecting if it can't be sent.
+    pub fn send(&mut self, message: &Message) {
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+        if let Err(err) = write_message(stream, message) {
+            self.disconnect(err.to_string());
+        }
+    }
+
+    /// Closes the connection, leaving the game where it is.
+    pub fn disconnect(&mut self, reason: String) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+        if !matches!(self.status, Status::Disconnected(_)) {
+            self.status = Status::Disconnected(reason);
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.disconnect("the game was closed".to_string());
+    }
+}
+
+fn write_message(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    let line = serde_json::to_string(message)?;
+    writeln!(stream, "{line}")?;
+    stream.flush()
+}
+
+/// Greets the other end of `stream`, checking that it's playing the same `game` with the same
+/// protocol, and then passes on everything it sends until the connection closes.
+fn run(stream: TcpStream, game: &str, sender: &Sender<Event>) {
+    let reason = match relay(stream, game, sender) {
+        Ok(()) => "the other player left".to_string(),
+        Err(err) => err.to_string(),
+    };
+    let _ = sender.send(Event::Closed(reason));
+}
+
+fn relay(mut stream: TcpStream, game: &str, sender: &Sender<Event>) -> io::Result<()> {
+    let hello = Message::Hello {
+        version: PROTOCOL_VERSION,
+        game: game.to_string(),
+    };
+    write_message(&mut stream, &hello)?;
+
+    let mut lines = BufReader::new(stream.try_clone()?).lines();
+    let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+    let read = |line: io::Result<String>| -> io::Result<Message> {
+        serde_json::from_str(&line?).map_err(|err| invalid(format!("invalid message: {err}")))
+    };
+
+    match lines.next().map(read).transpose()? {
+        Some(Message::Hello { version, .. }) if version != PROTOCOL_VERSION => {
+            return Err(invalid(format!(
+                "the other player speaks version {version} of the protocol, not {PROTOCOL_VERSION}"
+            )));
+        }
+        Some(Message::Hello { game: theirs, .. }) if theirs != game => {
+            return Err(invalid(format!("the other player is playing {theirs}")));
+        }
+        Some(Message::Hello { .. }) => {}
+        _ => return Err(invalid("the other player didn't say hello".to_string())),
+    }
+    if sender.send(Event::Connected(stream)).is_err() {
+        return Ok(());
+    }
+
+    for line in lines {
+        let message = read(line)?;
+        if sender.send(Event::Received(message)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// Receives on `connection` until `done` holds for it and the messages received, giving up
+    /// after a few seconds.
+    fn receive_until(
+        connection: &mut Connection,
+        done: impl Fn(&Connection, &[Message]) -> bool,
+    ) -> Vec<Message> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut messages = Vec::new();
+        while !done(connection, &messages) && Instant::now() < deadline {
+            messages.extend(connection.receive());
+            thread::sleep(Duration::from_millis(10));
+        }
+        messages
+    }
+
+    #[test]
+    fn messages_are_one_line_of_json() {
+        let message = Message::Move {
+            move_: "e4".to_string(),
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(json, r#"{"type":"move","move":"e4"}"#);
+        assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), message);
+    }
+
+    #[test]
+    fn plays_moves_between_host_and_guest() {
+        let mut host = Connection::host(0, "Chess").unwrap();
+        let Status::Waiting(address) = *host.status() else {
+            panic!("the host should be waiting");
+        };
+        let mut guest = Connection::join(format!("127.0.0.1:{}", address.port()), "chess");
+        assert!(host.is_hosting() && !guest.is_hosting());
+
+        receive_until(&mut host, |host, _| host.is_connected());
+        receive_until(&mut guest, |guest, _| guest.is_connected());
+        host.send(&Message::Move {
+            move_: "e4".to_string(),
+        });
+        let messages = receive_until(&mut guest, |_, messages| !messages.is_empty());
+        assert_eq!(
+            messages,
+            [Message::Move {
+                move_: "e4".to_string()
+            }]
+        );
+
+        drop(host);
+        receive_until(&mut guest, |guest, _| !guest.is_connected());
+        assert!(matches!(guest.status(), Status::Disconnected(_)));
+    }
+
+    #[test]
+    fn refuses_a_different_game() {
+        let host = Connection::host(0, "Chess").unwrap();
+        let Status::Waiting(address) = *host.status() else {
+            panic!("the host should be waiting");
+        };
+        let mut guest = Connection::join(format!("127.0.0.1:{}", address.port()), "Go");
+        receive_until(&mut guest, |guest, _| {
+            matches!(guest.status(), Status::Disconnected(_))
+        });
+        assert_eq!(
+            *guest.status(),
+            Status::Disconnected("the other player is playing chess".to_string())
+        );
+    }
+}
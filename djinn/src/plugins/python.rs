@@ -1,19 +1,48 @@
-use crate::games::{Game, WinState};
+use crate::games::{Game, GameError, SearchLimits, WinState};
 use convert_case::{Case, Casing};
 use pyo3::prelude::{PyAnyMethods, PyModule};
 use pyo3::{PyObject, PyResult, Python};
 use std::fs;
 use std::path::Path;
+use tracing::{error, warn};
 
 pub struct Plugin(PyObject);
 
-impl From<bool> for WinState {
-    fn from(decisive: bool) -> Self {
-        if decisive {
-            WinState::Decisive
-        } else {
-            WinState::Draw
+impl Clone for Plugin {
+    fn clone(&self) -> Self {
+        // Plugins keep their state in Python, so it has to be copied there
+        Python::with_gil(|py| {
+            let copy = PyModule::import_bound(py, "copy")
+                .and_then(|copy| copy.call_method1("deepcopy", (&self.0,)));
+            match copy {
+                Ok(game) => Plugin(game.unbind()),
+                Err(err) => {
+                    warn!("Failed to copy the plugin's game, so replaying it instead: {err}");
+                    self.replay_from_start(py)
+                }
+            }
+        })
+    }
+}
+
+impl Plugin {
+    /// A new game of the same plugin with this game's moves played again, for games holding
+    /// something that can't be copied.
+    fn replay_from_start(&self, py: Python<'_>) -> Self {
+        let game = self
+            .0
+            .bind(py)
+            .get_type()
+            .call0()
+            .expect("Failed to start a new game of the plugin");
+        let mut plugin = Plugin(game.unbind());
+        for move_ in self.move_history() {
+            if let Err(err) = plugin.play_move(&move_) {
+                error!("Failed to replay the plugin's move {move_}: {err}");
+                break;
+            }
         }
+        plugin
     }
 }
 
@@ -68,14 +97,39 @@ impl Game for Plugin {
         })
     }
 
+    /// Plugins that don't say whose turn it is are taken to alternate turns.
+    fn side_to_move(&self) -> usize {
+        Python::with_gil(|py| {
+            let game = self.0.bind(py);
+            if !game.hasattr("side_to_move").unwrap_or(false) {
+                return self.move_history().len() % 2;
+            }
+            game.call_method0("side_to_move")
+                .expect("Failed to call Python method 'side_to_move'")
+                .extract::<usize>()
+                .expect("Failed to extract the side to move")
+        })
+    }
+
     fn win_state(&self) -> Option<WinState> {
         Python::with_gil(|py| {
-            self.0
+            let win_state = self
+                .0
                 .call_method0(py, "win_state")
-                .expect("Failed to call Python method 'win_state'")
-                .extract::<Option<bool>>(py)
-                .expect("Failed to extract Option<WinState>")
-                .map(WinState::from)
+                .expect("Failed to call Python method 'win_state'");
+            if win_state.is_none(py) {
+                return None;
+            }
+            // Plugins used to only say whether the game was decisive, in which case whoever
+            // moved last won
+            let winner = match win_state.extract::<bool>(py) {
+                Ok(false) => return Some(WinState::Draw),
+                Ok(true) => (self.move_history().len() + 1) % 2,
+                Err(_) => win_state
+                    .extract::<usize>(py)
+                    .expect("Failed to extract the winning side"),
+            };
+            Some(WinState::won_by(winner))
         })
     }
 
@@ -89,21 +143,22 @@ impl Game for Plugin {
         })
     }
 
-    fn play_move(&mut self, move_: &str) {
+    fn play_move(&mut self, move_: &str) -> Result<(), GameError> {
         Python::with_gil(|py| {
             self.0
                 .call_method1(py, "play_move", (move_,))
-                .expect("Failed to call Python method 'play_move'");
-        });
+                .map_err(|err| GameError::Plugin(format!("Failed to play {move_}: {err}")))?;
+            Ok(())
+        })
     }
 
-    fn computer_move(&self) -> String {
+    fn computer_move(&self, _limits: SearchLimits) -> Result<String, GameError> {
+        // Plugins decide how strongly they play themselves
         Python::with_gil(|py| {
             self.0
                 .call_method0(py, "computer_move")
-                .expect("Failed to call Python method 'computer_move'")
-                .extract::<String>(py)
-                .expect("Failed to extract Python string")
+                .and_then(|move_| move_.extract::<String>(py))
+                .map_err(|err| GameError::Plugin(format!("Failed to choose a move: {err}")))
         })
     }
 
@@ -144,3 +199,41 @@ impl<'py> PythonPluginManager<'py> {
             .map(|p| Plugin(p.unbind()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn games_that_cant_be_copied_are_replayed() {
+        let directory = env::temp_dir().join(format!("djinn-plugin-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("locked.py");
+        // A lock is one of the things Python can't deep-copy
+        let code = r#"
+import threading
+
+class Locked:
+    def __init__(self):
+        self.lock = threading.Lock()
+        self.moves = []
+
+    def move_history(self):
+        return self.moves
+
+    def play_move(self, move):
+        self.moves.append(move)
+"#;
+        fs::write(&path, code).unwrap();
+        let plugin = Python::with_gil(|py| PythonPluginManager::new(py).load_plugin(&path));
+        fs::remove_dir_all(&directory).unwrap();
+
+        let mut plugin = plugin.unwrap();
+        plugin.play_move("a1").unwrap();
+        let mut copy = plugin.clone();
+        copy.play_move("b2").unwrap();
+        assert_eq!(plugin.move_history(), ["a1"]);
+        assert_eq!(copy.move_history(), ["a1", "b2"]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::game_screen::SavedGame;
+
+/// The name of the file in the data directory that the session is saved to.
+const FILE_NAME: &str = "session.json";
+
+/// The games that were being played when djinn was last closed, for it to carry on with the next
+/// time it starts.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub games: Vec<SavedGame>,
+    /// The name of the game that was open, if djinn was closed from a game rather than the home
+    /// screen.
+    pub open: Option<String>,
+}
+
+impl Session {
+    /// Reads the session saved in `directory`, if there is one.
+    pub fn load(directory: &Path) -> io::Result<Option<Self>> {
+        let json = match fs::read_to_string(directory.join(FILE_NAME)) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    /// Saves the session to `directory`, replacing the one saved before.
+    pub fn save(&self, directory: &Path) -> io::Result<()> {
+        fs::create_dir_all(directory)?;
+        fs::write(directory.join(FILE_NAME), serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn saves_and_loads_the_open_games() {
+        let directory = env::temp_dir().join(format!("djinn-session-{}", std::process::id()));
+        assert_eq!(Session::load(&directory).unwrap(), None);
+
+        let game = r#"{
+            "name": "Connect Four",
+            "options": [],
+            "moves": ["d", "d", "e"],
+            "mode": "Computer",
+            "plays_second": true,
+            "flipped": false,
+            "difficulty": { "Custom": 6 }
+        }"#;
+        let session = Session {
+            games: vec![serde_json::from_str(game).unwrap()],
+            open: Some("Connect Four".to_string()),
+        };
+        session.save(&directory).unwrap();
+        let loaded = Session::load(&directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(loaded, Some(session));
+    }
+}
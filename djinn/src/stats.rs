@@ -0,0 +1,252 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::games::Difficulty;
+
+/// The name of the file in the data directory that results are added to, one JSON record per line.
+const FILE_NAME: &str = "stats.jsonl";
+/// The player's rating in each game before they've finished any rated games in it.
+const INITIAL_RATING: f32 = 1200.0;
+/// The most the player's rating can change by after a game.
+const K_FACTOR: f32 = 32.0;
+
+/// How a game against the computer ended for the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+/// A finished game against the computer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub game: String,
+    pub outcome: Outcome,
+    pub difficulty: Difficulty,
+    pub duration: Duration,
+    /// The number of moves played by both sides.
+    pub moves: usize,
+    /// When the game finished, in seconds since the Unix epoch.
+    pub finished: u64,
+}
+
+/// Adds `record` to the results kept in `directory`.
+pub fn record(directory: &Path, record: &Record) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(directory.join(FILE_NAME))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+}
+
+/// The results of every game of `game` kept in `directory`, oldest first.
+pub fn load(directory: &Path, game: &str) -> io::Result<Vec<Record>> {
+    let mut records = load_all(directory)?;
+    records.retain(|record| record.game == game);
+    Ok(records)
+}
+
+/// The results of every game kept in `directory`, oldest first.
+pub fn load_all(directory: &Path) -> io::Result<Vec<Record>> {
+    let lines = match fs::read_to_string(directory.join(FILE_NAME)) {
+        Ok(lines) => lines,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    lines
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+/// The rating the computer plays at on each difficulty, as if it were a player. Custom
+/// difficulties search to a depth that means something different in every game, so games played
+/// on them aren't rated.
+fn engine_rating(difficulty: Difficulty) -> Option<f32> {
+    match difficulty {
+        Difficulty::Easy => Some(800.0),
+        Difficulty::Medium => Some(1200.0),
+        Difficulty::Hard => Some(1600.0),
+        Difficulty::Custom(_) => None,
+    }
+}
+
+/// The player's Elo rating after the games of `records`, taken in order, or `None` if none of
+/// them were rated.
+pub fn rating<'a>(records: impl IntoIterator<Item = &'a Record>) -> Option<u32> {
+    let mut rating = None;
+    for record in records {
+        let Some(engine) = engine_rating(record.difficulty) else {
+            continue;
+        };
+        let player = rating.unwrap_or(INITIAL_RATING);
+        let expected = 1.0 / (1.0 + 10f32.powf((engine - player) / 400.0));
+        let score = match record.outcome {
+            Outcome::Win => 1.0,
+            Outcome::Loss => 0.0,
+            Outcome::Draw => 0.5,
+        };
+        rating = Some(player + K_FACTOR * (score - expected));
+    }
+    rating.map(|rating| rating.round().max(0.0) as u32)
+}
+
+/// The player's record over some games against the computer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    /// The time spent on all of the games together.
+    pub duration: Duration,
+    /// The number of moves played in all of the games together.
+    pub moves: usize,
+}
+
+impl Stats {
+    pub fn of<'a>(records: impl IntoIterator<Item = &'a Record>) -> Self {
+        records
+            .into_iter()
+            .fold(Self::default(), |mut stats, record| {
+                match record.outcome {
+                    Outcome::Win => stats.wins += 1,
+                    Outcome::Loss => stats.losses += 1,
+                    Outcome::Draw => stats.draws += 1,
+                }
+                stats.duration += record.duration;
+                stats.moves += record.moves;
+                stats
+            })
+    }
+
+    pub fn played(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let plural = |count: usize, word: &str, words: &str| {
+            format!("{count} {}", if count == 1 { word } else { words })
+        };
+        write!(
+            f,
+            "{}, {}, {}",
+            plural(self.wins, "win", "wins"),
+            plural(self.losses, "loss", "losses"),
+            plural(self.draws, "draw", "draws"),
+        )?;
+        let played = self.played();
+        if let Some(moves) = self.moves.checked_div(played) {
+            let duration = clock::format(self.duration / played as u32);
+            write!(f, ", {moves} moves in {duration} a game")?;
+        }
+        Ok(())
+    }
+}
+
+/// The lines of the statistics view: the player's record over all of `records`, then at each
+/// difficulty they've played.
+pub fn summarise(records: &[Record]) -> Vec<String> {
+    if records.is_empty() {
+        return vec!["No games finished against the computer yet".to_string()];
+    }
+    let mut difficulties: Vec<Difficulty> = Vec::new();
+    for record in records {
+        if !difficulties.contains(&record.difficulty) {
+            difficulties.push(record.difficulty);
+        }
+    }
+
+    let mut lines = Vec::new();
+    if let Some(rating) = rating(records) {
+        lines.push(format!("Rating: {rating}"));
+    }
+    lines.push(format!("All: {}", Stats::of(records)));
+    lines.extend(difficulties.into_iter().map(|difficulty| {
+        let stats = Stats::of(
+            records
+                .iter()
+                .filter(|record| record.difficulty == difficulty),
+        );
+        format!("{difficulty}: {stats}")
+    }));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn record(game: &str, outcome: Outcome, difficulty: Difficulty, minutes: u64) -> Record {
+        Record {
+            game: game.to_string(),
+            outcome,
+            difficulty,
+            duration: Duration::from_secs(minutes * 60),
+            moves: 30,
+            finished: 0,
+        }
+    }
+
+    #[test]
+    fn keeps_the_results_of_each_game() {
+        let directory = env::temp_dir().join(format!("djinn-stats-{}", std::process::id()));
+        assert_eq!(load(&directory, "Chess").unwrap(), []);
+
+        let won = record("Chess", Outcome::Win, Difficulty::Easy, 5);
+        let lost = record("Othello", Outcome::Loss, Difficulty::Hard, 3);
+        super::record(&directory, &won).unwrap();
+        super::record(&directory, &lost).unwrap();
+        let chess = load(&directory, "Chess").unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(chess, [won]);
+    }
+
+    #[test]
+    fn summarises_by_difficulty() {
+        let records = [
+            record("Chess", Outcome::Win, Difficulty::Easy, 4),
+            record("Chess", Outcome::Loss, Difficulty::Hard, 8),
+            record("Chess", Outcome::Win, Difficulty::Easy, 6),
+        ];
+        assert_eq!(
+            summarise(&records),
+            [
+                "Rating: 1203",
+                "All: 2 wins, 1 loss, 0 draws, 30 moves in 6:00 a game",
+                "Easy: 2 wins, 0 losses, 0 draws, 30 moves in 5:00 a game",
+                "Hard: 0 wins, 1 loss, 0 draws, 30 moves in 8:00 a game",
+            ]
+        );
+        assert_eq!(summarise(&[]).len(), 1);
+    }
+
+    #[test]
+    fn rates_the_player_against_each_difficulty() {
+        let custom = record("Chess", Outcome::Win, Difficulty::Custom(3), 1);
+        assert_eq!(rating([&custom]), None);
+
+        // Beating an equal opponent gains half of the most there is to gain
+        let medium = record("Chess", Outcome::Win, Difficulty::Medium, 1);
+        assert_eq!(rating([&custom, &medium]), Some(1216));
+        // Losing to a much weaker opponent loses nearly all of it
+        let easy = record("Chess", Outcome::Loss, Difficulty::Easy, 1);
+        assert_eq!(rating([&easy]), Some(1171));
+        let hard = record("Chess", Outcome::Draw, Difficulty::Hard, 1);
+        assert_eq!(rating([&hard]), Some(1213));
+    }
+}
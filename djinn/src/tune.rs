@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use itertools::Itertools;
+use shakmaty::fen::Fen;
+use shakmaty::CastlingMode;
+
+use crate::games::chess::EvalParams;
+use crate::minimax::tune::{self, Sample, Tuner};
+
+// Results are matched in this order, so that "1/2-1/2" isn't mistaken for a decisive result
+const RESULTS: [(&str, f64); 6] = [
+    ("1/2-1/2", 0.5),
+    ("1-0", 1.0),
+    ("0-1", 0.0),
+    ("[0.5]", 0.5),
+    ("[1.0]", 1.0),
+    ("[0.0]", 0.0),
+];
+
+/// A chess position, stored as the evaluation's features so they only have to be computed once.
+type Features = Vec<(usize, f64)>;
+
+/// Tunes the chess evaluation against a file of labelled positions, and prints the tuned
+/// parameters as JSON.
+///
+/// Each line of the file holds a FEN (or EPD) followed by the result of the game, either as a
+/// PGN result (`1-0`, `0-1`, `1/2-1/2`, optionally quoted as in `c9 "1-0";`), or as a score in
+/// brackets (`[1.0]`, `[0.5]`, `[0.0]`).
+pub fn run(positions: &Path, iterations: usize) -> Result<()> {
+    let contents = fs::read_to_string(positions)
+        .wrap_err_with(|| format!("failed to read {}", positions.display()))?;
+
+    let samples = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_sample(line).wrap_err_with(|| format!("on line {}", i + 1)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut params = EvalParams::default().to_vec();
+    let tuner = Tuner {
+        max_iterations: iterations,
+        ..Default::default()
+    };
+
+    let initial_error = {
+        let scaling = tune::optimal_scaling(&samples, &params, &evaluate);
+        tune::error(&samples, &params, scaling, &evaluate)
+    };
+    let error = tuner.tune(&samples, &mut params, evaluate);
+    eprintln!(
+        "Tuned {} positions: error {initial_error:.6} -> {error:.6}",
+        samples.len()
+    );
+
+    let params = EvalParams::from_slice(&params);
+    let json = serde_json::json!({
+        "piece_values": params.piece_values,
+        "piece_square_tables": params
+            .piece_square_tables
+            .iter()
+            .map(|table| table.to_vec())
+            .collect_vec(),
+    });
+    println!("{}", serde_json::to_string_pretty(&json)?);
+
+    Ok(())
+}
+
+fn evaluate(features: &Features, params: &[f64]) -> f64 {
+    features
+        .iter()
+        .map(|(index, coefficient)| params[*index] * coefficient)
+        .sum()
+}
+
+fn parse_sample(line: &str) -> Result<Sample<Features>> {
+    let (index, result) = RESULTS
+        .iter()
+        .find_map(|(marker, result)| line.find(marker).map(|i| (i, *result)))
+        .ok_or_else(|| eyre!("no game result found"))?;
+
+    // Drop the EPD opcode and quote that may come before the result
+    let fen = line[..index]
+        .split_whitespace()
+        .take_while(|field| *field != "c9" && !field.starts_with(['"', '[']))
+        .join(" ");
+
+    let position: shakmaty::Chess = fen
+        .parse::<Fen>()?
+        .into_position(CastlingMode::Standard)
+        .map_err(|err| eyre!("{err}"))?;
+
+    Ok(Sample {
+        position: EvalParams::features(&position),
+        result,
+    })
+}